@@ -0,0 +1,75 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+use simple_redis::{Backend, Expiry, SetCondition};
+
+const LIVE: usize = 10_000;
+const EFFORT: usize = 200;
+
+fn backend(indexed: bool) -> Backend {
+    if indexed {
+        Backend::with_expire_index()
+    } else {
+        Backend::new()
+    }
+}
+
+fn set(backend: &Backend, key: String, at: u64) {
+    backend.string_set_with(key, b"value".to_vec(), SetCondition::Always, Expiry::At(at));
+}
+
+// LIVE keys expiring far in the future, and `due` more that have expired already.
+fn populate(indexed: bool, due: usize) -> Backend {
+    let backend = backend(indexed);
+    for i in 0..LIVE {
+        set(&backend, format!("live{}", i), u64::MAX);
+    }
+    for i in 0..due {
+        set(&backend, format!("due{}", i), 1);
+    }
+    backend
+}
+
+// Runs active expiration cycles until every due key is removed. Sampling finds fewer and
+// fewer of them as they get rare among the keys with a time to live.
+fn reclaim(backend: Backend) -> usize {
+    let mut cycles = 0;
+    while backend.size() > LIVE {
+        backend.active_expire(EFFORT);
+        cycles += 1;
+    }
+    cycles
+}
+
+fn bench_active_expire(c: &mut Criterion) {
+    let mut group = c.benchmark_group("active_expire");
+    group.sample_size(10);
+    for due in [100, 1_000] {
+        for (name, indexed) in [("sampling", false), ("index", true)] {
+            group.bench_with_input(BenchmarkId::new(name, due), &due, |b, &due| {
+                b.iter_batched(|| populate(indexed, due), reclaim, BatchSize::LargeInput)
+            });
+        }
+    }
+    group.finish();
+
+    // The index is written along with every time to live.
+    let mut group = c.benchmark_group("set_ttl");
+    group.sample_size(10);
+    for (name, indexed) in [("sampling", false), ("index", true)] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || backend(indexed),
+                |backend| {
+                    for i in 0..LIVE {
+                        set(&backend, format!("key{}", i), u64::MAX);
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_active_expire);
+criterion_main!(benches);
@@ -0,0 +1,52 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use dashmap::DashMap;
+
+use simple_redis::Backend;
+
+const CHUNK: &[u8] = b"chunk-01";
+
+// Appends through the backend, which extends the stored buffer in place.
+fn append_in_place(n: usize) -> usize {
+    let backend = Backend::new();
+    let mut len = 0;
+    for _ in 0..n {
+        len = backend.string_append("key".to_string(), CHUNK);
+    }
+    len
+}
+
+// The naive way: read a copy of the value, extend it and write it back.
+fn append_copy(n: usize) -> usize {
+    let map: DashMap<String, Vec<u8>> = DashMap::new();
+    let mut len = 0;
+    for _ in 0..n {
+        let mut value = map.get("key").map(|v| v.clone()).unwrap_or_default();
+        value.extend_from_slice(CHUNK);
+        len = value.len();
+        map.insert("key".to_string(), value);
+    }
+    len
+}
+
+fn bench_append(c: &mut Criterion) {
+    let mut group = c.benchmark_group("append");
+    group.sample_size(10);
+    // Copying is quadratic in the number of chunks, so it is not run for 1M of them.
+    for n in [1_000, 10_000] {
+        group.bench_with_input(BenchmarkId::new("in_place", n), &n, |b, &n| {
+            b.iter(|| append_in_place(n))
+        });
+        group.bench_with_input(BenchmarkId::new("copy", n), &n, |b, &n| {
+            b.iter(|| append_copy(n))
+        });
+    }
+    group.bench_with_input(
+        BenchmarkId::new("in_place", 1_000_000),
+        &1_000_000,
+        |b, &n| b.iter(|| append_in_place(n)),
+    );
+    group.finish();
+}
+
+criterion_group!(benches, bench_append);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use simple_redis::Backend;
+
+const INCREMENTS_PER_THREAD: usize = 10_000;
+
+// Every thread increments the same key, the elapsed time covers all of them.
+fn run(threads: usize, incr: impl Fn(&Backend) + Sync) -> Duration {
+    let backend = Backend::new();
+    let start = Instant::now();
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                for _ in 0..INCREMENTS_PER_THREAD {
+                    incr(&backend);
+                }
+            });
+        }
+    });
+    start.elapsed()
+}
+
+fn bench_counter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hot_counter");
+    group.sample_size(10);
+    for threads in [1, 8, 32, 64] {
+        // INCRBY takes the key's shard write lock for every increment.
+        group.bench_with_input(
+            BenchmarkId::new("string", threads),
+            &threads,
+            |b, &threads| {
+                b.iter_custom(|iters| {
+                    (0..iters)
+                        .map(|_| {
+                            run(threads, |backend| {
+                                backend.string_incr_by("hits".to_string(), 1).unwrap();
+                            })
+                        })
+                        .sum()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("striped", threads),
+            &threads,
+            |b, &threads| {
+                b.iter_custom(|iters| {
+                    (0..iters)
+                        .map(|_| run(threads, |backend| backend.counter_incr_by("hits", 1)))
+                        .sum()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_counter);
+criterion_main!(benches);
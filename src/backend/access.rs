@@ -0,0 +1,73 @@
+use rand::RngExt;
+
+// Redis's initial LFU counter, so new keys aren't evicted before they had a chance to be
+// accessed again, and its defaults for lfu-log-factor and lfu-decay-time.
+const LFU_INIT_VAL: u8 = 5;
+const LFU_LOG_FACTOR: f64 = 10.0;
+const LFU_DECAY_MS: u64 = 60_000;
+
+// When a key was last read or written, and how often, like the LRU and LFU data Redis keeps
+// per object. The frequency is a logarithmic counter that saturates at 255 after about a
+// million accesses, and decays by one for every minute the key is not accessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Access {
+    // Unix timestamp in milliseconds.
+    at: u64,
+    counter: u8,
+}
+
+impl Access {
+    pub fn new(now: u64) -> Self {
+        Self {
+            at: now,
+            counter: LFU_INIT_VAL,
+        }
+    }
+
+    pub fn touch(&mut self, now: u64) {
+        let counter = self.freq(now);
+        // The more accesses already counted, the less likely a new one is to be.
+        let base = counter.saturating_sub(LFU_INIT_VAL) as f64;
+        let increment =
+            counter < u8::MAX && rand::rng().random_bool(1.0 / (base * LFU_LOG_FACTOR + 1.0));
+        self.counter = counter + increment as u8;
+        self.at = now;
+    }
+
+    pub fn idle_ms(&self, now: u64) -> u64 {
+        now.saturating_sub(self.at)
+    }
+
+    pub fn freq(&self, now: u64) -> u8 {
+        let periods = self.idle_ms(now) / LFU_DECAY_MS;
+        self.counter
+            .saturating_sub(periods.min(u8::MAX as u64) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Access, LFU_DECAY_MS, LFU_INIT_VAL};
+
+    #[test]
+    fn test_access_freq() {
+        let mut access = Access::new(0);
+        assert_eq!(access.freq(0), LFU_INIT_VAL);
+
+        // The first accesses are always counted, the next ones less and less often.
+        access.touch(1);
+        assert_eq!(access.freq(1), LFU_INIT_VAL + 1);
+        for _ in 0..1000 {
+            access.touch(1);
+        }
+        let freq = access.freq(1);
+        assert!(freq > LFU_INIT_VAL + 1 && freq < 50, "freq {}", freq);
+        assert_eq!(access.idle_ms(1001), 1000);
+
+        // Decays by one per idle period.
+        assert_eq!(access.freq(1 + 3 * LFU_DECAY_MS), freq - 3);
+        assert_eq!(access.freq(u64::MAX), 0);
+        access.touch(1 + 3 * LFU_DECAY_MS);
+        assert!(access.freq(1 + 3 * LFU_DECAY_MS) >= freq - 3);
+    }
+}
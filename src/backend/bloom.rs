@@ -0,0 +1,260 @@
+use std::collections::hash_map::DefaultHasher;
+use std::f64::consts::LN_2;
+use std::hash::{Hash, Hasher};
+
+use super::dump::{Dump, Reader, Writer};
+use super::BackendError;
+
+pub const BLOOM_DEFAULT_ERROR_RATE: f64 = 0.01;
+pub const BLOOM_DEFAULT_CAPACITY: u64 = 100;
+pub const BLOOM_DEFAULT_EXPANSION: u32 = 2;
+
+// Each new sub-filter gets a tighter error rate so that the compound error rate
+// of the whole scalable filter stays bounded by the requested one.
+const BLOOM_TIGHTENING_RATIO: f64 = 0.5;
+
+// Limits on a single sub-filter, so no request can make the server allocate more than the
+// 512 MB of the largest string, or spend unbounded time hashing each item.
+const BLOOM_MAX_CAPACITY: u64 = 1 << 30;
+const BLOOM_MAX_BITS: u64 = 1 << 32;
+const BLOOM_MAX_HASHES: u32 = 64;
+
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    filters: Vec<SubFilter>,
+    // An expansion of 0 means the filter does not scale.
+    expansion: u32,
+}
+
+#[derive(Debug, Clone)]
+struct SubFilter {
+    bits: Vec<u64>,
+    n_bits: u64,
+    n_hashes: u32,
+    capacity: u64,
+    error_rate: f64,
+    count: u64,
+}
+
+impl BloomFilter {
+    // Fails if the filter would exceed the size limits.
+    pub fn new(error_rate: f64, capacity: u64, expansion: u32) -> Result<Self, BackendError> {
+        Ok(Self {
+            filters: vec![SubFilter::new(error_rate, capacity)?],
+            expansion,
+        })
+    }
+
+    // Returns true if the item was newly added, false if it may already exist.
+    pub fn add(&mut self, item: &str) -> Result<bool, BackendError> {
+        if self.contains(item) {
+            return Ok(false);
+        }
+
+        let last = self.filters.last().expect("bloom filter has no sub-filter");
+        if last.count >= last.capacity {
+            if self.expansion == 0 {
+                return Err(BackendError::BloomFilterFull);
+            }
+            let capacity = last.capacity.saturating_mul(self.expansion as u64);
+            let error_rate = last.error_rate * BLOOM_TIGHTENING_RATIO;
+            self.filters.push(SubFilter::new(error_rate, capacity)?);
+        }
+
+        let last = self
+            .filters
+            .last_mut()
+            .expect("bloom filter has no sub-filter");
+        last.insert(item);
+        Ok(true)
+    }
+
+    pub fn contains(&self, item: &str) -> bool {
+        self.filters.iter().any(|f| f.contains(item))
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.filters.iter().map(|f| f.capacity).sum()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.filters.iter().map(|f| f.count).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for BloomFilter {
+    fn default() -> Self {
+        Self::new(
+            BLOOM_DEFAULT_ERROR_RATE,
+            BLOOM_DEFAULT_CAPACITY,
+            BLOOM_DEFAULT_EXPANSION,
+        )
+        .expect("default bloom filter exceeds the size limits")
+    }
+}
+
+impl SubFilter {
+    fn new(error_rate: f64, capacity: u64) -> Result<Self, BackendError> {
+        // m = -n * ln(p) / ln(2)^2, k = -log2(p)
+        // The limits are checked on the floats, before the casts could saturate.
+        let capacity = capacity.max(1);
+        let n_bits = (-(capacity as f64) * error_rate.ln() / (LN_2 * LN_2)).ceil();
+        let n_hashes = (-error_rate.log2()).ceil().max(1.0);
+        if capacity > BLOOM_MAX_CAPACITY
+            || n_bits > BLOOM_MAX_BITS as f64
+            || n_hashes > BLOOM_MAX_HASHES as f64
+        {
+            return Err(BackendError::BloomFilterTooLarge);
+        }
+        let n_bits = (n_bits as u64).max(64);
+        let n_hashes = n_hashes as u32;
+        Ok(Self {
+            bits: vec![0; n_bits.div_ceil(64) as usize],
+            n_bits,
+            n_hashes,
+            capacity,
+            error_rate,
+            count: 0,
+        })
+    }
+
+    fn insert(&mut self, item: &str) {
+        let (h1, h2) = hash_pair(item);
+        for i in 0..self.n_hashes as u64 {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.n_bits;
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.count += 1;
+    }
+
+    fn contains(&self, item: &str) -> bool {
+        let (h1, h2) = hash_pair(item);
+        (0..self.n_hashes as u64).all(|i| {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.n_bits;
+            self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0
+        })
+    }
+}
+
+// Items are hashed with std's SipHash, whose fixed keys make the bits of a restored filter
+// valid as long as the hasher itself doesn't change.
+impl Dump for BloomFilter {
+    const TAG: u8 = 3;
+
+    fn write(&self, w: &mut Writer) {
+        w.put_u32(self.expansion);
+        w.put_u64(self.filters.len() as u64);
+        for f in &self.filters {
+            w.put_u64(f.n_bits);
+            w.put_u32(f.n_hashes);
+            w.put_u64(f.capacity);
+            w.put_f64(f.error_rate);
+            w.put_u64(f.count);
+            for word in &f.bits {
+                w.put_u64(*word);
+            }
+        }
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let expansion = r.u32()?;
+        let len = r.len()?;
+        let mut filters = Vec::new();
+        for _ in 0..len {
+            // Both are divisors or loop bounds when hashing items.
+            let n_bits = r.u64().filter(|n| (1..=BLOOM_MAX_BITS).contains(n))?;
+            let n_hashes = r.u32().filter(|n| (1..=BLOOM_MAX_HASHES).contains(n))?;
+            let capacity = r.u64()?;
+            let error_rate = r.f64()?;
+            let count = r.u64()?;
+            let words = usize::try_from(n_bits.div_ceil(64)).ok()?;
+            let bits = (0..words).map(|_| r.u64()).collect::<Option<_>>()?;
+            filters.push(SubFilter {
+                bits,
+                n_bits,
+                n_hashes,
+                capacity,
+                error_rate,
+                count,
+            });
+        }
+        // Adding items expects at least one sub-filter.
+        if filters.is_empty() {
+            return None;
+        }
+        Some(Self { filters, expansion })
+    }
+}
+
+// Kirsch-Mitzenmacher double hashing: derive all k positions from two hashes.
+fn hash_pair(item: &str) -> (u64, u64) {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    let h1 = hasher.finish();
+
+    let mut hasher = DefaultHasher::new();
+    h1.hash(&mut hasher);
+    item.hash(&mut hasher);
+    // Make sure the step is odd so that it never degenerates to zero.
+    let h2 = hasher.finish() | 1;
+
+    (h1, h2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bloom_add_contains() {
+        let mut filter = BloomFilter::default();
+        assert_eq!(filter.add("hello"), Ok(true));
+        assert_eq!(filter.add("hello"), Ok(false));
+        assert!(filter.contains("hello"));
+        assert!(!filter.contains("world"));
+        assert_eq!(filter.len(), 1);
+    }
+
+    #[test]
+    fn test_bloom_scaling() {
+        let mut filter = BloomFilter::new(0.01, 10, 2).unwrap();
+        for i in 0..100 {
+            filter.add(&i.to_string()).unwrap();
+        }
+        for i in 0..100 {
+            assert!(filter.contains(&i.to_string()));
+        }
+        assert!(filter.filters.len() > 1);
+        assert!(filter.capacity() >= 100);
+    }
+
+    #[test]
+    fn test_bloom_non_scaling_full() {
+        let mut filter = BloomFilter::new(0.01, 2, 0).unwrap();
+        assert_eq!(filter.add("a"), Ok(true));
+        assert_eq!(filter.add("b"), Ok(true));
+        assert!(filter.add("c").is_err());
+    }
+
+    #[test]
+    fn test_bloom_size_limits() {
+        assert_eq!(
+            BloomFilter::new(0.01, 1_000_000_000_000_000, 2).err(),
+            Some(BackendError::BloomFilterTooLarge)
+        );
+        assert!(BloomFilter::new(1e-300, 100, 2).is_err());
+        // Enough bits for the capacity, but too many hashes.
+        assert!(BloomFilter::new(1e-30, 1, 2).is_err());
+        assert!(BloomFilter::new(0.01, 1_000_000, 2).is_ok());
+
+        // Scaling stops at the limits too.
+        let mut filter = BloomFilter::new(0.01, 1000, u32::MAX).unwrap();
+        filter.filters[0].count = 1000;
+        assert_eq!(filter.add("a"), Err(BackendError::BloomFilterTooLarge));
+        assert_eq!(filter.filters.len(), 1);
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicI64, AtomicUsize, Ordering},
+};
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    // One stripe per core, so threads running on different cores rarely share one.
+    static ref STRIPES: usize = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(8);
+}
+
+static NEXT_THREAD_STRIPE: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // Threads are spread over the stripes round-robin, on their first increment.
+    static THREAD_STRIPE: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+// Each stripe lives on its own cache line, so increments from different threads don't
+// invalidate each other's caches.
+#[derive(Debug, Default)]
+#[repr(align(64))]
+struct Stripe(AtomicI64);
+
+// A counter split into stripes that are incremented independently and summed on read, so
+// concurrent increments of the same key neither lock nor contend on a single atomic.
+// Like the stripes, the total wraps around on overflow.
+#[derive(Debug)]
+pub struct StripedCounter {
+    stripes: Box<[Stripe]>,
+}
+
+impl StripedCounter {
+    pub fn incr_by(&self, delta: i64) {
+        let index = THREAD_STRIPE.with(|stripe| {
+            stripe.get().unwrap_or_else(|| {
+                let index = NEXT_THREAD_STRIPE.fetch_add(1, Ordering::Relaxed);
+                stripe.set(Some(index));
+                index
+            })
+        });
+        self.stripes[index % self.stripes.len()]
+            .0
+            .fetch_add(delta, Ordering::Relaxed);
+    }
+
+    // Not a snapshot, increments racing with the read may or may not be included.
+    pub fn get(&self) -> i64 {
+        self.stripes.iter().fold(0, |sum, stripe| {
+            sum.wrapping_add(stripe.0.load(Ordering::Relaxed))
+        })
+    }
+}
+
+impl Default for StripedCounter {
+    fn default() -> Self {
+        Self {
+            stripes: (0..*STRIPES).map(|_| Stripe::default()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use super::StripedCounter;
+
+    #[test]
+    fn test_striped_counter() {
+        let counter = Arc::new(StripedCounter::default());
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        counter.incr_by(2);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.get(), 16_000);
+
+        counter.incr_by(-16_001);
+        assert_eq!(counter.get(), -1);
+
+        // Wraps around like the stripes do.
+        counter.incr_by(i64::MIN);
+        assert_eq!(counter.get(), i64::MAX);
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    collections::BTreeMap,
+    mem,
+    sync::{Mutex, MutexGuard},
+};
+
+use bytes::Bytes;
+use tokio::sync::Notify;
+
+// The jobs pushed with DELAY.PUSH, ordered by the unix time in milliseconds at which they are
+// due, then by the order they were pushed in. `Backend::delay_mover` moves them onto their
+// list once due.
+#[derive(Debug, Default)]
+pub(crate) struct DelayQueue {
+    jobs: Mutex<Jobs>,
+    // Notified when a job is pushed that is due before all the others, so the mover doesn't
+    // sleep past it. A notification sent while the mover is busy is kept for its next wait.
+    earlier: Notify,
+}
+
+#[derive(Debug, Default)]
+struct Jobs {
+    // The target list and the payload of each job, by due time and push order.
+    queue: BTreeMap<(u64, u64), (String, Bytes)>,
+    pushed: u64,
+}
+
+impl DelayQueue {
+    pub(crate) fn push(&self, at: u64, key: String, payload: Bytes) {
+        let mut jobs = self.lock();
+        let earliest = jobs
+            .queue
+            .first_key_value()
+            .is_none_or(|(&(next, _), _)| at < next);
+        let seq = jobs.pushed;
+        jobs.pushed += 1;
+        jobs.queue.insert((at, seq), (key, payload));
+        if earliest {
+            self.earlier.notify_one();
+        }
+    }
+
+    // Removes the jobs due at `now` and returns them in order.
+    pub(crate) fn pop_due(&self, now: u64) -> Vec<(String, Bytes)> {
+        let mut jobs = self.lock();
+        let later = jobs.queue.split_off(&(now.saturating_add(1), 0));
+        mem::replace(&mut jobs.queue, later).into_values().collect()
+    }
+
+    pub(crate) fn next_due(&self) -> Option<u64> {
+        self.lock().queue.first_key_value().map(|(&(at, _), _)| at)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.lock().queue.len()
+    }
+
+    pub(crate) fn clear(&self) {
+        self.lock().queue.clear();
+    }
+
+    // Resolves once a job was pushed that is due before all the others.
+    pub(crate) async fn earlier(&self) {
+        self.earlier.notified().await
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Jobs> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DelayQueue;
+
+    #[test]
+    fn test_delay_queue() {
+        let queue = DelayQueue::default();
+        assert_eq!(queue.next_due(), None);
+
+        queue.push(20, "q".to_string(), "b".into());
+        queue.push(10, "q".to_string(), "a".into());
+        queue.push(20, "r".to_string(), "c".into());
+        assert_eq!(queue.next_due(), Some(10));
+
+        // Jobs due at the same time come out in the order they were pushed.
+        assert_eq!(queue.pop_due(9), vec![]);
+        assert_eq!(queue.pop_due(10), vec![("q".to_string(), "a".into())]);
+        assert_eq!(
+            queue.pop_due(30),
+            vec![("q".to_string(), "b".into()), ("r".to_string(), "c".into())]
+        );
+        assert_eq!(queue.len(), 0);
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+use dashmap::DashSet;
+
+use super::{BackendError, StringValue, StripedCounter};
+
+// Bumped whenever the encoding of a value changes, payloads of other versions are rejected.
+const DUMP_VERSION: u16 = 4;
+
+// Version and checksum.
+const DUMP_FOOTER_LEN: usize = 2 + 8;
+
+// CRC-64/Jones, the checksum Redis uses, in its reflected form.
+const CRC64_POLY: u64 = 0x95ac_9329_ac4b_c9b5;
+const CRC64_TABLE: [u64; 256] = crc64_table();
+
+// A value that can be serialized by DUMP and re-created by RESTORE. The tag identifies
+// the keyspace the value belongs to.
+pub(super) trait Dump: Sized {
+    const TAG: u8;
+
+    fn write(&self, w: &mut Writer);
+
+    // None if the data is malformed.
+    fn read(r: &mut Reader) -> Option<Self>;
+}
+
+// Integers and floats are little-endian, byte strings and collections are prefixed with
+// their length as a u64.
+#[derive(Debug, Default)]
+pub(super) struct Writer(Vec<u8>);
+
+#[derive(Debug)]
+pub(super) struct Reader<'a>(&'a [u8]);
+
+// Payloads are laid out like Redis's: the type tag and the value, then the format version
+// and a CRC-64 of everything before it. The value encoding is this server's own, so they
+// can't be exchanged with Redis.
+pub(super) fn dump<V: Dump>(value: &V) -> Vec<u8> {
+    let mut w = Writer::default();
+    w.put_u8(V::TAG);
+    value.write(&mut w);
+    w.put_u16(DUMP_VERSION);
+    let crc = crc64(&w.0);
+    w.put_u64(crc);
+    w.0
+}
+
+// Verifies the version and checksum of the payload, returns its tag and the encoded value.
+pub(super) fn check(payload: &[u8]) -> Result<(u8, &[u8]), BackendError> {
+    if payload.len() < 1 + DUMP_FOOTER_LEN {
+        return Err(BackendError::BadDumpPayload);
+    }
+    let (data, crc) = payload.split_at(payload.len() - 8);
+    let version = u16::from_le_bytes([data[data.len() - 2], data[data.len() - 1]]);
+    let crc = u64::from_le_bytes(crc.try_into().expect("checksum is 8 bytes"));
+    if version != DUMP_VERSION || crc != crc64(data) {
+        return Err(BackendError::BadDumpPayload);
+    }
+    Ok((data[0], &data[1..data.len() - 2]))
+}
+
+// Decodes a value checked by `check`, which must span the whole of it.
+pub(super) fn load<V: Dump>(data: &[u8]) -> Result<V, BackendError> {
+    let mut r = Reader(data);
+    match V::read(&mut r) {
+        Some(value) if r.0.is_empty() => Ok(value),
+        _ => Err(BackendError::BadDataFormat),
+    }
+}
+
+impl Writer {
+    pub fn put_u8(&mut self, n: u8) {
+        self.0.push(n);
+    }
+
+    pub fn put_u16(&mut self, n: u16) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn put_u32(&mut self, n: u32) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn put_u64(&mut self, n: u64) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn put_i64(&mut self, n: i64) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn put_f64(&mut self, n: f64) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn put_bytes(&mut self, data: &[u8]) {
+        self.put_u64(data.len() as u64);
+        self.0.extend_from_slice(data);
+    }
+}
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, rest) = self.0.split_first_chunk()?;
+        self.0 = rest;
+        Some(*head)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        self.take().map(u8::from_le_bytes)
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    pub fn i64(&mut self) -> Option<i64> {
+        self.take().map(i64::from_le_bytes)
+    }
+
+    pub fn f64(&mut self) -> Option<f64> {
+        self.take().map(f64::from_le_bytes)
+    }
+
+    // Checked against the remaining data, so a corrupted length can't make the caller
+    // allocate more than the payload holds.
+    pub fn len(&mut self) -> Option<usize> {
+        self.u64()
+            .and_then(|n| usize::try_from(n).ok())
+            .filter(|n| *n <= self.0.len())
+    }
+
+    pub fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.len()?;
+        let (data, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(data)
+    }
+
+    pub fn string(&mut self) -> Option<String> {
+        self.bytes()
+            .and_then(|data| String::from_utf8(data.to_vec()).ok())
+    }
+}
+
+impl Dump for StringValue {
+    const TAG: u8 = 0;
+
+    fn write(&self, w: &mut Writer) {
+        w.put_bytes(&self.as_bytes());
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        r.bytes().map(|data| StringValue::new(data.to_vec()))
+    }
+}
+
+impl Dump for DashSet<String> {
+    const TAG: u8 = 2;
+
+    fn write(&self, w: &mut Writer) {
+        w.put_u64(self.len() as u64);
+        for member in self.iter() {
+            w.put_bytes(member.as_bytes());
+        }
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let len = r.len()?;
+        let set = DashSet::new();
+        for _ in 0..len {
+            set.insert(r.string()?);
+        }
+        Some(set)
+    }
+}
+
+impl Dump for VecDeque<Bytes> {
+    const TAG: u8 = 6;
+
+    fn write(&self, w: &mut Writer) {
+        w.put_u64(self.len() as u64);
+        for element in self {
+            w.put_bytes(element);
+        }
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let len = r.len()?;
+        let mut list = VecDeque::with_capacity(len);
+        for _ in 0..len {
+            list.push_back(Bytes::copy_from_slice(r.bytes()?));
+        }
+        Some(list)
+    }
+}
+
+// Only the total is kept, it lands on a single stripe when restored.
+impl Dump for StripedCounter {
+    const TAG: u8 = 5;
+
+    fn write(&self, w: &mut Writer) {
+        w.put_i64(self.get());
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let counter = StripedCounter::default();
+        counter.incr_by(r.i64()?);
+        Some(counter)
+    }
+}
+
+fn crc64(data: &[u8]) -> u64 {
+    data.iter().fold(0, |crc, byte| {
+        CRC64_TABLE[((crc ^ *byte as u64) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+const fn crc64_table() -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u64;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC64_POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc64() {
+        // The check value of CRC-64/Jones, also used by Redis's own test.
+        assert_eq!(crc64(b"123456789"), 0xe9c6_d914_c4b8_d9ca);
+    }
+
+    #[test]
+    fn test_dump_check_load() {
+        let payload = dump(&StringValue::new(b"hello".to_vec()));
+        let (tag, data) = check(&payload).unwrap();
+        assert_eq!(tag, StringValue::TAG);
+        assert_eq!(
+            load::<StringValue>(data),
+            Ok(StringValue::new(b"hello".to_vec()))
+        );
+        // Truncated data is malformed.
+        assert_eq!(
+            load::<StringValue>(&data[..data.len() - 1]),
+            Err(BackendError::BadDataFormat)
+        );
+
+        let mut corrupted = payload.clone();
+        corrupted[3] ^= 1;
+        assert_eq!(check(&corrupted), Err(BackendError::BadDumpPayload));
+        assert_eq!(
+            check(&payload[..payload.len() - 1]),
+            Err(BackendError::BadDumpPayload)
+        );
+        assert_eq!(check(b""), Err(BackendError::BadDumpPayload));
+
+        // A length larger than the remaining data.
+        let mut w = Writer::default();
+        w.put_u64(u64::MAX);
+        assert_eq!(load::<StringValue>(&w.0), Err(BackendError::BadDataFormat));
+
+        let counter = StripedCounter::default();
+        counter.incr_by(-7);
+        let payload = dump(&counter);
+        let (_, data) = check(&payload).unwrap();
+        assert_eq!(load::<StripedCounter>(data).unwrap().get(), -7);
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    collections::BTreeSet,
+    mem,
+    sync::{Mutex, MutexGuard},
+};
+
+// Keys ordered by the unix time in milliseconds at which they expire, so that active
+// expiration finds the due ones in O(due) instead of sampling. Entries are only added along
+// with the expiration times, and never removed when a key's time to live changes or the key
+// is deleted: each one is checked against the expiration times map once due, and dropped if
+// stale. Those left behind are bounded by compacting the index against the map.
+#[derive(Debug, Default)]
+pub(crate) struct ExpireIndex(Mutex<BTreeSet<(u64, String)>>);
+
+impl ExpireIndex {
+    pub(crate) fn insert(&self, at: u64, key: String) {
+        self.lock().insert((at, key));
+    }
+
+    // Removes and returns up to `limit` entries due at `now`, earliest first.
+    pub(crate) fn pop_due(&self, now: u64, limit: usize) -> Vec<(u64, String)> {
+        let mut index = self.lock();
+        let mut due = Vec::new();
+        while due.len() < limit && index.first().is_some_and(|(at, _)| *at <= now) {
+            due.extend(index.pop_first());
+        }
+        due
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    // Replaces the entries with the ones given, read while holding the index so that none
+    // added meanwhile is lost.
+    pub(crate) fn rebuild(&self, entries: impl FnOnce() -> BTreeSet<(u64, String)>) {
+        let mut index = self.lock();
+        *index = entries();
+    }
+
+    pub(crate) fn take(&self) -> BTreeSet<(u64, String)> {
+        mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeSet<(u64, String)>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExpireIndex;
+
+    #[test]
+    fn test_expire_index() {
+        let index = ExpireIndex::default();
+        index.insert(30, "c".to_string());
+        index.insert(10, "a".to_string());
+        index.insert(20, "b".to_string());
+        index.insert(20, "b".to_string());
+        assert_eq!(index.len(), 3);
+
+        // Earliest first, no more than the limit.
+        assert_eq!(index.pop_due(5, 10), vec![]);
+        assert_eq!(index.pop_due(25, 1), vec![(10, "a".to_string())]);
+        assert_eq!(index.pop_due(25, 10), vec![(20, "b".to_string())]);
+        assert_eq!(index.len(), 1);
+
+        index.rebuild(Default::default);
+        assert_eq!(index.len(), 0);
+    }
+}
@@ -0,0 +1,122 @@
+// Redis-style glob matching on bytes:
+// - `*` matches any sequence, `?` matches any single byte.
+// - `[abc]`, `[a-z]` and `[^abc]` match a single byte in, or not in, the class.
+// - `\` escapes the next byte, both inside and outside a class.
+pub fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // Where to resume after the last `*`: the pattern index after it, and the string index it
+    // has been matched up to. Backtracking to the last star is enough since the other
+    // tokens always match a single byte.
+    let mut star: Option<(usize, usize)> = None;
+
+    while s < string.len() {
+        if p < pattern.len() {
+            if pattern[p] == b'*' {
+                p += 1;
+                star = Some((p, s));
+                continue;
+            }
+            if let Some(next) = match_token(pattern, p, string[s]) {
+                p = next;
+                s += 1;
+                continue;
+            }
+        }
+        match star {
+            Some((star_p, star_s)) => {
+                p = star_p;
+                s = star_s + 1;
+                star = Some((star_p, s));
+            }
+            None => return false,
+        }
+    }
+
+    while p < pattern.len() && pattern[p] == b'*' {
+        p += 1;
+    }
+    p == pattern.len()
+}
+
+// Matches a byte against the token starting at p, returns the index of the next token.
+fn match_token(pattern: &[u8], p: usize, c: u8) -> Option<usize> {
+    match pattern[p] {
+        b'?' => Some(p + 1),
+        b'[' => match_class(pattern, p + 1, c),
+        // A trailing backslash matches itself.
+        b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == c).then_some(p + 2),
+        b => (b == c).then_some(p + 1),
+    }
+}
+
+// An unterminated class extends to the end of the pattern, like in Redis.
+fn match_class(pattern: &[u8], mut p: usize, c: u8) -> Option<usize> {
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+
+    let mut matched = false;
+    while p < pattern.len() {
+        match pattern[p] {
+            b']' => {
+                p += 1;
+                break;
+            }
+            b'\\' if p + 1 < pattern.len() => {
+                matched |= pattern[p + 1] == c;
+                p += 2;
+            }
+            lo if p + 2 < pattern.len() && pattern[p + 1] == b'-' => {
+                let hi = pattern[p + 2];
+                matched |= lo.min(hi) <= c && c <= lo.max(hi);
+                p += 3;
+            }
+            b => {
+                matched |= b == c;
+                p += 1;
+            }
+        }
+    }
+    (matched != negate).then_some(p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn test_glob_match() {
+        for (pattern, string, expected) in [
+            ("*", "", true),
+            ("*", "anything", true),
+            ("h?llo", "hello", true),
+            ("h?llo", "hllo", false),
+            ("h*llo", "hllo", true),
+            ("h*llo", "heeeello", true),
+            ("h*llo", "hello!", false),
+            ("*a*b*", "xxaxxbxx", true),
+            ("*a*b", "xxbxxa", false),
+            ("h[ae]llo", "hallo", true),
+            ("h[ae]llo", "hillo", false),
+            ("h[^e]llo", "hallo", true),
+            ("h[^e]llo", "hello", false),
+            ("h[a-b]llo", "hbllo", true),
+            ("h[b-a]llo", "hallo", true),
+            ("h[a-b]llo", "hcllo", false),
+            ("h\\*llo", "h*llo", true),
+            ("h\\*llo", "hello", false),
+            ("[\\]]", "]", true),
+            ("a\\", "a\\", true),
+            ("user:[0-9]*", "user:42:name", true),
+        ] {
+            assert_eq!(
+                glob_match(pattern.as_bytes(), string.as_bytes()),
+                expected,
+                "{} against {}",
+                pattern,
+                string
+            );
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::{BTreeSet, HashMap};
+
+use bytes::BytesMut;
+use dashmap::DashMap;
+
+use crate::{RespDecode, RespEncode, RespFrame};
+
+use super::dump::{Dump, Reader, Writer};
+
+// The fields of a hash, along with the expiration times set by HEXPIRE like the field TTLs
+// of Redis 7.4. Expired fields are only removed by `expire_fields`, which the backend calls
+// before accessing them.
+#[derive(Debug, Default)]
+pub struct Hash {
+    fields: DashMap<String, RespFrame>,
+    // Unix timestamps in milliseconds, by field and ordered by time.
+    expire: HashMap<String, u64>,
+    expire_order: BTreeSet<(u64, String)>,
+}
+
+impl Hash {
+    pub fn fields(&self) -> &DashMap<String, RespFrame> {
+        &self.fields
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    // Returns true if the field is new. Overwriting a field removes its time to live, like
+    // HSET does in Redis.
+    pub fn insert(&mut self, field: String, value: RespFrame) -> bool {
+        self.persist(&field);
+        self.fields.insert(field, value).is_none()
+    }
+
+    // Like `insert`, but keeps the time to live, for the commands modifying a value in place.
+    pub fn update(&mut self, field: String, value: RespFrame) {
+        self.fields.insert(field, value);
+    }
+
+    // Returns false if the field does not exist.
+    pub fn remove(&mut self, field: &str) -> bool {
+        self.persist(field);
+        self.fields.remove(field).is_some()
+    }
+
+    pub fn expire_time(&self, field: &str) -> Option<u64> {
+        self.expire.get(field).copied()
+    }
+
+    // The field must exist.
+    pub fn set_expire_time(&mut self, field: &str, at: u64) {
+        self.persist(field);
+        self.expire.insert(field.to_string(), at);
+        self.expire_order.insert((at, field.to_string()));
+    }
+
+    // Removes the time to live of the field, returns false if it has none.
+    pub fn persist(&mut self, field: &str) -> bool {
+        match self.expire.remove(field) {
+            Some(at) => self.expire_order.remove(&(at, field.to_string())),
+            None => false,
+        }
+    }
+
+    // The earliest expiration time of the fields, None if none of them has a time to live.
+    pub fn next_expire(&self) -> Option<u64> {
+        self.expire_order.first().map(|(at, _)| *at)
+    }
+
+    // Removes the fields expired at `now`, returns how many there were.
+    pub fn expire_fields(&mut self, now: u64) -> usize {
+        let mut removed = 0;
+        while self.next_expire().is_some_and(|at| at <= now) {
+            let (_, field) = self.expire_order.pop_first().expect("checked above");
+            self.expire.remove(&field);
+            self.fields.remove(&field);
+            removed += 1;
+        }
+        removed
+    }
+}
+
+impl FromIterator<(String, RespFrame)> for Hash {
+    fn from_iter<I: IntoIterator<Item = (String, RespFrame)>>(iter: I) -> Self {
+        Self {
+            fields: iter.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+}
+
+// Field values are arbitrary frames, stored in their RESP encoding, followed by the fields
+// with a time to live.
+impl Dump for Hash {
+    const TAG: u8 = 1;
+
+    fn write(&self, w: &mut Writer) {
+        w.put_u64(self.fields.len() as u64);
+        for entry in self.fields.iter() {
+            w.put_bytes(entry.key().as_bytes());
+            w.put_bytes(&entry.value().clone().encode());
+        }
+        w.put_u64(self.expire.len() as u64);
+        for (field, at) in &self.expire {
+            w.put_bytes(field.as_bytes());
+            w.put_u64(*at);
+        }
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let mut hash = Hash::default();
+        for _ in 0..r.len()? {
+            let field = r.string()?;
+            let mut buf = BytesMut::from(r.bytes()?);
+            let value = RespFrame::decode(&mut buf)
+                .ok()
+                .filter(|_| buf.is_empty())?;
+            hash.fields.insert(field, value);
+        }
+        for _ in 0..r.len()? {
+            let field = r.string().filter(|f| hash.fields.contains_key(f))?;
+            let at = r.u64()?;
+            hash.set_expire_time(&field, at);
+        }
+        Some(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Hash;
+    use crate::{
+        backend::dump::{check, dump, load},
+        BulkString, RespFrame,
+    };
+
+    #[test]
+    fn test_hash_field_expiration() {
+        let mut hash: Hash = [
+            ("a".to_string(), RespFrame::Integer(1)),
+            ("b".to_string(), RespFrame::Integer(2)),
+            ("c".to_string(), RespFrame::Integer(3)),
+        ]
+        .into_iter()
+        .collect();
+        hash.set_expire_time("a", 10);
+        hash.set_expire_time("b", 30);
+        hash.set_expire_time("b", 20);
+        assert_eq!(hash.expire_time("b"), Some(20));
+        assert_eq!(hash.next_expire(), Some(10));
+
+        // Updating a value keeps its time to live, overwriting it doesn't.
+        hash.update("a".to_string(), RespFrame::Integer(4));
+        assert_eq!(hash.expire_time("a"), Some(10));
+        assert!(!hash.insert("a".to_string(), RespFrame::Integer(5)));
+        assert_eq!(hash.expire_time("a"), None);
+        assert_eq!(hash.next_expire(), Some(20));
+
+        hash.set_expire_time("c", 20);
+        assert_eq!(hash.expire_fields(19), 0);
+        assert_eq!(hash.expire_fields(20), 2);
+        assert_eq!(hash.len(), 1);
+        assert_eq!(hash.next_expire(), None);
+
+        hash.set_expire_time("a", 10);
+        assert!(hash.persist("a"));
+        assert!(!hash.persist("a"));
+        assert!(hash.remove("a"));
+        assert!(hash.is_empty());
+    }
+
+    #[test]
+    fn test_hash_dump() {
+        let mut hash: Hash = [
+            ("a".to_string(), RespFrame::Integer(1)),
+            ("b".to_string(), BulkString::new("x").into()),
+        ]
+        .into_iter()
+        .collect();
+        hash.set_expire_time("b", 1000);
+
+        let payload = dump(&hash);
+        let (_, data) = check(&payload).unwrap();
+        let restored: Hash = load(data).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(*restored.fields().get("a").unwrap(), RespFrame::Integer(1));
+        assert_eq!(restored.expire_time("a"), None);
+        assert_eq!(restored.expire_time("b"), Some(1000));
+        assert_eq!(restored.next_expire(), Some(1000));
+    }
+}
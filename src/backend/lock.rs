@@ -0,0 +1,99 @@
+use std::collections::hash_map::RandomState;
+
+use dashmap::{DashMap, RwLockWriteGuard, SharedValue};
+
+pub(crate) type Shard<V> = hashbrown::HashMap<String, SharedValue<V>, RandomState>;
+
+// Write-locks every DashMap shard holding one of the given keys, so that a multi-key
+// operation can check and update all of them atomically.
+// Shards are always locked in index order, which keeps concurrent KeyLocks deadlock-free.
+// While a KeyLock is alive, the same map must not be accessed through the DashMap API.
+pub(crate) struct KeyLock<'a, V> {
+    map: &'a DashMap<String, V>,
+    shards: Vec<(usize, RwLockWriteGuard<'a, Shard<V>>)>,
+}
+
+impl<'a, V> KeyLock<'a, V> {
+    pub(crate) fn new<'k>(
+        map: &'a DashMap<String, V>,
+        keys: impl IntoIterator<Item = &'k str>,
+    ) -> Self {
+        let mut indexes: Vec<usize> = keys.into_iter().map(|k| map.determine_map(k)).collect();
+        indexes.sort_unstable();
+        indexes.dedup();
+
+        let shards = indexes
+            .into_iter()
+            .map(|i| (i, map.shards()[i].write()))
+            .collect();
+        Self { map, shards }
+    }
+
+    pub(crate) fn contains_key(&self, key: &str) -> bool {
+        self.shard(key).contains_key(key)
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&V> {
+        self.shard(key).get(key).map(|v| v.get())
+    }
+
+    pub(crate) fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.shard_mut(key).get_mut(key).map(|v| v.get_mut())
+    }
+
+    pub(crate) fn insert(&mut self, key: String, value: V) -> Option<V> {
+        self.shard_mut(&key)
+            .insert(key, SharedValue::new(value))
+            .map(|v| v.into_inner())
+    }
+
+    pub(crate) fn remove(&mut self, key: &str) -> Option<V> {
+        self.shard_mut(key).remove(key).map(|v| v.into_inner())
+    }
+
+    fn position(&self, key: &str) -> usize {
+        let index = self.map.determine_map(key);
+        self.shards
+            .binary_search_by_key(&index, |(i, _)| *i)
+            .unwrap_or_else(|_| panic!("key {:?} is not locked", key))
+    }
+
+    fn shard(&self, key: &str) -> &Shard<V> {
+        &self.shards[self.position(key)].1
+    }
+
+    fn shard_mut(&mut self, key: &str) -> &mut Shard<V> {
+        let pos = self.position(key);
+        &mut self.shards[pos].1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dashmap::DashMap;
+
+    use super::KeyLock;
+
+    #[test]
+    fn test_key_lock() {
+        let map: DashMap<String, i64> = DashMap::new();
+        map.insert("a".to_string(), 1);
+
+        let keys = ["a", "b", "c"];
+        let mut lock = KeyLock::new(&map, keys);
+        assert!(lock.contains_key("a"));
+        assert!(!lock.contains_key("b"));
+        assert_eq!(lock.get("a"), Some(&1));
+        assert_eq!(lock.get("b"), None);
+        *lock.get_mut("a").unwrap() += 1;
+        assert_eq!(lock.get_mut("b"), None);
+        assert_eq!(lock.insert("a".to_string(), 3), Some(2));
+        assert_eq!(lock.insert("b".to_string(), 3), None);
+        assert_eq!(lock.remove("c"), None);
+        assert_eq!(lock.remove("a"), Some(3));
+        drop(lock);
+
+        assert_eq!(map.get("a").map(|v| *v), None);
+        assert_eq!(map.get("b").map(|v| *v), Some(3));
+    }
+}
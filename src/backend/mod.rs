@@ -1,18 +1,270 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future;
 use std::ops::Deref;
 use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use dashmap::{DashMap, DashSet};
+use bytes::Bytes;
+use dashmap::{
+    mapref::{entry::Entry, one::RefMut},
+    DashMap, DashSet,
+};
+use rand::{seq::SliceRandom, RngExt};
+use thiserror::Error;
+use tracing::info;
 
-use crate::{RespArray, RespFrame, RespNull};
+use crate::{BulkString, RespArray, RespFrame, RespNull, SimpleError};
+
+// Bloom filters are experimental, their API is not settled yet.
+#[cfg(feature = "unstable")]
+pub use self::bloom::{
+    BloomFilter, BLOOM_DEFAULT_CAPACITY, BLOOM_DEFAULT_ERROR_RATE, BLOOM_DEFAULT_EXPANSION,
+};
+#[cfg(not(feature = "unstable"))]
+pub(crate) use self::bloom::{BloomFilter, BLOOM_DEFAULT_EXPANSION};
+pub use self::counter::StripedCounter;
+pub use self::ratelimit::RateLimitResult;
+// The building blocks of sorted sets, their API is not settled yet.
+#[cfg(feature = "unstable")]
+pub use self::score::{cmp_score_member, format_score, parse_score};
+#[cfg(not(feature = "unstable"))]
+pub(crate) use self::score::{format_score, parse_score};
+#[cfg(feature = "unstable")]
+pub use self::skiplist::SkipList;
+// Same for streams.
+#[cfg(feature = "unstable")]
+pub use self::stream::{
+    StreamAddId, StreamAutoClaimResult, StreamClaimOptions, StreamConsumerInfo, StreamFields,
+    StreamGroupInfo, StreamId, StreamInfo, StreamPendingEntry, StreamPendingSummary,
+    StreamTrimOptions, StreamTrimStrategy,
+};
+#[cfg(not(feature = "unstable"))]
+pub(crate) use self::stream::{
+    StreamAddId, StreamAutoClaimResult, StreamClaimOptions, StreamConsumerInfo, StreamFields,
+    StreamGroupInfo, StreamId, StreamInfo, StreamPendingEntry, StreamPendingSummary,
+    StreamTrimOptions, StreamTrimStrategy,
+};
+pub use self::string::StringValue;
+pub use self::waiter::Blocked;
+pub use self::zset::{Aggregate, LexBound, LexRange, RangeBy, ScoreCondition, ScoreRange};
+
+use self::access::Access;
+use self::delay::DelayQueue;
+use self::dump::Dump;
+use self::expire_index::ExpireIndex;
+use self::glob::glob_match;
+use self::hash::Hash;
+use self::lock::{KeyLock, Shard};
+use self::ratelimit::TokenBucket;
+use self::score::format_float;
+use self::stream::{ConsumerGroup, Stream};
+use self::waiter::Waiters;
+use self::zset::{weighted, SortedSet};
+
+mod access;
+#[cfg_attr(not(feature = "unstable"), allow(dead_code))]
+mod bloom;
+mod counter;
+mod delay;
+mod dump;
+mod expire_index;
+mod glob;
+mod hash;
+mod lock;
+mod ratelimit;
+mod score;
+#[cfg_attr(not(feature = "unstable"), allow(dead_code))]
+mod skiplist;
+mod stream;
+mod string;
+mod waiter;
+mod zset;
+
+// Errors are displayed as the error replies Redis sends to clients.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum BackendError {
+    #[error("ERR value is not an integer or out of range")]
+    NotInteger,
+    #[error("ERR increment or decrement would overflow")]
+    Overflow,
+    #[error("ERR value is not a valid float")]
+    NotFloat,
+    #[error("ERR increment would produce NaN or Infinity")]
+    NanOrInfinity,
+    #[error("ERR string exceeds maximum allowed size (proto-max-bulk-len)")]
+    StringTooLong,
+    #[error("ERR no such key")]
+    NoSuchKey,
+    #[error("ERR value is not a valid UTF-8 string")]
+    NotUtf8,
+    #[error("ERR hash value is not an integer")]
+    HashNotInteger,
+    #[error("ERR hash value is not a float")]
+    HashNotFloat,
+    #[error("ERR non scaling filter is full")]
+    BloomFilterFull,
+    #[error("ERR filter exceeds maximum allowed size")]
+    BloomFilterTooLarge,
+    #[error("BUSYKEY Target key name already exists.")]
+    BusyKey,
+    #[error("ERR DUMP payload version or checksum are wrong")]
+    BadDumpPayload,
+    #[error("ERR Bad data format")]
+    BadDataFormat,
+    #[error("ERR index out of range")]
+    IndexOutOfRange,
+    #[error("ERR resulting score is not a number (NaN)")]
+    ScoreNan,
+    #[error("ERR The ID specified in XADD must be greater than 0-0")]
+    StreamIdZero,
+    #[error("ERR The ID specified in XADD is equal or smaller than the target stream top item")]
+    StreamIdTooSmall,
+    #[error("ERR The stream has exhausted the last possible ID, unable to add more items")]
+    StreamExhausted,
+    #[error(
+        "ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want \
+        to use the MKSTREAM option to create an empty stream automatically."
+    )]
+    NoStream,
+    #[error("BUSYGROUP Consumer Group name already exists")]
+    BusyGroup,
+    #[error("NOGROUP No such key '{key}' or consumer group '{group}'")]
+    NoGroup { key: String, group: String },
+}
+
+// The condition SET checks before writing the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SetCondition {
+    #[default]
+    Always,
+    IfAbsent,
+    IfExists,
+}
+
+// What happens to the key's time to live when its value is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Expiry {
+    #[default]
+    Persist,
+    // Unix timestamp in milliseconds.
+    At(u64),
+    Keep,
+}
+
+// The conditions EXPIRE checks against the current time to live before setting a new one,
+// as its NX, XX, GT and LT options. For GT and LT, a key without one counts as never expiring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExpireCondition {
+    pub if_none: bool,
+    pub if_some: bool,
+    pub if_greater: bool,
+    pub if_less: bool,
+}
+
+impl ExpireCondition {
+    fn holds(&self, current: Option<u64>, at: u64) -> bool {
+        let effective = current.unwrap_or(u64::MAX);
+        !(self.if_none && current.is_some()
+            || self.if_some && current.is_none()
+            || self.if_greater && at <= effective
+            || self.if_less && at >= effective)
+    }
+}
+
+// What setting the time to live of a hash field did, as replied by HEXPIRE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldExpireResult {
+    NoSuchField,
+    ConditionNotMet,
+    Set,
+    // The time was in the past.
+    Deleted,
+}
+
+// How SINTER, SUNION and SDIFF combine their sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperation {
+    Inter,
+    Union,
+    // The members of the first set that are in none of the others.
+    Diff,
+}
+
+// The end of a list the list commands push to or pop from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListEnd {
+    Left,
+    Right,
+}
+
+// Type names of the keyspaces, in the order SCAN walks through them.
+// Bloom filters use the RedisBloom type name.
+const KEYSPACE_TYPES: [&str; 9] = [
+    "string",
+    "hash",
+    "set",
+    "MBbloom--",
+    "ratelimit",
+    "counter",
+    "list",
+    "zset",
+    "stream",
+];
+
+const RANDOM_KEY_MAX_ATTEMPTS: usize = 100;
+
+// Keys with a time to live sampled per round of active expiration, same as Redis.
+const ACTIVE_EXPIRE_SAMPLE: usize = 20;
+// Stale entries the expiration index may hold beyond one per time to live before it is
+// compacted.
+const EXPIRE_INDEX_SLACK: usize = 1024;
+
+// Keys visited per batch by the bulk TTL jobs, and the pause between batches, so that a job
+// over a large keyspace leaves the shards to the clients in between. SCAN goes a shard at a
+// time, so a batch may visit more keys when the shards are large.
+const BULK_TTL_BATCH: usize = 100;
+const BULK_TTL_PAUSE: Duration = Duration::from_millis(10);
+
+// Same limit as Redis's default proto-max-bulk-len.
+const STRING_MAX_LEN: usize = 512 * 1024 * 1024;
 
 #[derive(Debug, Clone)]
 pub struct Backend(Arc<BackendInner>);
 
 #[derive(Debug)]
 pub struct BackendInner {
-    pub(crate) string: DashMap<String, RespFrame>,
-    pub(crate) hmap: DashMap<String, DashMap<String, RespFrame>>,
+    pub(crate) string: DashMap<String, StringValue>,
+    pub(crate) hmap: DashMap<String, Hash>,
     pub(crate) set: DashMap<String, DashSet<String>>,
+    pub(crate) bloom: DashMap<String, BloomFilter>,
+    pub(crate) ratelimit: DashMap<String, TokenBucket>,
+    pub(crate) counter: DashMap<String, StripedCounter>,
+    pub(crate) list: DashMap<String, VecDeque<Bytes>>,
+    pub(crate) zset: DashMap<String, SortedSet>,
+    pub(crate) stream: DashMap<String, Stream>,
+    // Unix timestamps in milliseconds after which keys are considered missing, whichever
+    // keyspaces hold them. An entry is only written while holding the key's entry in one of
+    // them, and removed along with the key.
+    pub(crate) expire: DashMap<String, u64>,
+    // The same ordered by time, for active expiration to find the due keys without sampling.
+    // Every time to live set also takes its lock, so it is only kept when enabled.
+    pub(crate) expire_index: Option<ExpireIndex>,
+    // When and how often commands read or wrote each key. An entry is only recorded while
+    // holding the key's entry in its keyspace, so removing the key and then its access data
+    // never leaves a stale one behind.
+    pub(crate) access: DashMap<String, Access>,
+    // The earliest expiration time among the fields of each hash, for the hashes with fields
+    // that have a time to live. It is only written while holding the hash's entry, and may be
+    // earlier than the actual one but never later, so entries left behind by removed hashes
+    // are harmless and dropped once due.
+    pub(crate) hash_field_expire: DashMap<String, u64>,
+    // The clients blocked on each key, woken by the writes that may serve them.
+    pub(crate) waiters: Waiters,
+    // The jobs of DELAY.PUSH not due yet.
+    pub(crate) delayed: DelayQueue,
+    #[cfg(feature = "chaos")]
+    pub(crate) chaos: crate::chaos::Chaos,
 }
 
 impl Deref for Backend {
@@ -35,6 +287,20 @@ impl Default for BackendInner {
             string: DashMap::new(),
             hmap: DashMap::new(),
             set: DashMap::new(),
+            bloom: DashMap::new(),
+            ratelimit: DashMap::new(),
+            counter: DashMap::new(),
+            list: DashMap::new(),
+            zset: DashMap::new(),
+            stream: DashMap::new(),
+            expire: DashMap::new(),
+            expire_index: None,
+            access: DashMap::new(),
+            hash_field_expire: DashMap::new(),
+            waiters: Waiters::default(),
+            delayed: DelayQueue::default(),
+            #[cfg(feature = "chaos")]
+            chaos: crate::chaos::Chaos::default(),
         }
     }
 }
@@ -44,76 +310,3403 @@ impl Backend {
         Self::default()
     }
 
-    pub fn string_get(&self, key: &str) -> Option<RespFrame> {
-        self.string.get(key).map(|v| v.value().clone())
+    // Active expiration goes through an index of the keys ordered by expiration time instead
+    // of sampling them, see `active_expire`.
+    pub fn with_expire_index() -> Self {
+        Self(Arc::new(BackendInner {
+            expire_index: Some(ExpireIndex::default()),
+            ..Default::default()
+        }))
     }
 
-    pub fn string_set(&self, key: String, value: RespFrame) {
-        self.string.insert(key, value);
+    // Blocks a client on the keys until the returned guard is dropped. Once registered, the
+    // writes to any of them that may serve the client wake it through `Blocked::woken`.
+    pub fn block(&self, keys: Vec<String>) -> Blocked {
+        Blocked::new(self.clone(), keys)
     }
 
-    pub fn hash_get(&self, key: &str, field: &str) -> Option<RespFrame> {
-        self.hmap
-            .get(key)
-            .and_then(|v| v.get(field).map(|v| v.value().clone()))
+    // Removes the key from every keyspace, returns false if none of them held it.
+    pub fn delete(&self, key: &str) -> bool {
+        self.expire_if_needed(key);
+        // Not short-circuiting, so the key is removed from all of them.
+        let removed = self.delete_in(&self.string, key)
+            | self.delete_in(&self.hmap, key)
+            | self.delete_in(&self.set, key)
+            | self.delete_in(&self.bloom, key)
+            | self.delete_in(&self.ratelimit, key)
+            | self.delete_in(&self.counter, key)
+            | self.delete_in(&self.list, key)
+            | self.delete_in(&self.zset, key)
+            | self.delete_in(&self.stream, key);
+        self.access.remove(key);
+        removed
     }
 
-    pub fn hash_set(&self, key: String, field: String, value: RespFrame) {
-        let hmap = self.hmap.entry(key).or_default();
-        hmap.insert(field, value);
+    // The time to live is removed under the entry lock, so a concurrent write can't be left
+    // without the one it just set.
+    fn delete_in<V>(&self, map: &DashMap<String, V>, key: &str) -> bool {
+        map.remove_if(key, |key, _| {
+            self.expire.remove(key);
+            true
+        })
+        .is_some()
     }
 
-    pub fn hash_get_all(&self, key: &str) -> Option<DashMap<String, RespFrame>> {
-        self.hmap.get(key).map(|v| v.clone())
+    // Must be called while holding the key's entry, like any write to the expiration times.
+    // The index is written last, so that compacting it never misses the new time.
+    fn set_expire(&self, key: String, at: u64) {
+        self.expire.insert(key.clone(), at);
+        if let Some(index) = &self.expire_index {
+            index.insert(at, key);
+        }
     }
 
-    pub fn hash_multi_get(&self, key: &str, fields: Vec<String>) -> RespFrame {
-        let mut array = Vec::new();
-        match self.hmap.get(key) {
-            Some(hmap) => {
-                for field in fields {
-                    if let Some(value) = hmap.get(&field) {
-                        array.push(value.value().clone());
-                    } else {
-                        array.push(RespFrame::Null(RespNull));
-                    }
+    // Records an access to the key, returns whether it exists.
+    pub fn touch(&self, key: &str) -> bool {
+        self.expire_if_needed(key);
+        self.touch_in(&self.string, key)
+            || self.touch_in(&self.hmap, key)
+            || self.touch_in(&self.set, key)
+            || self.touch_in(&self.bloom, key)
+            || self.touch_in(&self.ratelimit, key)
+            || self.touch_in(&self.counter, key)
+            || self.touch_in(&self.list, key)
+            || self.touch_in(&self.zset, key)
+            || self.touch_in(&self.stream, key)
+    }
+
+    fn touch_in<V>(&self, map: &DashMap<String, V>, key: &str) -> bool {
+        map.get(key).map(|_| self.record_access(key)).is_some()
+    }
+
+    // Must be called while holding the key's entry in its keyspace.
+    fn record_access(&self, key: &str) {
+        let now = now_ms();
+        match self.access.get_mut(key) {
+            Some(mut access) => access.touch(now),
+            None => {
+                self.access.insert(key.to_string(), Access::new(now));
+            }
+        }
+    }
+
+    pub fn exists(&self, key: &str) -> bool {
+        self.expire_if_needed(key);
+        self.string.contains_key(key)
+            || self.hmap.contains_key(key)
+            || self.set.contains_key(key)
+            || self.bloom.contains_key(key)
+            || self.ratelimit.contains_key(key)
+            || self.counter.contains_key(key)
+            || self.list.contains_key(key)
+            || self.zset.contains_key(key)
+            || self.stream.contains_key(key)
+    }
+
+    // Moves the value of src, whatever its type, to dst along with its time to live.
+    // Unless `replace` is set, nothing happens and false is returned if dst already exists.
+    // The move itself is atomic, clearing dst from the other keyspaces beforehand is not.
+    pub fn rename(&self, src: &str, dst: &str, replace: bool) -> Result<bool, BackendError> {
+        if !self.exists(src) {
+            return Err(BackendError::NoSuchKey);
+        }
+        if src == dst {
+            return Ok(replace);
+        }
+        if !replace && self.exists(dst) {
+            return Ok(false);
+        }
+
+        self.delete(dst);
+        let moved = self.move_key(&self.string, src, dst)
+            || self.move_key(&self.hmap, src, dst)
+            || self.move_key(&self.set, src, dst)
+            || self.move_key(&self.bloom, src, dst)
+            || self.move_key(&self.ratelimit, src, dst)
+            || self.move_key(&self.counter, src, dst)
+            || self.move_key(&self.list, src, dst)
+            || self.move_key(&self.zset, src, dst)
+            || self.move_key(&self.stream, src, dst);
+        // src may have been removed concurrently after the check above.
+        if moved {
+            Ok(true)
+        } else {
+            Err(BackendError::NoSuchKey)
+        }
+    }
+
+    // Moves the value within the keyspace, returns false if src is not in it.
+    fn move_key<V>(&self, map: &DashMap<String, V>, src: &str, dst: &str) -> bool {
+        let mut lock = KeyLock::new(map, [src, dst]);
+        let value = match lock.remove(src) {
+            Some(value) => value,
+            None => return false,
+        };
+        lock.insert(dst.to_string(), value);
+        self.waiters.wake(dst);
+        if let Some((_, at)) = self.expire.remove(src) {
+            self.set_expire(dst.to_string(), at);
+        } else {
+            self.expire.remove(dst);
+        }
+        if let Some((_, access)) = self.access.remove(src) {
+            self.access.insert(dst.to_string(), access);
+        }
+        if let Some((_, at)) = self.hash_field_expire.remove(src) {
+            self.hash_field_expire.insert(dst.to_string(), at);
+        }
+        self.record_access(dst);
+        true
+    }
+
+    // Sets the unix time in milliseconds at which the key expires if the condition holds,
+    // returns false if it does not or the key does not exist. A time in the past deletes
+    // the key right away.
+    pub fn expire_at(&self, key: &str, at: u64, condition: ExpireCondition) -> bool {
+        self.expire_if_needed(key);
+        let past = at <= now_ms();
+        let set = self
+            .expire_in(&self.string, key, at, condition, past)
+            .or_else(|| self.expire_in(&self.hmap, key, at, condition, past))
+            .or_else(|| self.expire_in(&self.set, key, at, condition, past))
+            .or_else(|| self.expire_in(&self.bloom, key, at, condition, past))
+            .or_else(|| self.expire_in(&self.ratelimit, key, at, condition, past))
+            .or_else(|| self.expire_in(&self.counter, key, at, condition, past))
+            .or_else(|| self.expire_in(&self.list, key, at, condition, past))
+            .or_else(|| self.expire_in(&self.zset, key, at, condition, past))
+            .or_else(|| self.expire_in(&self.stream, key, at, condition, past))
+            .unwrap_or(false);
+        // The entry lock has been released, deleting under it would deadlock.
+        if set && past {
+            self.delete(key);
+        }
+        set
+    }
+
+    fn expire_in<V>(
+        &self,
+        map: &DashMap<String, V>,
+        key: &str,
+        at: u64,
+        condition: ExpireCondition,
+        past: bool,
+    ) -> Option<bool> {
+        let _value = map.get(key)?;
+        let current = self.expire.get(key).map(|at| *at);
+        if !condition.holds(current, at) {
+            return Some(false);
+        }
+        if !past {
+            self.set_expire(key.to_string(), at);
+        }
+        Some(true)
+    }
+
+    // Removes the time to live of the key, returns false if it does not exist or has none.
+    pub fn persist(&self, key: &str) -> bool {
+        self.expire_if_needed(key);
+        self.persist_in(&self.string, key)
+            .or_else(|| self.persist_in(&self.hmap, key))
+            .or_else(|| self.persist_in(&self.set, key))
+            .or_else(|| self.persist_in(&self.bloom, key))
+            .or_else(|| self.persist_in(&self.ratelimit, key))
+            .or_else(|| self.persist_in(&self.counter, key))
+            .or_else(|| self.persist_in(&self.list, key))
+            .or_else(|| self.persist_in(&self.zset, key))
+            .or_else(|| self.persist_in(&self.stream, key))
+            .unwrap_or(false)
+    }
+
+    fn persist_in<V>(&self, map: &DashMap<String, V>, key: &str) -> Option<bool> {
+        map.get(key).map(|_| self.expire.remove(key).is_some())
+    }
+
+    // The unix time in milliseconds at which the key expires: None if the key does not exist,
+    // Some(None) if it has no time to live.
+    pub fn expire_time(&self, key: &str) -> Option<Option<u64>> {
+        self.expire_if_needed(key);
+        self.expire_time_in(&self.string, key)
+            .or_else(|| self.expire_time_in(&self.hmap, key))
+            .or_else(|| self.expire_time_in(&self.set, key))
+            .or_else(|| self.expire_time_in(&self.bloom, key))
+            .or_else(|| self.expire_time_in(&self.ratelimit, key))
+            .or_else(|| self.expire_time_in(&self.counter, key))
+            .or_else(|| self.expire_time_in(&self.list, key))
+            .or_else(|| self.expire_time_in(&self.zset, key))
+            .or_else(|| self.expire_time_in(&self.stream, key))
+    }
+
+    fn expire_time_in<V>(&self, map: &DashMap<String, V>, key: &str) -> Option<Option<u64>> {
+        map.get(key).map(|_| self.expire.get(key).map(|at| *at))
+    }
+
+    // Serializes the value of the key, whatever its type, None if it does not exist.
+    pub fn dump(&self, key: &str) -> Option<Vec<u8>> {
+        self.expire_hash_if_needed(key);
+        self.dump_in(&self.string, key)
+            .or_else(|| self.dump_in(&self.hmap, key))
+            .or_else(|| self.dump_in(&self.set, key))
+            .or_else(|| self.dump_in(&self.bloom, key))
+            .or_else(|| self.dump_in(&self.ratelimit, key))
+            .or_else(|| self.dump_in(&self.counter, key))
+            .or_else(|| self.dump_in(&self.list, key))
+            .or_else(|| self.dump_in(&self.zset, key))
+            .or_else(|| self.dump_in(&self.stream, key))
+    }
+
+    fn dump_in<V: Dump>(&self, map: &DashMap<String, V>, key: &str) -> Option<Vec<u8>> {
+        map.get(key).map(|value| {
+            self.record_access(key);
+            dump::dump(value.value())
+        })
+    }
+
+    // Re-creates the key from a DUMP payload, expiring at the unix time in milliseconds `at`
+    // if given. Unless `replace` is set, fails if the key already exists. With a time in the
+    // past the value is not restored, but an existing key is still replaced, like in Redis.
+    pub fn restore(
+        &self,
+        key: &str,
+        payload: &[u8],
+        at: Option<u64>,
+        replace: bool,
+    ) -> Result<(), BackendError> {
+        if !replace && self.exists(key) {
+            return Err(BackendError::BusyKey);
+        }
+        let (tag, data) = dump::check(payload)?;
+        match tag {
+            StringValue::TAG => self.restore_in(&self.string, key, dump::load(data)?, at, replace),
+            Hash::TAG => {
+                let hash: Hash = dump::load(data)?;
+                // Registered beforehand, an earlier time being harmless if restoring fails.
+                if let Some(at) = hash.next_expire() {
+                    self.hash_field_expire.insert(key.to_string(), at);
                 }
+                self.restore_in(&self.hmap, key, hash, at, replace)
             }
-            None => {
-                for _ in fields {
-                    array.push(RespFrame::Null(RespNull));
+            <DashSet<String>>::TAG => {
+                self.restore_in(&self.set, key, dump::load(data)?, at, replace)
+            }
+            BloomFilter::TAG => self.restore_in(&self.bloom, key, dump::load(data)?, at, replace),
+            TokenBucket::TAG => {
+                self.restore_in(&self.ratelimit, key, dump::load(data)?, at, replace)
+            }
+            StripedCounter::TAG => {
+                self.restore_in(&self.counter, key, dump::load(data)?, at, replace)
+            }
+            <VecDeque<Bytes>>::TAG => {
+                self.restore_in(&self.list, key, dump::load(data)?, at, replace)
+            }
+            SortedSet::TAG => self.restore_in(&self.zset, key, dump::load(data)?, at, replace),
+            Stream::TAG => self.restore_in(&self.stream, key, dump::load(data)?, at, replace),
+            _ => Err(BackendError::BadDataFormat),
+        }
+    }
+
+    fn restore_in<V>(
+        &self,
+        map: &DashMap<String, V>,
+        key: &str,
+        value: V,
+        at: Option<u64>,
+        replace: bool,
+    ) -> Result<(), BackendError> {
+        if replace {
+            self.delete(key);
+        }
+        if at.is_some_and(|at| at <= now_ms()) {
+            return Ok(());
+        }
+        // The key may have been created concurrently since the check above.
+        match map.entry(key.to_string()) {
+            Entry::Occupied(_) => Err(BackendError::BusyKey),
+            Entry::Vacant(entry) => {
+                if let Some(at) = at {
+                    self.set_expire(entry.key().clone(), at);
                 }
+                self.record_access(entry.key());
+                entry.insert(value);
+                self.waiters.wake(key);
+                Ok(())
             }
         }
-        RespFrame::Array(RespArray::new(array))
     }
 
-    pub fn set_add(&self, key: String, members: Vec<String>) -> RespFrame {
-        let mut count = 0;
-        let set = self.set.entry(key).or_default();
-        for member in members {
-            if set.insert(member) {
-                count += 1;
+    // All the live keys matching the glob pattern, whichever keyspace holds them.
+    pub fn keys(&self, pattern: &[u8]) -> Vec<String> {
+        let mut keys = HashSet::new();
+        let mut collect = |key: &String| {
+            if glob_match(pattern, key.as_bytes()) && !keys.contains(key) {
+                keys.insert(key.clone());
+            }
+        };
+        // Expired keys are skipped, removing them here would deadlock with the iteration.
+        let mut live = |key: &String| {
+            if !self.is_expired(key) {
+                collect(key);
+            }
+        };
+        self.string.iter().for_each(|e| live(e.key()));
+        self.hmap.iter().for_each(|e| live(e.key()));
+        self.set.iter().for_each(|e| live(e.key()));
+        self.bloom.iter().for_each(|e| live(e.key()));
+        self.ratelimit.iter().for_each(|e| live(e.key()));
+        self.counter.iter().for_each(|e| live(e.key()));
+        self.list.iter().for_each(|e| live(e.key()));
+        self.zset.iter().for_each(|e| live(e.key()));
+        self.stream.iter().for_each(|e| live(e.key()));
+        keys.into_iter().collect()
+    }
+
+    // Walks whole DashMap shards, keyspace after keyspace, until at least `count` keys have
+    // been visited. The cursor is the index of the next shard to visit, 0 once the scan is done.
+    // Keys never move between shards, so a key present during the whole scan is returned at
+    // least once, whatever the concurrent updates.
+    pub fn scan(
+        &self,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+        key_type: Option<&str>,
+    ) -> (u64, Vec<String>) {
+        let shard_counts = [
+            self.string.shards().len(),
+            self.hmap.shards().len(),
+            self.set.shards().len(),
+            self.bloom.shards().len(),
+            self.ratelimit.shards().len(),
+            self.counter.shards().len(),
+            self.list.shards().len(),
+            self.zset.shards().len(),
+            self.stream.shards().len(),
+        ];
+        let total: usize = shard_counts.iter().sum();
+
+        let mut keys = Vec::new();
+        let mut visited = 0;
+        let mut next = cursor as usize;
+        while next < total && visited < count {
+            // Find the keyspace and the shard within it.
+            let (mut keyspace, mut shard) = (0, next);
+            while shard >= shard_counts[keyspace] {
+                shard -= shard_counts[keyspace];
+                keyspace += 1;
             }
+            next += 1;
+
+            if key_type.is_some_and(|t| !t.eq_ignore_ascii_case(KEYSPACE_TYPES[keyspace])) {
+                continue;
+            }
+            visited += match keyspace {
+                0 => self.scan_shard(&self.string, shard, pattern, &mut keys),
+                1 => self.scan_shard(&self.hmap, shard, pattern, &mut keys),
+                2 => self.scan_shard(&self.set, shard, pattern, &mut keys),
+                3 => self.scan_shard(&self.bloom, shard, pattern, &mut keys),
+                4 => self.scan_shard(&self.ratelimit, shard, pattern, &mut keys),
+                5 => self.scan_shard(&self.counter, shard, pattern, &mut keys),
+                6 => self.scan_shard(&self.list, shard, pattern, &mut keys),
+                7 => self.scan_shard(&self.zset, shard, pattern, &mut keys),
+                _ => self.scan_shard(&self.stream, shard, pattern, &mut keys),
+            };
         }
-        RespFrame::Integer(count)
+
+        let next = if next >= total { 0 } else { next as u64 };
+        (next, keys)
     }
 
-    pub fn set_is_member(&self, key: &str, member: &str) -> RespFrame {
-        let ret = match self.set.get(key) {
-            Some(set) => {
-                if set.contains(member) {
-                    1
-                } else {
-                    0
+    // Collects the live keys of the shard matching the pattern, returns the number of keys visited.
+    fn scan_shard<V>(
+        &self,
+        map: &DashMap<String, V>,
+        shard: usize,
+        pattern: Option<&[u8]>,
+        keys: &mut Vec<String>,
+    ) -> usize {
+        let shard = map.shards()[shard].read();
+        keys.extend(
+            shard
+                .keys()
+                .filter(|key| pattern.is_none_or(|p| glob_match(p, key.as_bytes())))
+                .filter(|key| !self.is_expired(key))
+                .cloned(),
+        );
+        shard.len()
+    }
+
+    // Commands of other types don't check for a string at the key, so HSET on a string key
+    // for instance makes it held by two keyspaces, and counted twice.
+    pub fn size(&self) -> usize {
+        self.string.len()
+            + self.hmap.len()
+            + self.set.len()
+            + self.bloom.len()
+            + self.ratelimit.len()
+            + self.counter.len()
+            + self.list.len()
+            + self.zset.len()
+            + self.stream.len()
+    }
+
+    // Empties every keyspace, and drops the delayed jobs. Each shard is swapped for an empty
+    // one under its lock, so the old contents can be freed afterward, in a background thread
+    // when asynchronous.
+    pub fn flush(&self, asynchronous: bool) {
+        let contents = (
+            take_shards(&self.string),
+            take_shards(&self.hmap),
+            take_shards(&self.set),
+            take_shards(&self.bloom),
+            take_shards(&self.ratelimit),
+            take_shards(&self.counter),
+            take_shards(&self.list),
+            take_shards(&self.zset),
+            take_shards(&self.stream),
+            take_shards(&self.expire),
+            take_shards(&self.access),
+            take_shards(&self.hash_field_expire),
+            self.expire_index.as_ref().map(ExpireIndex::take),
+        );
+        self.delayed.clear();
+        if asynchronous {
+            thread::spawn(move || drop(contents));
+        }
+    }
+
+    // Picks a key uniformly at random, without collecting the whole keyspace: only the shard
+    // sizes are read, then the chosen shard is walked up to the chosen key.
+    pub fn random_key(&self) -> Option<String> {
+        // Expired keys are skipped, giving up after a few attempts like Redis does.
+        for _ in 0..RANDOM_KEY_MAX_ATTEMPTS {
+            let shards: Vec<(usize, usize, usize)> = [
+                shard_lens(&self.string),
+                shard_lens(&self.hmap),
+                shard_lens(&self.set),
+                shard_lens(&self.bloom),
+                shard_lens(&self.ratelimit),
+                shard_lens(&self.counter),
+                shard_lens(&self.list),
+                shard_lens(&self.zset),
+                shard_lens(&self.stream),
+            ]
+            .into_iter()
+            .enumerate()
+            .flat_map(|(keyspace, lens)| {
+                lens.into_iter()
+                    .enumerate()
+                    .filter(|(_, len)| *len > 0)
+                    .map(move |(shard, len)| (keyspace, shard, len))
+            })
+            .collect();
+            let total: usize = shards.iter().map(|(_, _, len)| len).sum();
+            if total == 0 {
+                return None;
+            }
+
+            let mut n = rand::rng().random_range(0..total);
+            let mut chosen = None;
+            for (keyspace, shard, len) in shards {
+                if n < len {
+                    chosen = Some((keyspace, shard));
+                    break;
                 }
+                n -= len;
+            }
+            let (keyspace, shard) = chosen?;
+            // The shard may have shrunk in the meantime, which is handled like an expired key.
+            let key = match keyspace {
+                0 => nth_shard_key(&self.string, shard, n),
+                1 => nth_shard_key(&self.hmap, shard, n),
+                2 => nth_shard_key(&self.set, shard, n),
+                3 => nth_shard_key(&self.bloom, shard, n),
+                4 => nth_shard_key(&self.ratelimit, shard, n),
+                5 => nth_shard_key(&self.counter, shard, n),
+                6 => nth_shard_key(&self.list, shard, n),
+                7 => nth_shard_key(&self.zset, shard, n),
+                _ => nth_shard_key(&self.stream, shard, n),
+            };
+            if let Some(key) = key.filter(|key| !self.is_expired(key)) {
+                return Some(key);
+            }
+        }
+        None
+    }
+
+    // The internal encoding name reported by OBJECT ENCODING.
+    pub fn object_encoding(&self, key: &str) -> Option<&'static str> {
+        self.expire_if_needed(key);
+        if let Some(value) = self.string.get(key) {
+            Some(value.encoding())
+        } else if self.hmap.contains_key(key) || self.set.contains_key(key) {
+            Some("hashtable")
+        } else if self.bloom.contains_key(key) || self.ratelimit.contains_key(key) {
+            Some("raw")
+        } else if self.counter.contains_key(key) {
+            Some("striped")
+        } else if self.list.contains_key(key) {
+            Some("quicklist")
+        } else if self.zset.contains_key(key) {
+            Some("skiplist")
+        } else if self.stream.contains_key(key) {
+            Some("stream")
+        } else {
+            None
+        }
+    }
+
+    // Seconds since the key was last read or written, as reported by OBJECT IDLETIME.
+    pub fn object_idle_time(&self, key: &str) -> Option<u64> {
+        self.object_access(key)
+            .map(|(access, now)| access.idle_ms(now) / 1000)
+    }
+
+    // The logarithmic access frequency reported by OBJECT FREQ.
+    pub fn object_freq(&self, key: &str) -> Option<u8> {
+        self.object_access(key)
+            .map(|(access, now)| access.freq(now))
+    }
+
+    // Reading the access data doesn't count as an access. Rate limits and counters that have
+    // only been incremented have none, and are reported as just created.
+    fn object_access(&self, key: &str) -> Option<(Access, u64)> {
+        if !self.exists(key) {
+            return None;
+        }
+        let now = now_ms();
+        let access = self.access.get(key).map(|access| *access);
+        Some((access.unwrap_or_else(|| Access::new(now)), now))
+    }
+
+    pub fn string_get(&self, key: &str) -> Option<Vec<u8>> {
+        self.expire_if_needed(key);
+        self.string.get(key).map(|v| {
+            self.record_access(key);
+            v.as_bytes().into_owned()
+        })
+    }
+
+    // Like SET without options, this discards any previous time to live.
+    pub fn string_set(&self, key: String, value: Vec<u8>) {
+        self.string_set_with(key, value, SetCondition::Always, Expiry::Persist);
+    }
+
+    // Writes the value if the condition holds, and updates the time to live accordingly.
+    // The condition holds against keys of any type, and a value of another type is replaced.
+    // Returns whether the value was written, along with the previous value if it was a string.
+    // Checking and clearing the other keyspaces is not atomic with the write.
+    pub fn string_set_with(
+        &self,
+        key: String,
+        value: Vec<u8>,
+        condition: SetCondition,
+        expiry: Expiry,
+    ) -> (bool, Option<Vec<u8>>) {
+        let exists = self.exists(&key);
+        match (exists, condition) {
+            (true, SetCondition::IfAbsent) => return (false, self.string_get(&key)),
+            (false, SetCondition::IfExists) => return (false, None),
+            _ => {}
+        }
+        // A replaced value takes its access data and field expiries along. The time to live is
+        // left alone, it is handled along with the string's below.
+        if exists {
+            // Not short-circuiting, so the key is removed from all of them.
+            let replaced = self.hmap.remove(&key).is_some()
+                | self.set.remove(&key).is_some()
+                | self.bloom.remove(&key).is_some()
+                | self.ratelimit.remove(&key).is_some()
+                | self.counter.remove(&key).is_some()
+                | self.list.remove(&key).is_some()
+                | self.zset.remove(&key).is_some()
+                | self.stream.remove(&key).is_some();
+            if replaced {
+                self.access.remove(&key);
+                self.hash_field_expire.remove(&key);
+            }
+        }
+
+        // The expiry table is only touched while holding the string entry, so the value
+        // and its time to live are updated together.
+        let entry = self.string.entry(key);
+        match expiry {
+            Expiry::Persist => {
+                self.expire.remove(entry.key());
+            }
+            Expiry::At(at) => {
+                self.set_expire(entry.key().clone(), at);
+            }
+            Expiry::Keep => {}
+        }
+        self.record_access(entry.key());
+        let value = StringValue::new(value);
+        let old = match entry {
+            Entry::Occupied(mut e) => Some(e.insert(value).into_bytes()),
+            Entry::Vacant(e) => {
+                e.insert(value);
+                None
             }
-            None => 0,
         };
-        RespFrame::Integer(ret)
+        (true, old)
     }
 
-    pub fn set_members(&self, key: &str) -> Option<DashSet<String>> {
-        self.set.get(key).map(|v| v.clone())
+    // Returns false if the key already exists, whatever its type. Checking the other keyspaces
+    // is not atomic with the write.
+    pub fn string_set_if_absent(&self, key: String, value: Vec<u8>) -> bool {
+        if self.exists(&key) {
+            return false;
+        }
+        match self.string.entry(key) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                self.record_access(entry.key());
+                entry.insert(StringValue::new(value));
+                true
+            }
+        }
+    }
+
+    // Sets all the pairs only if none of the keys exist, whatever their type, as a single
+    // atomic operation. Only checking the other keyspaces beforehand is not atomic.
+    pub fn string_multi_set_if_absent(&self, pairs: Vec<(String, Vec<u8>)>) -> bool {
+        // Before locking the strings, which `exists` also reads.
+        if pairs.iter().any(|(key, _)| self.exists(key)) {
+            return false;
+        }
+        let mut lock = KeyLock::new(&self.string, pairs.iter().map(|(k, _)| k.as_str()));
+        if pairs.iter().any(|(k, _)| lock.contains_key(k)) {
+            return false;
+        }
+        for (key, value) in pairs {
+            self.record_access(&key);
+            lock.insert(key, StringValue::new(value));
+        }
+        true
+    }
+
+    // Atomic read-modify-write of a string: `f` runs under the entry lock with the current
+    // value, None if the key is missing, and returns the new one, None to delete the key.
+    // The time to live is kept unless the key is deleted. `f` must not access the backend,
+    // which could deadlock on the entry lock. `f` gets a copy of the value, so the stored one
+    // is kept if it panics. Large values are better modified in place with `update_bytes`.
+    pub fn update<K, F>(&self, key: K, f: F)
+    where
+        K: Into<String>,
+        F: FnOnce(Option<StringValue>) -> Option<StringValue>,
+    {
+        let key = key.into();
+        self.expire_if_needed(&key);
+        match self.string.entry(key) {
+            Entry::Occupied(mut entry) => {
+                let current = entry.get().clone();
+                match f(Some(current)) {
+                    Some(value) => {
+                        self.record_access(entry.key());
+                        *entry.get_mut() = value;
+                    }
+                    None => {
+                        self.expire.remove(entry.key());
+                        self.access.remove(entry.key());
+                        entry.remove();
+                    }
+                }
+            }
+            Entry::Vacant(entry) => {
+                if let Some(value) = f(None) {
+                    self.record_access(entry.key());
+                    entry.insert(value);
+                }
+            }
+        }
+    }
+
+    // Writes to the bytes of a string in place under the entry lock, the string being created
+    // empty if the key is missing. Unlike with `update` nothing is copied, so a panic in `f`
+    // may leave the bytes partially written, but never loses them. The time to live is kept.
+    fn update_bytes<R>(&self, key: String, f: impl FnOnce(&mut Vec<u8>) -> R) -> R {
+        self.expire_if_needed(&key);
+        match self.string.entry(key) {
+            Entry::Occupied(mut entry) => {
+                self.record_access(entry.key());
+                f(entry.get_mut().make_raw())
+            }
+            Entry::Vacant(entry) => {
+                let mut value = StringValue::default();
+                let ret = f(value.make_raw());
+                self.record_access(entry.key());
+                entry.insert(value);
+                ret
+            }
+        }
+    }
+
+    // Missing keys are treated as 0, the new value is stored with the int encoding.
+    // `update` copies the current value, which is cheap for the numbers this accepts, but a
+    // long string is copied in full just to be rejected.
+    pub fn string_incr_by(&self, key: String, delta: i64) -> Result<i64, BackendError> {
+        let mut ret = Err(BackendError::NotInteger);
+        self.update(key, |value| {
+            let value = value.unwrap_or(StringValue::Int(0));
+            let current = match &value {
+                StringValue::Int(n) => Ok(*n),
+                StringValue::Raw(data) => parse_integer(data),
+            };
+            ret = current.and_then(|n| n.checked_add(delta).ok_or(BackendError::Overflow));
+            match ret {
+                Ok(n) => Some(StringValue::Int(n)),
+                Err(_) => Some(value),
+            }
+        });
+        ret
+    }
+
+    // The result is stored and returned formatted like Redis does. Like with `string_incr_by`,
+    // a long string is copied in full by `update` just to be rejected.
+    pub fn string_incr_by_float(&self, key: String, delta: f64) -> Result<String, BackendError> {
+        let mut ret = Err(BackendError::NotFloat);
+        self.update(key, |value| {
+            let value = value.unwrap_or(StringValue::Int(0));
+            ret = parse_float(&value.as_bytes()).and_then(|n| {
+                Some(n + delta)
+                    .filter(|n| n.is_finite())
+                    .map(format_float)
+                    .ok_or(BackendError::NanOrInfinity)
+            });
+            match &ret {
+                Ok(n) => Some(StringValue::new(n.clone().into_bytes())),
+                Err(_) => Some(value),
+            }
+        });
+        ret
+    }
+
+    // Appends in place to the stored buffer, returns the new length.
+    pub fn string_append(&self, key: String, value: &[u8]) -> usize {
+        self.update_bytes(key, |buf| {
+            buf.extend_from_slice(value);
+            buf.len()
+        })
+    }
+
+    pub fn string_len(&self, key: &str) -> usize {
+        self.expire_if_needed(key);
+        self.string
+            .get(key)
+            .map(|v| {
+                self.record_access(key);
+                v.len()
+            })
+            .unwrap_or(0)
+    }
+
+    // Both ends are inclusive, negative indexes count from the end of the string.
+    pub fn string_get_range(&self, key: &str, start: i64, end: i64) -> Vec<u8> {
+        self.expire_if_needed(key);
+        let value = match self.string.get(key) {
+            Some(value) => value,
+            None => return Vec::new(),
+        };
+        self.record_access(key);
+        let data = value.as_bytes();
+        // Unlike lists, a negative end before the first byte is clamped to the first byte.
+        let end = end.max(-(data.len() as i64));
+        match normalize_range(start, end, data.len()) {
+            Some((start, end)) => data[start..=end].to_vec(),
+            None => Vec::new(),
+        }
+    }
+
+    // Length in characters rather than bytes.
+    #[cfg(feature = "str-ext")]
+    pub fn string_char_len(&self, key: &str) -> Result<usize, BackendError> {
+        self.expire_if_needed(key);
+        match self.string.get(key) {
+            Some(value) => {
+                self.record_access(key);
+                Ok(as_utf8(&value.as_bytes())?.chars().count())
+            }
+            None => Ok(0),
+        }
+    }
+
+    // Same as string_get_range, with the indexes counted in characters rather than bytes.
+    #[cfg(feature = "str-ext")]
+    pub fn string_get_char_range(
+        &self,
+        key: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<String, BackendError> {
+        self.expire_if_needed(key);
+        let value = match self.string.get(key) {
+            Some(value) => value,
+            None => return Ok(String::new()),
+        };
+        self.record_access(key);
+        let data = value.as_bytes();
+        let chars: Vec<char> = as_utf8(&data)?.chars().collect();
+        let end = end.max(-(chars.len() as i64));
+        match normalize_range(start, end, chars.len()) {
+            Some((start, end)) => Ok(chars[start..=end].iter().collect()),
+            None => Ok(String::new()),
+        }
+    }
+
+    // Overwrites the string at the offset, zero-padding it if it is shorter than the offset.
+    // Returns the length of the string after modification.
+    pub fn string_set_range(
+        &self,
+        key: String,
+        offset: usize,
+        value: &[u8],
+    ) -> Result<usize, BackendError> {
+        let end = offset
+            .checked_add(value.len())
+            .filter(|end| *end <= STRING_MAX_LEN)
+            .ok_or(BackendError::StringTooLong)?;
+
+        // An empty value never creates the key.
+        if value.is_empty() {
+            return Ok(self.string_len(&key));
+        }
+
+        Ok(self.update_bytes(key, |buf| {
+            if buf.len() < end {
+                buf.resize(end, 0);
+            }
+            buf[offset..end].copy_from_slice(value);
+            buf.len()
+        }))
+    }
+
+    // Bits are numbered from the most significant bit of the first byte, like Redis does.
+    // Offsets past the end of the string read as 0.
+    pub fn string_get_bit(&self, key: &str, offset: usize) -> u8 {
+        self.expire_if_needed(key);
+        let value = match self.string.get(key) {
+            Some(value) => value,
+            None => return 0,
+        };
+        self.record_access(key);
+        match value.as_bytes().get(offset / 8) {
+            Some(byte) => (byte >> (7 - offset % 8)) & 1,
+            None => 0,
+        }
+    }
+
+    // Zero-pads the string up to the byte holding the bit, returns the previous bit.
+    pub fn string_set_bit(
+        &self,
+        key: String,
+        offset: usize,
+        bit: bool,
+    ) -> Result<u8, BackendError> {
+        let index = offset / 8;
+        if index >= STRING_MAX_LEN {
+            return Err(BackendError::StringTooLong);
+        }
+
+        let mask = 1 << (7 - offset % 8);
+        Ok(self.update_bytes(key, |buf| {
+            if buf.len() <= index {
+                buf.resize(index + 1, 0);
+            }
+            let old = (buf[index] & mask != 0) as u8;
+            if bit {
+                buf[index] |= mask;
+            } else {
+                buf[index] &= !mask;
+            }
+            old
+        }))
+    }
+
+    // Removes expired keys that may never be accessed again, like Redis's active expire cycle:
+    // keys with a time to live are sampled at random, and another round starts as long as more
+    // than a quarter of the sample had expired. At most `effort` keys are checked in total.
+    // With the expiration index, the due keys are taken from it in order instead, so all of
+    // them are found however few they are among the keys with a time to live. Returns the
+    // number of keys removed.
+    pub fn active_expire(&self, effort: usize) -> usize {
+        if let Some(index) = &self.expire_index {
+            return self.active_expire_indexed(index, effort);
+        }
+        self.expire_cycle(&self.expire, effort, |key| {
+            self.expire_key(key);
+        })
+    }
+
+    fn active_expire_indexed(&self, index: &ExpireIndex, effort: usize) -> usize {
+        let mut removed = 0;
+        for (at, key) in index.pop_due(now_ms(), effort) {
+            // Stale entries, for keys since deleted or given another time to live, are dropped.
+            if self.expire.get(&key).is_some_and(|current| *current == at) {
+                self.expire_key(&key);
+                removed += 1;
+            }
+        }
+        if index.len() > self.expire.len() * 2 + EXPIRE_INDEX_SLACK {
+            index.rebuild(|| {
+                self.expire
+                    .iter()
+                    .map(|entry| (*entry.value(), entry.key().clone()))
+                    .collect()
+            });
+        }
+        removed
+    }
+
+    // Like `expire_if_needed`, also dropping the expiration time if no keyspace held the key
+    // anymore.
+    fn expire_key(&self, key: &str) -> bool {
+        let removed = self.expire_if_needed(key);
+        self.expire.remove_if(key, |_, at| *at <= now_ms());
+        removed
+    }
+
+    // The same for the fields of hashes, returns the number of hashes that had expired fields.
+    pub fn active_expire_fields(&self, effort: usize) -> usize {
+        self.expire_cycle(&self.hash_field_expire, effort, |key| {
+            self.expire_hash_fields(key)
+        })
+    }
+
+    // Samples keys of the expiration times map, and calls `expire` on the ones that are due.
+    fn expire_cycle<F>(
+        &self,
+        expire_times: &DashMap<String, u64>,
+        effort: usize,
+        expire: F,
+    ) -> usize
+    where
+        F: Fn(&str),
+    {
+        let (mut checked, mut removed) = (0, 0);
+        while checked < effort {
+            let sample = ACTIVE_EXPIRE_SAMPLE.min(effort - checked);
+            let mut expired = 0;
+            for _ in 0..sample {
+                let key = match random_map_key(expire_times) {
+                    Some(key) => key,
+                    None if expire_times.is_empty() => return removed,
+                    // The chosen shard shrank in the meantime.
+                    None => continue,
+                };
+                if expire_times.get(&key).is_some_and(|at| *at <= now_ms()) {
+                    expire(&key);
+                    expired += 1;
+                }
+            }
+            checked += sample;
+            removed += expired;
+            if expired * 4 <= sample {
+                break;
+            }
+        }
+        removed
+    }
+
+    // Sets the time to live of the keys matching the pattern, as EXPIRE with the condition
+    // would, for operators retrofitting them onto keys written without. The keys are walked
+    // with SCAN on a background thread, `BULK_TTL_BATCH` at a time, and each one expires
+    // `seconds` after it was visited. The handle returns the number of keys updated.
+    pub fn bulk_set_ttl(
+        &self,
+        pattern: String,
+        seconds: u64,
+        condition: ExpireCondition,
+    ) -> JoinHandle<usize> {
+        let backend = self.clone();
+        bulk_ttl_job("DEBUG SET-TTL", move |cursor| {
+            let (next, keys) = backend.scan(cursor, Some(pattern.as_bytes()), BULK_TTL_BATCH, None);
+            let at = now_ms().saturating_add(seconds.saturating_mul(1000));
+            let updated = keys
+                .iter()
+                .filter(|key| backend.expire_at(key, at, condition))
+                .count();
+            (next, updated)
+        })
+    }
+
+    // Removes the expired keys matching the pattern on a background thread, like a run of active
+    // expiration restricted to them. SCAN skips expired keys, so the expiration times are walked
+    // instead, a shard at a time. The handle returns the number of keys removed.
+    pub fn bulk_expire_scan(&self, pattern: String) -> JoinHandle<usize> {
+        let backend = self.clone();
+        bulk_ttl_job("DEBUG EXPIRE-SCAN", move |cursor| {
+            let shards = backend.expire.shards();
+            let now = now_ms();
+            let keys: Vec<String> = shards[cursor as usize]
+                .read()
+                .iter()
+                .filter(|(key, at)| {
+                    *at.get() <= now && glob_match(pattern.as_bytes(), key.as_bytes())
+                })
+                .map(|(key, _)| key.clone())
+                .collect();
+            let removed = keys.iter().filter(|key| backend.expire_key(key)).count();
+            let next = (cursor as usize + 1) % shards.len();
+            (next as u64, removed)
+        })
+    }
+
+    // Removes the key if its time to live is over, so the caller sees it as missing. Returns
+    // whether it was.
+    fn expire_if_needed(&self, key: &str) -> bool {
+        if !self.is_expired(key) {
+            return false;
+        }
+        let expired = self.expire_if_needed_in(&self.string, key)
+            | self.expire_if_needed_in(&self.hmap, key)
+            | self.expire_if_needed_in(&self.set, key)
+            | self.expire_if_needed_in(&self.bloom, key)
+            | self.expire_if_needed_in(&self.ratelimit, key)
+            | self.expire_if_needed_in(&self.counter, key)
+            | self.expire_if_needed_in(&self.list, key)
+            | self.expire_if_needed_in(&self.zset, key)
+            | self.expire_if_needed_in(&self.stream, key);
+        if expired {
+            self.access.remove(key);
+        }
+        expired
+    }
+
+    fn expire_if_needed_in<V>(&self, map: &DashMap<String, V>, key: &str) -> bool {
+        // Check again under the entry lock, the key may have been set meanwhile.
+        map.remove_if(key, |key, _| {
+            self.expire
+                .remove_if(key, |_, at| *at <= now_ms())
+                .is_some()
+        })
+        .is_some()
+    }
+
+    fn is_expired(&self, key: &str) -> bool {
+        self.expire.get(key).is_some_and(|at| *at <= now_ms())
+    }
+
+    // Like `expire_if_needed`, also removing the expired fields if the key holds a hash.
+    fn expire_hash_if_needed(&self, key: &str) {
+        self.expire_if_needed(key);
+        if self
+            .hash_field_expire
+            .get(key)
+            .is_some_and(|at| *at <= now_ms())
+        {
+            self.expire_hash_fields(key);
+        }
+    }
+
+    // Removes the expired fields of the hash, and the hash itself if none is left.
+    fn expire_hash_fields(&self, key: &str) {
+        let now = now_ms();
+        let mut found = false;
+        self.hmap.remove_if_mut(key, |key, hmap| {
+            found = true;
+            hmap.expire_fields(now);
+            self.hash_fields_changed(key, hmap)
+        });
+        // Drop the entry left behind by a removed hash. One set by a hash created meanwhile
+        // is in the future.
+        if !found {
+            self.hash_field_expire.remove_if(key, |_, at| *at <= now);
+        }
+    }
+
+    // Brings the hash's entry of hash_field_expire up to date after fields were removed or
+    // their time to live changed, and drops the key's metadata if no field is left. Must be
+    // called while holding the hash's entry, returns whether the hash is empty.
+    fn hash_fields_changed(&self, key: &str, hmap: &Hash) -> bool {
+        match hmap.next_expire() {
+            Some(at) => {
+                self.hash_field_expire.insert(key.to_string(), at);
+            }
+            None => {
+                self.hash_field_expire.remove(key);
+            }
+        }
+        let empty = hmap.is_empty();
+        if empty {
+            self.expire.remove(key);
+            self.access.remove(key);
+        }
+        empty
+    }
+
+    pub fn hash_get(&self, key: &str, field: &str) -> Option<RespFrame> {
+        self.expire_hash_if_needed(key);
+        let hmap = self.hmap.get(key)?;
+        self.record_access(key);
+        hmap.fields().get(field).map(|v| v.value().clone())
+    }
+
+    // Returns true if the field is new, false if its value was updated.
+    pub fn hash_set(&self, key: String, field: String, value: RespFrame) -> bool {
+        self.expire_hash_if_needed(&key);
+        let mut hmap = self.hmap.entry(key).or_default();
+        self.record_access(hmap.key());
+        hmap.insert(field, value)
+    }
+
+    // Sets all the fields at once, returns how many of them are new.
+    pub fn hash_set_multi(&self, key: String, fields: Vec<(String, RespFrame)>) -> usize {
+        self.expire_hash_if_needed(&key);
+        let mut hmap = self.hmap.entry(key).or_default();
+        self.record_access(hmap.key());
+        let mut added = 0;
+        for (field, value) in fields {
+            if hmap.insert(field, value) {
+                added += 1;
+            }
+        }
+        added
+    }
+
+    pub fn hash_exists(&self, key: &str, field: &str) -> bool {
+        self.expire_hash_if_needed(key);
+        self.hmap.get(key).is_some_and(|hmap| {
+            self.record_access(key);
+            hmap.fields().contains_key(field)
+        })
+    }
+
+    // The length of the field's value as a string, 0 if the key or field does not exist.
+    pub fn hash_str_len(&self, key: &str, field: &str) -> usize {
+        self.expire_hash_if_needed(key);
+        self.hmap.get(key).map_or(0, |hmap| {
+            self.record_access(key);
+            match hmap.fields().get(field).as_deref() {
+                Some(RespFrame::BulkString(BulkString::Normal(value))) => value.len(),
+                Some(RespFrame::Integer(n)) => n.to_string().len(),
+                _ => 0,
+            }
+        })
+    }
+
+    // 0 if the key does not exist.
+    pub fn hash_len(&self, key: &str) -> usize {
+        self.expire_hash_if_needed(key);
+        self.hmap.get(key).map_or(0, |hmap| {
+            self.record_access(key);
+            hmap.len()
+        })
+    }
+
+    // Returns the number of fields removed. The hash is removed along with its last field.
+    pub fn hash_delete(&self, key: &str, fields: &[String]) -> usize {
+        self.expire_hash_if_needed(key);
+        let mut removed = 0;
+        self.hmap.remove_if_mut(key, |key, hmap| {
+            removed = fields.iter().filter(|field| hmap.remove(field)).count();
+            let empty = self.hash_fields_changed(key, hmap);
+            if !empty {
+                self.record_access(key);
+            }
+            empty
+        });
+        removed
+    }
+
+    // Sets the unix time in milliseconds at which each field expires if the condition holds,
+    // like `expire_at` does for keys. A time in the past deletes the fields right away, and
+    // the hash along with its last field.
+    pub fn hash_expire_at(
+        &self,
+        key: &str,
+        fields: &[String],
+        at: u64,
+        condition: ExpireCondition,
+    ) -> Vec<FieldExpireResult> {
+        self.expire_hash_if_needed(key);
+        let past = at <= now_ms();
+        let mut results = vec![FieldExpireResult::NoSuchField; fields.len()];
+        self.hmap.remove_if_mut(key, |key, hmap| {
+            for (field, result) in fields.iter().zip(results.iter_mut()) {
+                if !hmap.fields().contains_key(field) {
+                    continue;
+                }
+                *result = if !condition.holds(hmap.expire_time(field), at) {
+                    FieldExpireResult::ConditionNotMet
+                } else if past {
+                    hmap.remove(field);
+                    FieldExpireResult::Deleted
+                } else {
+                    hmap.set_expire_time(field, at);
+                    FieldExpireResult::Set
+                };
+            }
+            let empty = self.hash_fields_changed(key, hmap);
+            if !empty {
+                self.record_access(key);
+            }
+            empty
+        });
+        results
+    }
+
+    // The unix time in milliseconds at which each field expires: None if the field does not
+    // exist, Some(None) if it has no time to live.
+    pub fn hash_expire_time(&self, key: &str, fields: &[String]) -> Vec<Option<Option<u64>>> {
+        self.expire_hash_if_needed(key);
+        let Some(hmap) = self.hmap.get(key) else {
+            return vec![None; fields.len()];
+        };
+        self.record_access(key);
+        fields
+            .iter()
+            .map(|field| {
+                hmap.fields()
+                    .contains_key(field)
+                    .then(|| hmap.expire_time(field))
+            })
+            .collect()
+    }
+
+    // Removes the time to live of each field: None if the field does not exist, false if it
+    // has none. The hash's entry of hash_field_expire may only get earlier than the actual
+    // one, so it is left as is.
+    pub fn hash_persist(&self, key: &str, fields: &[String]) -> Vec<Option<bool>> {
+        self.expire_hash_if_needed(key);
+        let Some(mut hmap) = self.hmap.get_mut(key) else {
+            return vec![None; fields.len()];
+        };
+        self.record_access(key);
+        fields
+            .iter()
+            .map(|field| {
+                hmap.fields()
+                    .contains_key(field)
+                    .then(|| hmap.persist(field))
+            })
+            .collect()
+    }
+
+    // Atomic read-modify-write of a hash field, like `update` for strings: `f` gets the
+    // current value, None if the field is missing, and returns the new one along with the
+    // result. Nothing is written if it fails, so a missing hash isn't created either.
+    fn hash_update_field<T, F>(&self, key: String, field: String, f: F) -> Result<T, BackendError>
+    where
+        F: FnOnce(Option<&RespFrame>) -> Result<(RespFrame, T), BackendError>,
+    {
+        self.expire_hash_if_needed(&key);
+        match self.hmap.entry(key) {
+            Entry::Occupied(mut entry) => {
+                // The field's shard lock must be released before inserting into it.
+                let (value, ret) = f(entry.get().fields().get(&field).as_deref())?;
+                self.record_access(entry.key());
+                entry.get_mut().update(field, value);
+                Ok(ret)
+            }
+            Entry::Vacant(entry) => {
+                let (value, ret) = f(None)?;
+                self.record_access(entry.key());
+                entry.insert(Hash::from_iter([(field, value)]));
+                Ok(ret)
+            }
+        }
+    }
+
+    // Missing fields are treated as 0. Fields are set by HSET as bulk strings, but integer
+    // frames are accepted too.
+    pub fn hash_incr_by(
+        &self,
+        key: String,
+        field: String,
+        delta: i64,
+    ) -> Result<i64, BackendError> {
+        self.hash_update_field(key, field, |value| {
+            let current = match value {
+                None => 0,
+                Some(RespFrame::Integer(n)) => *n,
+                Some(RespFrame::BulkString(BulkString::Normal(data))) => {
+                    parse_integer(data).map_err(|_| BackendError::HashNotInteger)?
+                }
+                Some(_) => return Err(BackendError::HashNotInteger),
+            };
+            let n = current.checked_add(delta).ok_or(BackendError::Overflow)?;
+            Ok((BulkString::new(n.to_string()).into(), n))
+        })
+    }
+
+    // The result is stored and returned formatted like INCRBYFLOAT.
+    pub fn hash_incr_by_float(
+        &self,
+        key: String,
+        field: String,
+        delta: f64,
+    ) -> Result<String, BackendError> {
+        self.hash_update_field(key, field, |value| {
+            let current = match value {
+                None => 0.0,
+                Some(RespFrame::Integer(n)) => *n as f64,
+                Some(RespFrame::BulkString(BulkString::Normal(data))) => {
+                    parse_float(data).map_err(|_| BackendError::HashNotFloat)?
+                }
+                Some(_) => return Err(BackendError::HashNotFloat),
+            };
+            let n = Some(current + delta)
+                .filter(|n| n.is_finite())
+                .ok_or(BackendError::NanOrInfinity)?;
+            let n = format_float(n);
+            Ok((BulkString::new(n.clone()).into(), n))
+        })
+    }
+
+    // Like `scan` over the shards of the hash, returns the fields matching the pattern along
+    // with their values. Only the visited shards are copied, not the whole hash.
+    pub fn hash_scan(
+        &self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+    ) -> (u64, Vec<(String, RespFrame)>) {
+        self.expire_hash_if_needed(key);
+        let Some(hmap) = self.hmap.get(key) else {
+            return (0, Vec::new());
+        };
+        self.record_access(key);
+
+        let shards = hmap.fields().shards();
+        let mut fields = Vec::new();
+        let mut visited = 0;
+        let mut next = cursor as usize;
+        while next < shards.len() && visited < count {
+            let shard = shards[next].read();
+            fields.extend(
+                shard
+                    .iter()
+                    .filter(|(field, _)| pattern.is_none_or(|p| glob_match(p, field.as_bytes())))
+                    .map(|(field, value)| (field.clone(), value.get().clone())),
+            );
+            visited += shard.len();
+            next += 1;
+        }
+
+        let next = if next >= shards.len() { 0 } else { next as u64 };
+        (next, fields)
+    }
+
+    // Fields picked at random along with their values, see `random_sample`. Empty if the key
+    // does not exist.
+    pub fn hash_random_fields(
+        &self,
+        key: &str,
+        count: usize,
+        repeat: bool,
+    ) -> Vec<(String, RespFrame)> {
+        self.expire_hash_if_needed(key);
+        let Some(hmap) = self.hmap.get(key) else {
+            return Vec::new();
+        };
+        self.record_access(key);
+        random_sample(hmap.fields().iter(), hmap.len(), count, repeat, |entry| {
+            (entry.key().clone(), entry.value().clone())
+        })
+    }
+
+    pub fn hash_get_all(&self, key: &str) -> Option<DashMap<String, RespFrame>> {
+        self.expire_hash_if_needed(key);
+        self.hmap.get(key).map(|v| {
+            self.record_access(key);
+            v.fields().clone()
+        })
+    }
+
+    pub fn hash_multi_get(&self, key: &str, fields: Vec<String>) -> RespFrame {
+        self.expire_hash_if_needed(key);
+        let mut array = Vec::new();
+        match self.hmap.get(key) {
+            Some(hmap) => {
+                self.record_access(key);
+                for field in fields {
+                    if let Some(value) = hmap.fields().get(&field) {
+                        array.push(value.value().clone());
+                    } else {
+                        array.push(RespFrame::Null(RespNull));
+                    }
+                }
+            }
+            None => {
+                for _ in fields {
+                    array.push(RespFrame::Null(RespNull));
+                }
+            }
+        }
+        RespFrame::Array(RespArray::new(array))
+    }
+
+    pub fn set_add(&self, key: String, members: Vec<String>) -> RespFrame {
+        self.expire_if_needed(&key);
+        let mut count = 0;
+        let set = self.set.entry(key).or_default();
+        self.record_access(set.key());
+        for member in members {
+            if set.insert(member) {
+                count += 1;
+            }
+        }
+        RespFrame::Integer(count)
+    }
+
+    pub fn set_is_member(&self, key: &str, member: &str) -> RespFrame {
+        self.expire_if_needed(key);
+        let ret = match self.set.get(key) {
+            Some(set) => {
+                self.record_access(key);
+                set.contains(member) as i64
+            }
+            None => 0,
+        };
+        RespFrame::Integer(ret)
+    }
+
+    // Combines the sets at the keys, missing keys counting as empty sets. The sets are locked
+    // together, so the result reflects them at a single point in time.
+    pub fn set_combine(&self, op: SetOperation, keys: &[String]) -> Vec<String> {
+        for key in keys {
+            self.expire_if_needed(key);
+        }
+        let lock = KeyLock::new(&self.set, keys.iter().map(String::as_str));
+        let sets: Vec<_> = keys.iter().map(|key| lock.get(key)).collect();
+        for key in keys {
+            if lock.contains_key(key) {
+                self.record_access(key);
+            }
+        }
+        combine_sets(op, &sets).into_iter().collect()
+    }
+
+    // Like `set_combine`, storing the result at dst in place of its value, whatever its type,
+    // and without a time to live. An empty result deletes dst. Returns the number of members
+    // stored. Storing the result is atomic, clearing dst from the other keyspaces is not.
+    pub fn set_combine_store(&self, op: SetOperation, dst: &str, keys: &[String]) -> usize {
+        self.expire_if_needed(dst);
+        for key in keys {
+            self.expire_if_needed(key);
+        }
+        self.delete_in(&self.string, dst);
+        self.delete_in(&self.hmap, dst);
+        self.delete_in(&self.bloom, dst);
+        self.delete_in(&self.ratelimit, dst);
+        self.delete_in(&self.counter, dst);
+        self.delete_in(&self.list, dst);
+        self.delete_in(&self.zset, dst);
+        self.delete_in(&self.stream, dst);
+
+        let all_keys = keys.iter().map(String::as_str).chain([dst]);
+        let mut lock = KeyLock::new(&self.set, all_keys);
+        let sets: Vec<_> = keys.iter().map(|key| lock.get(key)).collect();
+        let result = combine_sets(op, &sets);
+        for key in keys {
+            if lock.contains_key(key) {
+                self.record_access(key);
+            }
+        }
+
+        let len = result.len();
+        self.expire.remove(dst);
+        if len == 0 {
+            lock.remove(dst);
+            self.access.remove(dst);
+        } else {
+            lock.insert(dst.to_string(), result.into_iter().collect());
+            self.record_access(dst);
+        }
+        len
+    }
+
+    // Moves the member from the set at src to the one at dst, creating it if needed. Both sets
+    // are locked together, so the member is never seen in neither or both of them. Returns
+    // false if src does not hold the member. src is deleted along with its last member.
+    pub fn set_move(&self, src: &str, dst: &str, member: &str) -> bool {
+        self.expire_if_needed(src);
+        self.expire_if_needed(dst);
+        let mut lock = KeyLock::new(&self.set, [src, dst]);
+        let Some(set) = lock.get(src) else {
+            return false;
+        };
+        if src == dst {
+            self.record_access(src);
+            return set.contains(member);
+        }
+        let Some(member) = set.remove(member) else {
+            self.record_access(src);
+            return false;
+        };
+        if set.is_empty() {
+            lock.remove(src);
+            self.expire.remove(src);
+            self.access.remove(src);
+        } else {
+            self.record_access(src);
+        }
+
+        match lock.get(dst) {
+            Some(set) => {
+                set.insert(member);
+            }
+            None => {
+                lock.insert(dst.to_string(), DashSet::from_iter([member]));
+            }
+        }
+        self.record_access(dst);
+        true
+    }
+
+    // Members picked at random, see `random_sample`. Empty if the key does not exist.
+    pub fn set_random_members(&self, key: &str, count: usize, repeat: bool) -> Vec<String> {
+        self.expire_if_needed(key);
+        let Some(set) = self.set.get(key) else {
+            return Vec::new();
+        };
+        self.record_access(key);
+        random_sample(set.iter(), set.len(), count, repeat, |member| {
+            member.key().clone()
+        })
+    }
+
+    // Returns how many members were removed. The set is deleted along with its last member.
+    pub fn set_remove(&self, key: &str, members: &[String]) -> usize {
+        self.expire_if_needed(key);
+        let mut removed = 0;
+        self.set.remove_if_mut(key, |key, set| {
+            removed = members
+                .iter()
+                .filter(|member| set.remove(*member).is_some())
+                .count();
+            let empty = set.is_empty();
+            if empty {
+                self.expire.remove(key);
+                self.access.remove(key);
+            } else {
+                self.record_access(key);
+            }
+            empty
+        });
+        removed
+    }
+
+    pub fn set_members(&self, key: &str) -> Option<DashSet<String>> {
+        self.expire_if_needed(key);
+        self.set.get(key).map(|v| {
+            self.record_access(key);
+            v.clone()
+        })
+    }
+
+    // Pushes the elements one after the other at the given end, creating the list if needed,
+    // so elements pushed to the left end up in reverse order. Returns the new length.
+    pub fn list_push(&self, key: String, elements: Vec<Bytes>, end: ListEnd) -> usize {
+        self.expire_if_needed(&key);
+        let mut list = self.list.entry(key).or_default();
+        self.record_access(list.key());
+        for element in elements {
+            match end {
+                ListEnd::Left => list.push_front(element),
+                ListEnd::Right => list.push_back(element),
+            }
+        }
+        self.waiters.wake(list.key());
+        list.len()
+    }
+
+    // None if the key does not exist. The list is deleted along with its last element.
+    pub fn list_pop(&self, key: &str, end: ListEnd) -> Option<Bytes> {
+        self.expire_if_needed(key);
+        let mut element = None;
+        self.list.remove_if_mut(key, |key, list| {
+            element = match end {
+                ListEnd::Left => list.pop_front(),
+                ListEnd::Right => list.pop_back(),
+            };
+            self.list_changed(key, list)
+        });
+        element
+    }
+
+    // Pops up to `count` elements at once, in the order they are popped one by one. None if
+    // the key does not exist. The list is deleted along with its last element.
+    pub fn list_pop_many(&self, key: &str, end: ListEnd, count: usize) -> Option<Vec<Bytes>> {
+        self.expire_if_needed(key);
+        let mut elements = None;
+        self.list.remove_if_mut(key, |key, list| {
+            let count = count.min(list.len());
+            elements = Some(match end {
+                ListEnd::Left => list.drain(..count).collect(),
+                ListEnd::Right => list.drain(list.len() - count..).rev().collect(),
+            });
+            self.list_changed(key, list)
+        });
+        elements
+    }
+
+    // Pops an element from one end of the list at src and pushes it to one end of the list at
+    // dst, creating it if needed. Both lists are locked together, so the element is never seen
+    // in neither or both of them. With src and dst the same, the list is rotated. None if src
+    // does not exist. src is deleted along with its last element.
+    pub fn list_move(&self, src: &str, dst: &str, from: ListEnd, to: ListEnd) -> Option<Bytes> {
+        self.expire_if_needed(src);
+        self.expire_if_needed(dst);
+        let mut lock = KeyLock::new(&self.list, [src, dst]);
+        let list = lock.get_mut(src)?;
+        let element = match from {
+            ListEnd::Left => list.pop_front(),
+            ListEnd::Right => list.pop_back(),
+        }?;
+        if src != dst && self.list_changed(src, list) {
+            lock.remove(src);
+        }
+
+        match lock.get_mut(dst) {
+            Some(list) => match to {
+                ListEnd::Left => list.push_front(element.clone()),
+                ListEnd::Right => list.push_back(element.clone()),
+            },
+            None => {
+                lock.insert(dst.to_string(), VecDeque::from([element.clone()]));
+            }
+        }
+        self.record_access(dst);
+        self.waiters.wake(dst);
+        Some(element)
+    }
+
+    // Stores the elements as a list at dst in place of its value, whatever its type, and
+    // without a time to live. No elements deletes dst. Returns the number of elements stored.
+    // Storing the list is atomic, clearing dst from the other keyspaces is not.
+    pub fn list_store(&self, dst: &str, elements: Vec<Bytes>) -> usize {
+        self.expire_if_needed(dst);
+        self.delete_in(&self.string, dst);
+        self.delete_in(&self.hmap, dst);
+        self.delete_in(&self.set, dst);
+        self.delete_in(&self.bloom, dst);
+        self.delete_in(&self.ratelimit, dst);
+        self.delete_in(&self.counter, dst);
+        self.delete_in(&self.zset, dst);
+        self.delete_in(&self.stream, dst);
+
+        let mut lock = KeyLock::new(&self.list, [dst]);
+        let len = elements.len();
+        self.expire.remove(dst);
+        if len == 0 {
+            lock.remove(dst);
+            self.access.remove(dst);
+        } else {
+            lock.insert(dst.to_string(), elements.into());
+            self.record_access(dst);
+            self.waiters.wake(dst);
+        }
+        len
+    }
+
+    // Inserts the element before or after the first occurrence of the pivot. Returns the new
+    // length, 0 if the key does not exist, or None if the pivot is not in the list.
+    pub fn list_insert(
+        &self,
+        key: &str,
+        before: bool,
+        pivot: &[u8],
+        element: Bytes,
+    ) -> Option<usize> {
+        self.expire_if_needed(key);
+        let Some(mut list) = self.list.get_mut(key) else {
+            return Some(0);
+        };
+        self.record_access(key);
+        let index = list.iter().position(|e| e.as_ref() == pivot)?;
+        list.insert(if before { index } else { index + 1 }, element);
+        Some(list.len())
+    }
+
+    // Removes the first `count` occurrences of the element from the head of the list, or from
+    // its tail if negative, all of them if 0. Returns how many were removed. The list is
+    // deleted along with its last element.
+    pub fn list_remove(&self, key: &str, count: i64, element: &[u8]) -> usize {
+        self.expire_if_needed(key);
+        let limit = match count {
+            0 => usize::MAX,
+            count => count.unsigned_abs().try_into().unwrap_or(usize::MAX),
+        };
+        let mut removed = 0;
+        self.list.remove_if_mut(key, |key, list| {
+            let matches = |i: &usize| list[*i].as_ref() == element;
+            let mut indexes: Vec<usize> = if count < 0 {
+                (0..list.len()).rev().filter(matches).take(limit).collect()
+            } else {
+                (0..list.len()).filter(matches).take(limit).collect()
+            };
+            indexes.sort_unstable();
+            removed = indexes.len();
+
+            let mut i = 0;
+            list.retain(|_| {
+                let keep = indexes.binary_search(&i).is_err();
+                i += 1;
+                keep
+            });
+            self.list_changed(key, list)
+        });
+        removed
+    }
+
+    // Keeps only the elements in the range, like `list_range` selects them. The list is
+    // deleted if none is left.
+    pub fn list_trim(&self, key: &str, start: i64, end: i64) {
+        self.expire_if_needed(key);
+        self.list.remove_if_mut(key, |key, list| {
+            match normalize_range(start, end, list.len()) {
+                Some((start, end)) => {
+                    list.truncate(end + 1);
+                    list.drain(..start);
+                }
+                None => list.clear(),
+            }
+            self.list_changed(key, list)
+        });
+    }
+
+    // Drops the key's metadata if the list is left empty, records an access otherwise. Must
+    // be called while holding the list's entry, returns whether the list is empty.
+    fn list_changed(&self, key: &str, list: &VecDeque<Bytes>) -> bool {
+        let empty = list.is_empty();
+        if empty {
+            self.expire.remove(key);
+            self.access.remove(key);
+        } else {
+            self.record_access(key);
+        }
+        empty
+    }
+
+    // Both ends are inclusive, negative indexes count from the end of the list. Only the
+    // elements in the range are cloned, which doesn't copy their data.
+    pub fn list_range(&self, key: &str, start: i64, end: i64) -> Vec<Bytes> {
+        self.expire_if_needed(key);
+        let Some(list) = self.list.get(key) else {
+            return Vec::new();
+        };
+        self.record_access(key);
+        match normalize_range(start, end, list.len()) {
+            Some((start, end)) => list.range(start..=end).cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // Negative indexes count from the end of the list. None if the key does not exist or the
+    // index is out of range.
+    pub fn list_index(&self, key: &str, index: i64) -> Option<Bytes> {
+        self.expire_if_needed(key);
+        let list = self.list.get(key)?;
+        self.record_access(key);
+        normalize_index(index, list.len()).map(|index| list[index].clone())
+    }
+
+    // Replaces the element at the index, negative indexes counting from the end of the list.
+    pub fn list_set(&self, key: &str, index: i64, element: Bytes) -> Result<(), BackendError> {
+        self.expire_if_needed(key);
+        let mut list = self.list.get_mut(key).ok_or(BackendError::NoSuchKey)?;
+        let index = normalize_index(index, list.len()).ok_or(BackendError::IndexOutOfRange)?;
+        self.record_access(key);
+        list[index] = element;
+        Ok(())
+    }
+
+    // The indexes of the occurrences of the element, counted from the head of the list. The
+    // search starts from the head, or from the tail if `rank` is negative, and skips the first
+    // `rank.abs() - 1` matches. It stops after `count` matches and `max_len` compared elements,
+    // either of them being unlimited if 0.
+    pub fn list_positions(
+        &self,
+        key: &str,
+        element: &[u8],
+        rank: i64,
+        count: usize,
+        max_len: usize,
+    ) -> Vec<usize> {
+        self.expire_if_needed(key);
+        let Some(list) = self.list.get(key) else {
+            return Vec::new();
+        };
+        self.record_access(key);
+        let indexes: Box<dyn Iterator<Item = usize>> = if rank < 0 {
+            Box::new((0..list.len()).rev())
+        } else {
+            Box::new(0..list.len())
+        };
+        let skip = rank
+            .unsigned_abs()
+            .saturating_sub(1)
+            .try_into()
+            .unwrap_or(usize::MAX);
+        let take = if count == 0 { usize::MAX } else { count };
+        let max_len = if max_len == 0 { usize::MAX } else { max_len };
+        indexes
+            .take(max_len)
+            .filter(|i| list[*i].as_ref() == element)
+            .skip(skip)
+            .take(take)
+            .collect()
+    }
+
+    // 0 if the key does not exist.
+    pub fn list_len(&self, key: &str) -> usize {
+        self.expire_if_needed(key);
+        self.list.get(key).map_or(0, |list| {
+            self.record_access(key);
+            list.len()
+        })
+    }
+
+    // Pushes the payload onto the tail of the list once `delay_ms` milliseconds have passed,
+    // so consumers popping from its head get the jobs in the order they are due. Nothing is
+    // moved unless `delay_mover` runs. Returns the number of jobs not due yet.
+    pub fn delay_push(&self, key: String, payload: Bytes, delay_ms: u64) -> usize {
+        self.delayed
+            .push(now_ms().saturating_add(delay_ms), key, payload);
+        self.delayed.len()
+    }
+
+    // Moves the due jobs onto their lists, waking the clients blocked on them. Returns the
+    // number of jobs moved.
+    pub fn delay_move_due(&self) -> usize {
+        let due = self.delayed.pop_due(now_ms());
+        let moved = due.len();
+        for (key, payload) in due {
+            self.list_push(key, vec![payload], ListEnd::Right);
+        }
+        moved
+    }
+
+    // Moves the jobs of DELAY.PUSH onto their lists as they become due, sleeping until the
+    // next one is in between. Never returns, `Server::run` runs it in the background.
+    pub async fn delay_mover(&self) {
+        loop {
+            self.delay_move_due();
+            let next = async {
+                match self.delayed.next_due() {
+                    Some(at) => {
+                        let ms = at.saturating_sub(now_ms());
+                        tokio::time::sleep(Duration::from_millis(ms)).await
+                    }
+                    None => future::pending().await,
+                }
+            };
+            tokio::select! {
+                _ = next => {}
+                _ = self.delayed.earlier() => {}
+            }
+        }
+    }
+
+    // Adds the members or updates their scores, creating the sorted set if needed. Returns
+    // the number of members added. Scores must not be NaN.
+    pub fn zset_add(&self, key: String, members: Vec<(f64, String)>) -> usize {
+        self.zset_add_with(key, members, ScoreCondition::default())
+            .0
+    }
+
+    // Like `zset_add`, only writing the score of the members for which the condition holds.
+    // Returns the number of members added, and of those whose score changed.
+    pub fn zset_add_with(
+        &self,
+        key: String,
+        members: Vec<(f64, String)>,
+        condition: ScoreCondition,
+    ) -> (usize, usize) {
+        self.expire_if_needed(&key);
+        let Some(mut zset) = self.zset_entry(key, condition) else {
+            return (0, 0);
+        };
+        self.record_access(zset.key());
+        let (mut added, mut updated) = (0, 0);
+        for (score, member) in members {
+            let current = zset.score(&member);
+            if !condition.holds(current, score) {
+                continue;
+            }
+            match current {
+                None => added += 1,
+                Some(current) if current != score => updated += 1,
+                Some(_) => {}
+            }
+            zset.insert(member, score);
+        }
+        self.waiters.wake(zset.key());
+        (added, updated)
+    }
+
+    // Adds the delta to the member's score, adding the member with the delta as its score if
+    // needed, and returns the new score. Fails if it would be NaN, from infinities of opposite
+    // signs.
+    pub fn zset_incr_by(
+        &self,
+        key: String,
+        member: String,
+        delta: f64,
+    ) -> Result<f64, BackendError> {
+        self.zset_incr_by_with(key, member, delta, ScoreCondition::default())
+            .map(|score| score.expect("no condition"))
+    }
+
+    // Like `zset_incr_by` if the condition holds for the new score, None otherwise.
+    pub fn zset_incr_by_with(
+        &self,
+        key: String,
+        member: String,
+        delta: f64,
+        condition: ScoreCondition,
+    ) -> Result<Option<f64>, BackendError> {
+        self.expire_if_needed(&key);
+        let Some(mut zset) = self.zset_entry(key, condition) else {
+            return Ok(None);
+        };
+        self.record_access(zset.key());
+        // A NaN or a failed condition needs an existing member, so an empty sorted set is
+        // never left behind.
+        let current = zset.score(&member);
+        let score = current.unwrap_or(0.0) + delta;
+        if score.is_nan() {
+            return Err(BackendError::ScoreNan);
+        }
+        if !condition.holds(current, score) {
+            return Ok(None);
+        }
+        zset.insert(member, score);
+        self.waiters.wake(zset.key());
+        Ok(Some(score))
+    }
+
+    // The sorted set to write to, created if needed unless only existing members may be
+    // written, so that no empty sorted set is created.
+    fn zset_entry(
+        &self,
+        key: String,
+        condition: ScoreCondition,
+    ) -> Option<RefMut<'_, String, SortedSet>> {
+        if condition.if_exists {
+            self.zset.get_mut(&key)
+        } else {
+            Some(self.zset.entry(key).or_default())
+        }
+    }
+
+    pub fn zset_score(&self, key: &str, member: &str) -> Option<f64> {
+        self.expire_if_needed(key);
+        self.zset.get(key).and_then(|zset| {
+            self.record_access(key);
+            zset.score(member)
+        })
+    }
+
+    // The score of each member, None for the missing ones, all read at once.
+    pub fn zset_scores(&self, key: &str, members: &[String]) -> Vec<Option<f64>> {
+        self.expire_if_needed(key);
+        match self.zset.get(key) {
+            Some(zset) => {
+                self.record_access(key);
+                members.iter().map(|member| zset.score(member)).collect()
+            }
+            None => vec![None; members.len()],
+        }
+    }
+
+    // The (score, member) pairs ranked from start to end, inclusive, negative ranks counting
+    // from the end. With `rev`, members are ranked from the highest score.
+    pub fn zset_range(&self, key: &str, start: i64, end: i64, rev: bool) -> Vec<(f64, String)> {
+        self.expire_if_needed(key);
+        let Some(zset) = self.zset.get(key) else {
+            return Vec::new();
+        };
+        self.record_access(key);
+        match normalize_range(start, end, zset.len()) {
+            Some((start, end)) => zset
+                .range(start, end, rev)
+                .map(|(score, member)| (score, member.to_string()))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // The rank of the member along with its score. With `rev`, members are ranked from the
+    // highest score.
+    pub fn zset_rank(&self, key: &str, member: &str, rev: bool) -> Option<(usize, f64)> {
+        self.expire_if_needed(key);
+        self.zset.get(key).and_then(|zset| {
+            self.record_access(key);
+            Some((zset.rank(member, rev)?, zset.score(member)?))
+        })
+    }
+
+    // The number of members in the range, found without walking them.
+    pub fn zset_count_by_score(&self, key: &str, range: &ScoreRange) -> usize {
+        self.expire_if_needed(key);
+        self.zset.get(key).map_or(0, |zset| {
+            self.record_access(key);
+            zset.score_span(range).1
+        })
+    }
+
+    // Like `zset_count_by_score`, for members ordered by name.
+    pub fn zset_count_by_lex(&self, key: &str, range: &LexRange) -> usize {
+        self.expire_if_needed(key);
+        self.zset.get(key).map_or(0, |zset| {
+            self.record_access(key);
+            zset.lex_span(range).1
+        })
+    }
+
+    // Like `hash_scan`, returns the members matching the pattern along with their scores,
+    // see `SortedSet::scan` for the cursor.
+    pub fn zset_scan(
+        &self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+    ) -> (u64, Vec<(f64, String)>) {
+        self.expire_if_needed(key);
+        let Some(zset) = self.zset.get(key) else {
+            return (0, Vec::new());
+        };
+        self.record_access(key);
+        let (next, members) = zset.scan(cursor, count);
+        let members = members
+            .into_iter()
+            .filter(|(_, member)| pattern.is_none_or(|p| glob_match(p, member.as_bytes())))
+            .map(|(score, member)| (score, member.to_string()))
+            .collect();
+        (next, members)
+    }
+
+    // Pops up to `count` members with the lowest scores, or the highest with `rev`, in the order
+    // they are popped one by one. The sorted set is deleted along with its last member.
+    pub fn zset_pop(&self, key: &str, count: usize, rev: bool) -> Vec<(f64, String)> {
+        self.expire_if_needed(key);
+        let mut popped = Vec::new();
+        self.zset.remove_if_mut(key, |key, zset| {
+            let count = count.min(zset.len());
+            if count > 0 {
+                popped = zset
+                    .range(0, count - 1, rev)
+                    .map(|(score, member)| (score, member.to_string()))
+                    .collect();
+                for (_, member) in &popped {
+                    zset.remove(member);
+                }
+            }
+            self.zset_changed(key, zset)
+        });
+        popped
+    }
+
+    // Combines the sorted sets at the keys, each score scaled by the weight of its input, the
+    // scores of a member found in several inputs combined with `aggregate`. Plain sets count
+    // as sorted sets with all scores 1, missing keys as empty ones. Replies the members in
+    // ascending order. The inputs are locked together, sorted sets before plain sets.
+    pub fn zset_combine(
+        &self,
+        op: SetOperation,
+        keys: &[String],
+        weights: &[f64],
+        aggregate: Aggregate,
+    ) -> Vec<(f64, String)> {
+        for key in keys {
+            self.expire_if_needed(key);
+        }
+        let zset_lock = KeyLock::new(&self.zset, keys.iter().map(String::as_str));
+        let set_lock = KeyLock::new(&self.set, keys.iter().map(String::as_str));
+        let inputs = zset_inputs(keys, &zset_lock, &set_lock);
+        let result = combine_zsets(op, &inputs, weights, aggregate);
+        for (key, input) in keys.iter().zip(&inputs) {
+            if input.is_some() {
+                self.record_access(key);
+            }
+        }
+        result
+            .iter()
+            .map(|(score, member)| (score, member.to_string()))
+            .collect()
+    }
+
+    // Like `zset_combine`, storing the result at dst in place of its value, whatever its type,
+    // and without a time to live. An empty result deletes dst. Returns the number of members
+    // stored. Storing the result is atomic, clearing dst from the other keyspaces is not.
+    pub fn zset_combine_store(
+        &self,
+        op: SetOperation,
+        dst: &str,
+        keys: &[String],
+        weights: &[f64],
+        aggregate: Aggregate,
+    ) -> usize {
+        self.expire_if_needed(dst);
+        for key in keys {
+            self.expire_if_needed(key);
+        }
+        self.delete_in(&self.string, dst);
+        self.delete_in(&self.hmap, dst);
+        self.delete_in(&self.bloom, dst);
+        self.delete_in(&self.ratelimit, dst);
+        self.delete_in(&self.counter, dst);
+        self.delete_in(&self.list, dst);
+        self.delete_in(&self.stream, dst);
+
+        // dst may be one of the inputs, even as a plain set, so it is replaced only once the
+        // result is known.
+        let all_keys = || keys.iter().map(String::as_str).chain([dst]);
+        let mut zset_lock = KeyLock::new(&self.zset, all_keys());
+        let mut set_lock = KeyLock::new(&self.set, all_keys());
+        let inputs = zset_inputs(keys, &zset_lock, &set_lock);
+        let result = combine_zsets(op, &inputs, weights, aggregate);
+        for (key, input) in keys.iter().zip(&inputs) {
+            if input.is_some() {
+                self.record_access(key);
+            }
+        }
+
+        set_lock.remove(dst);
+        self.zset_store(&mut zset_lock, dst, result)
+    }
+
+    // Stores the members of the src sorted set selected like `SortedSet::select` at dst, in
+    // place of its value, whatever its type, and without a time to live. An empty selection
+    // deletes dst. Returns the number of members stored. Storing the selection is atomic,
+    // clearing dst from the other keyspaces is not.
+    pub fn zset_range_store(
+        &self,
+        dst: &str,
+        src: &str,
+        by: &RangeBy,
+        rev: bool,
+        limit: Option<(i64, i64)>,
+    ) -> usize {
+        self.expire_if_needed(dst);
+        self.expire_if_needed(src);
+        self.delete_in(&self.string, dst);
+        self.delete_in(&self.hmap, dst);
+        self.delete_in(&self.set, dst);
+        self.delete_in(&self.bloom, dst);
+        self.delete_in(&self.ratelimit, dst);
+        self.delete_in(&self.counter, dst);
+        self.delete_in(&self.list, dst);
+        self.delete_in(&self.stream, dst);
+
+        let mut lock = KeyLock::new(&self.zset, [src, dst]);
+        let mut result = SortedSet::default();
+        if let Some(zset) = lock.get(src) {
+            for (score, member) in zset.select(by, rev, limit) {
+                result.insert(member.to_string(), score);
+            }
+            self.record_access(src);
+        }
+        self.zset_store(&mut lock, dst, result)
+    }
+
+    // Replaces the sorted set at dst, already cleared from the other keyspaces, and drops its
+    // time to live. Returns the number of members stored, dst being deleted if there are none.
+    fn zset_store(&self, lock: &mut KeyLock<SortedSet>, dst: &str, zset: SortedSet) -> usize {
+        let len = zset.len();
+        self.expire.remove(dst);
+        if len == 0 {
+            lock.remove(dst);
+            self.access.remove(dst);
+        } else {
+            lock.insert(dst.to_string(), zset);
+            self.record_access(dst);
+            self.waiters.wake(dst);
+        }
+        len
+    }
+
+    // Returns the number of members removed. The sorted set is deleted along with its last
+    // member.
+    pub fn zset_remove(&self, key: &str, members: &[String]) -> usize {
+        self.expire_if_needed(key);
+        let mut removed = 0;
+        self.zset.remove_if_mut(key, |key, zset| {
+            for member in members {
+                if zset.remove(member) {
+                    removed += 1;
+                }
+            }
+            self.zset_changed(key, zset)
+        });
+        removed
+    }
+
+    // Removes the members ranked from start to end, inclusive, negative ranks counting from
+    // the end. Returns the number of members removed.
+    pub fn zset_remove_range_by_rank(&self, key: &str, start: i64, end: i64) -> usize {
+        self.zset_remove_span(key, |zset| {
+            normalize_range(start, end, zset.len())
+                .map_or((0, 0), |(start, end)| (start, end - start + 1))
+        })
+    }
+
+    pub fn zset_remove_range_by_score(&self, key: &str, range: &ScoreRange) -> usize {
+        self.zset_remove_span(key, |zset| zset.score_span(range))
+    }
+
+    pub fn zset_remove_range_by_lex(&self, key: &str, range: &LexRange) -> usize {
+        self.zset_remove_span(key, |zset| zset.lex_span(range))
+    }
+
+    // Removes the members spanned by the given first rank and count.
+    fn zset_remove_span(
+        &self,
+        key: &str,
+        span: impl FnOnce(&SortedSet) -> (usize, usize),
+    ) -> usize {
+        self.expire_if_needed(key);
+        let mut removed = 0;
+        self.zset.remove_if_mut(key, |key, zset| {
+            let (start, count) = span(zset);
+            removed = zset.remove_ranks(start, count);
+            self.zset_changed(key, zset)
+        });
+        removed
+    }
+
+    // Like `list_changed`, for sorted sets.
+    fn zset_changed(&self, key: &str, zset: &SortedSet) -> bool {
+        let empty = zset.is_empty();
+        if empty {
+            self.expire.remove(key);
+            self.access.remove(key);
+        } else {
+            self.record_access(key);
+        }
+        empty
+    }
+
+    pub fn zset_card(&self, key: &str) -> usize {
+        self.expire_if_needed(key);
+        self.zset.get(key).map_or(0, |zset| {
+            self.record_access(key);
+            zset.len()
+        })
+    }
+
+    // Appends an entry to the stream, creating it unless `no_create` is set, then trims it if
+    // `trim` is given. Returns the ID of the entry, None if the stream does not exist and was
+    // not created. Streams are kept once empty, like in Redis.
+    pub(crate) fn stream_add(
+        &self,
+        key: String,
+        id: StreamAddId,
+        fields: StreamFields,
+        no_create: bool,
+        trim: Option<&StreamTrimOptions>,
+    ) -> Result<Option<StreamId>, BackendError> {
+        self.expire_if_needed(&key);
+        let id = match self.stream.entry(key) {
+            Entry::Occupied(mut entry) => {
+                let stream = entry.get_mut();
+                let id = stream.add(id, fields, now_ms())?;
+                if let Some(trim) = trim {
+                    stream.trim(trim);
+                }
+                self.record_access(entry.key());
+                self.waiters.wake_all(entry.key());
+                id
+            }
+            Entry::Vacant(_) if no_create => return Ok(None),
+            // Only inserted once the ID is valid, so a failed XADD doesn't leave a stream.
+            Entry::Vacant(entry) => {
+                let mut stream = Stream::default();
+                let id = stream.add(id, fields, now_ms())?;
+                if let Some(trim) = trim {
+                    stream.trim(trim);
+                }
+                self.record_access(entry.key());
+                let stream = entry.insert(stream);
+                self.waiters.wake_all(stream.key());
+                id
+            }
+        };
+        Ok(Some(id))
+    }
+
+    pub(crate) fn stream_len(&self, key: &str) -> usize {
+        self.expire_if_needed(key);
+        self.stream.get(key).map_or(0, |stream| {
+            self.record_access(key);
+            stream.len()
+        })
+    }
+
+    // Returns the number of entries trimmed, 0 if the stream does not exist.
+    pub(crate) fn stream_trim(&self, key: &str, trim: &StreamTrimOptions) -> usize {
+        self.expire_if_needed(key);
+        let Some(mut stream) = self.stream.get_mut(key) else {
+            return 0;
+        };
+        self.record_access(key);
+        stream.trim(trim)
+    }
+
+    // Returns the number of entries deleted, 0 if the stream does not exist.
+    pub(crate) fn stream_delete(&self, key: &str, ids: &[StreamId]) -> usize {
+        self.expire_if_needed(key);
+        let Some(mut stream) = self.stream.get_mut(key) else {
+            return 0;
+        };
+        self.record_access(key);
+        stream.delete(ids)
+    }
+
+    // The ID of the last entry added to the stream, 0-0 if it does not exist.
+    pub(crate) fn stream_last_id(&self, key: &str) -> StreamId {
+        self.expire_if_needed(key);
+        self.stream.get(key).map_or(StreamId::MIN, |stream| {
+            self.record_access(key);
+            stream.last_id()
+        })
+    }
+
+    // Up to `count` entries with IDs between start and end included, from the last one if
+    // `rev`.
+    pub(crate) fn stream_range(
+        &self,
+        key: &str,
+        start: StreamId,
+        end: StreamId,
+        rev: bool,
+        count: Option<usize>,
+    ) -> Vec<(StreamId, StreamFields)> {
+        self.expire_if_needed(key);
+        let Some(stream) = self.stream.get(key) else {
+            return Vec::new();
+        };
+        self.record_access(key);
+        stream
+            .range(start, end, rev)
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, fields)| (*id, fields.clone()))
+            .collect()
+    }
+
+    // Creates a consumer group that has been delivered the entries up to `last_id`, or up to
+    // the last entry of the stream if None. Unless `create` is set, the stream must exist.
+    pub(crate) fn stream_group_create(
+        &self,
+        key: String,
+        group: String,
+        last_id: Option<StreamId>,
+        create: bool,
+    ) -> Result<(), BackendError> {
+        self.expire_if_needed(&key);
+        let mut stream = if create {
+            self.stream.entry(key).or_default()
+        } else {
+            self.stream.get_mut(&key).ok_or(BackendError::NoStream)?
+        };
+        self.record_access(stream.key());
+        let last_id = last_id.unwrap_or_else(|| stream.last_id());
+        if stream.create_group(group, last_id) {
+            Ok(())
+        } else {
+            Err(BackendError::BusyGroup)
+        }
+    }
+
+    // Returns false if the group does not exist.
+    pub(crate) fn stream_group_destroy(
+        &self,
+        key: &str,
+        group: &str,
+    ) -> Result<bool, BackendError> {
+        self.expire_if_needed(key);
+        let mut stream = self.stream.get_mut(key).ok_or(BackendError::NoStream)?;
+        self.record_access(key);
+        Ok(stream.destroy_group(group))
+    }
+
+    // Returns false if the consumer already exists.
+    pub(crate) fn stream_group_create_consumer(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+    ) -> Result<bool, BackendError> {
+        self.stream_group(key, group, |group| {
+            group.create_consumer(consumer, now_ms())
+        })
+    }
+
+    // Returns the number of entries that were pending for the consumer, which are no longer
+    // pending for the group either.
+    pub(crate) fn stream_group_delete_consumer(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+    ) -> Result<usize, BackendError> {
+        self.stream_group(key, group, |group| {
+            group.delete_consumer(consumer).unwrap_or(0)
+        })
+    }
+
+    // Sets the ID of the last entry delivered to the group, the last ID of the stream if None.
+    pub(crate) fn stream_group_set_id(
+        &self,
+        key: &str,
+        group: &str,
+        last_id: Option<StreamId>,
+    ) -> Result<(), BackendError> {
+        self.expire_if_needed(key);
+        let mut stream = self.stream.get_mut(key).ok_or(BackendError::NoStream)?;
+        self.record_access(key);
+        let last_id = last_id.unwrap_or_else(|| stream.last_id());
+        match stream.group_mut(group) {
+            Some(group) => {
+                group.set_last_id(last_id);
+                Ok(())
+            }
+            None => Err(no_group(key, group)),
+        }
+    }
+
+    // Reads entries as the consumer of the group like `Stream::read_group`, failing if the
+    // stream or the group does not exist.
+    pub(crate) fn stream_read_group(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        after: Option<StreamId>,
+        count: Option<usize>,
+        no_ack: bool,
+    ) -> Result<Vec<(StreamId, Option<StreamFields>)>, BackendError> {
+        self.stream_with_group(key, group, |stream| {
+            stream.read_group(group, consumer, after, count, no_ack, now_ms())
+        })
+    }
+
+    // Returns the number of entries that were pending for the group, 0 if the stream or the
+    // group does not exist.
+    pub(crate) fn stream_ack(&self, key: &str, group: &str, ids: &[StreamId]) -> usize {
+        self.expire_if_needed(key);
+        let Some(mut stream) = self.stream.get_mut(key) else {
+            return 0;
+        };
+        self.record_access(key);
+        stream.group_mut(group).map_or(0, |group| group.ack(ids))
+    }
+
+    pub(crate) fn stream_pending_summary(
+        &self,
+        key: &str,
+        group: &str,
+    ) -> Result<StreamPendingSummary, BackendError> {
+        self.stream_with_group(key, group, |stream| {
+            stream.group(group).map(ConsumerGroup::pending_summary)
+        })
+    }
+
+    // Up to `count` pending entries of the group like `ConsumerGroup::pending_range`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn stream_pending(
+        &self,
+        key: &str,
+        group: &str,
+        start: StreamId,
+        end: StreamId,
+        count: usize,
+        consumer: Option<&str>,
+        min_idle: u64,
+    ) -> Result<Vec<StreamPendingEntry>, BackendError> {
+        self.stream_with_group(key, group, |stream| {
+            let group = stream.group(group)?;
+            Some(group.pending_range(start, end, count, consumer, min_idle, now_ms()))
+        })
+    }
+
+    // Transfers pending entries of the group to the consumer like `Stream::claim`.
+    pub(crate) fn stream_claim(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        ids: &[StreamId],
+        options: &StreamClaimOptions,
+    ) -> Result<Vec<(StreamId, StreamFields)>, BackendError> {
+        self.stream_with_group(key, group, |stream| {
+            stream.claim(group, consumer, ids, options, now_ms())
+        })
+    }
+
+    // Transfers pending entries of the group to the consumer like `Stream::auto_claim`.
+    pub(crate) fn stream_auto_claim(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        start: StreamId,
+        count: usize,
+        options: &StreamClaimOptions,
+    ) -> Result<StreamAutoClaimResult, BackendError> {
+        self.stream_with_group(key, group, |stream| {
+            stream.auto_claim(group, consumer, start, count, options, now_ms())
+        })
+    }
+
+    pub(crate) fn stream_info(&self, key: &str) -> Result<StreamInfo, BackendError> {
+        self.expire_if_needed(key);
+        let stream = self.stream.get(key).ok_or(BackendError::NoSuchKey)?;
+        self.record_access(key);
+        Ok(stream.info())
+    }
+
+    pub(crate) fn stream_group_infos(
+        &self,
+        key: &str,
+    ) -> Result<Vec<StreamGroupInfo>, BackendError> {
+        self.expire_if_needed(key);
+        let stream = self.stream.get(key).ok_or(BackendError::NoSuchKey)?;
+        self.record_access(key);
+        Ok(stream.group_infos())
+    }
+
+    pub(crate) fn stream_consumer_infos(
+        &self,
+        key: &str,
+        group: &str,
+    ) -> Result<Vec<StreamConsumerInfo>, BackendError> {
+        self.expire_if_needed(key);
+        let stream = self.stream.get(key).ok_or(BackendError::NoSuchKey)?;
+        self.record_access(key);
+        match stream.group(group) {
+            Some(group) => Ok(group.consumer_infos(now_ms())),
+            None => Err(no_group(key, group)),
+        }
+    }
+
+    // Runs `f` on the stream, failing with NOGROUP if the stream does not exist, or if `f`
+    // returns None because the group does not.
+    fn stream_with_group<T>(
+        &self,
+        key: &str,
+        group: &str,
+        f: impl FnOnce(&mut Stream) -> Option<T>,
+    ) -> Result<T, BackendError> {
+        self.expire_if_needed(key);
+        let mut stream = self
+            .stream
+            .get_mut(key)
+            .ok_or_else(|| no_group(key, group))?;
+        self.record_access(key);
+        f(&mut stream).ok_or_else(|| no_group(key, group))
+    }
+
+    // Runs `f` on the group, failing if the stream or the group does not exist.
+    fn stream_group<T>(
+        &self,
+        key: &str,
+        group: &str,
+        f: impl FnOnce(&mut ConsumerGroup) -> T,
+    ) -> Result<T, BackendError> {
+        self.expire_if_needed(key);
+        let mut stream = self.stream.get_mut(key).ok_or(BackendError::NoStream)?;
+        self.record_access(key);
+        match stream.group_mut(group) {
+            Some(group) => Ok(f(group)),
+            None => Err(no_group(key, group)),
+        }
+    }
+
+    // Returns false if the key already holds a bloom filter.
+    pub(crate) fn bloom_reserve(&self, key: String, filter: BloomFilter) -> bool {
+        self.expire_if_needed(&key);
+        match self.bloom.entry(key) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                self.record_access(entry.key());
+                entry.insert(filter);
+                true
+            }
+        }
+    }
+
+    // Adds the items to the filter, creating a default one if the key does not exist.
+    // Each item is replied with 1 if newly added, 0 if it may already exist, or an error.
+    pub(crate) fn bloom_add(&self, key: String, items: Vec<String>) -> Vec<RespFrame> {
+        self.expire_if_needed(&key);
+        let mut filter = self.bloom.entry(key).or_default();
+        self.record_access(filter.key());
+        items
+            .iter()
+            .map(|item| match filter.add(item) {
+                Ok(added) => RespFrame::Integer(added as i64),
+                Err(e) => e.into(),
+            })
+            .collect()
+    }
+
+    pub(crate) fn bloom_exists(&self, key: &str, items: Vec<String>) -> Vec<RespFrame> {
+        self.expire_if_needed(key);
+        let filter = self.bloom.get(key);
+        if filter.is_some() {
+            self.record_access(key);
+        }
+        items
+            .iter()
+            .map(|item| {
+                let exists = filter.as_ref().is_some_and(|f| f.contains(item));
+                RespFrame::Integer(exists as i64)
+            })
+            .collect()
+    }
+
+    // Buckets keep their own timestamps, so checks are not recorded as key accesses.
+    pub fn ratelimit_check(
+        &self,
+        key: String,
+        max_tokens: u64,
+        refill_rate: f64,
+        cost: u64,
+    ) -> RateLimitResult {
+        self.expire_if_needed(&key);
+        let now = Instant::now();
+        let mut bucket = self
+            .ratelimit
+            .entry(key)
+            .or_insert_with(|| TokenBucket::new(max_tokens, now));
+        bucket.check(max_tokens, refill_rate, cost, now)
+    }
+
+    // Only holds the shard's read lock once the counter exists, so concurrent increments
+    // don't serialize. For the same reason, increments are not recorded as key accesses.
+    pub fn counter_incr_by(&self, key: &str, delta: i64) {
+        self.expire_if_needed(key);
+        if let Some(counter) = self.counter.get(key) {
+            counter.incr_by(delta);
+            return;
+        }
+        self.counter
+            .entry(key.to_string())
+            .or_default()
+            .incr_by(delta);
+    }
+
+    pub fn counter_get(&self, key: &str) -> Option<i64> {
+        self.expire_if_needed(key);
+        self.counter.get(key).map(|counter| {
+            self.record_access(key);
+            counter.get()
+        })
+    }
+
+    // Drops the buckets that have been idle long enough to refill completely.
+    pub fn ratelimit_cleanup(&self) {
+        let now = Instant::now();
+        self.ratelimit.retain(|key, bucket| {
+            let idle = bucket.is_idle(now);
+            if idle {
+                self.expire.remove(key);
+                self.access.remove(key);
+            }
+            !idle
+        });
+    }
+}
+
+impl From<BackendError> for RespFrame {
+    fn from(e: BackendError) -> Self {
+        SimpleError::new(e.to_string()).into()
+    }
+}
+
+// Runs `batch` on a background thread from cursor 0 until it returns 0 as the next cursor,
+// pausing `BULK_TTL_PAUSE` in between. Returns the sum of the counts of the batches.
+fn bulk_ttl_job<F>(name: &'static str, mut batch: F) -> JoinHandle<usize>
+where
+    F: FnMut(u64) -> (u64, usize) + Send + 'static,
+{
+    thread::spawn(move || {
+        let (mut cursor, mut total) = (0, 0);
+        loop {
+            let (next, count) = batch(cursor);
+            total += count;
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+            thread::sleep(BULK_TTL_PAUSE);
+        }
+        info!("{} is done, {} key(s) affected", name, total);
+        total
+    })
+}
+
+fn take_shards<V>(map: &DashMap<String, V>) -> Vec<Shard<V>> {
+    map.shards()
+        .iter()
+        .map(|shard| std::mem::take(&mut *shard.write()))
+        .collect()
+}
+
+fn shard_lens<V>(map: &DashMap<String, V>) -> Vec<usize> {
+    map.shards()
+        .iter()
+        .map(|shard| shard.read().len())
+        .collect()
+}
+
+// A key picked uniformly at random, without collecting the map.
+fn random_map_key<V>(map: &DashMap<String, V>) -> Option<String> {
+    let lens = shard_lens(map);
+    let total: usize = lens.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let mut n = rand::rng().random_range(0..total);
+    let shard = lens.iter().position(|len| {
+        if n < *len {
+            true
+        } else {
+            n -= len;
+            false
+        }
+    })?;
+    nth_shard_key(map, shard, n)
+}
+
+fn nth_shard_key<V>(map: &DashMap<String, V>, shard: usize, n: usize) -> Option<String> {
+    map.shards()[shard].read().keys().nth(n).cloned()
+}
+
+// An input of the sorted set commands combining several keys, where plain sets are accepted.
+#[derive(Clone, Copy)]
+enum ZSetInput<'a> {
+    Sorted(&'a SortedSet),
+    Plain(&'a DashSet<String>),
+}
+
+impl ZSetInput<'_> {
+    fn len(&self) -> usize {
+        match self {
+            ZSetInput::Sorted(zset) => zset.len(),
+            ZSetInput::Plain(set) => set.len(),
+        }
+    }
+
+    fn score(&self, member: &str) -> Option<f64> {
+        match self {
+            ZSetInput::Sorted(zset) => zset.score(member),
+            ZSetInput::Plain(set) => set.contains(member).then_some(1.0),
+        }
+    }
+
+    fn members(&self) -> Vec<(f64, String)> {
+        match self {
+            ZSetInput::Sorted(zset) => zset
+                .iter()
+                .map(|(score, member)| (score, member.to_string()))
+                .collect(),
+            ZSetInput::Plain(set) => set.iter().map(|member| (1.0, member.clone())).collect(),
+        }
+    }
+}
+
+// The input at each key, a sorted set taking precedence over a plain set.
+fn zset_inputs<'a>(
+    keys: &[String],
+    zset_lock: &'a KeyLock<SortedSet>,
+    set_lock: &'a KeyLock<DashSet<String>>,
+) -> Vec<Option<ZSetInput<'a>>> {
+    keys.iter()
+        .map(|key| {
+            zset_lock
+                .get(key)
+                .map(ZSetInput::Sorted)
+                .or_else(|| set_lock.get(key).map(ZSetInput::Plain))
+        })
+        .collect()
+}
+
+fn combine_zsets(
+    op: SetOperation,
+    inputs: &[Option<ZSetInput>],
+    weights: &[f64],
+    aggregate: Aggregate,
+) -> SortedSet {
+    let mut scores: HashMap<String, f64> = HashMap::new();
+    match op {
+        // Walk the smallest input, any missing one makes the intersection empty.
+        SetOperation::Inter => {
+            let Some(inputs) = inputs.iter().copied().collect::<Option<Vec<_>>>() else {
+                return SortedSet::default();
+            };
+            let Some(smallest) = inputs.iter().min_by_key(|input| input.len()) else {
+                return SortedSet::default();
+            };
+            for (_, member) in smallest.members() {
+                let found: Option<Vec<f64>> = inputs
+                    .iter()
+                    .zip(weights)
+                    .map(|(input, &w)| Some(weighted(input.score(&member)?, w)))
+                    .collect();
+                let score = found.and_then(|found| {
+                    found
+                        .into_iter()
+                        .reduce(|acc, score| aggregate.apply(acc, score))
+                });
+                if let Some(score) = score {
+                    scores.insert(member, score);
+                }
+            }
+        }
+        SetOperation::Union => {
+            for (input, &w) in inputs.iter().zip(weights) {
+                for (score, member) in input.iter().flat_map(|input| input.members()) {
+                    let score = weighted(score, w);
+                    scores
+                        .entry(member)
+                        .and_modify(|acc| *acc = aggregate.apply(*acc, score))
+                        .or_insert(score);
+                }
+            }
+        }
+        SetOperation::Diff => {
+            if let Some((Some(first), others)) = inputs.split_first() {
+                for (score, member) in first.members() {
+                    let excluded = others
+                        .iter()
+                        .flatten()
+                        .any(|input| input.score(&member).is_some());
+                    if !excluded {
+                        scores.insert(member, weighted(score, weights[0]));
+                    }
+                }
+            }
+        }
+    }
+    let mut zset = SortedSet::default();
+    for (member, score) in scores {
+        zset.insert(member, score);
+    }
+    zset
+}
+
+fn combine_sets(op: SetOperation, sets: &[Option<&DashSet<String>>]) -> HashSet<String> {
+    let Some((first, others)) = sets.split_first() else {
+        return HashSet::new();
+    };
+    match op {
+        // Walk the smallest set, any missing one makes the intersection empty.
+        SetOperation::Inter => {
+            let Some(sets) = sets.iter().copied().collect::<Option<Vec<_>>>() else {
+                return HashSet::new();
+            };
+            let smallest = sets.iter().min_by_key(|set| set.len()).expect("not empty");
+            smallest
+                .iter()
+                .filter(|member| sets.iter().all(|set| set.contains(member.key())))
+                .map(|member| member.key().clone())
+                .collect()
+        }
+        SetOperation::Union => sets
+            .iter()
+            .flatten()
+            .flat_map(|set| set.iter().map(|member| member.key().clone()))
+            .collect(),
+        SetOperation::Diff => first
+            .iter()
+            .flat_map(|set| set.iter())
+            .filter(|member| {
+                !others
+                    .iter()
+                    .flatten()
+                    .any(|set| set.contains(member.key()))
+            })
+            .map(|member| member.key().clone())
+            .collect(),
+    }
+}
+
+// Picks `count` elements of the iterator at random, in random order. They are distinct unless
+// `repeat` is set, so at most `len`, the number of elements of the iterator. The iterator is
+// walked once and only the picked elements are passed to `f`.
+fn random_sample<I, T, F>(iter: I, len: usize, count: usize, repeat: bool, mut f: F) -> Vec<T>
+where
+    I: Iterator,
+    F: FnMut(&I::Item) -> T,
+{
+    if len == 0 {
+        return Vec::new();
+    }
+    let mut rng = rand::rng();
+    let mut indexes = if repeat {
+        (0..count).map(|_| rng.random_range(0..len)).collect()
+    } else {
+        rand::seq::index::sample(&mut rng, len, count.min(len)).into_vec()
+    };
+    indexes.sort_unstable();
+
+    let mut indexes = indexes.into_iter().peekable();
+    let mut picked = Vec::with_capacity(count.min(len));
+    for (i, item) in iter.enumerate() {
+        if indexes.peek().is_none() {
+            break;
+        }
+        while indexes.next_if_eq(&i).is_some() {
+            picked.push(f(&item));
+        }
+    }
+    picked.shuffle(&mut rng);
+    picked
+}
+
+fn no_group(key: &str, group: &str) -> BackendError {
+    BackendError::NoGroup {
+        key: key.to_string(),
+        group: group.to_string(),
+    }
+}
+
+// Current unix time in milliseconds.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(feature = "str-ext")]
+fn as_utf8(value: &[u8]) -> Result<&str, BackendError> {
+    std::str::from_utf8(value).map_err(|_| BackendError::NotUtf8)
+}
+
+fn parse_integer(value: &[u8]) -> Result<i64, BackendError> {
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or(BackendError::NotInteger)
+}
+
+fn parse_float(value: &[u8]) -> Result<f64, BackendError> {
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|v| v.is_finite())
+        .ok_or(BackendError::NotFloat)
+}
+
+// Converts a Redis-style inclusive range (negative indexes count from the end) into
+// valid inclusive indexes for a sequence of the given length, None if the range is empty.
+fn normalize_range(start: i64, end: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let end = if end < 0 { len + end } else { end.min(len - 1) };
+    if len == 0 || start > end || start >= len {
+        return None;
+    }
+    Some((start as usize, end as usize))
+}
+
+// Converts a Redis-style index (negative ones count from the end) into a valid index for a
+// sequence of the given length, None if it is out of range.
+fn normalize_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 {
+        index.checked_add(len as i64)?
+    } else {
+        index
+    };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::time::Duration;
+
+    use super::{
+        now_ms, random_sample, Backend, Blocked, BloomFilter, ExpireCondition, Expiry, ListEnd,
+        RespFrame, RespNull, SetCondition, StreamAddId, StringValue,
+    };
+
+    #[test]
+    fn test_random_sample() {
+        let items = ["a", "b", "c", "d"];
+        let mut picked = random_sample(items.iter(), 4, 3, false, |item| **item);
+        assert_eq!(picked.len(), 3);
+        picked.sort();
+        picked.dedup();
+        assert_eq!(picked.len(), 3);
+
+        let picked = random_sample(items.iter(), 4, 100, false, |item| **item);
+        assert_eq!(picked.len(), 4);
+
+        let picked = random_sample(items.iter(), 4, 100, true, |item| **item);
+        assert_eq!(picked.len(), 100);
+        assert!(picked.iter().all(|item| items.contains(item)));
+
+        assert!(random_sample(std::iter::empty::<&str>(), 0, 3, true, |item| *item).is_empty());
+    }
+
+    #[test]
+    fn test_update() {
+        let backend = Backend::new();
+        backend.update("a", |value| {
+            assert_eq!(value, None);
+            Some(StringValue::Int(1))
+        });
+        backend.expire.insert("a".to_string(), now_ms() + 10_000);
+
+        // The time to live is kept when the value is replaced.
+        backend.update("a", |value| match value {
+            Some(StringValue::Int(n)) => Some(StringValue::Int(n + 1)),
+            _ => None,
+        });
+        assert_eq!(backend.string_get("a"), Some(b"2".to_vec()));
+        assert!(backend.expire.contains_key("a"));
+
+        backend.update("a", |_| None);
+        assert!(!backend.exists("a"));
+        assert!(!backend.expire.contains_key("a"));
+
+        // Returning None for a missing key doesn't create it.
+        backend.update("b", |_| None);
+        assert!(!backend.exists("b"));
+
+        // A panic keeps the stored value.
+        backend.string_set("c".to_string(), b"value".to_vec());
+        let ret = panic::catch_unwind(AssertUnwindSafe(|| {
+            backend.update("c", |_| panic!("update failed"))
+        }));
+        assert!(ret.is_err());
+        assert_eq!(backend.string_get("c"), Some(b"value".to_vec()));
+    }
+
+    #[test]
+    fn test_lazy_expiration() {
+        let backend = Backend::new();
+        backend.string_set("string".to_string(), b"value".to_vec());
+        backend.hash_set(
+            "hash".to_string(),
+            "field".to_string(),
+            RespFrame::Integer(1),
+        );
+        backend.set_add("set".to_string(), vec!["member".to_string()]);
+        backend.bloom_reserve("bloom".to_string(), BloomFilter::default());
+        backend.bloom_add("bloom".to_string(), vec!["item".to_string()]);
+        for key in ["string", "hash", "set", "bloom"] {
+            backend.expire.insert(key.to_string(), 1);
+        }
+
+        // Every read path sees the keys as missing, and removes them.
+        assert_eq!(backend.string_get("string"), None);
+        assert_eq!(backend.hash_get_all("hash").map(|h| h.len()), None);
+        assert_eq!(backend.set_members("set").map(|s| s.len()), None);
+        assert_eq!(
+            backend.bloom_exists("bloom", vec!["item".to_string()]),
+            vec![RespFrame::Integer(0)]
+        );
+        assert_eq!(backend.size(), 0);
+        assert!(backend.expire.is_empty());
+
+        // Writes start from an empty value, without the old time to live.
+        backend.string_set("string".to_string(), b"1".to_vec());
+        backend.expire.insert("string".to_string(), 1);
+        assert_eq!(backend.string_incr_by("string".to_string(), 5), Ok(5));
+        assert_eq!(backend.expire_time("string"), Some(None));
+    }
+
+    #[test]
+    fn test_active_expire() {
+        let backend = Backend::new();
+        for i in 0..50 {
+            let key = format!("key{}", i);
+            backend.string_set(key.clone(), b"value".to_vec());
+            backend.expire.insert(key, 1);
+        }
+
+        // The effort caps the number of keys checked.
+        assert_eq!(backend.active_expire(10), 10);
+        assert_eq!(backend.string.len(), 40);
+
+        // Every sampled key is expired, so rounds go on until none is left.
+        assert_eq!(backend.active_expire(usize::MAX), 40);
+        assert!(backend.string.is_empty());
+        assert!(backend.expire.is_empty());
+
+        // Keys that are not expired yet are kept.
+        backend.string_set("live".to_string(), b"value".to_vec());
+        backend.expire.insert("live".to_string(), u64::MAX);
+        assert_eq!(backend.active_expire(usize::MAX), 0);
+        assert!(backend.exists("live"));
+    }
+
+    #[test]
+    fn test_active_expire_indexed() {
+        let backend = Backend::with_expire_index();
+        let set = |key: &str, at| {
+            backend.string_set_with(
+                key.to_string(),
+                b"value".to_vec(),
+                SetCondition::Always,
+                Expiry::At(at),
+            );
+        };
+        for i in 0..50 {
+            set(&format!("key{}", i), 1);
+        }
+        set("live", u64::MAX);
+
+        // The due keys are found in order, however few they are.
+        assert_eq!(backend.active_expire(10), 10);
+        assert_eq!(backend.active_expire(usize::MAX), 40);
+        assert_eq!(backend.string.len(), 1);
+        assert_eq!(backend.active_expire(usize::MAX), 0);
+
+        // Keys given another time to live since, or deleted, are left alone.
+        set("moved", 1);
+        set("moved", u64::MAX);
+        set("deleted", 1);
+        backend.delete("deleted");
+        assert_eq!(backend.active_expire(usize::MAX), 0);
+        assert!(backend.exists("moved"));
+
+        // Stale entries are compacted away once they outnumber the times to live.
+        let index = backend.expire_index.as_ref().unwrap();
+        for i in 0..2000 {
+            let key = format!("stale{}", i);
+            set(&key, u64::MAX - 1);
+            backend.persist(&key);
+        }
+        assert_eq!(index.len(), 2002);
+        backend.active_expire(usize::MAX);
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn test_bulk_ttl() {
+        let backend = Backend::new();
+        for i in 0..250 {
+            backend.string_set(format!("user:{}", i), b"value".to_vec());
+        }
+        backend.hash_set(
+            "user:hash".to_string(),
+            "f".to_string(),
+            RespFrame::Null(RespNull),
+        );
+        backend.string_set("other".to_string(), b"value".to_vec());
+
+        // Every matching key of every type gets a time to live, in several batches.
+        let before = now_ms();
+        assert_eq!(
+            backend
+                .bulk_set_ttl("user:*".to_string(), 100, ExpireCondition::default())
+                .join()
+                .unwrap(),
+            251
+        );
+        let at = *backend.expire.get("user:hash").unwrap();
+        assert!(at >= before + 100_000);
+        assert!(!backend.expire.contains_key("other"));
+
+        // The condition is checked against each key's own.
+        let nx = ExpireCondition {
+            if_none: true,
+            ..Default::default()
+        };
+        backend.string_set("user:new".to_string(), b"value".to_vec());
+        assert_eq!(
+            backend
+                .bulk_set_ttl("user:*".to_string(), 100, nx)
+                .join()
+                .unwrap(),
+            1
+        );
+        assert_eq!(*backend.expire.get("user:hash").unwrap(), at);
+
+        // Only the matching keys that are due are removed.
+        for key in ["user:1", "user:2", "other"] {
+            backend.expire.insert(key.to_string(), 1);
+        }
+        assert_eq!(
+            backend
+                .bulk_expire_scan("user:*".to_string())
+                .join()
+                .unwrap(),
+            2
+        );
+        assert_eq!(backend.string.len(), 250);
+        assert!(!backend.exists("user:1"));
+        assert!(backend.string.contains_key("other"));
+    }
+
+    #[test]
+    fn test_active_expire_fields() {
+        let backend = Backend::new();
+        let later = now_ms() + 60_000;
+        let fields = ["a".to_string(), "b".to_string()];
+        for i in 0..30 {
+            let key = format!("hash{}", i);
+            let values = fields
+                .iter()
+                .map(|field| (field.clone(), RespFrame::Integer(1)))
+                .collect();
+            backend.hash_set_multi(key.clone(), values);
+            backend.hash_expire_at(&key, &fields[1..], later, ExpireCondition::default());
+        }
+        assert_eq!(backend.hash_field_expire.len(), 30);
+        assert_eq!(backend.active_expire_fields(usize::MAX), 0);
+
+        // Make the fields due, as if time had passed.
+        for mut hash in backend.hmap.iter_mut() {
+            hash.set_expire_time("b", 1);
+        }
+        for mut at in backend.hash_field_expire.iter_mut() {
+            *at = 1;
+        }
+
+        // The effort caps the number of hashes checked.
+        assert_eq!(backend.active_expire_fields(10), 10);
+        assert_eq!(backend.active_expire_fields(usize::MAX), 20);
+        assert!(backend.hash_field_expire.is_empty());
+        assert_eq!(backend.hmap.len(), 30);
+        assert!(backend.hmap.iter().all(|hash| hash.len() == 1));
+
+        // Fields that are not expired yet are kept.
+        backend.hash_expire_at("hash0", &fields[..1], later, ExpireCondition::default());
+        backend.hash_set("hash0".to_string(), "b".to_string(), RespFrame::Integer(1));
+        backend
+            .hmap
+            .get_mut("hash0")
+            .unwrap()
+            .set_expire_time("b", 1);
+        backend.hash_field_expire.insert("hash0".to_string(), 1);
+        assert_eq!(backend.active_expire_fields(usize::MAX), 1);
+        assert_eq!(backend.hash_len("hash0"), 1);
+        assert_eq!(*backend.hash_field_expire.get("hash0").unwrap(), later);
+
+        // Hashes left without fields are removed, along with their metadata.
+        backend.expire.insert("hash1".to_string(), u64::MAX);
+        backend
+            .hmap
+            .get_mut("hash1")
+            .unwrap()
+            .set_expire_time("a", 1);
+        backend.hash_field_expire.insert("hash1".to_string(), 1);
+        assert_eq!(backend.active_expire_fields(usize::MAX), 1);
+        assert!(!backend.exists("hash1"));
+        assert!(!backend.expire.contains_key("hash1"));
+        assert!(!backend.hash_field_expire.contains_key("hash1"));
+    }
+
+    #[test]
+    fn test_set_move_concurrent() {
+        let backend = Backend::new();
+        let members: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+        backend.set_add("a".to_string(), members.clone());
+        backend.set_add("b".to_string(), vec!["sentinel".to_string()]);
+
+        // Members bounce between the sets in both directions, each one is always in exactly
+        // one of them.
+        let threads: Vec<_> = [("a", "b"), ("b", "a")]
+            .into_iter()
+            .map(|(src, dst)| {
+                let backend = backend.clone();
+                let members = members.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..10 {
+                        for member in &members {
+                            backend.set_move(src, dst, member);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let len = |key| backend.set_members(key).map_or(0, |set| set.len());
+        assert_eq!(len("a") + len("b"), 101);
+    }
+
+    #[tokio::test]
+    async fn test_block_wakes_in_order() {
+        async fn woken(blocked: &Blocked) -> bool {
+            tokio::time::timeout(Duration::from_millis(50), blocked.woken())
+                .await
+                .is_ok()
+        }
+        let backend = Backend::new();
+        let first = backend.block(vec!["a".to_string(), "b".to_string()]);
+        let second = backend.block(vec!["b".to_string()]);
+
+        // Only the client blocked the longest is woken, and a wakeup sent before it waits is
+        // not lost.
+        backend.list_push("b".to_string(), vec!["x".into()], ListEnd::Left);
+        assert!(woken(&first).await);
+        assert!(!woken(&second).await);
+
+        // Once it stops waiting, the next one is woken as the list still exists.
+        drop(first);
+        assert!(woken(&second).await);
+        backend.list_pop("b", ListEnd::Left);
+        drop(second);
+
+        // Renaming a key into a watched one wakes its clients too.
+        let blocked = backend.block(vec!["c".to_string()]);
+        backend.list_push("d".to_string(), vec!["x".into()], ListEnd::Left);
+        assert!(!woken(&blocked).await);
+        backend.rename("d", "c", false).unwrap();
+        assert!(woken(&blocked).await);
+
+        // So does adding to a sorted set.
+        let blocked = backend.block(vec!["z".to_string()]);
+        backend.zset_add("z".to_string(), vec![(1.0, "a".to_string())]);
+        assert!(woken(&blocked).await);
+
+        // And appending to a stream.
+        let blocked = backend.block(vec!["s".to_string()]);
+        let fields = vec![("f".into(), "v".into())];
+        backend
+            .stream_add("s".to_string(), StreamAddId::Auto, fields, false, None)
+            .unwrap();
+        assert!(woken(&blocked).await);
+
+        // Which wakes every reader, as the first one may wait for later entries and keep
+        // blocking without waking the others.
+        let first = backend.block(vec!["s".to_string()]);
+        let second = backend.block(vec!["s".to_string()]);
+        let fields = vec![("f".into(), "v".into())];
+        backend
+            .stream_add("s".to_string(), StreamAddId::Auto, fields, false, None)
+            .unwrap();
+        assert!(woken(&first).await);
+        assert!(woken(&second).await);
+    }
+
+    #[tokio::test]
+    async fn test_delay_mover() {
+        let backend = Backend::new();
+        let mover = tokio::spawn({
+            let backend = backend.clone();
+            async move { backend.delay_mover().await }
+        });
+        let blocked = backend.block(vec!["jobs".to_string()]);
+
+        // The mover sleeps until the next job is due, and is woken for an earlier one.
+        backend.delay_push("jobs".to_string(), "later".into(), 60_000);
+        backend.delay_push("jobs".to_string(), "soon".into(), 50);
+        tokio::time::timeout(Duration::from_secs(1), blocked.woken())
+            .await
+            .unwrap();
+        assert_eq!(backend.list_range("jobs", 0, -1), vec!["soon"]);
+        assert_eq!(backend.delayed.len(), 1);
+
+        // Flushing drops the jobs not due yet.
+        backend.flush(false);
+        assert_eq!(backend.delayed.len(), 0);
+        mover.abort();
     }
 }
@@ -0,0 +1,145 @@
+use std::time::{Duration, Instant};
+
+use super::dump::{Dump, Reader, Writer};
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+    // The bucket is full again from this point on, so it can be dropped when idle.
+    // None if that is too far in the future to represent.
+    full_at: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitResult {
+    pub allowed: bool,
+    pub remaining: u64,
+    // None if the request was allowed, or if it can never be (cost > max_tokens).
+    pub retry_after: Option<Duration>,
+}
+
+impl TokenBucket {
+    pub fn new(max_tokens: u64, now: Instant) -> Self {
+        Self {
+            tokens: max_tokens as f64,
+            last_refill: now,
+            full_at: Some(now),
+        }
+    }
+
+    // Refill the bucket according to the elapsed time, then try to take `cost` tokens.
+    pub fn check(
+        &mut self,
+        max_tokens: u64,
+        refill_rate: f64,
+        cost: u64,
+        now: Instant,
+    ) -> RateLimitResult {
+        let max = max_tokens as f64;
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * refill_rate).min(max);
+        self.last_refill = now;
+
+        let cost = cost as f64;
+        let allowed = self.tokens >= cost;
+        if allowed {
+            self.tokens -= cost;
+        }
+        self.full_at = Duration::try_from_secs_f64((max - self.tokens) / refill_rate)
+            .ok()
+            .and_then(|d| now.checked_add(d));
+
+        let retry_after = if allowed || cost > max {
+            None
+        } else {
+            Some(
+                Duration::try_from_secs_f64((cost - self.tokens) / refill_rate)
+                    .unwrap_or(Duration::MAX),
+            )
+        };
+        RateLimitResult {
+            allowed,
+            remaining: self.tokens.floor() as u64,
+            retry_after,
+        }
+    }
+
+    // A full bucket behaves exactly like a missing one.
+    pub fn is_idle(&self, now: Instant) -> bool {
+        self.full_at.is_some_and(|full_at| now >= full_at)
+    }
+}
+
+// Instants can't outlive the process, they are stored relative to the time of the dump.
+impl Dump for TokenBucket {
+    const TAG: u8 = 4;
+
+    fn write(&self, w: &mut Writer) {
+        let now = Instant::now();
+        w.put_f64(self.tokens);
+        w.put_u64(now.saturating_duration_since(self.last_refill).as_millis() as u64);
+        match self.full_at {
+            Some(full_at) => {
+                w.put_u8(1);
+                w.put_u64(full_at.saturating_duration_since(now).as_millis() as u64);
+            }
+            None => w.put_u8(0),
+        }
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let now = Instant::now();
+        let tokens = r.f64().filter(|n| n.is_finite())?;
+        let elapsed = Duration::from_millis(r.u64()?);
+        let full_at = match r.u8()? {
+            0 => None,
+            1 => now.checked_add(Duration::from_millis(r.u64()?)),
+            _ => return None,
+        };
+        Some(Self {
+            tokens,
+            last_refill: now.checked_sub(elapsed).unwrap_or(now),
+            full_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, now);
+
+        let ret = bucket.check(2, 1.0, 1, now);
+        assert!(ret.allowed);
+        assert_eq!(ret.remaining, 1);
+
+        let ret = bucket.check(2, 1.0, 1, now);
+        assert!(ret.allowed);
+        assert_eq!(ret.remaining, 0);
+
+        let ret = bucket.check(2, 1.0, 1, now);
+        assert!(!ret.allowed);
+        assert_eq!(ret.retry_after, Some(Duration::from_secs(1)));
+
+        let later = now + Duration::from_millis(1500);
+        assert!(!bucket.is_idle(later));
+        let ret = bucket.check(2, 1.0, 1, later);
+        assert!(ret.allowed);
+        assert_eq!(ret.remaining, 0);
+
+        let ret = bucket.check(2, 1.0, 3, later);
+        assert!(!ret.allowed);
+        assert_eq!(ret.retry_after, None);
+
+        assert!(bucket.is_idle(later + Duration::from_secs(2)));
+    }
+}
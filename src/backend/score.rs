@@ -0,0 +1,145 @@
+use std::cmp::Ordering;
+
+use super::BackendError;
+
+// Sorted set scores: parsed like Redis's strtod (inf, +inf and -inf accepted, NaN rejected)
+// and replied like Redis's "%.17g", so clients get the same strings from both servers.
+pub fn parse_score(value: &[u8]) -> Result<f64, BackendError> {
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|v| !v.is_nan())
+        .ok_or(BackendError::NotFloat)
+}
+
+pub fn format_score(score: f64) -> String {
+    if score.is_infinite() {
+        return if score > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+
+    // Like %g: the exponent of the value rounded to 17 significant digits decides between the
+    // fixed and the exponential notation, then trailing zeros are dropped.
+    let (mantissa, exp) = round_significant(score, 17);
+    if (-4..17).contains(&exp) {
+        let fixed = format!("{:.*}", (16 - exp) as usize, score);
+        trim_fraction(&fixed).to_string()
+    } else {
+        let sign = if exp < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", trim_fraction(&mantissa), sign, exp.abs())
+    }
+}
+
+// INCRBYFLOAT results: Redis adds in long double and replies with "%.17Lf" minus the trailing
+// zeros, which hides the rounding error of the addition, so 0.1 + 0.2 gives 0.3. Doubles need
+// rounding to the 15 significant digits they always hold for that, the 17 decimals still
+// bounding the fixed notation. The value must be finite.
+pub fn format_float(value: f64) -> String {
+    let (_, exp) = round_significant(value, 15);
+    let decimals = (14 - exp).clamp(0, 17) as usize;
+    trim_fraction(&format!("{:.*}", decimals, value)).to_string()
+}
+
+// Orders by score, then by member bytes. This is the only order sorted sets use.
+pub fn cmp_score_member(
+    score: f64,
+    member: &str,
+    other_score: f64,
+    other_member: &str,
+) -> Ordering {
+    // Scores are never NaN, so partial_cmp always succeeds.
+    score
+        .partial_cmp(&other_score)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| member.as_bytes().cmp(other_member.as_bytes()))
+}
+
+// The mantissa and exponent of the value rounded to the significant digits, in the
+// exponential notation.
+fn round_significant(value: f64, digits: usize) -> (String, i32) {
+    let exponential = format!("{:.*e}", digits - 1, value);
+    match exponential.split_once('e') {
+        Some((mantissa, exp)) => (mantissa.to_string(), exp.parse().unwrap_or(0)),
+        None => (exponential, 0),
+    }
+}
+
+fn trim_fraction(s: &str) -> &str {
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::{cmp_score_member, format_float, format_score, parse_score};
+    use crate::BackendError;
+
+    #[test]
+    fn test_parse_score() {
+        assert_eq!(parse_score(b"1.5"), Ok(1.5));
+        assert_eq!(parse_score(b"-3"), Ok(-3.0));
+        assert_eq!(parse_score(b"1e3"), Ok(1000.0));
+        assert_eq!(parse_score(b"inf"), Ok(f64::INFINITY));
+        assert_eq!(parse_score(b"+inf"), Ok(f64::INFINITY));
+        assert_eq!(parse_score(b"-inf"), Ok(f64::NEG_INFINITY));
+        for invalid in [&b"nan"[..], b"NaN", b"", b" 1", b"1 ", b"abc"] {
+            assert_eq!(parse_score(invalid), Err(BackendError::NotFloat));
+        }
+    }
+
+    // Expected values are printf("%.17g") outputs, which is what Redis replies with.
+    #[test]
+    fn test_format_score() {
+        for (score, expected) in [
+            (0.0, "0"),
+            (1.0, "1"),
+            (-2.5, "-2.5"),
+            (10.0, "10"),
+            (2.3, "2.2999999999999998"),
+            (0.1, "0.10000000000000001"),
+            (0.0001, "0.0001"),
+            (0.00001, "1.0000000000000001e-05"),
+            (1e16, "10000000000000000"),
+            (1e17, "1e+17"),
+            (123456789012345678.0, "1.2345678901234568e+17"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (f64::INFINITY, "inf"),
+            (f64::NEG_INFINITY, "-inf"),
+        ] {
+            assert_eq!(format_score(score), expected, "formatting {:e}", score);
+        }
+    }
+
+    // Expected values are what Redis replies to INCRBYFLOAT.
+    #[test]
+    fn test_format_float() {
+        for (value, expected) in [
+            (0.0, "0"),
+            (5.0, "5"),
+            (-2.5, "-2.5"),
+            (0.1 + 0.2, "0.3"),
+            (10.5 + 0.1, "10.6"),
+            (1.1 + 2.2, "3.3"),
+            (1e20, "100000000000000000000"),
+            (1e-20, "0"),
+            (0.000123, "0.000123"),
+        ] {
+            assert_eq!(format_float(value), expected, "formatting {:e}", value);
+        }
+    }
+
+    #[test]
+    fn test_cmp_score_member() {
+        assert_eq!(cmp_score_member(1.0, "b", 2.0, "a"), Ordering::Less);
+        assert_eq!(cmp_score_member(1.0, "b", 1.0, "a"), Ordering::Greater);
+        assert_eq!(cmp_score_member(0.0, "a", -0.0, "a"), Ordering::Equal);
+        assert_eq!(
+            cmp_score_member(f64::NEG_INFINITY, "z", f64::MIN, "a"),
+            Ordering::Less
+        );
+    }
+}
@@ -0,0 +1,454 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use super::score::cmp_score_member;
+
+const SKIPLIST_MAX_LEVEL: usize = 32;
+// A node is promoted to the next level with a probability of 1/SKIPLIST_P.
+const SKIPLIST_P: u64 = 4;
+
+// An ordered index of (score, member) pairs, modeled after Redis's zskiplist.
+// Nodes live in an arena and link to each other by index, so the list is plain data
+// (Send + Sync, no unsafe) and can sit behind a DashMap entry lock.
+// Each forward link records its span, which makes rank queries O(log n).
+#[derive(Debug, Clone)]
+pub struct SkipList {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    tail: Option<usize>,
+    level: usize,
+    len: usize,
+    seed: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    score: f64,
+    member: String,
+    backward: Option<usize>,
+    levels: Vec<Level>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Level {
+    forward: Option<usize>,
+    span: usize,
+}
+
+pub struct Iter<'a> {
+    list: &'a SkipList,
+    next: Option<usize>,
+    remaining: usize,
+    rev: bool,
+}
+
+// The head node is a sentinel stored at index 0.
+const HEAD: usize = 0;
+
+impl SkipList {
+    pub fn new() -> Self {
+        let head = Node {
+            score: 0.0,
+            member: String::new(),
+            backward: None,
+            levels: vec![Level::default(); SKIPLIST_MAX_LEVEL],
+        };
+        Self {
+            nodes: vec![head],
+            free: Vec::new(),
+            tail: None,
+            level: 1,
+            len: 0,
+            seed: RandomState::new().build_hasher().finish() | 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The caller must make sure the (score, member) pair is not in the list yet,
+    // and that the score is not NaN.
+    pub fn insert(&mut self, score: f64, member: String) {
+        let mut update = [HEAD; SKIPLIST_MAX_LEVEL];
+        let mut rank = [0usize; SKIPLIST_MAX_LEVEL];
+
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            rank[i] = if i == self.level - 1 { 0 } else { rank[i + 1] };
+            while let Some(f) = self.nodes[x].levels[i].forward {
+                if self.cmp_node(f, score, &member) != Ordering::Less {
+                    break;
+                }
+                rank[i] += self.nodes[x].levels[i].span;
+                x = f;
+            }
+            update[i] = x;
+        }
+
+        let level = self.random_level();
+        if level > self.level {
+            for i in self.level..level {
+                rank[i] = 0;
+                update[i] = HEAD;
+                self.nodes[HEAD].levels[i].span = self.len;
+            }
+            self.level = level;
+        }
+
+        let node = Node {
+            score,
+            member,
+            backward: if update[0] == HEAD {
+                None
+            } else {
+                Some(update[0])
+            },
+            levels: vec![Level::default(); level],
+        };
+        let x = self.alloc(node);
+
+        for i in 0..level {
+            let prev = self.nodes[update[i]].levels[i];
+            self.nodes[x].levels[i] = Level {
+                forward: prev.forward,
+                span: prev.span - (rank[0] - rank[i]),
+            };
+            self.nodes[update[i]].levels[i] = Level {
+                forward: Some(x),
+                span: rank[0] - rank[i] + 1,
+            };
+        }
+        for (i, &prev) in update.iter().enumerate().take(self.level).skip(level) {
+            self.nodes[prev].levels[i].span += 1;
+        }
+
+        match self.nodes[x].levels[0].forward {
+            Some(f) => self.nodes[f].backward = Some(x),
+            None => self.tail = Some(x),
+        }
+        self.len += 1;
+    }
+
+    // Returns false if the (score, member) pair is not in the list.
+    pub fn remove(&mut self, score: f64, member: &str) -> bool {
+        let mut update = [HEAD; SKIPLIST_MAX_LEVEL];
+
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(f) = self.nodes[x].levels[i].forward {
+                if self.cmp_node(f, score, member) != Ordering::Less {
+                    break;
+                }
+                x = f;
+            }
+            update[i] = x;
+        }
+
+        match self.nodes[x].levels[0].forward {
+            Some(x) if self.cmp_node(x, score, member) == Ordering::Equal => {
+                self.unlink(x, &update);
+                self.release(x);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // 0-based rank of the (score, member) pair.
+    pub fn rank(&self, score: f64, member: &str) -> Option<usize> {
+        let mut rank = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(f) = self.nodes[x].levels[i].forward {
+                if self.cmp_node(f, score, member) == Ordering::Greater {
+                    break;
+                }
+                rank += self.nodes[x].levels[i].span;
+                x = f;
+            }
+            if x != HEAD && self.cmp_node(x, score, member) == Ordering::Equal {
+                return Some(rank - 1);
+            }
+        }
+        None
+    }
+
+    pub fn get_by_rank(&self, rank: usize) -> Option<(f64, &str)> {
+        self.node_by_rank(rank).map(|x| {
+            let node = &self.nodes[x];
+            (node.score, node.member.as_str())
+        })
+    }
+
+    // Number of leading elements that satisfy the predicate.
+    // The predicate must be monotone: once it turns false it stays false for the rest of the list.
+    pub fn count_while(&self, pred: impl Fn(f64, &str) -> bool) -> usize {
+        let mut rank = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(f) = self.nodes[x].levels[i].forward {
+                let node = &self.nodes[f];
+                if !pred(node.score, &node.member) {
+                    break;
+                }
+                rank += self.nodes[x].levels[i].span;
+                x = f;
+            }
+        }
+        rank
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        self.iter_from(0)
+    }
+
+    // Iterates in ascending order starting at the given rank.
+    pub fn iter_from(&self, rank: usize) -> Iter<'_> {
+        Iter {
+            list: self,
+            next: self.node_by_rank(rank),
+            remaining: self.len.saturating_sub(rank),
+            rev: false,
+        }
+    }
+
+    // Iterates in descending order starting at the given rank (counted from the start).
+    pub fn rev_iter_from(&self, rank: usize) -> Iter<'_> {
+        let next = self.node_by_rank(rank);
+        Iter {
+            list: self,
+            next,
+            remaining: if next.is_some() { rank + 1 } else { 0 },
+            rev: true,
+        }
+    }
+
+    fn node_by_rank(&self, rank: usize) -> Option<usize> {
+        if rank >= self.len {
+            return None;
+        }
+        if rank == self.len - 1 {
+            return self.tail;
+        }
+
+        // Spans are 1-based: the first element is at distance 1 from the head.
+        let target = rank + 1;
+        let mut traversed = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(f) = self.nodes[x].levels[i].forward {
+                if traversed + self.nodes[x].levels[i].span > target {
+                    break;
+                }
+                traversed += self.nodes[x].levels[i].span;
+                x = f;
+            }
+            if traversed == target {
+                return Some(x);
+            }
+        }
+        None
+    }
+
+    fn cmp_node(&self, x: usize, score: f64, member: &str) -> Ordering {
+        let node = &self.nodes[x];
+        cmp_score_member(node.score, &node.member, score, member)
+    }
+
+    fn unlink(&mut self, x: usize, update: &[usize; SKIPLIST_MAX_LEVEL]) {
+        for (i, &prev) in update.iter().enumerate().take(self.level) {
+            if self.nodes[prev].levels[i].forward == Some(x) {
+                let removed = self.nodes[x].levels[i];
+                let prev = &mut self.nodes[prev].levels[i];
+                prev.span += removed.span;
+                prev.span -= 1;
+                prev.forward = removed.forward;
+            } else {
+                self.nodes[prev].levels[i].span -= 1;
+            }
+        }
+
+        let backward = self.nodes[x].backward;
+        match self.nodes[x].levels[0].forward {
+            Some(f) => self.nodes[f].backward = backward,
+            None => self.tail = backward,
+        }
+
+        while self.level > 1 && self.nodes[HEAD].levels[self.level - 1].forward.is_none() {
+            self.level -= 1;
+        }
+        self.len -= 1;
+    }
+
+    fn alloc(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(x) => {
+                self.nodes[x] = node;
+                x
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn release(&mut self, x: usize) {
+        let node = &mut self.nodes[x];
+        node.member = String::new();
+        node.levels = Vec::new();
+        node.backward = None;
+        self.free.push(x);
+    }
+
+    fn random_level(&mut self) -> usize {
+        // xorshift64
+        let mut level = 1;
+        loop {
+            self.seed ^= self.seed << 13;
+            self.seed ^= self.seed >> 7;
+            self.seed ^= self.seed << 17;
+            if level >= SKIPLIST_MAX_LEVEL || !self.seed.is_multiple_of(SKIPLIST_P) {
+                return level;
+            }
+            level += 1;
+        }
+    }
+}
+
+impl Default for SkipList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (f64, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = self.next?;
+        let node = &self.list.nodes[x];
+        self.next = if self.rev {
+            node.backward
+        } else {
+            node.levels[0].forward
+        };
+        self.remaining -= 1;
+        Some((node.score, node.member.as_str()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_skiplist_insert_rank() {
+        let mut list = SkipList::new();
+        list.insert(2.0, "b".to_string());
+        list.insert(1.0, "a".to_string());
+        list.insert(2.0, "a".to_string());
+        list.insert(3.0, "c".to_string());
+
+        assert_eq!(list.len(), 4);
+        assert_eq!(
+            list.iter().collect::<Vec<_>>(),
+            vec![(1.0, "a"), (2.0, "a"), (2.0, "b"), (3.0, "c")]
+        );
+        assert_eq!(list.rank(2.0, "b"), Some(2));
+        assert_eq!(list.rank(2.0, "c"), None);
+        assert_eq!(list.get_by_rank(1), Some((2.0, "a")));
+        assert_eq!(list.get_by_rank(4), None);
+        assert_eq!(list.count_while(|score, _| score < 2.0), 1);
+        assert_eq!(
+            list.rev_iter_from(2).collect::<Vec<_>>(),
+            vec![(2.0, "b"), (2.0, "a"), (1.0, "a")]
+        );
+    }
+
+    #[test]
+    fn test_skiplist_remove() {
+        let mut list = SkipList::new();
+        for i in 0..100 {
+            list.insert(i as f64, i.to_string());
+        }
+        for i in (0..100).step_by(2) {
+            assert!(list.remove(i as f64, &i.to_string()));
+        }
+        assert!(!list.remove(0.0, "0"));
+        assert!(!list.remove(2.0, "1"));
+        assert_eq!(list.len(), 50);
+        assert_eq!(list.get_by_rank(0), Some((1.0, "1")));
+        assert_eq!(list.rank(99.0, "99"), Some(49));
+        assert_eq!(list.iter_from(48).count(), 2);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(i8, u8),
+        Remove(u8),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            (any::<i8>(), 0u8..32).prop_map(|(score, member)| Op::Insert(score, member)),
+            (0u8..32).prop_map(Op::Remove),
+        ]
+    }
+
+    proptest! {
+        // Compare against a model that keeps member -> score and sorts on demand.
+        #[test]
+        fn test_skiplist_matches_model(ops in prop::collection::vec(op(), 1..200)) {
+            let mut list = SkipList::new();
+            let mut model: BTreeMap<String, f64> = BTreeMap::new();
+
+            for op in ops {
+                match op {
+                    Op::Insert(score, member) => {
+                        let member = member.to_string();
+                        if let Some(old) = model.insert(member.clone(), score as f64) {
+                            prop_assert!(list.remove(old, &member));
+                        }
+                        list.insert(score as f64, member);
+                    }
+                    Op::Remove(member) => {
+                        let member = member.to_string();
+                        if let Some(score) = model.remove(&member) {
+                            prop_assert!(list.remove(score, &member));
+                        }
+                    }
+                }
+
+                let mut sorted: Vec<(f64, &str)> =
+                    model.iter().map(|(m, s)| (*s, m.as_str())).collect();
+                sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then(a.1.cmp(b.1)));
+
+                prop_assert_eq!(list.len(), sorted.len());
+                prop_assert_eq!(list.iter().collect::<Vec<_>>(), sorted.clone());
+                for (i, (score, member)) in sorted.iter().enumerate() {
+                    prop_assert_eq!(list.rank(*score, member), Some(i));
+                    prop_assert_eq!(list.get_by_rank(i), Some((*score, *member)));
+                }
+                let below_zero = sorted.iter().filter(|(s, _)| *s < 0.0).count();
+                prop_assert_eq!(list.count_while(|s, _| s < 0.0), below_zero);
+            }
+        }
+    }
+}
@@ -0,0 +1,1232 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use bytes::Bytes;
+
+use super::dump::{Dump, Reader, Writer};
+use super::BackendError;
+
+// The ID of a stream entry: the unix time in milliseconds it was added at and a sequence
+// number among the entries of the same millisecond, written "<ms>-<seq>". IDs only grow
+// within a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+// The ID XADD gives the new entry: "*" generates it from the clock, "<ms>-*" only generates
+// the sequence number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamAddId {
+    Auto,
+    AutoSeq(u64),
+    Explicit(StreamId),
+}
+
+// The field-value pairs of an entry, in the order they were given.
+pub type StreamFields = Vec<(Bytes, Bytes)>;
+
+// How XTRIM and XADD trim a stream, from its first entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamTrimOptions {
+    pub strategy: StreamTrimStrategy,
+    // "~", only trimming whole nodes of entries like Redis, so possibly fewer entries.
+    pub approximate: bool,
+    // The most entries trimmed at once when approximate, None for no limit.
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamTrimStrategy {
+    // Keeps up to this many entries.
+    MaxLen(usize),
+    // Keeps the entries with IDs from this one.
+    MinId(StreamId),
+}
+
+// The entries of a stream ordered by ID, like Redis's radix tree of listpacks, along with its
+// consumer groups by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stream {
+    entries: BTreeMap<StreamId, StreamFields>,
+    // The ID of the last entry added, which new IDs must be greater than even once the entry
+    // has been deleted.
+    last_id: StreamId,
+    // The greatest ID deleted by XDEL, 0-0 if none, as deletions break counting entries.
+    max_deleted_id: StreamId,
+    // The number of entries ever added, tracked to tell the lag of the consumer groups.
+    entries_added: u64,
+    groups: BTreeMap<String, ConsumerGroup>,
+}
+
+// A group of consumers sharing the entries of a stream, each entry being delivered to only
+// one of them. Delivered entries stay pending until acknowledged, in the pending entries
+// list (PEL) of the group, and in the one of the consumer they were delivered to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConsumerGroup {
+    // The ID of the last entry delivered, later ones are delivered by reading ">".
+    last_id: StreamId,
+    // The number of entries delivered up to `last_id` included, None if unknown.
+    entries_read: Option<u64>,
+    pending: BTreeMap<StreamId, PendingEntry>,
+    consumers: BTreeMap<String, Consumer>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingEntry {
+    pub consumer: String,
+    // Unix time in milliseconds of the last delivery.
+    pub delivered_at: u64,
+    pub delivery_count: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Consumer {
+    pending: BTreeSet<StreamId>,
+    // Unix time in milliseconds of the last read by the consumer.
+    seen_at: u64,
+    // Unix time in milliseconds the consumer was last delivered or claimed entries, if ever.
+    active_at: Option<u64>,
+}
+
+// The pending entries of a group in short, as XPENDING replies without a range.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamPendingSummary {
+    pub count: usize,
+    // The smallest and greatest pending IDs, None if there are none.
+    pub range: Option<(StreamId, StreamId)>,
+    // The number of pending entries of each consumer having some.
+    pub consumers: Vec<(String, usize)>,
+}
+
+// A pending entry, as XPENDING replies it with a range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamPendingEntry {
+    pub id: StreamId,
+    pub consumer: String,
+    // Milliseconds since the last delivery.
+    pub idle: u64,
+    pub delivery_count: u64,
+}
+
+// The options of XCLAIM, XAUTOCLAIM only having `min_idle` and `just_id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StreamClaimOptions {
+    // Entries delivered less than this many milliseconds ago are not claimed.
+    pub min_idle: u64,
+    // Unix time in milliseconds set as the last delivery of the claimed entries, now if None.
+    pub delivered_at: Option<u64>,
+    // Set as the delivery count, which is otherwise incremented unless `just_id`.
+    pub retry_count: Option<u64>,
+    // Also claims the entries of the stream that are not pending.
+    pub force: bool,
+    pub just_id: bool,
+    // Set as the ID of the last entry delivered to the group, if greater.
+    pub last_id: Option<StreamId>,
+}
+
+// What XAUTOCLAIM did.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamAutoClaimResult {
+    // Where the next call resumes scanning the pending entries, 0-0 once they have all been.
+    pub next: StreamId,
+    pub claimed: Vec<(StreamId, StreamFields)>,
+    // The pending entries that had been deleted from the stream, no longer pending.
+    pub deleted: Vec<StreamId>,
+}
+
+// What XINFO STREAM replies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamInfo {
+    pub length: usize,
+    pub last_id: StreamId,
+    pub max_deleted_id: StreamId,
+    pub entries_added: u64,
+    // 0-0 if the stream is empty.
+    pub first_id: StreamId,
+    pub groups: usize,
+    pub first_entry: Option<(StreamId, StreamFields)>,
+    pub last_entry: Option<(StreamId, StreamFields)>,
+}
+
+// What XINFO GROUPS replies for each group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamGroupInfo {
+    pub name: String,
+    pub consumers: usize,
+    pub pending: usize,
+    pub last_delivered_id: StreamId,
+    pub entries_read: Option<u64>,
+    // The number of entries not delivered to the group yet, None if deleted entries make it
+    // unknown.
+    pub lag: Option<u64>,
+}
+
+// What XINFO CONSUMERS replies for each consumer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamConsumerInfo {
+    pub name: String,
+    pub pending: usize,
+    // Milliseconds since the last read.
+    pub idle: u64,
+    // Milliseconds since entries were last delivered to or claimed by the consumer, None if
+    // never.
+    pub inactive: Option<u64>,
+}
+
+// The entries per node of Redis's radix tree, which approximate trimming removes at once.
+const STREAM_NODE_ENTRIES: usize = 100;
+
+// Like Redis, XAUTOCLAIM looks at up to this many pending entries per entry it may claim.
+const AUTO_CLAIM_ATTEMPTS_FACTOR: usize = 10;
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> Self {
+        Self { ms, seq }
+    }
+
+    // The smallest ID greater than this one, None for the maximum.
+    pub fn next(self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => self.ms.checked_add(1).map(|ms| StreamId::new(ms, 0)),
+        }
+    }
+
+    // The greatest ID smaller than this one, None for the minimum.
+    pub fn prev(self) -> Option<StreamId> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => self.ms.checked_sub(1).map(|ms| StreamId::new(ms, u64::MAX)),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+impl Stream {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    // Appends the entry, generating its ID like Redis: from the clock `now`, or from the last
+    // ID when the clock went backward or several entries are added within a millisecond.
+    pub fn add(
+        &mut self,
+        id: StreamAddId,
+        fields: StreamFields,
+        now: u64,
+    ) -> Result<StreamId, BackendError> {
+        let last = self.last_id;
+        let id = match id {
+            StreamAddId::Auto if now > last.ms => StreamId::new(now, 0),
+            StreamAddId::Auto => last.next().ok_or(BackendError::StreamExhausted)?,
+            StreamAddId::AutoSeq(ms) if ms > last.ms => StreamId::new(ms, 0),
+            StreamAddId::AutoSeq(ms) => match last.seq.checked_add(1) {
+                Some(seq) if ms == last.ms => StreamId::new(ms, seq),
+                _ => return Err(BackendError::StreamIdTooSmall),
+            },
+            StreamAddId::Explicit(id) if id == StreamId::MIN => {
+                return Err(BackendError::StreamIdZero)
+            }
+            StreamAddId::Explicit(id) if id <= last => return Err(BackendError::StreamIdTooSmall),
+            StreamAddId::Explicit(id) => id,
+        };
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        self.entries_added += 1;
+        Ok(id)
+    }
+
+    // Removes entries from the first one per `trim`, returning how many.
+    pub fn trim(&mut self, trim: &StreamTrimOptions) -> usize {
+        let excess = match trim.strategy {
+            StreamTrimStrategy::MaxLen(len) => self.entries.len().saturating_sub(len),
+            StreamTrimStrategy::MinId(id) => self.entries.range(..id).count(),
+        };
+        let trimmed = if trim.approximate {
+            let excess = excess.min(trim.limit.unwrap_or(usize::MAX));
+            excess - excess % STREAM_NODE_ENTRIES
+        } else {
+            excess
+        };
+        for _ in 0..trimmed {
+            self.entries.pop_first();
+        }
+        trimmed
+    }
+
+    // Returns the number of entries deleted. They stay pending in the consumer groups, like
+    // in Redis.
+    pub fn delete(&mut self, ids: &[StreamId]) -> usize {
+        let mut deleted = 0;
+        for id in ids {
+            if self.entries.remove(id).is_some() {
+                self.max_deleted_id = self.max_deleted_id.max(*id);
+                deleted += 1;
+            }
+        }
+        deleted
+    }
+
+    pub fn create_group(&mut self, name: String, last_id: StreamId) -> bool {
+        if self.groups.contains_key(&name) {
+            return false;
+        }
+        let group = ConsumerGroup {
+            last_id,
+            ..Default::default()
+        };
+        self.groups.insert(name, group);
+        true
+    }
+
+    pub fn destroy_group(&mut self, name: &str) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    pub fn group(&self, name: &str) -> Option<&ConsumerGroup> {
+        self.groups.get(name)
+    }
+
+    pub fn group_mut(&mut self, name: &str) -> Option<&mut ConsumerGroup> {
+        self.groups.get_mut(name)
+    }
+
+    // Reads up to `count` entries as the consumer of the group, created if needed. Without an
+    // ID, like ">", the entries never delivered to the group are delivered to the consumer,
+    // pending unless `no_ack` is set. Otherwise the entries pending for the consumer after
+    // the ID are delivered again, without their fields if they have been deleted since. None
+    // if the group does not exist.
+    pub fn read_group(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        after: Option<StreamId>,
+        count: Option<usize>,
+        no_ack: bool,
+        now: u64,
+    ) -> Option<Vec<(StreamId, Option<StreamFields>)>> {
+        let name = group;
+        let group = self.groups.get_mut(name)?;
+        group.consumer_mut(consumer, now).seen_at = now;
+        let count = count.unwrap_or(usize::MAX);
+
+        let Some(after) = after else {
+            let entries: Vec<_> = match group.last_id.next() {
+                Some(start) => self
+                    .entries
+                    .range(start..)
+                    .take(count)
+                    .map(|(id, fields)| (*id, Some(fields.clone())))
+                    .collect(),
+                None => Vec::new(),
+            };
+            let Some((last, _)) = entries.last() else {
+                return Some(entries);
+            };
+            // The read counter goes on unless entries it would count have been deleted, then
+            // it's told from the position of the entry, like in Redis.
+            let mut entries_read = self.groups[name].entries_read;
+            for (id, _) in &entries {
+                entries_read = match entries_read {
+                    Some(read) if !self.has_tombstones(*id) => Some(read + 1),
+                    _ => self.entries_up_to(*id),
+                };
+            }
+            let group = self.groups.get_mut(name)?;
+            group.last_id = *last;
+            group.entries_read = entries_read;
+            if !no_ack {
+                for (id, _) in &entries {
+                    group.deliver(*id, consumer, now);
+                }
+                group.consumer_mut(consumer, now).active_at = Some(now);
+            }
+            return Some(entries);
+        };
+
+        let Some(start) = after.next() else {
+            return Some(Vec::new());
+        };
+        let ids: Vec<StreamId> = group.consumers[consumer]
+            .pending
+            .range(start..)
+            .take(count)
+            .copied()
+            .collect();
+        let entries = ids
+            .into_iter()
+            .map(|id| {
+                if let Some(entry) = group.pending.get_mut(&id) {
+                    entry.delivered_at = now;
+                    entry.delivery_count += 1;
+                }
+                (id, self.entries.get(&id).cloned())
+            })
+            .collect();
+        Some(entries)
+    }
+
+    pub fn info(&self) -> StreamInfo {
+        let first_entry = self.entries.first_key_value();
+        StreamInfo {
+            length: self.entries.len(),
+            last_id: self.last_id,
+            max_deleted_id: self.max_deleted_id,
+            entries_added: self.entries_added,
+            first_id: first_entry.map_or(StreamId::MIN, |(id, _)| *id),
+            groups: self.groups.len(),
+            first_entry: first_entry.map(|(id, fields)| (*id, fields.clone())),
+            last_entry: self
+                .entries
+                .last_key_value()
+                .map(|(id, fields)| (*id, fields.clone())),
+        }
+    }
+
+    pub fn group_infos(&self) -> Vec<StreamGroupInfo> {
+        self.groups
+            .iter()
+            .map(|(name, group)| StreamGroupInfo {
+                name: name.clone(),
+                consumers: group.consumers.len(),
+                pending: group.pending.len(),
+                last_delivered_id: group.last_id,
+                entries_read: group.entries_read,
+                lag: self.lag(group),
+            })
+            .collect()
+    }
+
+    // The number of entries added but not delivered to the group, like Redis: counted from
+    // the entries read by the group, or from the position of its last ID in the stream.
+    fn lag(&self, group: &ConsumerGroup) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+        let read = match group.entries_read {
+            Some(read) if !self.has_tombstones(group.last_id) => Some(read),
+            _ => self.entries_up_to(group.last_id),
+        }?;
+        Some(self.entries_added.saturating_sub(read))
+    }
+
+    // Whether entries from the ID on have been deleted.
+    fn has_tombstones(&self, from: StreamId) -> bool {
+        !self.entries.is_empty()
+            && self.max_deleted_id != StreamId::MIN
+            && from <= self.max_deleted_id
+    }
+
+    // The number of entries ever added up to the ID included, None if it can't be told
+    // because the ID is in the future, or entries around it have been deleted.
+    fn entries_up_to(&self, id: StreamId) -> Option<u64> {
+        if self.entries_added == 0 || id == self.last_id {
+            return Some(self.entries_added);
+        }
+        if id > self.last_id {
+            return None;
+        }
+        let Some((first, _)) = self.entries.first_key_value() else {
+            return Some(self.entries_added);
+        };
+        // Only trimmed entries come before the first one, which are still counted.
+        if self.max_deleted_id < *first {
+            let before = self.entries_added - self.entries.len() as u64;
+            if id < *first {
+                return Some(before);
+            }
+            if id == *first {
+                return Some(before + 1);
+            }
+        }
+        None
+    }
+
+    // Transfers to the consumer the pending entries among `ids` idle for long enough, see
+    // `StreamClaimOptions`. The entries deleted from the stream are no longer pending instead.
+    // None if the group does not exist.
+    pub fn claim(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        ids: &[StreamId],
+        options: &StreamClaimOptions,
+        now: u64,
+    ) -> Option<Vec<(StreamId, StreamFields)>> {
+        let group = self.groups.get_mut(group)?;
+        if let Some(last_id) = options.last_id {
+            group.last_id = group.last_id.max(last_id);
+        }
+
+        let mut claimed = Vec::new();
+        for &id in ids {
+            let Some(fields) = self.entries.get(&id) else {
+                group.ack(&[id]);
+                continue;
+            };
+            match group.pending.get(&id) {
+                Some(entry) if now.saturating_sub(entry.delivered_at) < options.min_idle => {
+                    continue
+                }
+                None if !options.force => continue,
+                _ => {}
+            }
+            group.claim(id, consumer, options, now);
+            claimed.push((id, fields.clone()));
+        }
+        Some(claimed)
+    }
+
+    // Scans the pending entries from `start`, transferring up to `count` of them to the
+    // consumer like `claim`. None if the group does not exist.
+    pub fn auto_claim(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        start: StreamId,
+        count: usize,
+        options: &StreamClaimOptions,
+        now: u64,
+    ) -> Option<StreamAutoClaimResult> {
+        let group = self.groups.get_mut(group)?;
+        let mut attempts = count.saturating_mul(AUTO_CLAIM_ATTEMPTS_FACTOR);
+        let mut result = StreamAutoClaimResult::default();
+
+        // The smallest ID not looked at yet, None once past the greatest.
+        let mut cursor = Some(start);
+        while let Some(from) = cursor {
+            if attempts == 0 || result.claimed.len() == count {
+                break;
+            }
+            let Some((&id, entry)) = group.pending.range(from..).next() else {
+                cursor = None;
+                break;
+            };
+            attempts -= 1;
+            cursor = id.next();
+            let idle = now.saturating_sub(entry.delivered_at);
+            match self.entries.get(&id) {
+                None => {
+                    group.ack(&[id]);
+                    result.deleted.push(id);
+                }
+                Some(_) if idle < options.min_idle => {}
+                Some(fields) => {
+                    group.claim(id, consumer, options, now);
+                    result.claimed.push((id, fields.clone()));
+                }
+            }
+        }
+        result.next = cursor
+            .and_then(|from| group.pending.range(from..).next())
+            .map_or(StreamId::MIN, |(id, _)| *id);
+        Some(result)
+    }
+
+    // The entries with IDs between start and end included, from the last one if `rev`.
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        rev: bool,
+    ) -> Box<dyn Iterator<Item = (&StreamId, &StreamFields)> + '_> {
+        // BTreeMap panics on ranges ending before they start.
+        if start > end {
+            return Box::new(std::iter::empty());
+        }
+        let range = self.entries.range(start..=end);
+        if rev {
+            Box::new(range.rev())
+        } else {
+            Box::new(range)
+        }
+    }
+}
+
+impl ConsumerGroup {
+    // The entries read are counted again from the ID.
+    pub fn set_last_id(&mut self, id: StreamId) {
+        self.last_id = id;
+        self.entries_read = None;
+    }
+
+    // Returns false if the consumer already exists.
+    pub fn create_consumer(&mut self, name: &str, now: u64) -> bool {
+        let exists = self.consumers.contains_key(name);
+        self.consumer_mut(name, now);
+        !exists
+    }
+
+    // Returns the number of entries that were pending for the consumer, None if it does not
+    // exist. They are no longer pending for the group either.
+    pub fn delete_consumer(&mut self, name: &str) -> Option<usize> {
+        let consumer = self.consumers.remove(name)?;
+        for id in &consumer.pending {
+            self.pending.remove(id);
+        }
+        Some(consumer.pending.len())
+    }
+
+    // Returns the number of entries that were pending.
+    pub fn ack(&mut self, ids: &[StreamId]) -> usize {
+        let mut acked = 0;
+        for id in ids {
+            if let Some(entry) = self.pending.remove(id) {
+                if let Some(consumer) = self.consumers.get_mut(&entry.consumer) {
+                    consumer.pending.remove(id);
+                }
+                acked += 1;
+            }
+        }
+        acked
+    }
+
+    pub fn consumer_infos(&self, now: u64) -> Vec<StreamConsumerInfo> {
+        self.consumers
+            .iter()
+            .map(|(name, consumer)| StreamConsumerInfo {
+                name: name.clone(),
+                pending: consumer.pending.len(),
+                idle: now.saturating_sub(consumer.seen_at),
+                inactive: consumer.active_at.map(|at| now.saturating_sub(at)),
+            })
+            .collect()
+    }
+
+    pub fn pending_summary(&self) -> StreamPendingSummary {
+        let range = match (
+            self.pending.first_key_value(),
+            self.pending.last_key_value(),
+        ) {
+            (Some((first, _)), Some((last, _))) => Some((*first, *last)),
+            _ => None,
+        };
+        let consumers = self
+            .consumers
+            .iter()
+            .filter(|(_, consumer)| !consumer.pending.is_empty())
+            .map(|(name, consumer)| (name.clone(), consumer.pending.len()))
+            .collect();
+        StreamPendingSummary {
+            count: self.pending.len(),
+            range,
+            consumers,
+        }
+    }
+
+    // Up to `count` pending entries with IDs between start and end included, idle for at
+    // least `min_idle` milliseconds, and only the consumer's if given.
+    pub fn pending_range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        count: usize,
+        consumer: Option<&str>,
+        min_idle: u64,
+        now: u64,
+    ) -> Vec<StreamPendingEntry> {
+        // BTreeMap panics on ranges ending before they start.
+        if start > end {
+            return Vec::new();
+        }
+        self.pending
+            .range(start..=end)
+            .map(|(id, entry)| StreamPendingEntry {
+                id: *id,
+                consumer: entry.consumer.clone(),
+                idle: now.saturating_sub(entry.delivered_at),
+                delivery_count: entry.delivery_count,
+            })
+            .filter(|entry| {
+                entry.idle >= min_idle && consumer.is_none_or(|name| entry.consumer == name)
+            })
+            .take(count)
+            .collect()
+    }
+
+    fn consumer_mut(&mut self, name: &str, now: u64) -> &mut Consumer {
+        self.consumers
+            .entry(name.to_string())
+            .or_insert_with(|| Consumer {
+                pending: BTreeSet::new(),
+                seen_at: now,
+                active_at: None,
+            })
+    }
+
+    // Makes the entry pending for the consumer. An entry already pending, when the group's
+    // last ID has been moved back, changes owner and counts as delivered for the first time,
+    // like in Redis.
+    fn deliver(&mut self, id: StreamId, consumer: &str, now: u64) {
+        let entry = PendingEntry {
+            consumer: consumer.to_string(),
+            delivered_at: now,
+            delivery_count: 1,
+        };
+        if let Some(previous) = self.pending.insert(id, entry) {
+            if let Some(owner) = self.consumers.get_mut(&previous.consumer) {
+                owner.pending.remove(&id);
+            }
+        }
+        self.consumer_mut(consumer, now).pending.insert(id);
+    }
+
+    // Makes the entry pending for the consumer instead of its previous owner, if any, and
+    // counts it as delivered again per the options.
+    fn claim(&mut self, id: StreamId, consumer: &str, options: &StreamClaimOptions, now: u64) {
+        if let Some(entry) = self.pending.get(&id) {
+            if let Some(owner) = self.consumers.get_mut(&entry.consumer) {
+                owner.pending.remove(&id);
+            }
+        }
+        let owner = self.consumer_mut(consumer, now);
+        owner.pending.insert(id);
+        owner.seen_at = now;
+        owner.active_at = Some(now);
+        // Only forced claims make entries pending, as never delivered.
+        let entry = self.pending.entry(id).or_insert_with(|| PendingEntry {
+            consumer: String::new(),
+            delivered_at: now,
+            delivery_count: 0,
+        });
+        entry.consumer = consumer.to_string();
+        entry.delivered_at = options.delivered_at.unwrap_or(now);
+        match options.retry_count {
+            Some(count) => entry.delivery_count = count,
+            None if !options.just_id => entry.delivery_count += 1,
+            None => {}
+        }
+    }
+}
+
+impl Dump for Stream {
+    const TAG: u8 = 8;
+
+    fn write(&self, w: &mut Writer) {
+        w.put_u64(self.last_id.ms);
+        w.put_u64(self.last_id.seq);
+        w.put_u64(self.max_deleted_id.ms);
+        w.put_u64(self.max_deleted_id.seq);
+        w.put_u64(self.entries_added);
+        w.put_u64(self.entries.len() as u64);
+        for (id, fields) in &self.entries {
+            w.put_u64(id.ms);
+            w.put_u64(id.seq);
+            w.put_u64(fields.len() as u64);
+            for (field, value) in fields {
+                w.put_bytes(field);
+                w.put_bytes(value);
+            }
+        }
+
+        w.put_u64(self.groups.len() as u64);
+        for (name, group) in &self.groups {
+            w.put_bytes(name.as_bytes());
+            w.put_u64(group.last_id.ms);
+            w.put_u64(group.last_id.seq);
+            put_option(w, group.entries_read);
+            w.put_u64(group.consumers.len() as u64);
+            for (name, consumer) in &group.consumers {
+                w.put_bytes(name.as_bytes());
+                w.put_u64(consumer.seen_at);
+                put_option(w, consumer.active_at);
+            }
+            w.put_u64(group.pending.len() as u64);
+            for (id, entry) in &group.pending {
+                w.put_u64(id.ms);
+                w.put_u64(id.seq);
+                w.put_bytes(entry.consumer.as_bytes());
+                w.put_u64(entry.delivered_at);
+                w.put_u64(entry.delivery_count);
+            }
+        }
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let mut stream = Stream {
+            last_id: StreamId::new(r.u64()?, r.u64()?),
+            max_deleted_id: StreamId::new(r.u64()?, r.u64()?),
+            entries_added: r.u64()?,
+            ..Default::default()
+        };
+        for _ in 0..r.len()? {
+            let id = StreamId::new(r.u64()?, r.u64()?);
+            let mut fields = Vec::new();
+            for _ in 0..r.len()? {
+                let field = Bytes::copy_from_slice(r.bytes()?);
+                let value = Bytes::copy_from_slice(r.bytes()?);
+                fields.push((field, value));
+            }
+            stream.entries.insert(id, fields);
+        }
+
+        for _ in 0..r.len()? {
+            let name = r.string()?;
+            let mut group = ConsumerGroup {
+                last_id: StreamId::new(r.u64()?, r.u64()?),
+                entries_read: read_option(r)?,
+                ..Default::default()
+            };
+            for _ in 0..r.len()? {
+                let name = r.string()?;
+                let consumer = Consumer {
+                    pending: BTreeSet::new(),
+                    seen_at: r.u64()?,
+                    active_at: read_option(r)?,
+                };
+                group.consumers.insert(name, consumer);
+            }
+            // The pending entries of the consumers are rebuilt from the group's.
+            for _ in 0..r.len()? {
+                let id = StreamId::new(r.u64()?, r.u64()?);
+                let entry = PendingEntry {
+                    consumer: r.string()?,
+                    delivered_at: r.u64()?,
+                    delivery_count: r.u64()?,
+                };
+                group.consumers.get_mut(&entry.consumer)?.pending.insert(id);
+                group.pending.insert(id, entry);
+            }
+            stream.groups.insert(name, group);
+        }
+        // The last ID can't be smaller than the ID of an entry.
+        match stream.entries.last_key_value() {
+            Some((id, _)) if *id > stream.last_id => None,
+            _ => Some(stream),
+        }
+    }
+}
+
+fn put_option(w: &mut Writer, n: Option<u64>) {
+    match n {
+        Some(n) => {
+            w.put_u8(1);
+            w.put_u64(n);
+        }
+        None => w.put_u8(0),
+    }
+}
+
+fn read_option(r: &mut Reader) -> Option<Option<u64>> {
+    match r.u8()? {
+        0 => Some(None),
+        1 => r.u64().map(Some),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{
+        PendingEntry, Stream, StreamAddId, StreamAutoClaimResult, StreamClaimOptions, StreamId,
+        StreamPendingEntry, StreamPendingSummary, StreamTrimOptions, StreamTrimStrategy,
+    };
+    use crate::backend::dump::{check, dump, load};
+    use crate::BackendError;
+
+    fn fields(field: &str, value: &str) -> Vec<(Bytes, Bytes)> {
+        vec![(field.to_string().into(), value.to_string().into())]
+    }
+
+    #[test]
+    fn test_stream_id() {
+        let id = StreamId::new(5, u64::MAX);
+        assert_eq!(id.next(), Some(StreamId::new(6, 0)));
+        assert_eq!(id.prev(), Some(StreamId::new(5, u64::MAX - 1)));
+        assert_eq!(StreamId::new(6, 0).prev(), Some(id));
+        assert_eq!(StreamId::MAX.next(), None);
+        assert_eq!(StreamId::MIN.prev(), None);
+        assert!(StreamId::new(1, 9) < StreamId::new(2, 0));
+        assert_eq!(id.to_string(), format!("5-{}", u64::MAX));
+    }
+
+    #[test]
+    fn test_stream_add() {
+        let mut stream = Stream::default();
+        let add = |stream: &mut Stream, id, now| stream.add(id, fields("f", "v"), now);
+
+        assert_eq!(
+            add(&mut stream, StreamAddId::Explicit(StreamId::MIN), 0),
+            Err(BackendError::StreamIdZero)
+        );
+        assert_eq!(
+            add(&mut stream, StreamAddId::AutoSeq(0), 0),
+            Ok(StreamId::new(0, 1))
+        );
+        assert_eq!(
+            add(&mut stream, StreamAddId::Auto, 10),
+            Ok(StreamId::new(10, 0))
+        );
+        // The clock went backward, or didn't move.
+        assert_eq!(
+            add(&mut stream, StreamAddId::Auto, 5),
+            Ok(StreamId::new(10, 1))
+        );
+        assert_eq!(
+            add(&mut stream, StreamAddId::AutoSeq(10), 0),
+            Ok(StreamId::new(10, 2))
+        );
+        assert_eq!(
+            add(&mut stream, StreamAddId::AutoSeq(9), 0),
+            Err(BackendError::StreamIdTooSmall)
+        );
+        assert_eq!(
+            add(&mut stream, StreamAddId::Explicit(StreamId::new(10, 2)), 0),
+            Err(BackendError::StreamIdTooSmall)
+        );
+        assert_eq!(
+            add(&mut stream, StreamAddId::Explicit(StreamId::new(12, 0)), 0),
+            Ok(StreamId::new(12, 0))
+        );
+        assert_eq!(stream.len(), 5);
+        assert_eq!(stream.last_id(), StreamId::new(12, 0));
+
+        assert_eq!(
+            add(&mut stream, StreamAddId::Explicit(StreamId::MAX), 0),
+            Ok(StreamId::MAX)
+        );
+        assert_eq!(
+            add(&mut stream, StreamAddId::Auto, 0),
+            Err(BackendError::StreamExhausted)
+        );
+        assert_eq!(
+            add(&mut stream, StreamAddId::AutoSeq(u64::MAX), 0),
+            Err(BackendError::StreamIdTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_stream_range() {
+        let mut stream = Stream::default();
+        for ms in 1..=4 {
+            let id = StreamAddId::Explicit(StreamId::new(ms, 0));
+            stream.add(id, fields("f", "v"), 0).unwrap();
+        }
+        let ids = |start, end, rev| -> Vec<u64> {
+            stream.range(start, end, rev).map(|(id, _)| id.ms).collect()
+        };
+
+        assert_eq!(ids(StreamId::MIN, StreamId::MAX, false), [1, 2, 3, 4]);
+        assert_eq!(ids(StreamId::MIN, StreamId::MAX, true), [4, 3, 2, 1]);
+        assert_eq!(ids(StreamId::new(2, 0), StreamId::new(3, 5), false), [2, 3]);
+        assert_eq!(ids(StreamId::new(2, 1), StreamId::new(3, 0), true), [3]);
+        assert!(ids(StreamId::new(3, 0), StreamId::new(2, 0), false).is_empty());
+    }
+
+    #[test]
+    fn test_stream_dump() {
+        let mut stream = Stream::default();
+        stream.add(StreamAddId::Auto, fields("a", "1"), 7).unwrap();
+        let id = StreamAddId::Explicit(StreamId::new(9, 3));
+        stream.add(id, Vec::new(), 0).unwrap();
+
+        let payload = dump(&stream);
+        let (tag, data) = check(&payload).unwrap();
+        assert_eq!(tag, 8);
+        assert_eq!(load::<Stream>(data), Ok(stream));
+    }
+
+    #[test]
+    fn test_stream_trim_and_delete() {
+        let mut stream = Stream::default();
+        for ms in 1..=250 {
+            let id = StreamAddId::Explicit(StreamId::new(ms, 0));
+            stream.add(id, fields("n", &ms.to_string()), 0).unwrap();
+        }
+        let trim = |strategy, approximate, limit| StreamTrimOptions {
+            strategy,
+            approximate,
+            limit,
+        };
+
+        // Approximate trimming only removes whole nodes, up to the limit.
+        let max_len = StreamTrimStrategy::MaxLen(10);
+        assert_eq!(stream.trim(&trim(max_len, true, Some(150))), 100);
+        assert_eq!(stream.trim(&trim(max_len, true, None)), 100);
+        assert_eq!(stream.len(), 50);
+        let min_id = StreamTrimStrategy::MinId(StreamId::new(241, 0));
+        assert_eq!(stream.trim(&trim(min_id, true, None)), 0);
+        assert_eq!(stream.trim(&trim(min_id, false, None)), 40);
+        assert_eq!(stream.trim(&trim(max_len, false, None)), 0);
+        assert_eq!(
+            stream.range(StreamId::MIN, StreamId::MAX, false).count(),
+            10
+        );
+
+        let ids = [
+            StreamId::new(241, 0),
+            StreamId::new(241, 0),
+            StreamId::new(1, 0),
+        ];
+        assert_eq!(stream.delete(&ids), 1);
+        assert_eq!(stream.len(), 9);
+        // Trimming and deleting don't let IDs go back.
+        assert_eq!(stream.last_id(), StreamId::new(250, 0));
+    }
+
+    #[test]
+    fn test_stream_group() {
+        let mut stream = Stream::default();
+        for ms in 1..=3 {
+            let id = StreamAddId::Explicit(StreamId::new(ms, 0));
+            stream.add(id, fields("n", &ms.to_string()), 0).unwrap();
+        }
+        assert!(stream.create_group("g".to_string(), StreamId::MIN));
+        assert!(!stream.create_group("g".to_string(), StreamId::MAX));
+        assert_eq!(stream.read_group("nope", "c", None, None, false, 0), None);
+
+        // New entries are delivered once, across consumers.
+        let read = stream
+            .read_group("g", "alice", None, Some(2), false, 10)
+            .unwrap();
+        let ids: Vec<StreamId> = read.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, [StreamId::new(1, 0), StreamId::new(2, 0)]);
+        assert_eq!(read[0].1, Some(fields("n", "1")));
+        let read = stream
+            .read_group("g", "bob", None, None, false, 20)
+            .unwrap();
+        assert_eq!(read, [(StreamId::new(3, 0), Some(fields("n", "3")))]);
+        let read = stream
+            .read_group("g", "bob", None, None, false, 20)
+            .unwrap();
+        assert!(read.is_empty());
+
+        // The history is the consumer's pending entries, delivered again.
+        let read = stream
+            .read_group("g", "alice", Some(StreamId::new(1, 0)), None, false, 30)
+            .unwrap();
+        assert_eq!(read, [(StreamId::new(2, 0), Some(fields("n", "2")))]);
+        let group = stream.group_mut("g").unwrap();
+        let entry = PendingEntry {
+            consumer: "alice".to_string(),
+            delivered_at: 30,
+            delivery_count: 2,
+        };
+        assert_eq!(group.pending.get(&StreamId::new(2, 0)), Some(&entry));
+
+        let ids = [
+            StreamId::new(1, 0),
+            StreamId::new(1, 0),
+            StreamId::new(9, 0),
+        ];
+        assert_eq!(group.ack(&ids), 1);
+        assert!(!group.create_consumer("alice", 40));
+        assert!(group.create_consumer("carol", 40));
+        assert_eq!(group.delete_consumer("carol"), Some(0));
+        assert_eq!(group.delete_consumer("carol"), None);
+
+        // Moving the last ID back delivers pending entries again to a new owner.
+        group.set_last_id(StreamId::new(2, 0));
+        let read = stream
+            .read_group("g", "alice", None, None, false, 50)
+            .unwrap();
+        assert_eq!(read.len(), 1);
+        let group = stream.group_mut("g").unwrap();
+        assert!(group.consumers["bob"].pending.is_empty());
+        assert_eq!(group.pending[&StreamId::new(3, 0)].consumer, "alice");
+        assert_eq!(group.delete_consumer("alice"), Some(2));
+        assert!(group.pending.is_empty());
+
+        // NOACK does not make entries pending.
+        group.set_last_id(StreamId::MIN);
+        let read = stream.read_group("g", "bob", None, None, true, 60).unwrap();
+        assert_eq!(read.len(), 3);
+        assert!(stream.group_mut("g").unwrap().pending.is_empty());
+
+        assert!(stream.destroy_group("g"));
+        assert!(!stream.destroy_group("g"));
+    }
+
+    #[test]
+    fn test_stream_pending_and_claim() {
+        let mut stream = Stream::default();
+        for ms in 1..=4 {
+            let id = StreamAddId::Explicit(StreamId::new(ms, 0));
+            stream.add(id, fields("n", &ms.to_string()), 0).unwrap();
+        }
+        stream.create_group("g".to_string(), StreamId::MIN);
+        stream.read_group("g", "alice", None, Some(2), false, 100);
+        stream.read_group("g", "bob", None, Some(1), false, 200);
+
+        let group = stream.group("g").unwrap();
+        let summary = StreamPendingSummary {
+            count: 3,
+            range: Some((StreamId::new(1, 0), StreamId::new(3, 0))),
+            consumers: vec![("alice".to_string(), 2), ("bob".to_string(), 1)],
+        };
+        assert_eq!(group.pending_summary(), summary);
+        let pending = group.pending_range(StreamId::MIN, StreamId::MAX, 10, None, 150, 300);
+        let ids: Vec<StreamId> = pending.iter().map(|entry| entry.id).collect();
+        assert_eq!(ids, [StreamId::new(1, 0), StreamId::new(2, 0)]);
+        let pending = group.pending_range(StreamId::MIN, StreamId::MAX, 1, Some("bob"), 0, 300);
+        let entry = StreamPendingEntry {
+            id: StreamId::new(3, 0),
+            consumer: "bob".to_string(),
+            idle: 100,
+            delivery_count: 1,
+        };
+        assert_eq!(pending, [entry]);
+        assert!(group
+            .pending_range(StreamId::MAX, StreamId::MIN, 10, None, 0, 300)
+            .is_empty());
+
+        // Only the entries idle for long enough are claimed, the others are left alone.
+        let options = StreamClaimOptions {
+            min_idle: 150,
+            ..Default::default()
+        };
+        let ids = [
+            StreamId::new(1, 0),
+            StreamId::new(3, 0),
+            StreamId::new(4, 0),
+        ];
+        let claimed = stream.claim("g", "bob", &ids, &options, 300).unwrap();
+        assert_eq!(claimed, [(StreamId::new(1, 0), fields("n", "1"))]);
+        let group = stream.group("g").unwrap();
+        assert_eq!(group.pending[&StreamId::new(1, 0)].consumer, "bob");
+        assert_eq!(group.pending[&StreamId::new(1, 0)].delivery_count, 2);
+        assert!(!group.consumers["alice"]
+            .pending
+            .contains(&StreamId::new(1, 0)));
+        assert!(!group.pending.contains_key(&StreamId::new(4, 0)));
+
+        // FORCE claims entries that are not pending, JUSTID doesn't count as a delivery.
+        let options = StreamClaimOptions {
+            delivered_at: Some(250),
+            force: true,
+            just_id: true,
+            last_id: Some(StreamId::new(4, 0)),
+            ..Default::default()
+        };
+        let claimed = stream
+            .claim("g", "carol", &ids[2..], &options, 300)
+            .unwrap();
+        assert_eq!(claimed.len(), 1);
+        let group = stream.group("g").unwrap();
+        let entry = PendingEntry {
+            consumer: "carol".to_string(),
+            delivered_at: 250,
+            delivery_count: 0,
+        };
+        assert_eq!(group.pending.get(&StreamId::new(4, 0)), Some(&entry));
+        assert_eq!(group.last_id, StreamId::new(4, 0));
+        assert_eq!(stream.claim("nope", "bob", &ids, &options, 300), None);
+
+        // Deleted entries are no longer pending when claimed.
+        stream.entries.remove(&StreamId::new(2, 0));
+        let options = StreamClaimOptions::default();
+        let result = stream
+            .auto_claim("g", "dave", StreamId::MIN, 2, &options, 400)
+            .unwrap();
+        let expected = StreamAutoClaimResult {
+            next: StreamId::new(4, 0),
+            claimed: vec![
+                (StreamId::new(1, 0), fields("n", "1")),
+                (StreamId::new(3, 0), fields("n", "3")),
+            ],
+            deleted: vec![StreamId::new(2, 0)],
+        };
+        assert_eq!(result, expected);
+        let result = stream
+            .auto_claim("g", "dave", result.next, 2, &options, 400)
+            .unwrap();
+        assert_eq!(result.next, StreamId::MIN);
+        assert_eq!(result.claimed.len(), 1);
+        let group = stream.group("g").unwrap();
+        assert_eq!(group.pending_summary().consumers, [("dave".to_string(), 3)]);
+    }
+
+    #[test]
+    fn test_stream_info_and_lag() {
+        let mut stream = Stream::default();
+        assert_eq!(stream.info().first_entry, None);
+        stream.create_group("empty".to_string(), StreamId::MIN);
+        assert_eq!(stream.group_infos()[0].lag, Some(0));
+        for ms in 1..=5 {
+            let id = StreamAddId::Explicit(StreamId::new(ms, 0));
+            stream.add(id, fields("n", &ms.to_string()), 0).unwrap();
+        }
+        stream.create_group("g".to_string(), StreamId::MIN);
+        stream.create_group("h".to_string(), StreamId::new(5, 0));
+
+        // The lag is told from the position of the last ID while nothing has been read.
+        let lags: Vec<_> = stream.group_infos().iter().map(|group| group.lag).collect();
+        assert_eq!(lags, [Some(5), Some(5), Some(0)]);
+        stream.read_group("g", "alice", None, Some(2), false, 10);
+        let group = &stream.group_infos()[1];
+        assert_eq!((group.entries_read, group.lag), (Some(2), Some(3)));
+        assert_eq!((group.consumers, group.pending), (1, 2));
+
+        // Entries deleted past the last ID make the lag unknown, until read past.
+        stream.delete(&[StreamId::new(4, 0)]);
+        assert_eq!(stream.group_infos()[1].lag, None);
+        stream.read_group("g", "alice", None, None, false, 20);
+        let group = &stream.group_infos()[1];
+        assert_eq!((group.entries_read, group.lag), (Some(5), Some(0)));
+        // Trimmed entries are still counted.
+        let trim = StreamTrimOptions {
+            strategy: StreamTrimStrategy::MaxLen(1),
+            approximate: false,
+            limit: None,
+        };
+        stream.trim(&trim);
+        stream.group_mut("h").unwrap().set_last_id(StreamId::MIN);
+        assert_eq!(stream.group_infos()[2].lag, Some(1));
+
+        let info = stream.info();
+        assert_eq!(info.length, 1);
+        assert_eq!(info.entries_added, 5);
+        assert_eq!(info.max_deleted_id, StreamId::new(4, 0));
+        assert_eq!(info.first_id, StreamId::new(5, 0));
+        assert_eq!(info.groups, 3);
+        assert_eq!(
+            info.last_entry,
+            Some((StreamId::new(5, 0), fields("n", "5")))
+        );
+
+        stream.group_mut("g").unwrap().create_consumer("bob", 15);
+        let consumers = stream.group("g").unwrap().consumer_infos(30);
+        let idle: Vec<_> = consumers
+            .iter()
+            .map(|consumer| (consumer.idle, consumer.inactive))
+            .collect();
+        assert_eq!(idle, [(10, Some(10)), (15, None)]);
+    }
+
+    #[test]
+    fn test_stream_dump_groups() {
+        let mut stream = Stream::default();
+        for ms in 1..=2 {
+            let id = StreamAddId::Explicit(StreamId::new(ms, 0));
+            stream.add(id, fields("n", &ms.to_string()), 0).unwrap();
+        }
+        stream.create_group("g".to_string(), StreamId::MIN);
+        stream.create_group("h".to_string(), StreamId::MAX);
+        stream.read_group("g", "alice", None, Some(1), false, 5);
+        stream.group_mut("g").unwrap().create_consumer("bob", 6);
+        stream.delete(&[StreamId::new(2, 0)]);
+
+        let payload = dump(&stream);
+        let (_, data) = check(&payload).unwrap();
+        assert_eq!(load::<Stream>(data), Ok(stream));
+    }
+}
@@ -0,0 +1,103 @@
+use std::borrow::Cow;
+
+// Longest string Redis stores with the embstr encoding.
+const EMBSTR_MAX_LEN: usize = 44;
+
+// Strings holding a decimal integer in canonical form are stored as an i64 like Redis does,
+// which saves the heap allocation for counters and other numeric values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StringValue {
+    Int(i64),
+    Raw(Vec<u8>),
+}
+
+impl StringValue {
+    pub fn new(value: Vec<u8>) -> Self {
+        // "007" or "+7" must read back unchanged, so only the canonical form is converted.
+        match std::str::from_utf8(&value)
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+        {
+            Some(n) if n.to_string().as_bytes() == value => Self::Int(n),
+            _ => Self::Raw(value),
+        }
+    }
+
+    pub fn as_bytes(&self) -> Cow<'_, [u8]> {
+        match self {
+            Self::Int(n) => Cow::Owned(n.to_string().into_bytes()),
+            Self::Raw(data) => Cow::Borrowed(data),
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self {
+            Self::Int(n) => n.to_string().into_bytes(),
+            Self::Raw(data) => data,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Int(n) => n.to_string().len(),
+            Self::Raw(data) => data.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Switches to the raw encoding so the bytes can be modified in place.
+    pub fn make_raw(&mut self) -> &mut Vec<u8> {
+        if let Self::Int(n) = self {
+            *self = Self::Raw(n.to_string().into_bytes());
+        }
+        match self {
+            Self::Raw(data) => data,
+            Self::Int(_) => unreachable!(),
+        }
+    }
+
+    // There is no actual embedding here, short strings are reported the way Redis creates them.
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Self::Int(_) => "int",
+            Self::Raw(data) if data.len() <= EMBSTR_MAX_LEN => "embstr",
+            Self::Raw(_) => "raw",
+        }
+    }
+}
+
+impl Default for StringValue {
+    fn default() -> Self {
+        Self::Raw(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StringValue;
+
+    #[test]
+    fn test_string_value_encoding() {
+        let value = StringValue::new(b"-42".to_vec());
+        assert_eq!(value, StringValue::Int(-42));
+        assert_eq!(value.encoding(), "int");
+        assert_eq!(value.len(), 3);
+        assert_eq!(value.as_bytes().as_ref(), b"-42");
+
+        for raw in [&b"007"[..], b"+7", b" 7", b"", b"99999999999999999999"] {
+            assert_eq!(
+                StringValue::new(raw.to_vec()),
+                StringValue::Raw(raw.to_vec())
+            );
+        }
+        assert_eq!(StringValue::new(b"hello".to_vec()).encoding(), "embstr");
+        assert_eq!(StringValue::new(vec![b'a'; 45]).encoding(), "raw");
+
+        let mut value = StringValue::new(b"12".to_vec());
+        value.make_raw().extend_from_slice(b"ab");
+        assert_eq!(value.into_bytes(), b"12ab");
+    }
+}
@@ -0,0 +1,88 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use dashmap::DashMap;
+use tokio::sync::Notify;
+
+use super::Backend;
+
+// The clients blocked on each key by the blocking commands, in the order they blocked.
+// A write that may serve them only wakes the first one, which retries its command and, once
+// it stops waiting, wakes the next one if the key still exists. Spurious wakeups are
+// harmless, the command is retried and the client blocks again. Stream readers are all
+// woken instead, as one that isn't served keeps waiting without waking the next.
+#[derive(Debug, Default)]
+pub(crate) struct Waiters(DashMap<String, VecDeque<Arc<Notify>>>);
+
+impl Waiters {
+    fn add(&self, key: &str, waiter: &Arc<Notify>) {
+        self.0
+            .entry(key.to_string())
+            .or_default()
+            .push_back(waiter.clone());
+    }
+
+    fn remove(&self, key: &str, waiter: &Arc<Notify>) {
+        self.0.remove_if_mut(key, |_, waiters| {
+            waiters.retain(|w| !Arc::ptr_eq(w, waiter));
+            waiters.is_empty()
+        });
+    }
+
+    // A wakeup sent before the client starts waiting is kept, so none is lost in between.
+    pub(crate) fn wake(&self, key: &str) {
+        if let Some(waiters) = self.0.get(key) {
+            if let Some(waiter) = waiters.front() {
+                waiter.notify_one();
+            }
+        }
+    }
+
+    // For writes serving clients that don't consume anything, like an XADD served to readers
+    // waiting for IDs after different ones.
+    pub(crate) fn wake_all(&self, key: &str) {
+        if let Some(waiters) = self.0.get(key) {
+            for waiter in waiters.iter() {
+                waiter.notify_one();
+            }
+        }
+    }
+}
+
+// A client blocked on some keys, see `Backend::block`. Dropping it stops the wait, waking the
+// next client blocked on each of the keys that exist, as this one may have been woken for them.
+#[derive(Debug)]
+pub struct Blocked {
+    backend: Backend,
+    keys: Vec<String>,
+    waiter: Arc<Notify>,
+}
+
+impl Blocked {
+    pub(crate) fn new(backend: Backend, keys: Vec<String>) -> Self {
+        let waiter = Arc::new(Notify::new());
+        for key in &keys {
+            backend.waiters.add(key, &waiter);
+        }
+        Self {
+            backend,
+            keys,
+            waiter,
+        }
+    }
+
+    // Resolves once one of the keys may have been written to.
+    pub async fn woken(&self) {
+        self.waiter.notified().await
+    }
+}
+
+impl Drop for Blocked {
+    fn drop(&mut self) {
+        for key in &self.keys {
+            self.backend.waiters.remove(key, &self.waiter);
+            if self.backend.exists(key) {
+                self.backend.waiters.wake(key);
+            }
+        }
+    }
+}
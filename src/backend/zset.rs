@@ -0,0 +1,536 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use super::dump::{Dump, Reader, Writer};
+use super::normalize_range;
+use super::skiplist::SkipList;
+
+// The members of a sorted set, by name for score lookups and ordered by score in a skiplist
+// for ranges and ranks, like Redis's dict and zskiplist pair. Both always hold the same
+// members with the same scores.
+#[derive(Debug, Clone, Default)]
+pub struct SortedSet {
+    scores: HashMap<String, f64>,
+    index: SkipList,
+}
+
+// A range of scores, as taken by ZCOUNT and ZREMRANGEBYSCORE: "1.5" is inclusive, "(1.5"
+// exclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreRange {
+    pub min: f64,
+    pub min_exclusive: bool,
+    pub max: f64,
+    pub max_exclusive: bool,
+}
+
+// A range of members, as taken by ZLEXCOUNT and ZREMRANGEBYLEX. It's only meaningful when all
+// the members have the same score, so that they are ordered by name, like in Redis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexRange {
+    pub min: LexBound,
+    pub max: LexBound,
+}
+
+// "-" and "+" stand for the infinities, "[a" is inclusive and "(a" exclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexBound {
+    NegInfinity,
+    PosInfinity,
+    Inclusive(String),
+    Exclusive(String),
+}
+
+// The members ZRANGESTORE selects: ranks counting from the end when negative like ZRANGE, or
+// a range of scores or members as its BYSCORE and BYLEX options.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RangeBy {
+    Rank(i64, i64),
+    Score(ScoreRange),
+    Lex(LexRange),
+}
+
+// The conditions ZADD checks before writing the score of a member, as its NX, XX, GT and LT
+// options. GT and LT only restrict updates, new members are added unless XX is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScoreCondition {
+    pub if_absent: bool,
+    pub if_exists: bool,
+    pub if_greater: bool,
+    pub if_less: bool,
+}
+
+impl ScoreCondition {
+    pub(crate) fn holds(&self, current: Option<f64>, score: f64) -> bool {
+        match current {
+            None => !self.if_exists,
+            Some(current) => {
+                !(self.if_absent
+                    || self.if_greater && score <= current
+                    || self.if_less && score >= current)
+            }
+        }
+    }
+}
+
+// How ZUNIONSTORE and ZINTERSTORE combine the scores of a member found in several inputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    // Like Redis, the NaN from adding infinities of opposite signs counts as 0.
+    pub(crate) fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            Aggregate::Sum => nan_to_zero(a + b),
+            Aggregate::Min => a.min(b),
+            Aggregate::Max => a.max(b),
+        }
+    }
+}
+
+// Scales the score by the weight of its input, 0 times an infinity counting as 0.
+pub(crate) fn weighted(score: f64, weight: f64) -> f64 {
+    nan_to_zero(score * weight)
+}
+
+fn nan_to_zero(score: f64) -> f64 {
+    if score.is_nan() {
+        0.0
+    } else {
+        score
+    }
+}
+
+impl ScoreRange {
+    fn is_below(&self, score: f64) -> bool {
+        score < self.min || self.min_exclusive && score == self.min
+    }
+
+    fn is_above(&self, score: f64) -> bool {
+        score > self.max || self.max_exclusive && score == self.max
+    }
+}
+
+impl LexRange {
+    fn is_below(&self, member: &str) -> bool {
+        let member = member.as_bytes();
+        match &self.min {
+            LexBound::NegInfinity => false,
+            LexBound::PosInfinity => true,
+            LexBound::Inclusive(min) => member < min.as_bytes(),
+            LexBound::Exclusive(min) => member <= min.as_bytes(),
+        }
+    }
+
+    fn is_above(&self, member: &str) -> bool {
+        let member = member.as_bytes();
+        match &self.max {
+            LexBound::NegInfinity => true,
+            LexBound::PosInfinity => false,
+            LexBound::Inclusive(max) => member > max.as_bytes(),
+            LexBound::Exclusive(max) => member >= max.as_bytes(),
+        }
+    }
+}
+
+impl SortedSet {
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn score(&self, member: &str) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    // Adds the member or updates its score, returns true if it is new. The score must not
+    // be NaN.
+    pub fn insert(&mut self, member: String, score: f64) -> bool {
+        match self.scores.insert(member.clone(), score) {
+            Some(old) => {
+                if old != score {
+                    self.index.remove(old, &member);
+                    self.index.insert(score, member);
+                }
+                false
+            }
+            None => {
+                self.index.insert(score, member);
+                true
+            }
+        }
+    }
+
+    // Returns false if the member does not exist.
+    pub fn remove(&mut self, member: &str) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => self.index.remove(score, member),
+            None => false,
+        }
+    }
+
+    // Removes `count` members starting at the rank `start`, returns how many there were.
+    pub fn remove_ranks(&mut self, start: usize, count: usize) -> usize {
+        let members: Vec<String> = self
+            .index
+            .iter_from(start)
+            .take(count)
+            .map(|(_, member)| member.to_string())
+            .collect();
+        for member in &members {
+            self.remove(member);
+        }
+        members.len()
+    }
+
+    // The members in the range as the rank of the first one and their number, both found by
+    // walking the skiplist in O(log n).
+    pub fn score_span(&self, range: &ScoreRange) -> (usize, usize) {
+        let start = self.index.count_while(|score, _| range.is_below(score));
+        let end = self.index.count_while(|score, _| !range.is_above(score));
+        (start, end.saturating_sub(start))
+    }
+
+    // Like `score_span`, for the members ordered by name.
+    pub fn lex_span(&self, range: &LexRange) -> (usize, usize) {
+        let start = self.index.count_while(|_, member| range.is_below(member));
+        let end = self.index.count_while(|_, member| !range.is_above(member));
+        (start, end.saturating_sub(start))
+    }
+
+    // The (score, member) pairs in the range, in descending order with `rev`, where ranks also
+    // count from the highest score. The limit skips as many members as its offset and keeps at
+    // most its count, all of them when negative, like the LIMIT option.
+    pub fn select(&self, by: &RangeBy, rev: bool, limit: Option<(i64, i64)>) -> Vec<(f64, &str)> {
+        let span = match by {
+            RangeBy::Rank(start, end) => normalize_range(*start, *end, self.len()),
+            RangeBy::Score(range) => self.span_ranks(self.score_span(range), rev),
+            RangeBy::Lex(range) => self.span_ranks(self.lex_span(range), rev),
+        };
+        let (offset, count) = limit.unwrap_or((0, -1));
+        let Some((start, end)) = span.filter(|_| offset >= 0 && count != 0) else {
+            return Vec::new();
+        };
+        let start = start.saturating_add(offset as usize);
+        let end = if count < 0 {
+            end
+        } else {
+            end.min(start.saturating_add(count as usize - 1))
+        };
+        if start > end {
+            return Vec::new();
+        }
+        self.range(start, end, rev).collect()
+    }
+
+    // Converts a span into the ranks of its first and last members, counted from the highest
+    // score with `rev`. None if it is empty.
+    fn span_ranks(&self, (first, count): (usize, usize), rev: bool) -> Option<(usize, usize)> {
+        if count == 0 {
+            return None;
+        }
+        let last = first + count - 1;
+        Some(if rev {
+            (self.len() - 1 - last, self.len() - 1 - first)
+        } else {
+            (first, last)
+        })
+    }
+
+    // The 0-based rank of the member by ascending score, or descending with `rev`, found by
+    // walking the skiplist in O(log n).
+    pub fn rank(&self, member: &str, rev: bool) -> Option<usize> {
+        let score = self.score(member)?;
+        let rank = self.index.rank(score, member)?;
+        Some(if rev { self.len() - 1 - rank } else { rank })
+    }
+
+    // Visits the members in the order of their hashes, the cursor being the hash to resume
+    // from, so members that stay in the set during a whole scan are returned at least once
+    // whatever else is added or removed. Returns the next cursor, 0 once done, and about
+    // `count` members, more when several have the same hash as they are never split.
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<(f64, &str)>) {
+        let mut members: Vec<_> = self
+            .scores
+            .iter()
+            .map(|(member, &score)| (member_hash(member), score, member.as_str()))
+            .filter(|&(hash, ..)| hash >= cursor)
+            .collect();
+        members.sort_unstable_by_key(|&(hash, ..)| hash);
+
+        let mut end = count.min(members.len());
+        while end > 0 && end < members.len() && members[end].0 == members[end - 1].0 {
+            end += 1;
+        }
+        let next = members.get(end).map_or(0, |&(hash, ..)| hash);
+        members.truncate(end);
+        let members = members
+            .into_iter()
+            .map(|(_, score, member)| (score, member))
+            .collect();
+        (next, members)
+    }
+
+    // The (score, member) pairs in ascending order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (f64, &str)> + '_ {
+        self.index.iter()
+    }
+
+    // The (score, member) pairs ranked from start to end inclusive, which must be valid ranks.
+    // With `rev`, members are ranked from the highest score and iterated in descending order.
+    pub fn range(
+        &self,
+        start: usize,
+        end: usize,
+        rev: bool,
+    ) -> impl ExactSizeIterator<Item = (f64, &str)> + '_ {
+        let iter = if rev {
+            self.index.rev_iter_from(self.len() - 1 - start)
+        } else {
+            self.index.iter_from(start)
+        };
+        iter.take(end - start + 1)
+    }
+}
+
+fn member_hash(member: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    member.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Dump for SortedSet {
+    const TAG: u8 = 7;
+
+    fn write(&self, w: &mut Writer) {
+        w.put_u64(self.len() as u64);
+        for (score, member) in self.iter() {
+            w.put_bytes(member.as_bytes());
+            w.put_f64(score);
+        }
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let mut zset = SortedSet::default();
+        for _ in 0..r.len()? {
+            let member = r.string()?;
+            let score = r.f64().filter(|score| !score.is_nan())?;
+            zset.insert(member, score);
+        }
+        Some(zset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        weighted, Aggregate, LexBound, LexRange, RangeBy, ScoreCondition, ScoreRange, SortedSet,
+    };
+    use crate::backend::dump::{check, dump, load};
+
+    #[test]
+    fn test_sorted_set() {
+        let mut zset = SortedSet::default();
+        assert!(zset.insert("b".to_string(), 2.0));
+        assert!(zset.insert("a".to_string(), 2.0));
+        assert!(zset.insert("c".to_string(), 1.0));
+        assert!(!zset.insert("c".to_string(), 3.0));
+        assert_eq!(zset.len(), 3);
+        assert_eq!(zset.score("c"), Some(3.0));
+
+        // Members with the same score are ordered by name.
+        let members: Vec<_> = zset.iter().collect();
+        assert_eq!(members, [(2.0, "a"), (2.0, "b"), (3.0, "c")]);
+        assert_eq!(zset.score("d"), None);
+
+        assert_eq!(zset.rank("a", false), Some(0));
+        assert_eq!(zset.rank("a", true), Some(2));
+        assert_eq!(zset.rank("d", false), None);
+
+        let members: Vec<_> = zset.range(1, 2, false).collect();
+        assert_eq!(members, [(2.0, "b"), (3.0, "c")]);
+        let members: Vec<_> = zset.range(0, 1, true).collect();
+        assert_eq!(members, [(3.0, "c"), (2.0, "b")]);
+
+        assert!(zset.remove("a"));
+        assert!(!zset.remove("a"));
+        assert_eq!(zset.score("a"), None);
+        assert_eq!(zset.rank("b", false), Some(0));
+        assert_eq!(zset.remove_ranks(0, 5), 2);
+        assert!(zset.is_empty());
+    }
+
+    #[test]
+    fn test_sorted_set_spans() {
+        let mut zset = SortedSet::default();
+        for (member, score) in [("a", 1.0), ("b", 2.0), ("c", 2.0), ("d", 3.0)] {
+            zset.insert(member.to_string(), score);
+        }
+        let scores = |min, min_exclusive, max, max_exclusive| ScoreRange {
+            min,
+            min_exclusive,
+            max,
+            max_exclusive,
+        };
+        assert_eq!(zset.score_span(&scores(2.0, false, 3.0, false)), (1, 3));
+        assert_eq!(zset.score_span(&scores(2.0, true, 3.0, true)), (3, 0));
+        assert_eq!(
+            zset.score_span(&scores(f64::NEG_INFINITY, false, 2.0, true)),
+            (0, 1)
+        );
+        assert_eq!(zset.score_span(&scores(3.0, false, 1.0, false)), (3, 0));
+
+        // Members with the same score are ordered by name.
+        let mut zset = SortedSet::default();
+        for member in ["a", "b", "c", "d"] {
+            zset.insert(member.to_string(), 0.0);
+        }
+        let lex = |min, max| LexRange { min, max };
+        let range = lex(
+            LexBound::Exclusive("a".to_string()),
+            LexBound::Inclusive("c".to_string()),
+        );
+        assert_eq!(zset.lex_span(&range), (1, 2));
+        assert_eq!(
+            zset.lex_span(&lex(LexBound::NegInfinity, LexBound::PosInfinity)),
+            (0, 4)
+        );
+        assert_eq!(
+            zset.lex_span(&lex(LexBound::PosInfinity, LexBound::PosInfinity)),
+            (4, 0)
+        );
+        assert_eq!(
+            zset.lex_span(&lex(LexBound::NegInfinity, LexBound::NegInfinity)),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn test_sorted_set_select() {
+        let mut zset = SortedSet::default();
+        for (member, score) in [("a", 1.0), ("b", 2.0), ("c", 3.0), ("d", 4.0), ("e", 5.0)] {
+            zset.insert(member.to_string(), score);
+        }
+        let members = |selected: Vec<(f64, &str)>| -> Vec<String> {
+            selected.into_iter().map(|(_, m)| m.to_string()).collect()
+        };
+        let scores = RangeBy::Score(ScoreRange {
+            min: 2.0,
+            min_exclusive: false,
+            max: 5.0,
+            max_exclusive: true,
+        });
+
+        assert_eq!(
+            members(zset.select(&RangeBy::Rank(1, -2), false, None)),
+            ["b", "c", "d"]
+        );
+        assert_eq!(
+            members(zset.select(&RangeBy::Rank(0, 1), true, None)),
+            ["e", "d"]
+        );
+        assert_eq!(members(zset.select(&scores, false, None)), ["b", "c", "d"]);
+        assert_eq!(members(zset.select(&scores, true, None)), ["d", "c", "b"]);
+        assert_eq!(members(zset.select(&scores, true, Some((1, 1)))), ["c"]);
+        assert_eq!(
+            members(zset.select(&scores, false, Some((1, -1)))),
+            ["c", "d"]
+        );
+        assert!(zset.select(&scores, false, Some((-1, 2))).is_empty());
+        assert!(zset.select(&scores, false, Some((3, 2))).is_empty());
+        assert!(zset.select(&scores, false, Some((0, 0))).is_empty());
+
+        let lex = RangeBy::Lex(LexRange {
+            min: LexBound::Exclusive("a".to_string()),
+            max: LexBound::PosInfinity,
+        });
+        assert_eq!(members(zset.select(&lex, true, Some((0, 2)))), ["e", "d"]);
+    }
+
+    #[test]
+    fn test_sorted_set_scan() {
+        let mut zset = SortedSet::default();
+        for i in 0..100 {
+            zset.insert(i.to_string(), i as f64);
+        }
+
+        // Members removed or added during the scan don't make it miss the others.
+        let mut seen = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (next, members) = zset.scan(cursor, 7);
+            assert!(members.len() <= 7);
+            seen.extend(members.iter().map(|(_, member)| member.to_string()));
+            if let Some(member) = seen.last() {
+                zset.remove(member);
+            }
+            zset.insert(format!("new{}", cursor), 0.0);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        for i in 0..100 {
+            let member = i.to_string();
+            assert!(seen.contains(&member) || zset.score(&member).is_none());
+        }
+    }
+
+    #[test]
+    fn test_score_condition() {
+        let always = ScoreCondition::default();
+        assert!(always.holds(None, 1.0) && always.holds(Some(2.0), 1.0));
+        let nx = ScoreCondition {
+            if_absent: true,
+            ..Default::default()
+        };
+        assert!(nx.holds(None, 1.0) && !nx.holds(Some(2.0), 1.0));
+        let xx = ScoreCondition {
+            if_exists: true,
+            ..Default::default()
+        };
+        assert!(!xx.holds(None, 1.0) && xx.holds(Some(2.0), 1.0));
+        let gt = ScoreCondition {
+            if_greater: true,
+            ..Default::default()
+        };
+        assert!(gt.holds(None, 1.0) && gt.holds(Some(0.5), 1.0) && !gt.holds(Some(1.0), 1.0));
+        let lt = ScoreCondition {
+            if_less: true,
+            ..Default::default()
+        };
+        assert!(lt.holds(None, 1.0) && lt.holds(Some(2.0), 1.0) && !lt.holds(Some(0.5), 1.0));
+    }
+
+    #[test]
+    fn test_aggregate() {
+        assert_eq!(Aggregate::Sum.apply(1.5, 2.0), 3.5);
+        assert_eq!(Aggregate::Sum.apply(f64::INFINITY, f64::NEG_INFINITY), 0.0);
+        assert_eq!(Aggregate::Min.apply(1.5, 2.0), 1.5);
+        assert_eq!(Aggregate::Max.apply(1.5, 2.0), 2.0);
+        assert_eq!(weighted(1.5, 2.0), 3.0);
+        assert_eq!(weighted(f64::INFINITY, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_sorted_set_dump() {
+        let mut zset = SortedSet::default();
+        zset.insert("a".to_string(), 1.5);
+        zset.insert("b".to_string(), f64::NEG_INFINITY);
+
+        let payload = dump(&zset);
+        let (_, data) = check(&payload).unwrap();
+        let restored: SortedSet = load(data).unwrap();
+        let members: Vec<_> = restored.iter().collect();
+        assert_eq!(members, [(f64::NEG_INFINITY, "b"), (1.5, "a")]);
+    }
+}
@@ -0,0 +1,85 @@
+use std::{mem, sync::RwLock, time::Duration};
+
+use rand::RngExt;
+
+// A fault injected into a connection when replying, to test how clients cope with a
+// misbehaving server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    // The reply is sent late.
+    Delay(Duration),
+    // The command is executed but never replied to.
+    Drop,
+    // Only the first half of the reply is sent, then the connection is closed.
+    PartialWrite,
+    // The connection is closed instead of replying.
+    Disconnect,
+}
+
+// The faults set with DEBUG CHAOS, along with the probability of each reply to be hit by
+// them. Only the replies to the other commands are affected, so chaos can always be turned
+// off again.
+#[derive(Debug, Default)]
+pub struct Chaos {
+    faults: RwLock<Vec<(Fault, f64)>>,
+}
+
+impl Chaos {
+    // Replaces the probability of the fault, or of its delay. A probability of 0 removes it.
+    pub fn set(&self, fault: Fault, probability: f64) {
+        let mut faults = self.faults.write().unwrap_or_else(|e| e.into_inner());
+        faults.retain(|(f, _)| mem::discriminant(f) != mem::discriminant(&fault));
+        if probability > 0.0 {
+            faults.push((fault, probability));
+        }
+    }
+
+    pub fn clear(&self) {
+        self.faults
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    // The fault to inject into the next reply, if any. Faults are rolled independently in
+    // the order they were set, the first hit wins.
+    pub fn roll(&self) -> Option<Fault> {
+        let faults = self.faults.read().unwrap_or_else(|e| e.into_inner());
+        if faults.is_empty() {
+            return None;
+        }
+        let mut rng = rand::rng();
+        faults
+            .iter()
+            .find(|(_, probability)| rng.random_bool(*probability))
+            .map(|(fault, _)| *fault)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Chaos, Fault};
+
+    #[test]
+    fn test_chaos_roll() {
+        let chaos = Chaos::default();
+        assert_eq!(chaos.roll(), None);
+
+        chaos.set(Fault::Drop, 1.0);
+        chaos.set(Fault::Disconnect, 1.0);
+        assert_eq!(chaos.roll(), Some(Fault::Drop));
+
+        // Setting a fault again replaces it.
+        chaos.set(Fault::Drop, 0.0);
+        assert_eq!(chaos.roll(), Some(Fault::Disconnect));
+        chaos.set(Fault::Delay(Duration::from_millis(1)), 1.0);
+        chaos.set(Fault::Delay(Duration::from_millis(2)), 1.0);
+        chaos.set(Fault::Disconnect, 0.0);
+        assert_eq!(chaos.roll(), Some(Fault::Delay(Duration::from_millis(2))));
+
+        chaos.clear();
+        assert_eq!(chaos.roll(), None);
+    }
+}
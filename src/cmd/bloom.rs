@@ -0,0 +1,301 @@
+use crate::{
+    cmd::CommandError, BloomFilter, RespArray, RespFrame, SimpleError, BLOOM_DEFAULT_EXPANSION,
+};
+
+use super::{
+    extract_args, parse_number, validate_command, BloomAdd, BloomExists, BloomMultiAdd,
+    BloomMultiExists, BloomReserve, CommandExecutor, RESP_OK,
+};
+
+impl CommandExecutor for BloomReserve {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let filter = match BloomFilter::new(self.error_rate, self.capacity, self.expansion) {
+            Ok(filter) => filter,
+            Err(e) => return e.into(),
+        };
+        if backend.bloom_reserve(self.key, filter) {
+            RESP_OK.clone()
+        } else {
+            SimpleError::new("ERR item exists").into()
+        }
+    }
+}
+
+impl TryFrom<RespArray> for BloomReserve {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["BF.RESERVE"], None)?;
+
+        // Parse the key, error rate and capacity.
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, error_rate, capacity) = match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(error_rate)),
+                Some(RespFrame::BulkString(capacity)),
+            ) => (
+                key.try_into()?,
+                parse_number::<f64>(error_rate, "error rate")?,
+                parse_number::<u64>(capacity, "capacity")?,
+            ),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid key, error rate or capacity".to_string(),
+                ))
+            }
+        };
+        if !(error_rate > 0.0 && error_rate < 1.0) {
+            return Err(CommandError::InvalidArgument(
+                "error rate should be between 0 and 1".to_string(),
+            ));
+        }
+        if capacity == 0 {
+            return Err(CommandError::InvalidArgument(
+                "capacity should be larger than 0".to_string(),
+            ));
+        }
+
+        // Parse the options.
+        let mut expansion = BLOOM_DEFAULT_EXPANSION;
+        while let Some(arg) = args.next() {
+            let option = match arg {
+                RespFrame::BulkString(option) => option.to_ascii_uppercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                b"NONSCALING" => expansion = 0,
+                b"EXPANSION" => match args.next() {
+                    Some(RespFrame::BulkString(n)) => {
+                        expansion = parse_number(n, "expansion")?;
+                        if expansion == 0 {
+                            return Err(CommandError::InvalidArgument(
+                                "expansion should be larger than 0".to_string(),
+                            ));
+                        }
+                    }
+                    _ => {
+                        return Err(CommandError::InvalidArgument(
+                            "Invalid expansion".to_string(),
+                        ))
+                    }
+                },
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        Ok(BloomReserve {
+            key,
+            error_rate,
+            capacity,
+            expansion,
+        })
+    }
+}
+
+impl CommandExecutor for BloomAdd {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let mut ret = backend.bloom_add(self.key, vec![self.item]);
+        ret.remove(0)
+    }
+}
+
+impl TryFrom<RespArray> for BloomAdd {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["BF.ADD"], Some(2))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(item))) => Ok(BloomAdd {
+                key: key.try_into()?,
+                item: item.try_into()?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or item".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for BloomMultiAdd {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespArray::new(backend.bloom_add(self.key, self.items)).into()
+    }
+}
+
+impl TryFrom<RespArray> for BloomMultiAdd {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["BF.MADD"], None)?;
+
+        let (key, items) = extract_key_and_items(value)?;
+        Ok(BloomMultiAdd { key, items })
+    }
+}
+
+impl CommandExecutor for BloomExists {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let mut ret = backend.bloom_exists(&self.key, vec![self.item]);
+        ret.remove(0)
+    }
+}
+
+impl TryFrom<RespArray> for BloomExists {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["BF.EXISTS"], Some(2))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(item))) => {
+                Ok(BloomExists {
+                    key: key.try_into()?,
+                    item: item.try_into()?,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or item".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for BloomMultiExists {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespArray::new(backend.bloom_exists(&self.key, self.items)).into()
+    }
+}
+
+impl TryFrom<RespArray> for BloomMultiExists {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["BF.MEXISTS"], None)?;
+
+        let (key, items) = extract_key_and_items(value)?;
+        Ok(BloomMultiExists { key, items })
+    }
+}
+
+// Parses "<key> <item> [<item> ...]" shared by the batch commands.
+fn extract_key_and_items(value: RespArray) -> Result<(String, Vec<String>), CommandError> {
+    // Parse the key.
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = match args.next() {
+        Some(RespFrame::BulkString(key)) => key.try_into()?,
+        _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    };
+
+    // Parse the items.
+    let mut items = Vec::new();
+    for arg in args {
+        match arg {
+            RespFrame::BulkString(item) => items.push(item.try_into()?),
+            _ => return Err(CommandError::InvalidArgument("Invalid item".to_string())),
+        }
+    }
+    if items.is_empty() {
+        return Err(CommandError::InvalidArgument(
+            "At least one item is required".to_string(),
+        ));
+    }
+
+    Ok((key, items))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{Backend, RespArray, RespDecode, RespFrame, SimpleError};
+
+    use super::{BloomAdd, BloomExists, BloomMultiAdd, BloomReserve, CommandExecutor, RESP_OK};
+
+    #[test]
+    fn test_bf_reserve_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$10\r\nbf.reserve\r\n$5\r\nbloom\r\n$4\r\n0.01\r\n$4\r\n1000\r\n$10\r\nnonscaling\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: BloomReserve = frame.try_into()?;
+        assert_eq!(result.key, "bloom");
+        assert_eq!(result.error_rate, 0.01);
+        assert_eq!(result.capacity, 1000);
+        assert_eq!(result.expansion, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bf_madd_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$7\r\nbf.madd\r\n$5\r\nbloom\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: BloomMultiAdd = frame.try_into()?;
+        assert_eq!(result.key, "bloom");
+        assert_eq!(result.items, vec!["a".to_string(), "b".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bf_reserve_add_exists_commands() -> Result<()> {
+        let backend = Backend::new();
+        let cmd = BloomReserve {
+            key: "bloom".to_string(),
+            error_rate: 0.01,
+            capacity: 100,
+            expansion: 2,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+
+        let cmd = BloomReserve {
+            key: "bloom".to_string(),
+            error_rate: 0.01,
+            capacity: 100,
+            expansion: 2,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR item exists").into()
+        );
+
+        let cmd = BloomAdd {
+            key: "bloom".to_string(),
+            item: "hello".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = BloomExists {
+            key: "bloom".to_string(),
+            item: "hello".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = BloomExists {
+            key: "bloom".to_string(),
+            item: "world".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        // Rejected instead of allocating the bits.
+        let cmd = BloomReserve {
+            key: "huge".to_string(),
+            error_rate: 0.01,
+            capacity: 1_000_000_000_000_000,
+            expansion: 2,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR filter exceeds maximum allowed size").into()
+        );
+        assert!(!backend.exists("huge"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+#[cfg(feature = "chaos")]
+use std::time::Duration;
+
+#[cfg(feature = "chaos")]
+use crate::chaos::Fault;
+use crate::{cmd::CommandError, RespArray, RespFrame};
+
+#[cfg(feature = "chaos")]
+use super::DebugChaos;
+use super::{
+    extract_args, parse_expire_condition, parse_number, validate_command, CommandExecutor,
+    DebugExpireScan, DebugSetTtl, RESP_OK,
+};
+
+// Both only start the job, which logs how many keys it affected once done.
+impl CommandExecutor for DebugSetTtl {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        backend.bulk_set_ttl(self.pattern, self.seconds, self.condition);
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for DebugExpireScan {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        backend.bulk_expire_scan(self.pattern);
+        RESP_OK.clone()
+    }
+}
+
+#[cfg(feature = "chaos")]
+impl CommandExecutor for DebugChaos {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match self.fault {
+            Some((fault, probability)) => backend.chaos.set(fault, probability),
+            None => backend.chaos.clear(),
+        }
+        RESP_OK.clone()
+    }
+}
+
+// DEBUG SET-TTL <pattern> <seconds> [NX | XX | GT | LT]
+// A time to live of zero would delete every matching key, which is not what this is for.
+impl TryFrom<RespArray> for DebugSetTtl {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["DEBUG", "SET-TTL"], None)?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        let (pattern, seconds) = match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(pattern)), Some(RespFrame::BulkString(seconds))) => (
+                pattern.try_into()?,
+                parse_number::<u64>(seconds, "seconds")?,
+            ),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid pattern or seconds".to_string(),
+                ))
+            }
+        };
+        if seconds == 0 {
+            return Err(CommandError::InvalidArgument(
+                "seconds must be positive".to_string(),
+            ));
+        }
+        let condition = parse_expire_condition(args)?;
+
+        Ok(DebugSetTtl {
+            pattern,
+            seconds,
+            condition,
+        })
+    }
+}
+
+// DEBUG EXPIRE-SCAN <pattern>
+impl TryFrom<RespArray> for DebugExpireScan {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["DEBUG", "EXPIRE-SCAN"], Some(1))?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(pattern)) => Ok(DebugExpireScan {
+                pattern: pattern.try_into()?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid pattern".to_string())),
+        }
+    }
+}
+
+// DEBUG CHAOS DELAY <probability> <milliseconds>
+// DEBUG CHAOS DROP | PARTIAL | DISCONNECT <probability>
+// DEBUG CHAOS OFF
+#[cfg(feature = "chaos")]
+impl TryFrom<RespArray> for DebugChaos {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["DEBUG", "CHAOS"], None)?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        let kind = match args.next() {
+            Some(RespFrame::BulkString(kind)) => kind.to_ascii_uppercase(),
+            _ => return Err(CommandError::InvalidArgument("Invalid fault".to_string())),
+        };
+        if kind.as_slice() == b"OFF" {
+            return match args.next() {
+                None => Ok(DebugChaos { fault: None }),
+                Some(_) => Err(CommandError::InvalidArgument(
+                    "OFF takes no argument".to_string(),
+                )),
+            };
+        }
+
+        let probability = match args.next() {
+            Some(RespFrame::BulkString(probability)) => {
+                parse_number::<f64>(probability, "probability")?
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid probability".to_string(),
+                ))
+            }
+        };
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(CommandError::InvalidArgument(
+                "probability must be between 0 and 1".to_string(),
+            ));
+        }
+        let fault = match (kind.as_slice(), args.next()) {
+            (b"DELAY", Some(RespFrame::BulkString(ms))) => {
+                Fault::Delay(Duration::from_millis(parse_number(ms, "delay")?))
+            }
+            (b"DROP", None) => Fault::Drop,
+            (b"PARTIAL", None) => Fault::PartialWrite,
+            (b"DISCONNECT", None) => Fault::Disconnect,
+            _ => return Err(CommandError::InvalidArgument("Invalid fault".to_string())),
+        };
+        if args.next().is_some() {
+            return Err(CommandError::InvalidArgument(
+                "Too many arguments".to_string(),
+            ));
+        }
+
+        Ok(DebugChaos {
+            fault: Some((fault, probability)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "chaos")]
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[cfg(feature = "chaos")]
+    use crate::chaos::Fault;
+    use crate::{Backend, ExpireCondition, RespArray, RespDecode};
+
+    #[cfg(feature = "chaos")]
+    use super::DebugChaos;
+    use super::{CommandExecutor, DebugExpireScan, DebugSetTtl, RESP_OK};
+
+    #[test]
+    fn test_debug_set_ttl_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$5\r\ndebug\r\n$7\r\nset-ttl\r\n$6\r\nuser:*\r\n$2\r\n60\r\n$2\r\nnx\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: DebugSetTtl = frame.try_into()?;
+        assert_eq!(result.pattern, "user:*");
+        assert_eq!(result.seconds, 60);
+        assert_eq!(
+            result.condition,
+            ExpireCondition {
+                if_none: true,
+                ..Default::default()
+            }
+        );
+
+        for invalid in [
+            &b"*4\r\n$5\r\ndebug\r\n$7\r\nset-ttl\r\n$1\r\n*\r\n$1\r\n0\r\n"[..],
+            b"*4\r\n$5\r\ndebug\r\n$7\r\nset-ttl\r\n$1\r\n*\r\n$2\r\n-1\r\n",
+            b"*5\r\n$5\r\ndebug\r\n$7\r\nset-ttl\r\n$1\r\n*\r\n$1\r\n1\r\n$2\r\nZZ\r\n",
+            b"*3\r\n$5\r\ndebug\r\n$7\r\nset-ttl\r\n$1\r\n*\r\n",
+        ] {
+            let mut buf = BytesMut::from(invalid);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(DebugSetTtl::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_expire_scan_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\ndebug\r\n$11\r\nexpire-scan\r\n$6\r\nuser:*\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: DebugExpireScan = frame.try_into()?;
+        assert_eq!(result.pattern, "user:*");
+
+        let mut buf = BytesMut::from(&b"*2\r\n$5\r\ndebug\r\n$11\r\nexpire-scan\r\n"[..]);
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(DebugExpireScan::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_set_ttl_command() {
+        let backend = Backend::new();
+        backend.string_set("user:1".to_string(), b"value".to_vec());
+
+        // The reply comes before the job is done.
+        let cmd = DebugSetTtl {
+            pattern: "user:*".to_string(),
+            seconds: 60,
+            condition: ExpireCondition::default(),
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        let cmd = DebugExpireScan {
+            pattern: "user:*".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+    }
+
+    #[cfg(feature = "chaos")]
+    #[test]
+    fn test_debug_chaos_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$5\r\ndebug\r\n$5\r\nchaos\r\n$5\r\ndelay\r\n$3\r\n0.5\r\n$3\r\n100\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: DebugChaos = frame.try_into()?;
+        assert_eq!(
+            result.fault,
+            Some((Fault::Delay(Duration::from_millis(100)), 0.5))
+        );
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\ndebug\r\n$5\r\nchaos\r\n$3\r\noff\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: DebugChaos = frame.try_into()?;
+        assert_eq!(result.fault, None);
+
+        for invalid in [
+            &b"*4\r\n$5\r\ndebug\r\n$5\r\nchaos\r\n$4\r\ndrop\r\n$1\r\n2\r\n"[..],
+            b"*4\r\n$5\r\ndebug\r\n$5\r\nchaos\r\n$5\r\ndelay\r\n$1\r\n1\r\n",
+            b"*5\r\n$5\r\ndebug\r\n$5\r\nchaos\r\n$4\r\ndrop\r\n$1\r\n1\r\n$1\r\n1\r\n",
+            b"*2\r\n$5\r\ndebug\r\n$5\r\nchaos\r\n",
+            b"*1\r\n$5\r\ndebug\r\n",
+        ] {
+            let mut buf = BytesMut::from(invalid);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(DebugChaos::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "chaos")]
+    #[test]
+    fn test_debug_chaos_command() {
+        let backend = Backend::new();
+        let cmd = DebugChaos {
+            fault: Some((Fault::Drop, 1.0)),
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.chaos.roll(), Some(Fault::Drop));
+
+        let cmd = DebugChaos { fault: None };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.chaos.roll(), None);
+    }
+}
@@ -1,8 +1,12 @@
-use crate::{cmd::CommandError, BulkString, RespArray, RespFrame};
+use crate::{
+    cmd::CommandError, now_ms, BulkString, ExpireCondition, FieldExpireResult, RespArray, RespFrame,
+};
 
 use super::{
-    extract_args, validate_command, CommandExecutor, HashGet, HashGetAll, HashMultiGet, HashSet,
-    RESP_OK,
+    extract_args, parse_expire_condition, parse_expire_time, parse_number, validate_command,
+    CommandExecutor, HashDelete, HashExists, HashExpire, HashGet, HashGetAll, HashIncrBy,
+    HashIncrByFloat, HashKeys, HashLen, HashMultiGet, HashMultiSet, HashPExpire, HashPersist,
+    HashRandField, HashScan, HashSet, HashStrLen, HashTtl, HashValues, RESP_OK, SCAN_DEFAULT_COUNT,
 };
 
 impl CommandExecutor for HashGet {
@@ -33,27 +37,11 @@ impl TryFrom<RespArray> for HashGet {
 
 impl CommandExecutor for HashGetAll {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        let hmap = backend.hmap.get(&self.key);
-
-        match hmap {
-            Some(hmap) => {
-                let mut data = Vec::with_capacity(hmap.len());
-                for v in hmap.iter() {
-                    let key = v.key().to_owned();
-                    data.push((key, v.value().clone()));
-                }
-                if self.sort {
-                    data.sort_by(|a, b| a.0.cmp(&b.0));
-                }
-                let ret = data
-                    .into_iter()
-                    .flat_map(|(k, v)| vec![BulkString::from(k).into(), v])
-                    .collect::<Vec<RespFrame>>();
-
-                RespArray::new(ret).into()
-            }
-            None => RespArray::new([]).into(),
-        }
+        let ret = hash_entries(backend, &self.key, self.sort)
+            .into_iter()
+            .flat_map(|(k, v)| vec![BulkString::from(k).into(), v])
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(ret).into()
     }
 }
 
@@ -75,152 +63,1492 @@ impl TryFrom<RespArray> for HashGetAll {
 
 impl CommandExecutor for HashSet {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        backend.hash_set(self.key, self.field, self.value);
-        RESP_OK.clone()
+        RespFrame::Integer(backend.hash_set_multi(self.key, self.fields) as i64)
     }
 }
 
 impl TryFrom<RespArray> for HashSet {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["HSET"], Some(3))?;
+        validate_command(&value, &["HSET"], None)?;
+
+        let (key, fields) = extract_key_and_field_values(value)?;
+        Ok(HashSet { key, fields })
+    }
+}
+
+// Deprecated in Redis in favor of the variadic HSET, it only differs in its reply.
+impl CommandExecutor for HashMultiSet {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        backend.hash_set_multi(self.key, self.fields);
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for HashMultiSet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HMSET"], None)?;
+
+        let (key, fields) = extract_key_and_field_values(value)?;
+        Ok(HashMultiSet { key, fields })
+    }
+}
+
+impl CommandExecutor for HashMultiGet {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        backend.hash_multi_get(&self.key, self.fields)
+    }
+}
+
+impl TryFrom<RespArray> for HashMultiGet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HMGET"], None)?;
+
+        let (key, fields) = extract_key_and_fields(value)?;
+        Ok(HashMultiGet { key, fields })
+    }
+}
+
+impl CommandExecutor for HashDelete {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.hash_delete(&self.key, &self.fields) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for HashDelete {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HDEL"], None)?;
+
+        let (key, fields) = extract_key_and_fields(value)?;
+        Ok(HashDelete { key, fields })
+    }
+}
+
+impl CommandExecutor for HashExists {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.hash_exists(&self.key, &self.field) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for HashExists {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HEXISTS"], Some(2))?;
 
         let mut args = extract_args(value, 1)?.into_iter();
-        match (args.next(), args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(field)), Some(value)) => {
-                Ok(HashSet {
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(field))) => {
+                Ok(HashExists {
                     key: key.try_into()?,
                     field: field.try_into()?,
-                    value,
                 })
             }
             _ => Err(CommandError::InvalidArgument(
-                "Invalid key, field or value".to_string(),
+                "Invalid key or field".to_string(),
             )),
         }
     }
 }
 
-impl CommandExecutor for HashMultiGet {
+impl CommandExecutor for HashLen {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        backend.hash_multi_get(&self.key, self.fields)
+        RespFrame::Integer(backend.hash_len(&self.key) as i64)
     }
 }
 
-impl TryFrom<RespArray> for HashMultiGet {
+impl TryFrom<RespArray> for HashLen {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["HMGET"], None)?;
+        validate_command(&value, &["HLEN"], Some(1))?;
 
-        // Parse the key.
         let mut args = extract_args(value, 1)?.into_iter();
-        let key = match args.next() {
-            Some(RespFrame::BulkString(key)) => key.try_into()?,
-            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
-        };
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(HashLen {
+                key: key.try_into()?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
 
-        // Parse the fields.
-        let mut fields = Vec::new();
-        for arg in args {
-            match arg {
-                RespFrame::BulkString(member) => fields.push(member.try_into()?),
-                _ => return Err(CommandError::InvalidArgument("Invalid field".to_string())),
+impl CommandExecutor for HashStrLen {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.hash_str_len(&self.key, &self.field) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for HashStrLen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HSTRLEN"], Some(2))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(field))) => {
+                Ok(HashStrLen {
+                    key: key.try_into()?,
+                    field: field.try_into()?,
+                })
             }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or field".to_string(),
+            )),
         }
+    }
+}
 
-        Ok(HashMultiGet { key, fields })
+impl CommandExecutor for HashKeys {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let ret = hash_entries(backend, &self.key, self.sort)
+            .into_iter()
+            .map(|(field, _)| BulkString::from(field).into())
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(ret).into()
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use anyhow::Result;
-    use bytes::BytesMut;
+impl TryFrom<RespArray> for HashKeys {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HKEYS"], Some(1))?;
 
-    use crate::{BulkString, RespArray, RespDecode, RespFrame};
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(HashKeys {
+                key: key.try_into()?,
+                sort: false,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
 
-    use super::{CommandExecutor, HashGet, HashGetAll, HashSet, RESP_OK};
+// Sorting orders the values by their field names, the same order as HGETALL.
+impl CommandExecutor for HashValues {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let ret = hash_entries(backend, &self.key, self.sort)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(ret).into()
+    }
+}
 
-    #[test]
-    fn test_hget_from_resp_array() -> Result<()> {
-        let mut buf = BytesMut::new();
-        buf.extend_from_slice(b"*3\r\n$4\r\nhget\r\n$3\r\nmap\r\n$5\r\nhello\r\n");
+impl TryFrom<RespArray> for HashValues {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HVALS"], Some(1))?;
 
-        let frame = RespArray::decode(&mut buf)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(HashValues {
+                key: key.try_into()?,
+                sort: false,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
 
-        let result: HashGet = frame.try_into()?;
-        assert_eq!(result.key, "map");
-        assert_eq!(result.field, "hello");
+impl CommandExecutor for HashIncrBy {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.hash_incr_by(self.key, self.field, self.delta) {
+            Ok(v) => RespFrame::Integer(v),
+            Err(e) => e.into(),
+        }
+    }
+}
 
-        Ok(())
+impl TryFrom<RespArray> for HashIncrBy {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HINCRBY"], Some(3))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(field)),
+                Some(RespFrame::BulkString(delta)),
+            ) => Ok(HashIncrBy {
+                key: key.try_into()?,
+                field: field.try_into()?,
+                delta: parse_number(delta, "increment")?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, field or increment".to_string(),
+            )),
+        }
     }
+}
 
-    #[test]
-    fn test_hgetall_from_resp_array() -> Result<()> {
-        let mut buf = BytesMut::new();
-        buf.extend_from_slice(b"*2\r\n$7\r\nhgetall\r\n$3\r\nmap\r\n");
+impl CommandExecutor for HashIncrByFloat {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.hash_incr_by_float(self.key, self.field, self.delta) {
+            Ok(v) => BulkString::new(v).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
 
-        let frame = RespArray::decode(&mut buf)?;
+impl TryFrom<RespArray> for HashIncrByFloat {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HINCRBYFLOAT"], Some(3))?;
 
-        let result: HashGetAll = frame.try_into()?;
-        assert_eq!(result.key, "map");
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, field, delta) = match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(field)),
+                Some(RespFrame::BulkString(delta)),
+            ) => (
+                key.try_into()?,
+                field.try_into()?,
+                parse_number::<f64>(delta, "increment")?,
+            ),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid key, field or increment".to_string(),
+                ))
+            }
+        };
+        if !delta.is_finite() {
+            return Err(CommandError::InvalidArgument(
+                "Invalid increment".to_string(),
+            ));
+        }
 
-        Ok(())
+        Ok(HashIncrByFloat { key, field, delta })
     }
+}
 
-    #[test]
-    fn test_hset_from_resp_array() -> Result<()> {
-        let mut buf = BytesMut::new();
-        buf.extend_from_slice(b"*4\r\n$4\r\nhset\r\n$3\r\nmap\r\n$5\r\nhello\r\n$5\r\nworld\r\n");
+// The reply is the next cursor and a flat array of fields and values, or of fields only
+// with NOVALUES.
+impl CommandExecutor for HashScan {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let (cursor, fields) = backend.hash_scan(
+            &self.key,
+            self.cursor,
+            self.pattern.as_deref().map(str::as_bytes),
+            self.count,
+        );
+        let ret: Vec<RespFrame> = if self.no_values {
+            fields
+                .into_iter()
+                .map(|(field, _)| BulkString::from(field).into())
+                .collect()
+        } else {
+            fields
+                .into_iter()
+                .flat_map(|(field, value)| [BulkString::from(field).into(), value])
+                .collect()
+        };
+        RespArray::new(vec![
+            BulkString::new(cursor.to_string()).into(),
+            RespArray::new(ret).into(),
+        ])
+        .into()
+    }
+}
 
-        let frame = RespArray::decode(&mut buf)?;
+impl TryFrom<RespArray> for HashScan {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HSCAN"], None)?;
 
-        let result: HashSet = frame.try_into()?;
-        assert_eq!(result.key, "map");
-        assert_eq!(result.field, "hello");
-        assert_eq!(result.value, RespFrame::BulkString(b"world".into()));
+        // Parse the key and the cursor.
+        let mut args = extract_args(value, 1)?.into_iter();
+        let mut scan = match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(cursor))) => HashScan {
+                key: key.try_into()?,
+                cursor: parse_number(cursor, "cursor")?,
+                pattern: None,
+                count: SCAN_DEFAULT_COUNT,
+                no_values: false,
+            },
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid key or cursor".to_string(),
+                ))
+            }
+        };
 
-        Ok(())
+        // Parse the options, MATCH and COUNT take a value.
+        while let Some(arg) = args.next() {
+            let option = match arg {
+                RespFrame::BulkString(option) => option.to_ascii_uppercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                b"MATCH" => match args.next() {
+                    Some(RespFrame::BulkString(pattern)) => {
+                        scan.pattern = Some(pattern.try_into()?)
+                    }
+                    _ => return Err(CommandError::InvalidArgument("Invalid pattern".to_string())),
+                },
+                b"COUNT" => {
+                    scan.count = match args.next() {
+                        Some(RespFrame::BulkString(count)) => parse_number(count, "count")?,
+                        _ => {
+                            return Err(CommandError::InvalidArgument("Invalid count".to_string()))
+                        }
+                    };
+                    if scan.count == 0 {
+                        return Err(CommandError::InvalidArgument(
+                            "count should be larger than 0".to_string(),
+                        ));
+                    }
+                }
+                b"NOVALUES" => scan.no_values = true,
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        Ok(scan)
     }
+}
 
-    #[test]
-    fn test_hset_hget_hgetall_commands() -> Result<()> {
-        let backend = crate::Backend::new();
-        let cmd = HashSet {
-            key: "map".to_string(),
-            field: "hello".to_string(),
-            value: RespFrame::BulkString(b"world".into()),
+// Without a count, replies with a single field or null. A positive count picks distinct
+// fields, up to the whole hash, a negative one picks as many fields as its absolute value,
+// possibly repeated.
+impl CommandExecutor for HashRandField {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let Some(count) = self.count else {
+            return match backend.hash_random_fields(&self.key, 1, false).pop() {
+                Some((field, _)) => BulkString::from(field).into(),
+                None => RespFrame::Null(crate::RespNull),
+            };
         };
-        let result = cmd.execute(&backend);
-        assert_eq!(result, RESP_OK.clone());
-
-        let cmd = HashSet {
-            key: "map".to_string(),
-            field: "hello1".to_string(),
-            value: RespFrame::BulkString(b"world1".into()),
+        let fields =
+            backend.hash_random_fields(&self.key, count.unsigned_abs() as usize, count < 0);
+        let ret: Vec<RespFrame> = if self.with_values {
+            fields
+                .into_iter()
+                .flat_map(|(field, value)| [BulkString::from(field).into(), value])
+                .collect()
+        } else {
+            fields
+                .into_iter()
+                .map(|(field, _)| BulkString::from(field).into())
+                .collect()
         };
-        cmd.execute(&backend);
+        RespArray::new(ret).into()
+    }
+}
 
-        let cmd = HashGet {
-            key: "map".to_string(),
-            field: "hello".to_string(),
-        };
-        let result = cmd.execute(&backend);
-        assert_eq!(result, RespFrame::BulkString(b"world".into()));
+impl TryFrom<RespArray> for HashRandField {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HRANDFIELD"], None)?;
 
-        let cmd = HashGetAll {
-            key: "map".to_string(),
-            sort: true,
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = match args.next() {
+            Some(RespFrame::BulkString(key)) => key.try_into()?,
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
         };
-        let result = cmd.execute(&backend);
+        let count = match args.next() {
+            Some(RespFrame::BulkString(count)) => Some(parse_number(count, "count")?),
+            None => None,
+            _ => return Err(CommandError::InvalidArgument("Invalid count".to_string())),
+        };
+        let with_values = match args.next() {
+            Some(RespFrame::BulkString(option))
+                if count.is_some() && option.to_ascii_uppercase().as_slice() == b"WITHVALUES" =>
+            {
+                true
+            }
+            None => false,
+            _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+        };
+        if args.next().is_some() {
+            return Err(CommandError::InvalidArgument(
+                "Too many arguments".to_string(),
+            ));
+        }
 
-        let expected = RespArray::new([
-            BulkString::from("hello").into(),
-            BulkString::from("world").into(),
-            BulkString::from("hello1").into(),
-            BulkString::from("world1").into(),
-        ]);
-        assert_eq!(result, expected.into());
-        Ok(())
+        Ok(HashRandField {
+            key,
+            count,
+            with_values,
+        })
+    }
+}
+
+// Replies an integer per field: -2 if the field does not exist, 0 if the condition is not
+// met, 1 if the time to live was set, or 2 if the field was deleted as the time is in the
+// past.
+impl CommandExecutor for HashExpire {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        field_expire_reply(backend.hash_expire_at(&self.key, &self.fields, self.at, self.condition))
+    }
+}
+
+impl TryFrom<RespArray> for HashExpire {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HEXPIRE"], None)?;
+
+        let (key, at, condition, fields) = extract_hash_expire(value, 1000)?;
+        Ok(HashExpire {
+            key,
+            at,
+            condition,
+            fields,
+        })
+    }
+}
+
+impl CommandExecutor for HashPExpire {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        field_expire_reply(backend.hash_expire_at(&self.key, &self.fields, self.at, self.condition))
+    }
+}
+
+impl TryFrom<RespArray> for HashPExpire {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HPEXPIRE"], None)?;
+
+        let (key, at, condition, fields) = extract_hash_expire(value, 1)?;
+        Ok(HashPExpire {
+            key,
+            at,
+            condition,
+            fields,
+        })
+    }
+}
+
+// Replies an integer per field: -2 if the field does not exist, -1 if it has no time to
+// live, or the remaining seconds rounded like TTL.
+impl CommandExecutor for HashTtl {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let now = now_ms();
+        let ttls = backend
+            .hash_expire_time(&self.key, &self.fields)
+            .into_iter()
+            .map(|at| match at {
+                None => RespFrame::Integer(-2),
+                Some(None) => RespFrame::Integer(-1),
+                Some(Some(at)) => {
+                    let ttl = (at.saturating_sub(now) + 500) / 1000;
+                    RespFrame::Integer(ttl.min(i64::MAX as u64) as i64)
+                }
+            });
+        RespArray::new(ttls.collect::<Vec<_>>()).into()
+    }
+}
+
+impl TryFrom<RespArray> for HashTtl {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HTTL"], None)?;
+
+        let (key, fields) = extract_key_and_fields_argument(value)?;
+        Ok(HashTtl { key, fields })
+    }
+}
+
+// Replies an integer per field: -2 if the field does not exist, -1 if it has no time to
+// live, or 1 if it was removed.
+impl CommandExecutor for HashPersist {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let results = backend
+            .hash_persist(&self.key, &self.fields)
+            .into_iter()
+            .map(|persisted| match persisted {
+                None => RespFrame::Integer(-2),
+                Some(false) => RespFrame::Integer(-1),
+                Some(true) => RespFrame::Integer(1),
+            });
+        RespArray::new(results.collect::<Vec<_>>()).into()
+    }
+}
+
+impl TryFrom<RespArray> for HashPersist {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["HPERSIST"], None)?;
+
+        let (key, fields) = extract_key_and_fields_argument(value)?;
+        Ok(HashPersist { key, fields })
+    }
+}
+
+fn field_expire_reply(results: Vec<FieldExpireResult>) -> RespFrame {
+    let results: Vec<RespFrame> = results
+        .into_iter()
+        .map(|result| {
+            RespFrame::Integer(match result {
+                FieldExpireResult::NoSuchField => -2,
+                FieldExpireResult::ConditionNotMet => 0,
+                FieldExpireResult::Set => 1,
+                FieldExpireResult::Deleted => 2,
+            })
+        })
+        .collect();
+    RespArray::new(results).into()
+}
+
+// The fields and values of the hash, sorted by field if `sort` is set. Empty if the key
+// does not exist.
+fn hash_entries(backend: &crate::Backend, key: &str, sort: bool) -> Vec<(String, RespFrame)> {
+    let mut data: Vec<(String, RespFrame)> = match backend.hash_get_all(key) {
+        Some(hmap) => hmap.into_iter().collect(),
+        None => Vec::new(),
+    };
+    if sort {
+        data.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    data
+}
+
+// Parses "<key> <field> [field ...]" shared by HMGET and HDEL.
+fn extract_key_and_fields(value: RespArray) -> Result<(String, Vec<String>), CommandError> {
+    // Parse the key.
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = match args.next() {
+        Some(RespFrame::BulkString(key)) => key.try_into()?,
+        _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    };
+
+    // Parse the fields.
+    let mut fields = Vec::new();
+    for arg in args {
+        match arg {
+            RespFrame::BulkString(field) => fields.push(field.try_into()?),
+            _ => return Err(CommandError::InvalidArgument("Invalid field".to_string())),
+        }
+    }
+
+    if fields.is_empty() {
+        return Err(CommandError::InvalidArgument(
+            "At least one field is required".to_string(),
+        ));
+    }
+
+    Ok((key, fields))
+}
+
+// Parses "<key> <field> <value> [field value ...]" shared by HSET and HMSET.
+fn extract_key_and_field_values(
+    value: RespArray,
+) -> Result<(String, Vec<(String, RespFrame)>), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = match args.next() {
+        Some(RespFrame::BulkString(key)) => key.try_into()?,
+        _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    };
+    if args.len() == 0 || args.len() % 2 != 0 {
+        return Err(CommandError::InvalidArgument(
+            "Fields and values must come in pairs".to_string(),
+        ));
+    }
+
+    let mut fields = Vec::with_capacity(args.len() / 2);
+    while let (Some(field), Some(value)) = (args.next(), args.next()) {
+        match field {
+            RespFrame::BulkString(field) => fields.push((field.try_into()?, value)),
+            _ => return Err(CommandError::InvalidArgument("Invalid field".to_string())),
+        }
+    }
+
+    Ok((key, fields))
+}
+
+// Parses "<key> <time> [NX | XX | GT | LT] FIELDS <numfields> <field> [field ...]" shared
+// by HEXPIRE and HPEXPIRE, the time being relative and counted in units of `unit_ms`
+// milliseconds.
+fn extract_hash_expire(
+    value: RespArray,
+    unit_ms: i64,
+) -> Result<(String, u64, ExpireCondition, Vec<String>), CommandError> {
+    let mut args = extract_args(value, 1)?;
+    let Some(start) = args.iter().position(|arg| match arg {
+        RespFrame::BulkString(arg) => arg.to_ascii_uppercase().as_slice() == b"FIELDS",
+        _ => false,
+    }) else {
+        return Err(CommandError::InvalidArgument(
+            "Missing FIELDS argument".to_string(),
+        ));
+    };
+    let fields = parse_fields_argument(args.split_off(start))?;
+
+    let mut args = args.into_iter();
+    let (key, n) = match (args.next(), args.next()) {
+        (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(n))) => {
+            (key.try_into()?, parse_number::<i64>(n, "expire time")?)
+        }
+        _ => {
+            return Err(CommandError::InvalidArgument(
+                "Invalid key or expire time".to_string(),
+            ))
+        }
+    };
+    let at = parse_expire_time(n, unit_ms, true)?;
+    let condition = parse_expire_condition(args)?;
+
+    Ok((key, at, condition, fields))
+}
+
+// Parses "<key> FIELDS <numfields> <field> [field ...]" shared by HTTL and HPERSIST.
+fn extract_key_and_fields_argument(
+    value: RespArray,
+) -> Result<(String, Vec<String>), CommandError> {
+    let mut args = extract_args(value, 1)?;
+    if args.is_empty() {
+        return Err(CommandError::InvalidArgument("Invalid key".to_string()));
+    }
+    let fields = parse_fields_argument(args.split_off(1))?;
+    match args.pop() {
+        Some(RespFrame::BulkString(key)) => Ok((key.try_into()?, fields)),
+        _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    }
+}
+
+// Parses "FIELDS <numfields> <field> [field ...]", the number of fields must match.
+fn parse_fields_argument(args: Vec<RespFrame>) -> Result<Vec<String>, CommandError> {
+    let mut args = args.into_iter();
+    let numfields: usize = match (args.next(), args.next()) {
+        (Some(RespFrame::BulkString(keyword)), Some(RespFrame::BulkString(n)))
+            if keyword.to_ascii_uppercase().as_slice() == b"FIELDS" =>
+        {
+            parse_number(n, "number of fields")?
+        }
+        _ => {
+            return Err(CommandError::InvalidArgument(
+                "Missing FIELDS argument".to_string(),
+            ))
+        }
+    };
+    if numfields == 0 || numfields != args.len() {
+        return Err(CommandError::InvalidArgument(
+            "The number of fields must match numfields".to_string(),
+        ));
+    }
+
+    let mut fields = Vec::with_capacity(numfields);
+    for arg in args {
+        match arg {
+            RespFrame::BulkString(field) => fields.push(field.try_into()?),
+            _ => return Err(CommandError::InvalidArgument("Invalid field".to_string())),
+        }
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        now_ms, BulkString, ExpireCondition, RespArray, RespDecode, RespFrame, SimpleError,
+    };
+
+    use super::{
+        CommandExecutor, HashDelete, HashExists, HashExpire, HashGet, HashGetAll, HashIncrBy,
+        HashIncrByFloat, HashKeys, HashLen, HashMultiSet, HashPExpire, HashPersist, HashRandField,
+        HashScan, HashSet, HashStrLen, HashTtl, HashValues, RESP_OK,
+    };
+
+    #[test]
+    fn test_hget_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$4\r\nhget\r\n$3\r\nmap\r\n$5\r\nhello\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HashGet = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert_eq!(result.field, "hello");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hgetall_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$7\r\nhgetall\r\n$3\r\nmap\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HashGetAll = frame.try_into()?;
+        assert_eq!(result.key, "map");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hset_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$4\r\nhset\r\n$3\r\nmap\r\n$5\r\nhello\r\n$5\r\nworld\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HashSet = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert_eq!(
+            result.fields,
+            [("hello".to_string(), RespFrame::BulkString(b"world".into()))]
+        );
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$5\r\nhmset\r\n$3\r\nmap\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n2\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: HashMultiSet = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert_eq!(
+            result.fields,
+            [
+                ("a".to_string(), RespFrame::BulkString(b"1".into())),
+                ("b".to_string(), RespFrame::BulkString(b"2".into()))
+            ]
+        );
+
+        for invalid in [
+            &b"*3\r\n$4\r\nhset\r\n$3\r\nmap\r\n$1\r\na\r\n"[..],
+            b"*5\r\n$4\r\nhset\r\n$3\r\nmap\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n",
+            b"*2\r\n$5\r\nhmset\r\n$3\r\nmap\r\n",
+        ] {
+            let mut buf = BytesMut::from(invalid);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hset_hget_hgetall_commands() -> Result<()> {
+        let backend = crate::Backend::new();
+        let cmd = HashSet {
+            key: "map".to_string(),
+            fields: vec![("hello".to_string(), RespFrame::BulkString(b"world".into()))],
+        };
+        let result = cmd.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(1));
+
+        // Only new fields are counted, a repeated field is set to its last value.
+        let cmd = HashSet {
+            key: "map".to_string(),
+            fields: vec![
+                ("hello".to_string(), RespFrame::BulkString(b"world".into())),
+                (
+                    "hello1".to_string(),
+                    RespFrame::BulkString(b"ignored".into()),
+                ),
+                (
+                    "hello1".to_string(),
+                    RespFrame::BulkString(b"world1".into()),
+                ),
+            ],
+        };
+        let result = cmd.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(1));
+
+        let cmd = HashGet {
+            key: "map".to_string(),
+            field: "hello".to_string(),
+        };
+        let result = cmd.execute(&backend);
+        assert_eq!(result, RespFrame::BulkString(b"world".into()));
+
+        let cmd = HashGetAll {
+            key: "map".to_string(),
+            sort: true,
+        };
+        let result = cmd.execute(&backend);
+
+        let expected = RespArray::new([
+            BulkString::from("hello").into(),
+            BulkString::from("world").into(),
+            BulkString::from("hello1").into(),
+            BulkString::from("world1").into(),
+        ]);
+        assert_eq!(result, expected.into());
+
+        let cmd = HashMultiSet {
+            key: "map".to_string(),
+            fields: vec![(
+                "hello2".to_string(),
+                RespFrame::BulkString(b"world2".into()),
+            )],
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.hash_len("map"), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdel_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$4\r\nhdel\r\n$3\r\nmap\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HashDelete = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert_eq!(result.fields, vec!["a".to_string(), "b".to_string()]);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\nhdel\r\n$3\r\nmap\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(HashDelete::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdel_command() {
+        let backend = crate::Backend::new();
+        for field in ["a", "b", "c"] {
+            backend.hash_set("map".to_string(), field.to_string(), RespFrame::Integer(1));
+        }
+        backend.expire_at("map", u64::MAX, Default::default());
+
+        let cmd = HashDelete {
+            key: "map".to_string(),
+            fields: vec!["a".to_string(), "missing".to_string(), "a".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.hash_get("map", "a"), None);
+        assert_eq!(backend.hash_get("map", "b"), Some(RespFrame::Integer(1)));
+
+        // The hash goes away along with its last field, and so does its time to live.
+        let cmd = HashDelete {
+            key: "map".to_string(),
+            fields: vec!["b".to_string(), "c".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert!(!backend.exists("map"));
+        assert!(!backend.expire.contains_key("map"));
+
+        let cmd = HashDelete {
+            key: "map".to_string(),
+            fields: vec!["b".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+    }
+
+    #[test]
+    fn test_hexists_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$7\r\nhexists\r\n$3\r\nmap\r\n$1\r\na\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HashExists = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert_eq!(result.field, "a");
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$4\r\nhlen\r\n$3\r\nmap\r\n$1\r\na\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(HashLen::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hexists_hlen_commands() {
+        let backend = crate::Backend::new();
+        backend.hash_set("map".to_string(), "a".to_string(), RespFrame::Integer(1));
+        backend.hash_set("map".to_string(), "b".to_string(), RespFrame::Integer(2));
+
+        let exists = |key: &str, field: &str| {
+            HashExists {
+                key: key.to_string(),
+                field: field.to_string(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(exists("map", "a"), RespFrame::Integer(1));
+        assert_eq!(exists("map", "missing"), RespFrame::Integer(0));
+        assert_eq!(exists("missing", "a"), RespFrame::Integer(0));
+
+        let len = |key: &str| {
+            HashLen {
+                key: key.to_string(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(len("map"), RespFrame::Integer(2));
+        assert_eq!(len("missing"), RespFrame::Integer(0));
+    }
+
+    #[test]
+    fn test_hstrlen_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$7\r\nhstrlen\r\n$3\r\nmap\r\n$1\r\na\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HashStrLen = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert_eq!(result.field, "a");
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$7\r\nhstrlen\r\n$3\r\nmap\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(HashStrLen::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hstrlen_command() {
+        let backend = crate::Backend::new();
+        backend.hash_set(
+            "map".to_string(),
+            "a".to_string(),
+            BulkString::from("hello").into(),
+        );
+        backend.hash_set("map".to_string(), "b".to_string(), RespFrame::Integer(-42));
+
+        let str_len = |key: &str, field: &str| {
+            HashStrLen {
+                key: key.to_string(),
+                field: field.to_string(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(str_len("map", "a"), RespFrame::Integer(5));
+        assert_eq!(str_len("map", "b"), RespFrame::Integer(3));
+        assert_eq!(str_len("map", "missing"), RespFrame::Integer(0));
+        assert_eq!(str_len("missing", "a"), RespFrame::Integer(0));
+    }
+
+    #[test]
+    fn test_hkeys_hvals_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$5\r\nhkeys\r\n$3\r\nmap\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HashKeys = frame.try_into()?;
+        assert_eq!(result.key, "map");
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$5\r\nhvals\r\n$3\r\nmap\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: HashValues = frame.try_into()?;
+        assert_eq!(result.key, "map");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hkeys_hvals_commands() {
+        let backend = crate::Backend::new();
+        backend.hash_set("map".to_string(), "b".to_string(), RespFrame::Integer(2));
+        backend.hash_set("map".to_string(), "a".to_string(), RespFrame::Integer(1));
+
+        let cmd = HashKeys {
+            key: "map".to_string(),
+            sort: true,
+        };
+        let expected = RespArray::new([BulkString::from("a").into(), BulkString::from("b").into()]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = HashValues {
+            key: "map".to_string(),
+            sort: true,
+        };
+        let expected = RespArray::new([RespFrame::Integer(1), RespFrame::Integer(2)]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = HashKeys {
+            key: "missing".to_string(),
+            sort: true,
+        };
+        assert_eq!(cmd.execute(&backend), RespArray::new([]).into());
+    }
+
+    #[test]
+    fn test_hincrby_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$7\r\nhincrby\r\n$3\r\nmap\r\n$1\r\na\r\n$2\r\n-5\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HashIncrBy = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert_eq!(result.field, "a");
+        assert_eq!(result.delta, -5);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$12\r\nhincrbyfloat\r\n$3\r\nmap\r\n$1\r\na\r\n$3\r\n0.5\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: HashIncrByFloat = frame.try_into()?;
+        assert_eq!(result.delta, 0.5);
+
+        for invalid in [
+            &b"*4\r\n$7\r\nhincrby\r\n$3\r\nmap\r\n$1\r\na\r\n$3\r\n1.5\r\n"[..],
+            b"*3\r\n$7\r\nhincrby\r\n$3\r\nmap\r\n$1\r\na\r\n",
+            b"*4\r\n$12\r\nhincrbyfloat\r\n$3\r\nmap\r\n$1\r\na\r\n$3\r\ninf\r\n",
+        ] {
+            let mut buf = BytesMut::from(invalid);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hincrby_hincrbyfloat_commands() {
+        let backend = crate::Backend::new();
+        let incr_by = |field: &str, delta: i64| {
+            HashIncrBy {
+                key: "map".to_string(),
+                field: field.to_string(),
+                delta,
+            }
+            .execute(&backend)
+        };
+        assert_eq!(incr_by("a", 5), RespFrame::Integer(5));
+        assert_eq!(incr_by("a", -7), RespFrame::Integer(-2));
+        assert_eq!(
+            backend.hash_get("map", "a"),
+            Some(BulkString::from("-2").into())
+        );
+
+        backend.hash_set(
+            "map".to_string(),
+            "s".to_string(),
+            BulkString::from("x").into(),
+        );
+        assert_eq!(
+            incr_by("s", 1),
+            SimpleError::new("ERR hash value is not an integer").into()
+        );
+        backend.hash_set(
+            "map".to_string(),
+            "max".to_string(),
+            BulkString::from(i64::MAX.to_string()).into(),
+        );
+        assert_eq!(
+            incr_by("max", 1),
+            SimpleError::new("ERR increment or decrement would overflow").into()
+        );
+
+        let incr_by_float = |field: &str, delta: f64| {
+            HashIncrByFloat {
+                key: "map".to_string(),
+                field: field.to_string(),
+                delta,
+            }
+            .execute(&backend)
+        };
+        assert_eq!(incr_by_float("a", 0.5), BulkString::from("-1.5").into());
+        assert_eq!(incr_by_float("f", 10.1), BulkString::from("10.1").into());
+        // The rounding error of the addition is not shown, like Redis.
+        assert_eq!(incr_by_float("g", 0.1), BulkString::from("0.1").into());
+        assert_eq!(incr_by_float("g", 0.2), BulkString::from("0.3").into());
+        assert_eq!(
+            backend.hash_get("map", "g"),
+            Some(BulkString::from("0.3").into())
+        );
+        assert_eq!(
+            incr_by_float("s", 1.0),
+            SimpleError::new("ERR hash value is not a float").into()
+        );
+        backend.hash_set(
+            "map".to_string(),
+            "big".to_string(),
+            BulkString::from("1e308").into(),
+        );
+        assert_eq!(
+            incr_by_float("big", 1e308),
+            SimpleError::new("ERR increment would produce NaN or Infinity").into()
+        );
+        assert_eq!(
+            backend.hash_get("map", "big"),
+            Some(BulkString::from("1e308").into())
+        );
+    }
+
+    #[test]
+    fn test_hscan_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*7\r\n$5\r\nhscan\r\n$3\r\nmap\r\n$1\r\n0\r\n$5\r\nmatch\r\n$2\r\na*\r\n$8\r\nnovalues\r\n$1\r\n1\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(HashScan::try_from(frame).is_err());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*8\r\n$5\r\nhscan\r\n$3\r\nmap\r\n$1\r\n7\r\n$5\r\nmatch\r\n$2\r\na*\r\n$8\r\nnovalues\r\n$5\r\ncount\r\n$2\r\n20\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HashScan = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert_eq!(result.cursor, 7);
+        assert_eq!(result.pattern.as_deref(), Some("a*"));
+        assert_eq!(result.count, 20);
+        assert!(result.no_values);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hscan_command() {
+        let backend = crate::Backend::new();
+        for i in 0..100 {
+            backend.hash_set(
+                "map".to_string(),
+                format!("field{}", i),
+                BulkString::from(i.to_string()).into(),
+            );
+        }
+
+        // Iterate until the cursor is back to 0, every field is returned.
+        let scan = |cursor: u64, no_values: bool| {
+            let cmd = HashScan {
+                key: "map".to_string(),
+                cursor,
+                pattern: Some("field1*".to_string()),
+                count: 10,
+                no_values,
+            };
+            match cmd.execute(&backend) {
+                RespFrame::Array(reply) => match (&reply[0], &reply[1]) {
+                    (RespFrame::BulkString(cursor), RespFrame::Array(items)) => (
+                        String::from_utf8_lossy(cursor).parse::<u64>().unwrap(),
+                        items.to_vec(),
+                    ),
+                    _ => panic!("unexpected reply"),
+                },
+                _ => panic!("unexpected reply"),
+            }
+        };
+        let mut items = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (next, batch) = scan(cursor, false);
+            items.extend(batch);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        let mut pairs: Vec<(Vec<u8>, RespFrame)> = items
+            .chunks(2)
+            .map(|pair| match &pair[0] {
+                RespFrame::BulkString(field) => (field.to_vec(), pair[1].clone()),
+                _ => panic!("unexpected field"),
+            })
+            .collect();
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(pairs.len(), 11);
+        assert_eq!(pairs[0], (b"field1".to_vec(), BulkString::from("1").into()));
+        assert_eq!(pairs[10].0, b"field19");
+
+        let (_, batch) = scan(0, true);
+        assert!(batch
+            .iter()
+            .all(|field| matches!(field, RespFrame::BulkString(f) if f.starts_with(b"field1"))));
+
+        let cmd = HashScan {
+            key: "missing".to_string(),
+            cursor: 0,
+            pattern: None,
+            count: 10,
+            no_values: false,
+        };
+        let expected = RespArray::new([BulkString::from("0").into(), RespArray::new([]).into()]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+    }
+
+    #[test]
+    fn test_hrandfield_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$10\r\nhrandfield\r\n$3\r\nmap\r\n$2\r\n-5\r\n$10\r\nwithvalues\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HashRandField = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert_eq!(result.count, Some(-5));
+        assert!(result.with_values);
+
+        for invalid in [
+            &b"*3\r\n$10\r\nhrandfield\r\n$3\r\nmap\r\n$10\r\nwithvalues\r\n"[..],
+            b"*4\r\n$10\r\nhrandfield\r\n$3\r\nmap\r\n$1\r\n1\r\n$1\r\nx\r\n",
+            b"*1\r\n$10\r\nhrandfield\r\n",
+        ] {
+            let mut buf = BytesMut::from(invalid);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(HashRandField::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hrandfield_command() {
+        let backend = crate::Backend::new();
+        backend.hash_set("map".to_string(), "a".to_string(), RespFrame::Integer(1));
+        backend.hash_set("map".to_string(), "b".to_string(), RespFrame::Integer(2));
+        backend.hash_set("map".to_string(), "c".to_string(), RespFrame::Integer(3));
+
+        let rand_field = |key: &str, count: Option<i64>, with_values: bool| match (HashRandField {
+            key: key.to_string(),
+            count,
+            with_values,
+        })
+        .execute(&backend)
+        {
+            RespFrame::Array(items) => items.to_vec(),
+            frame => vec![frame],
+        };
+
+        let field = rand_field("map", None, false);
+        assert!(["a", "b", "c"]
+            .iter()
+            .any(|f| field == [BulkString::from(*f).into()]));
+        assert_eq!(
+            rand_field("missing", None, false),
+            [RespFrame::Null(crate::RespNull)]
+        );
+
+        // A positive count returns distinct fields, at most all of them.
+        let mut fields = rand_field("map", Some(10), false);
+        fields.sort_by_key(|f| format!("{:?}", f));
+        assert_eq!(
+            fields,
+            [
+                BulkString::from("a").into(),
+                BulkString::from("b").into(),
+                BulkString::from("c").into()
+            ]
+        );
+        assert_eq!(rand_field("map", Some(2), false).len(), 2);
+        assert!(rand_field("map", Some(0), false).is_empty());
+
+        // A negative count may repeat fields.
+        assert_eq!(rand_field("map", Some(-10), false).len(), 10);
+        let pairs = rand_field("map", Some(-4), true);
+        assert_eq!(pairs.len(), 8);
+        for pair in pairs.chunks(2) {
+            let expected = match &pair[0] {
+                RespFrame::BulkString(field) => (field[0] - b'a' + 1) as i64,
+                _ => panic!("unexpected field"),
+            };
+            assert_eq!(pair[1], RespFrame::Integer(expected));
+        }
+        assert!(rand_field("missing", Some(-3), true).is_empty());
+    }
+
+    #[test]
+    fn test_hexpire_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*8\r\n$7\r\nhexpire\r\n$3\r\nmap\r\n$2\r\n10\r\n$2\r\ngt\r\n$6\r\nfields\r\n$1\r\n2\r\n$1\r\na\r\n$1\r\nb\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let now = now_ms();
+        let result: HashExpire = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert!(result.at >= now + 10_000 && result.at <= now_ms() + 10_000);
+        assert!(result.condition.if_greater);
+        assert_eq!(result.fields, ["a", "b"]);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$8\r\nhpexpire\r\n$3\r\nmap\r\n$2\r\n-5\r\n$6\r\nFIELDS\r\n$1\r\n1\r\n$1\r\na\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: HashPExpire = frame.try_into()?;
+        assert!(result.at < now_ms());
+        assert_eq!(result.condition, ExpireCondition::default());
+        assert_eq!(result.fields, ["a"]);
+
+        for invalid in [
+            // The number of fields doesn't match.
+            &b"*6\r\n$7\r\nhexpire\r\n$3\r\nmap\r\n$2\r\n10\r\n$6\r\nfields\r\n$1\r\n2\r\n$1\r\na\r\n"[..],
+            b"*5\r\n$7\r\nhexpire\r\n$3\r\nmap\r\n$2\r\n10\r\n$6\r\nfields\r\n$1\r\n0\r\n",
+            b"*5\r\n$7\r\nhexpire\r\n$3\r\nmap\r\n$2\r\n10\r\n$1\r\n1\r\n$1\r\na\r\n",
+            b"*8\r\n$7\r\nhexpire\r\n$3\r\nmap\r\n$2\r\n10\r\n$2\r\nnx\r\n$2\r\nxx\r\n$6\r\nfields\r\n$1\r\n1\r\n$1\r\na\r\n",
+            b"*5\r\n$7\r\nhexpire\r\n$3\r\nmap\r\n$6\r\nfields\r\n$1\r\n1\r\n$1\r\na\r\n",
+        ] {
+            let mut buf = BytesMut::from(invalid);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(HashExpire::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_httl_hpersist_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$4\r\nhttl\r\n$3\r\nmap\r\n$6\r\nfields\r\n$1\r\n2\r\n$1\r\na\r\n$1\r\nb\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HashTtl = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert_eq!(result.fields, ["a", "b"]);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$8\r\nhpersist\r\n$3\r\nmap\r\n$6\r\nfields\r\n$1\r\n1\r\n$1\r\na\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: HashPersist = frame.try_into()?;
+        assert_eq!(result.key, "map");
+        assert_eq!(result.fields, ["a"]);
+
+        for invalid in [
+            &b"*4\r\n$4\r\nhttl\r\n$3\r\nmap\r\n$1\r\n1\r\n$1\r\na\r\n"[..],
+            b"*5\r\n$4\r\nhttl\r\n$3\r\nmap\r\n$6\r\nfields\r\n$1\r\n2\r\n$1\r\na\r\n",
+            b"*1\r\n$4\r\nhttl\r\n",
+        ] {
+            let mut buf = BytesMut::from(invalid);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(HashTtl::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hexpire_httl_hpersist_commands() {
+        let backend = crate::Backend::new();
+        backend.hash_set("map".to_string(), "a".to_string(), RespFrame::Integer(1));
+        backend.hash_set("map".to_string(), "b".to_string(), RespFrame::Integer(2));
+        let fields = |fields: &[&str]| fields.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+        let ints = |ints: &[i64]| -> RespFrame {
+            RespArray::new(
+                ints.iter()
+                    .map(|n| RespFrame::Integer(*n))
+                    .collect::<Vec<_>>(),
+            )
+            .into()
+        };
+        let ttl = |key: &str, f: &[&str]| {
+            HashTtl {
+                key: key.to_string(),
+                fields: fields(f),
+            }
+            .execute(&backend)
+        };
+
+        let cmd = HashExpire {
+            key: "map".to_string(),
+            at: now_ms() + 100_000,
+            condition: ExpireCondition::default(),
+            fields: fields(&["a", "c"]),
+        };
+        assert_eq!(cmd.execute(&backend), ints(&[1, -2]));
+        assert_eq!(ttl("map", &["a", "b", "c"]), ints(&[100, -1, -2]));
+        assert_eq!(ttl("missing", &["a"]), ints(&[-2]));
+
+        // NX only sets fields without a time to live, GT only later times.
+        let cmd = HashPExpire {
+            key: "map".to_string(),
+            at: now_ms() + 200_000,
+            condition: ExpireCondition {
+                if_none: true,
+                ..Default::default()
+            },
+            fields: fields(&["a", "b"]),
+        };
+        assert_eq!(cmd.execute(&backend), ints(&[0, 1]));
+        let cmd = HashExpire {
+            key: "map".to_string(),
+            at: now_ms() + 150_000,
+            condition: ExpireCondition {
+                if_greater: true,
+                ..Default::default()
+            },
+            fields: fields(&["a", "b"]),
+        };
+        assert_eq!(cmd.execute(&backend), ints(&[1, 0]));
+        assert_eq!(ttl("map", &["a", "b"]), ints(&[150, 200]));
+
+        // HPERSIST and HSET both remove the time to live, HINCRBY keeps it.
+        let cmd = HashPersist {
+            key: "map".to_string(),
+            fields: fields(&["a", "a", "c"]),
+        };
+        assert_eq!(cmd.execute(&backend), ints(&[1, -1, -2]));
+        backend
+            .hash_incr_by("map".to_string(), "b".to_string(), 1)
+            .unwrap();
+        assert_eq!(ttl("map", &["b"]), ints(&[200]));
+        backend.hash_set("map".to_string(), "b".to_string(), RespFrame::Integer(2));
+        assert_eq!(ttl("map", &["a", "b"]), ints(&[-1, -1]));
+
+        // A time in the past deletes the fields, and the hash once empty.
+        let cmd = HashPExpire {
+            key: "map".to_string(),
+            at: 0,
+            condition: ExpireCondition::default(),
+            fields: fields(&["a"]),
+        };
+        assert_eq!(cmd.execute(&backend), ints(&[2]));
+        assert_eq!(backend.hash_len("map"), 1);
+        let cmd = HashPExpire {
+            key: "map".to_string(),
+            at: 0,
+            condition: ExpireCondition::default(),
+            fields: fields(&["b"]),
+        };
+        assert_eq!(cmd.execute(&backend), ints(&[2]));
+        assert!(!backend.exists("map"));
+    }
+
+    #[test]
+    fn test_hash_fields_lazy_expiration() {
+        let backend = crate::Backend::new();
+        backend.hash_set("map".to_string(), "a".to_string(), RespFrame::Integer(1));
+        backend.hash_set("map".to_string(), "b".to_string(), RespFrame::Integer(2));
+        let cmd = HashPExpire {
+            key: "map".to_string(),
+            at: now_ms() + 20,
+            condition: ExpireCondition::default(),
+            fields: vec!["a".to_string()],
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([RespFrame::Integer(1)]).into()
+        );
+        assert_eq!(backend.hash_len("map"), 2);
+
+        std::thread::sleep(std::time::Duration::from_millis(30));
+
+        // Expired fields are gone for every command, the hash along with its last field.
+        assert_eq!(backend.hash_get("map", "a"), None);
+        assert_eq!(backend.hash_len("map"), 1);
+        let cmd = HashPExpire {
+            key: "map".to_string(),
+            at: now_ms() + 20,
+            condition: ExpireCondition::default(),
+            fields: vec!["b".to_string()],
+        };
+        cmd.execute(&backend);
+        std::thread::sleep(std::time::Duration::from_millis(30));
+        assert_eq!(backend.hash_get_all("map").map(|h| h.len()), None);
+        assert!(!backend.exists("map"));
     }
 }
@@ -0,0 +1,1396 @@
+use crate::{
+    cmd::CommandError, now_ms, BulkString, ExpireCondition, RespArray, RespFrame, RespNull,
+};
+
+use super::{
+    extract_args, extract_key, extract_keys, parse_expire_condition, parse_expire_time,
+    parse_number, validate_command, CommandExecutor, KeyDelete, KeyDump, KeyExists, KeyExpire,
+    KeyExpireAt, KeyExpireTime, KeyPExpire, KeyPExpireAt, KeyPExpireTime, KeyPTtl, KeyPersist,
+    KeyRename, KeyRenameNx, KeyRestore, KeyTouch, KeyTtl, KeyUnlink, Keys, ObjectEncoding,
+    ObjectFreq, ObjectIdleTime, ObjectRefCount, RandomKey, Scan, RESP_OK, SCAN_DEFAULT_COUNT,
+};
+
+impl CommandExecutor for KeyDelete {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let count = self.keys.iter().filter(|key| backend.delete(key)).count();
+        RespFrame::Integer(count as i64)
+    }
+}
+
+impl TryFrom<RespArray> for KeyDelete {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["DEL"], None)?;
+
+        Ok(KeyDelete {
+            keys: extract_keys(value)?,
+        })
+    }
+}
+
+// Values are freed as soon as they are removed, so UNLINK behaves the same as DEL.
+impl CommandExecutor for KeyUnlink {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        KeyDelete { keys: self.keys }.execute(backend)
+    }
+}
+
+impl TryFrom<RespArray> for KeyUnlink {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["UNLINK"], None)?;
+
+        Ok(KeyUnlink {
+            keys: extract_keys(value)?,
+        })
+    }
+}
+
+// A key given several times is counted each time, like in Redis.
+impl CommandExecutor for KeyExists {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let count = self.keys.iter().filter(|key| backend.exists(key)).count();
+        RespFrame::Integer(count as i64)
+    }
+}
+
+impl TryFrom<RespArray> for KeyExists {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["EXISTS"], None)?;
+
+        Ok(KeyExists {
+            keys: extract_keys(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for KeyTouch {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let count = self.keys.iter().filter(|key| backend.touch(key)).count();
+        RespFrame::Integer(count as i64)
+    }
+}
+
+impl TryFrom<RespArray> for KeyTouch {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["TOUCH"], None)?;
+
+        Ok(KeyTouch {
+            keys: extract_keys(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for KeyRename {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.rename(&self.src, &self.dst, true) {
+            Ok(_) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for KeyRename {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["RENAME"], Some(2))?;
+
+        let (src, dst) = extract_src_and_dst(value)?;
+        Ok(KeyRename { src, dst })
+    }
+}
+
+impl CommandExecutor for KeyRenameNx {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.rename(&self.src, &self.dst, false) {
+            Ok(renamed) => RespFrame::Integer(renamed as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for KeyRenameNx {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["RENAMENX"], Some(2))?;
+
+        let (src, dst) = extract_src_and_dst(value)?;
+        Ok(KeyRenameNx { src, dst })
+    }
+}
+
+impl CommandExecutor for KeyExpire {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.expire_at(&self.key, self.at, self.condition) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for KeyExpire {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["EXPIRE"], None)?;
+
+        let (key, at, condition) = extract_key_and_expire_time(value, 1000, true)?;
+        Ok(KeyExpire { key, at, condition })
+    }
+}
+
+impl CommandExecutor for KeyPExpire {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.expire_at(&self.key, self.at, self.condition) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for KeyPExpire {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["PEXPIRE"], None)?;
+
+        let (key, at, condition) = extract_key_and_expire_time(value, 1, true)?;
+        Ok(KeyPExpire { key, at, condition })
+    }
+}
+
+impl CommandExecutor for KeyExpireAt {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.expire_at(&self.key, self.at, self.condition) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for KeyExpireAt {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["EXPIREAT"], None)?;
+
+        let (key, at, condition) = extract_key_and_expire_time(value, 1000, false)?;
+        Ok(KeyExpireAt { key, at, condition })
+    }
+}
+
+impl CommandExecutor for KeyPExpireAt {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.expire_at(&self.key, self.at, self.condition) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for KeyPExpireAt {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["PEXPIREAT"], None)?;
+
+        let (key, at, condition) = extract_key_and_expire_time(value, 1, false)?;
+        Ok(KeyPExpireAt { key, at, condition })
+    }
+}
+
+impl CommandExecutor for KeyPersist {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.persist(&self.key) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for KeyPersist {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["PERSIST"], Some(1))?;
+
+        Ok(KeyPersist {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for KeyTtl {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        // Rounded to the nearest second, like Redis.
+        ttl_reply(backend, &self.key, |at| {
+            (at.saturating_sub(now_ms()) + 500) / 1000
+        })
+    }
+}
+
+impl TryFrom<RespArray> for KeyTtl {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["TTL"], Some(1))?;
+
+        Ok(KeyTtl {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for KeyPTtl {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        ttl_reply(backend, &self.key, |at| at.saturating_sub(now_ms()))
+    }
+}
+
+impl TryFrom<RespArray> for KeyPTtl {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["PTTL"], Some(1))?;
+
+        Ok(KeyPTtl {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for KeyExpireTime {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        ttl_reply(backend, &self.key, |at| at / 1000)
+    }
+}
+
+impl TryFrom<RespArray> for KeyExpireTime {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["EXPIRETIME"], Some(1))?;
+
+        Ok(KeyExpireTime {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for KeyPExpireTime {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        ttl_reply(backend, &self.key, |at| at)
+    }
+}
+
+impl TryFrom<RespArray> for KeyPExpireTime {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["PEXPIRETIME"], Some(1))?;
+
+        Ok(KeyPExpireTime {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for KeyDump {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.dump(&self.key) {
+            Some(payload) => BulkString::new(payload).into(),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for KeyDump {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["DUMP"], Some(1))?;
+
+        Ok(KeyDump {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for KeyRestore {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let at = match (self.ttl, self.absolute) {
+            (0, _) => None,
+            (ttl, true) => Some(ttl),
+            (ttl, false) => Some(now_ms().saturating_add(ttl)),
+        };
+        match backend.restore(&self.key, &self.payload, at, self.replace) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for KeyRestore {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["RESTORE"], None)?;
+
+        // Parse the key, ttl and payload.
+        let mut args = extract_args(value, 1)?.into_iter();
+        let mut restore = match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(ttl)),
+                Some(RespFrame::BulkString(payload)),
+            ) => KeyRestore {
+                key: key.try_into()?,
+                ttl: parse_number(ttl, "ttl")?,
+                payload: payload.to_vec(),
+                replace: false,
+                absolute: false,
+            },
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid key, ttl or payload".to_string(),
+                ))
+            }
+        };
+
+        // Parse the options.
+        for arg in args {
+            let option = match arg {
+                RespFrame::BulkString(option) => option.to_ascii_uppercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                b"REPLACE" => restore.replace = true,
+                b"ABSTTL" => restore.absolute = true,
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        Ok(restore)
+    }
+}
+
+impl CommandExecutor for Keys {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let keys: Vec<RespFrame> = backend
+            .keys(self.pattern.as_bytes())
+            .into_iter()
+            .map(|key| RespFrame::BulkString(BulkString::new(key)))
+            .collect();
+        RespArray::new(keys).into()
+    }
+}
+
+impl TryFrom<RespArray> for Keys {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["KEYS"], Some(1))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(pattern)) => Ok(Keys {
+                pattern: pattern.try_into()?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid pattern".to_string())),
+        }
+    }
+}
+
+impl CommandExecutor for Scan {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let (cursor, keys) = backend.scan(
+            self.cursor,
+            self.pattern.as_deref().map(str::as_bytes),
+            self.count,
+            self.key_type.as_deref(),
+        );
+        let keys: Vec<RespFrame> = keys
+            .into_iter()
+            .map(|key| RespFrame::BulkString(BulkString::new(key)))
+            .collect();
+        RespArray::new(vec![
+            BulkString::new(cursor.to_string()).into(),
+            RespArray::new(keys).into(),
+        ])
+        .into()
+    }
+}
+
+impl TryFrom<RespArray> for Scan {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SCAN"], None)?;
+
+        // Parse the cursor.
+        let mut args = extract_args(value, 1)?.into_iter();
+        let cursor = match args.next() {
+            Some(RespFrame::BulkString(cursor)) => parse_number(cursor, "cursor")?,
+            _ => return Err(CommandError::InvalidArgument("Invalid cursor".to_string())),
+        };
+
+        // Parse the options, each of them takes a value.
+        let mut scan = Scan {
+            cursor,
+            pattern: None,
+            count: SCAN_DEFAULT_COUNT,
+            key_type: None,
+        };
+        while let Some(arg) = args.next() {
+            let (option, value) = match (arg, args.next()) {
+                (RespFrame::BulkString(option), Some(RespFrame::BulkString(value))) => {
+                    (option.to_ascii_uppercase(), value)
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                b"MATCH" => scan.pattern = Some(value.try_into()?),
+                b"COUNT" => {
+                    scan.count = parse_number(value, "count")?;
+                    if scan.count == 0 {
+                        return Err(CommandError::InvalidArgument(
+                            "count should be larger than 0".to_string(),
+                        ));
+                    }
+                }
+                b"TYPE" => scan.key_type = Some(value.try_into()?),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        Ok(scan)
+    }
+}
+
+impl CommandExecutor for RandomKey {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.random_key() {
+            Some(key) => BulkString::new(key).into(),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for RandomKey {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["RANDOMKEY"], Some(0))?;
+        Ok(RandomKey)
+    }
+}
+
+impl CommandExecutor for ObjectEncoding {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.object_encoding(&self.key) {
+            Some(encoding) => BulkString::new(encoding).into(),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ObjectEncoding {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(ObjectEncoding {
+            key: extract_object_key(value, "ENCODING")?,
+        })
+    }
+}
+
+// Values are never shared between keys, so each one is referenced once.
+impl CommandExecutor for ObjectRefCount {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if backend.exists(&self.key) {
+            RespFrame::Integer(1)
+        } else {
+            RespFrame::Null(RespNull)
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ObjectRefCount {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(ObjectRefCount {
+            key: extract_object_key(value, "REFCOUNT")?,
+        })
+    }
+}
+
+impl CommandExecutor for ObjectIdleTime {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.object_idle_time(&self.key) {
+            Some(seconds) => RespFrame::Integer(seconds.min(i64::MAX as u64) as i64),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ObjectIdleTime {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(ObjectIdleTime {
+            key: extract_object_key(value, "IDLETIME")?,
+        })
+    }
+}
+
+// Unlike Redis, frequencies are tracked whatever the eviction policy, so this never fails.
+impl CommandExecutor for ObjectFreq {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.object_freq(&self.key) {
+            Some(freq) => RespFrame::Integer(freq as i64),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ObjectFreq {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(ObjectFreq {
+            key: extract_object_key(value, "FREQ")?,
+        })
+    }
+}
+
+// Parses "<src> <dst>" shared by RENAME and RENAMENX.
+fn extract_src_and_dst(value: RespArray) -> Result<(String, String), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    match (args.next(), args.next()) {
+        (Some(RespFrame::BulkString(src)), Some(RespFrame::BulkString(dst))) => {
+            Ok((src.try_into()?, dst.try_into()?))
+        }
+        _ => Err(CommandError::InvalidArgument(
+            "Invalid source or destination".to_string(),
+        )),
+    }
+}
+
+// Parses "<key> <time> [NX | XX | GT | LT]" shared by the expire commands, see
+// `parse_expire_time` for how the time is converted.
+fn extract_key_and_expire_time(
+    value: RespArray,
+    unit_ms: i64,
+    relative: bool,
+) -> Result<(String, u64, ExpireCondition), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    let (key, n) = match (args.next(), args.next()) {
+        (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(n))) => {
+            (key.try_into()?, parse_number::<i64>(n, "expire time")?)
+        }
+        _ => {
+            return Err(CommandError::InvalidArgument(
+                "Invalid key or expire time".to_string(),
+            ))
+        }
+    };
+
+    let at = parse_expire_time(n, unit_ms, relative)?;
+    let condition = parse_expire_condition(args)?;
+
+    Ok((key, at, condition))
+}
+
+// Parses "OBJECT <subcommand> <key>".
+fn extract_object_key(value: RespArray, subcommand: &'static str) -> Result<String, CommandError> {
+    validate_command(&value, &["OBJECT", subcommand], Some(1))?;
+
+    let mut args = extract_args(value, 2)?.into_iter();
+    match args.next() {
+        Some(RespFrame::BulkString(key)) => Ok(key.try_into()?),
+        _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    }
+}
+
+// Replies -2 if the key does not exist, -1 if it has no time to live, or the expire time
+// converted by `f` otherwise.
+fn ttl_reply(backend: &crate::Backend, key: &str, f: impl FnOnce(u64) -> u64) -> RespFrame {
+    match backend.expire_time(key) {
+        None => RespFrame::Integer(-2),
+        Some(None) => RespFrame::Integer(-1),
+        Some(Some(at)) => RespFrame::Integer(f(at).min(i64::MAX as u64) as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        cmd::Command, now_ms, Backend, BackendError, ExpireCondition, Expiry, ListEnd, RespArray,
+        RespDecode, RespFrame, RespNull, SetCondition, StreamAddId, StreamId,
+    };
+
+    use super::{
+        CommandExecutor, KeyDelete, KeyDump, KeyExists, KeyExpire, KeyExpireAt, KeyExpireTime,
+        KeyPExpire, KeyPExpireTime, KeyPTtl, KeyPersist, KeyRename, KeyRenameNx, KeyRestore,
+        KeyTouch, KeyTtl, KeyUnlink, Keys, ObjectEncoding, ObjectFreq, ObjectIdleTime,
+        ObjectRefCount, RandomKey, Scan, RESP_OK,
+    };
+
+    #[test]
+    fn test_del_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$3\r\ndel\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: KeyDelete = frame.try_into()?;
+        assert_eq!(result.keys, vec!["a".to_string(), "b".to_string()]);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$6\r\nunlink\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(KeyUnlink::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_del_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("string".to_string(), b"value".to_vec());
+        backend.hash_set(
+            "hash".to_string(),
+            "field".to_string(),
+            RespFrame::Integer(1),
+        );
+        backend.set_add("set".to_string(), vec!["member".to_string()]);
+        backend.bloom_add("bloom".to_string(), vec!["item".to_string()]);
+
+        let cmd = KeyDelete {
+            keys: vec![
+                "string".to_string(),
+                "hash".to_string(),
+                "missing".to_string(),
+                "string".to_string(),
+            ],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(backend.string_get("string"), None);
+        assert!(backend.hash_get_all("hash").is_none());
+
+        let cmd = KeyUnlink {
+            keys: vec!["set".to_string(), "bloom".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert!(backend.set_members("set").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_exists_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("string".to_string(), b"value".to_vec());
+        backend.set_add("set".to_string(), vec!["member".to_string()]);
+
+        let cmd = KeyExists {
+            keys: vec![
+                "string".to_string(),
+                "set".to_string(),
+                "missing".to_string(),
+                "string".to_string(),
+            ],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_touch_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\ntouch\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: KeyTouch = frame.try_into()?;
+        assert_eq!(result.keys, vec!["a".to_string(), "b".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_touch_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set_add("set".to_string(), vec!["member".to_string()]);
+        backend.access.remove("set");
+
+        let cmd = KeyTouch {
+            keys: vec!["set".to_string(), "missing".to_string(), "set".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert!(backend.access.contains_key("set"));
+        assert!(!backend.access.contains_key("missing"));
+
+        // The access time goes away along with the key.
+        backend.delete("set");
+        assert!(!backend.access.contains_key("set"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expire_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nexpire\r\n$1\r\na\r\n$2\r\n10\r\n");
+
+        let before = now_ms();
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: KeyExpire = frame.try_into()?;
+        assert_eq!(result.key, "a");
+        assert!(result.at >= before + 10_000 && result.at <= now_ms() + 10_000);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$8\r\nexpireat\r\n$1\r\na\r\n$2\r\n-5\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: KeyExpireAt = frame.try_into()?;
+        assert_eq!(result.at, 0);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nexpire\r\n$1\r\na\r\n$19\r\n9223372036854775807\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Result<KeyExpire, _> = frame.try_into();
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expire_commands() -> Result<()> {
+        let backend = Backend::new();
+        backend.hash_set(
+            "hash".to_string(),
+            "field".to_string(),
+            RespFrame::Integer(1),
+        );
+        backend.set_add("set".to_string(), vec!["member".to_string()]);
+
+        let cmd = KeyPExpire {
+            key: "missing".to_string(),
+            at: u64::MAX,
+            condition: ExpireCondition::default(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert!(!backend.expire.contains_key("missing"));
+
+        let cmd = KeyPExpire {
+            key: "hash".to_string(),
+            at: now_ms() + 10_000,
+            condition: ExpireCondition::default(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(backend.hash_get("hash", "field").is_some());
+
+        // Expired keys are missing for every read path, whatever their type.
+        backend.expire.insert("hash".to_string(), 1);
+        assert!(backend.hash_get("hash", "field").is_none());
+        assert!(!backend.exists("hash"));
+        assert!(!backend.expire.contains_key("hash"));
+
+        backend.expire.insert("set".to_string(), 1);
+        assert!(backend.keys(b"*").is_empty());
+        assert_eq!(backend.random_key(), None);
+        assert_eq!(
+            backend.set_is_member("set", "member"),
+            RespFrame::Integer(0)
+        );
+
+        // A time in the past deletes the key.
+        backend.set_add("set".to_string(), vec!["member".to_string()]);
+        let cmd = KeyExpireAt {
+            key: "set".to_string(),
+            at: 0,
+            condition: ExpireCondition::default(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(!backend.exists("set"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expire_options_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$7\r\npexpire\r\n$1\r\na\r\n$2\r\n10\r\n$2\r\nxx\r\n$2\r\nlt\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: KeyPExpire = frame.try_into()?;
+        assert_eq!(
+            result.condition,
+            ExpireCondition {
+                if_some: true,
+                if_less: true,
+                ..Default::default()
+            }
+        );
+
+        for options in [
+            &b"$2\r\nnx\r\n$2\r\ngt\r\n"[..],
+            b"$2\r\ngt\r\n$2\r\nlt\r\n",
+        ] {
+            let mut buf = BytesMut::new();
+            buf.extend_from_slice(b"*5\r\n$6\r\nexpire\r\n$1\r\na\r\n$2\r\n10\r\n");
+            buf.extend_from_slice(options);
+
+            let frame = RespArray::decode(&mut buf)?;
+
+            let result: Result<KeyExpire, _> = frame.try_into();
+            assert!(result.is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expire_conditions() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("a".to_string(), b"value".to_vec());
+        let (nx, xx) = (
+            ExpireCondition {
+                if_none: true,
+                ..Default::default()
+            },
+            ExpireCondition {
+                if_some: true,
+                ..Default::default()
+            },
+        );
+        let (gt, lt) = (
+            ExpireCondition {
+                if_greater: true,
+                ..Default::default()
+            },
+            ExpireCondition {
+                if_less: true,
+                ..Default::default()
+            },
+        );
+        let later = now_ms() + 100_000;
+
+        // Without a time to live, the key counts as never expiring for GT and LT.
+        assert!(!backend.expire_at("a", later, xx));
+        assert!(!backend.expire_at("a", later, gt));
+        assert!(backend.expire_at("a", later, lt));
+        assert!(!backend.expire_at("a", later + 1, nx));
+
+        assert!(backend.expire_at("a", later + 1, gt));
+        assert!(!backend.expire_at("a", later + 1, gt));
+        assert!(!backend.expire_at("a", later + 2, lt));
+        assert!(backend.expire_at("a", later, xx));
+        assert_eq!(backend.expire_time("a"), Some(Some(later)));
+
+        // The condition is checked before a time in the past deletes the key.
+        assert!(!backend.expire_at("a", 0, nx));
+        assert!(backend.exists("a"));
+        assert!(backend.expire_at("a", 0, lt));
+        assert!(!backend.exists("a"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persist_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.hash_set(
+            "hash".to_string(),
+            "field".to_string(),
+            RespFrame::Integer(1),
+        );
+        backend.expire_at("hash", u64::MAX, ExpireCondition::default());
+
+        let persist = |key: &str| {
+            KeyPersist {
+                key: key.to_string(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(persist("missing"), RespFrame::Integer(0));
+        assert_eq!(persist("hash"), RespFrame::Integer(1));
+        assert_eq!(backend.expire_time("hash"), Some(None));
+        // Nothing left to clear.
+        assert_eq!(persist("hash"), RespFrame::Integer(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ttl_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\npttl\r\n$1\r\na\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: KeyPTtl = frame.try_into()?;
+        assert_eq!(result.key, "a");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ttl_commands() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("persistent".to_string(), b"value".to_vec());
+        backend.set_add("volatile".to_string(), vec!["member".to_string()]);
+        let at = now_ms() + 100_000;
+        backend.expire_at("volatile", at, ExpireCondition::default());
+
+        let ttl = |key: &str| {
+            KeyTtl {
+                key: key.to_string(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(ttl("missing"), RespFrame::Integer(-2));
+        assert_eq!(ttl("persistent"), RespFrame::Integer(-1));
+        assert_eq!(ttl("volatile"), RespFrame::Integer(100));
+
+        let cmd = KeyPTtl {
+            key: "volatile".to_string(),
+        };
+        match cmd.execute(&backend) {
+            RespFrame::Integer(ms) => assert!(ms > 99_000 && ms <= 100_000),
+            frame => panic!("unexpected reply {:?}", frame),
+        }
+
+        let cmd = KeyExpireTime {
+            key: "volatile".to_string(),
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Integer((at / 1000) as i64)
+        );
+
+        let cmd = KeyPExpireTime {
+            key: "persistent".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(-1));
+
+        let cmd = KeyPExpireTime {
+            key: "volatile".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(at as i64));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$7\r\nrestore\r\n$1\r\na\r\n$2\r\n10\r\n$3\r\n\x00\r\n\r\n$7\r\nreplace\r\n$6\r\nABSTTL\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: KeyRestore = frame.try_into()?;
+        assert_eq!(result.key, "a");
+        assert_eq!(result.ttl, 10);
+        assert_eq!(result.payload, b"\x00\r\n");
+        assert!(result.replace);
+        assert!(result.absolute);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$7\r\nrestore\r\n$1\r\na\r\n$2\r\n-1\r\n$1\r\nx\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(KeyRestore::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dump_restore_commands() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("string".to_string(), b"value".to_vec());
+        backend.hash_set(
+            "hash".to_string(),
+            "field".to_string(),
+            RespFrame::Integer(1),
+        );
+        backend.set_add("set".to_string(), vec!["member".to_string()]);
+        backend.bloom_add("bloom".to_string(), vec!["item".to_string()]);
+        backend.ratelimit_check("ratelimit".to_string(), 10, 1.0, 4);
+        backend.counter_incr_by("counter", 3);
+        backend.list_push(
+            "list".to_string(),
+            vec!["a".into(), "b".into()],
+            ListEnd::Right,
+        );
+        backend.zset_add("zset".to_string(), vec![(1.5, "a".to_string())]);
+        let id = StreamAddId::Explicit(StreamId::new(1, 2));
+        let fields = vec![("f".into(), "v".into())];
+        backend.stream_add("stream".to_string(), id, fields, false, None)?;
+
+        let dump = |key: &str| match (KeyDump {
+            key: key.to_string(),
+        })
+        .execute(&backend)
+        {
+            RespFrame::BulkString(payload) => payload.to_vec(),
+            frame => panic!("unexpected reply {:?}", frame),
+        };
+        let restore = |key: &str, payload: Vec<u8>, ttl: u64, replace: bool| {
+            KeyRestore {
+                key: key.to_string(),
+                ttl,
+                payload,
+                replace,
+                absolute: false,
+            }
+            .execute(&backend)
+        };
+
+        for key in [
+            "string",
+            "hash",
+            "set",
+            "bloom",
+            "ratelimit",
+            "counter",
+            "list",
+            "zset",
+            "stream",
+        ] {
+            let payload = dump(key);
+            assert_eq!(
+                restore(key, payload.clone(), 0, false),
+                BackendError::BusyKey.into()
+            );
+            assert_eq!(restore(key, payload, 0, true), RESP_OK.clone());
+        }
+        assert_eq!(backend.string_get("string"), Some(b"value".to_vec()));
+        assert_eq!(
+            backend.hash_get("hash", "field"),
+            Some(RespFrame::Integer(1))
+        );
+        assert_eq!(
+            backend.set_is_member("set", "member"),
+            RespFrame::Integer(1)
+        );
+        assert_eq!(
+            backend.bloom_exists("bloom", vec!["item".to_string()]),
+            vec![RespFrame::Integer(1)]
+        );
+        assert_eq!(
+            backend
+                .ratelimit_check("ratelimit".to_string(), 10, 1.0, 1)
+                .remaining,
+            5
+        );
+        assert_eq!(backend.counter_get("counter"), Some(3));
+        assert_eq!(backend.list_pop("list", ListEnd::Left), Some("a".into()));
+        assert_eq!(backend.list_len("list"), 1);
+        assert_eq!(backend.zset_score("zset", "a"), Some(1.5));
+        let entries = backend.stream_range("stream", StreamId::MIN, StreamId::MAX, false, None);
+        assert_eq!(
+            entries,
+            [(StreamId::new(1, 2), vec![("f".into(), "v".into())])]
+        );
+
+        let cmd = KeyDump {
+            key: "missing".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+
+        // The value can be restored under another key, with a time to live.
+        let payload = dump("hash");
+        assert_eq!(
+            restore("copy", payload.clone(), 10_000, false),
+            RESP_OK.clone()
+        );
+        assert_eq!(
+            backend.hash_get("copy", "field"),
+            Some(RespFrame::Integer(1))
+        );
+        assert!(backend.expire.get("copy").is_some_and(|at| *at > now_ms()));
+
+        // An expired absolute time only replaces the existing key.
+        let cmd = KeyRestore {
+            key: "copy".to_string(),
+            ttl: 1,
+            payload: payload.clone(),
+            replace: true,
+            absolute: true,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert!(!backend.exists("copy"));
+
+        let mut corrupted = payload;
+        corrupted[1] ^= 1;
+        assert_eq!(
+            restore("other", corrupted, 0, false),
+            BackendError::BadDumpPayload.into()
+        );
+        assert!(!backend.exists("other"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_commands() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set_with(
+            "src".to_string(),
+            b"value".to_vec(),
+            SetCondition::Always,
+            Expiry::At(u64::MAX),
+        );
+        backend.set_add("dst".to_string(), vec!["member".to_string()]);
+
+        let cmd = KeyRenameNx {
+            src: "src".to_string(),
+            dst: "dst".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        // The value replaces the set and keeps its time to live.
+        let cmd = KeyRename {
+            src: "src".to_string(),
+            dst: "dst".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.string_get("dst"), Some(b"value".to_vec()));
+        assert!(backend.set_members("dst").is_none());
+        assert!(!backend.exists("src"));
+        assert_eq!(backend.expire.get("dst").map(|at| *at), Some(u64::MAX));
+
+        let cmd = KeyRename {
+            src: "src".to_string(),
+            dst: "dst".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), BackendError::NoSuchKey.into());
+
+        let cmd = KeyRenameNx {
+            src: "dst".to_string(),
+            dst: "other".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.string_get("other"), Some(b"value".to_vec()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("user:1".to_string(), b"alice".to_vec());
+        backend.string_set("user:2".to_string(), b"bob".to_vec());
+        backend.set_add("user:3".to_string(), vec!["carol".to_string()]);
+        backend.set_add("user:1".to_string(), vec!["alice".to_string()]);
+        backend.string_set("session".to_string(), b"token".to_vec());
+
+        let cmd = Keys {
+            pattern: "user:[12]*".to_string(),
+        };
+        let mut keys = match cmd.execute(&backend) {
+            RespFrame::Array(keys) => keys.to_vec(),
+            frame => panic!("unexpected reply {:?}", frame),
+        };
+        keys.sort_by_key(|key| format!("{:?}", key));
+        assert_eq!(
+            keys,
+            vec![
+                RespFrame::BulkString("user:1".into()),
+                RespFrame::BulkString("user:2".into()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$4\r\nscan\r\n$1\r\n0\r\n$5\r\nmatch\r\n$6\r\nuser:*\r\n$5\r\ncount\r\n$3\r\n100\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Scan = frame.try_into()?;
+        assert_eq!(result.cursor, 0);
+        assert_eq!(result.pattern.as_deref(), Some("user:*"));
+        assert_eq!(result.count, 100);
+        assert_eq!(result.key_type, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_command() -> Result<()> {
+        let backend = Backend::new();
+        for i in 0..100 {
+            backend.string_set(format!("user:{}", i), b"name".to_vec());
+        }
+        backend.set_add("user:set".to_string(), vec!["member".to_string()]);
+        backend.string_set("session".to_string(), b"token".to_vec());
+
+        // Iterate until the cursor is back to 0, deleting keys along the way.
+        let mut cursor = 0;
+        let mut keys = Vec::new();
+        loop {
+            let cmd = Scan {
+                cursor,
+                pattern: Some("user:*".to_string()),
+                count: 10,
+                key_type: Some("string".to_string()),
+            };
+            let mut reply = match cmd.execute(&backend) {
+                RespFrame::Array(reply) => reply.into_iter(),
+                frame => panic!("unexpected reply {:?}", frame),
+            };
+            cursor = match reply.next() {
+                Some(RespFrame::BulkString(cursor)) => {
+                    String::from_utf8(cursor.to_vec())?.parse()?
+                }
+                frame => panic!("unexpected cursor {:?}", frame),
+            };
+            match reply.next() {
+                Some(RespFrame::Array(batch)) => keys.extend(batch),
+                frame => panic!("unexpected keys {:?}", frame),
+            }
+            backend.delete("user:99");
+            if cursor == 0 {
+                break;
+            }
+        }
+
+        keys.sort_by_key(|key| format!("{:?}", key));
+        keys.dedup();
+        assert!(keys.len() == 99 || keys.len() == 100);
+        assert!(!keys.contains(&RespFrame::BulkString("session".into())));
+        assert!(!keys.contains(&RespFrame::BulkString("user:set".into())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_randomkey_command() -> Result<()> {
+        let backend = Backend::new();
+        assert_eq!(RandomKey.execute(&backend), RespFrame::Null(RespNull));
+
+        backend.string_set("string".to_string(), b"value".to_vec());
+        backend.set_add("set".to_string(), vec!["member".to_string()]);
+        let mut seen = Vec::new();
+        for _ in 0..100 {
+            match RandomKey.execute(&backend) {
+                RespFrame::BulkString(key) if !seen.contains(&key) => seen.push(key),
+                RespFrame::BulkString(_) => {}
+                frame => panic!("unexpected reply {:?}", frame),
+            }
+        }
+        seen.sort_by_key(|key| key.to_vec());
+        assert_eq!(seen, vec!["set".into(), "string".into()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_encoding_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nobject\r\n$8\r\nencoding\r\n$5\r\nhello\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ObjectEncoding = frame.try_into()?;
+        assert_eq!(result.key, "hello");
+
+        // Dispatched on the subcommand.
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nobject\r\n$4\r\nfreq\r\n$5\r\nhello\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(matches!(
+            Command::try_from(frame)?,
+            Command::ObjectFreq(ObjectFreq { key }) if key == "hello"
+        ));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nobject\r\n$4\r\nhelp\r\n$5\r\nhello\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(matches!(Command::try_from(frame)?, Command::Unsupported(_)));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$6\r\nobject\r\n$8\r\nidletime\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Command::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_access_commands() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("key".to_string(), b"value".to_vec());
+        backend.counter_incr_by("counter", 1);
+
+        let refcount = |key: &str| {
+            ObjectRefCount {
+                key: key.to_string(),
+            }
+            .execute(&backend)
+        };
+        let idle_time = |key: &str| {
+            ObjectIdleTime {
+                key: key.to_string(),
+            }
+            .execute(&backend)
+        };
+        let freq = |key: &str| {
+            ObjectFreq {
+                key: key.to_string(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(refcount("key"), RespFrame::Integer(1));
+        assert_eq!(refcount("missing"), RespFrame::Null(RespNull));
+        assert_eq!(idle_time("key"), RespFrame::Integer(0));
+        assert_eq!(idle_time("missing"), RespFrame::Null(RespNull));
+        assert_eq!(freq("missing"), RespFrame::Null(RespNull));
+
+        // Keys start with the initial frequency, the first access after that always counts.
+        assert_eq!(freq("key"), RespFrame::Integer(5));
+        assert_eq!(freq("counter"), RespFrame::Integer(5));
+        backend.string_get("key");
+        assert_eq!(freq("key"), RespFrame::Integer(6));
+
+        // The access data moves along with the value.
+        backend.rename("key", "other", true)?;
+        match freq("other") {
+            RespFrame::Integer(n) => assert!(n >= 6),
+            frame => panic!("unexpected reply {:?}", frame),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_encoding_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("int".to_string(), b"12345".to_vec());
+        backend.string_set("embstr".to_string(), b"hello".to_vec());
+        backend.string_set("raw".to_string(), vec![b'a'; 100]);
+        backend.set_add("set".to_string(), vec!["member".to_string()]);
+        backend.list_push("list".to_string(), vec!["a".into()], ListEnd::Left);
+        backend.zset_add("zset".to_string(), vec![(1.0, "a".to_string())]);
+        let fields = vec![("f".into(), "v".into())];
+        backend.stream_add("stream".to_string(), StreamAddId::Auto, fields, false, None)?;
+
+        for (key, expected) in [
+            ("int", RespFrame::BulkString("int".into())),
+            ("embstr", RespFrame::BulkString("embstr".into())),
+            ("raw", RespFrame::BulkString("raw".into())),
+            ("set", RespFrame::BulkString("hashtable".into())),
+            ("list", RespFrame::BulkString("quicklist".into())),
+            ("zset", RespFrame::BulkString("skiplist".into())),
+            ("stream", RespFrame::BulkString("stream".into())),
+            ("missing", RespFrame::Null(RespNull)),
+        ] {
+            let cmd = ObjectEncoding {
+                key: key.to_string(),
+            };
+            assert_eq!(cmd.execute(&backend), expected);
+        }
+
+        // Appending to an integer switches it to a plain string.
+        backend.string_append("int".to_string(), b"6");
+        let cmd = ObjectEncoding {
+            key: "int".to_string(),
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::BulkString("embstr".into())
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1458 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::{cmd::CommandError, BulkString, ListEnd, RespArray, RespFrame, RespNull};
+
+use super::{
+    extract_args, extract_key, extract_key_and_count, extract_keys_and_timeout, parse_number,
+    parse_timeout, validate_command, BlockingCommand, CommandExecutor, DelayPush,
+    ListBlockingLeftPop, ListBlockingMultiPop, ListBlockingRightPop, ListIndex, ListInsert,
+    ListLeftPop, ListLeftPush, ListLen, ListMove, ListMultiPop, ListPos, ListRange, ListRemove,
+    ListRightPop, ListRightPopLeftPush, ListRightPush, ListSet, ListTrim, RESP_OK,
+};
+
+impl CommandExecutor for ListLeftPush {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.list_push(self.key, self.elements, ListEnd::Left) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for ListLeftPush {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LPUSH"], None)?;
+
+        let (key, elements) = extract_key_and_elements(value)?;
+        Ok(ListLeftPush { key, elements })
+    }
+}
+
+impl CommandExecutor for ListRightPush {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.list_push(self.key, self.elements, ListEnd::Right) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for ListRightPush {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["RPUSH"], None)?;
+
+        let (key, elements) = extract_key_and_elements(value)?;
+        Ok(ListRightPush { key, elements })
+    }
+}
+
+impl CommandExecutor for ListLeftPop {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        pop(backend, &self.key, ListEnd::Left, self.count)
+    }
+}
+
+impl TryFrom<RespArray> for ListLeftPop {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LPOP"], None)?;
+
+        let (key, count) = extract_key_and_count(value)?;
+        Ok(ListLeftPop { key, count })
+    }
+}
+
+impl CommandExecutor for ListRightPop {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        pop(backend, &self.key, ListEnd::Right, self.count)
+    }
+}
+
+impl TryFrom<RespArray> for ListRightPop {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["RPOP"], None)?;
+
+        let (key, count) = extract_key_and_count(value)?;
+        Ok(ListRightPop { key, count })
+    }
+}
+
+impl BlockingCommand for ListBlockingLeftPop {
+    fn keys(&self) -> Vec<String> {
+        self.keys.clone()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn try_execute(&self, backend: &crate::Backend) -> Option<RespFrame> {
+        blocking_pop(backend, &self.keys, ListEnd::Left)
+    }
+}
+
+impl CommandExecutor for ListBlockingLeftPop {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        self.try_execute(backend)
+            .unwrap_or_else(|| self.timeout_reply())
+    }
+}
+
+impl TryFrom<RespArray> for ListBlockingLeftPop {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["BLPOP"], None)?;
+
+        let (keys, timeout) = extract_keys_and_timeout(value)?;
+        Ok(ListBlockingLeftPop { keys, timeout })
+    }
+}
+
+impl BlockingCommand for ListBlockingRightPop {
+    fn keys(&self) -> Vec<String> {
+        self.keys.clone()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn try_execute(&self, backend: &crate::Backend) -> Option<RespFrame> {
+        blocking_pop(backend, &self.keys, ListEnd::Right)
+    }
+}
+
+impl CommandExecutor for ListBlockingRightPop {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        self.try_execute(backend)
+            .unwrap_or_else(|| self.timeout_reply())
+    }
+}
+
+impl TryFrom<RespArray> for ListBlockingRightPop {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["BRPOP"], None)?;
+
+        let (keys, timeout) = extract_keys_and_timeout(value)?;
+        Ok(ListBlockingRightPop { keys, timeout })
+    }
+}
+
+impl CommandExecutor for ListMultiPop {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        multi_pop(backend, &self.keys, self.end, self.count)
+            .unwrap_or(RespFrame::Array(RespArray::Null))
+    }
+}
+
+impl TryFrom<RespArray> for ListMultiPop {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LMPOP"], None)?;
+
+        let (keys, end, count) = extract_multi_pop(extract_args(value, 1)?.into_iter())?;
+        Ok(ListMultiPop { keys, end, count })
+    }
+}
+
+impl BlockingCommand for ListBlockingMultiPop {
+    fn keys(&self) -> Vec<String> {
+        self.keys.clone()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn try_execute(&self, backend: &crate::Backend) -> Option<RespFrame> {
+        multi_pop(backend, &self.keys, self.end, self.count)
+    }
+}
+
+impl CommandExecutor for ListBlockingMultiPop {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        self.try_execute(backend)
+            .unwrap_or_else(|| self.timeout_reply())
+    }
+}
+
+impl TryFrom<RespArray> for ListBlockingMultiPop {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["BLMPOP"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let timeout = match args.next() {
+            Some(RespFrame::BulkString(timeout)) => parse_timeout(timeout)?,
+            _ => return Err(CommandError::InvalidArgument("Invalid timeout".to_string())),
+        };
+        let (keys, end, count) = extract_multi_pop(args)?;
+        Ok(ListBlockingMultiPop {
+            keys,
+            end,
+            count,
+            timeout,
+        })
+    }
+}
+
+impl CommandExecutor for ListLen {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.list_len(&self.key) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for ListLen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LLEN"], Some(1))?;
+
+        Ok(ListLen {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for ListRange {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        elements_reply(backend.list_range(&self.key, self.start, self.end))
+    }
+}
+
+impl TryFrom<RespArray> for ListRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LRANGE"], Some(3))?;
+
+        let (key, start, end) = extract_key_and_range(value)?;
+        Ok(ListRange { key, start, end })
+    }
+}
+
+impl CommandExecutor for ListIndex {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        element_reply(backend.list_index(&self.key, self.index))
+    }
+}
+
+impl TryFrom<RespArray> for ListIndex {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LINDEX"], Some(2))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(index))) => {
+                Ok(ListIndex {
+                    key: key.try_into()?,
+                    index: parse_number(index, "index")?,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or index".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for ListSet {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.list_set(&self.key, self.index, self.element) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+// Replies with the number of delayed jobs not due yet, of all the lists.
+impl CommandExecutor for DelayPush {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.delay_push(self.key, self.payload, self.delay_ms) as i64)
+    }
+}
+
+// DELAY.PUSH <key> <payload> <delay_ms>
+impl TryFrom<RespArray> for DelayPush {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["DELAY.PUSH"], Some(3))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(BulkString::Normal(payload))),
+                Some(RespFrame::BulkString(delay_ms)),
+            ) => Ok(DelayPush {
+                key: key.try_into()?,
+                payload: payload.into(),
+                delay_ms: parse_number(delay_ms, "delay")?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, payload or delay".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ListSet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LSET"], Some(3))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(index)),
+                Some(RespFrame::BulkString(BulkString::Normal(element))),
+            ) => Ok(ListSet {
+                key: key.try_into()?,
+                index: parse_number(index, "index")?,
+                element: element.into(),
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, index or element".to_string(),
+            )),
+        }
+    }
+}
+
+// Replies -1 if the pivot is not found, 0 if the key does not exist.
+impl CommandExecutor for ListInsert {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.list_insert(&self.key, self.before, &self.pivot, self.element) {
+            Some(len) => RespFrame::Integer(len as i64),
+            None => RespFrame::Integer(-1),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ListInsert {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LINSERT"], Some(4))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(position)),
+                Some(RespFrame::BulkString(BulkString::Normal(pivot))),
+                Some(RespFrame::BulkString(BulkString::Normal(element))),
+            ) => {
+                let before = match position.to_ascii_uppercase().as_slice() {
+                    b"BEFORE" => true,
+                    b"AFTER" => false,
+                    _ => {
+                        return Err(CommandError::InvalidArgument(
+                            "Position must be BEFORE or AFTER".to_string(),
+                        ))
+                    }
+                };
+                Ok(ListInsert {
+                    key: key.try_into()?,
+                    before,
+                    pivot: pivot.into(),
+                    element: element.into(),
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, position, pivot or element".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for ListRemove {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.list_remove(&self.key, self.count, &self.element) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for ListRemove {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LREM"], Some(3))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(count)),
+                Some(RespFrame::BulkString(BulkString::Normal(element))),
+            ) => Ok(ListRemove {
+                key: key.try_into()?,
+                count: parse_number(count, "count")?,
+                element: element.into(),
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, count or element".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for ListTrim {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        backend.list_trim(&self.key, self.start, self.end);
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for ListTrim {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LTRIM"], Some(3))?;
+
+        let (key, start, end) = extract_key_and_range(value)?;
+        Ok(ListTrim { key, start, end })
+    }
+}
+
+impl CommandExecutor for ListMove {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        element_reply(backend.list_move(&self.src, &self.dst, self.from, self.to))
+    }
+}
+
+impl TryFrom<RespArray> for ListMove {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LMOVE"], Some(4))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(src)),
+                Some(RespFrame::BulkString(dst)),
+                Some(RespFrame::BulkString(from)),
+                Some(RespFrame::BulkString(to)),
+            ) => Ok(ListMove {
+                src: src.try_into()?,
+                dst: dst.try_into()?,
+                from: parse_list_end(&from)?,
+                to: parse_list_end(&to)?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid source, destination or direction".to_string(),
+            )),
+        }
+    }
+}
+
+// The legacy form of LMOVE src dst RIGHT LEFT.
+impl CommandExecutor for ListRightPopLeftPush {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        element_reply(backend.list_move(&self.src, &self.dst, ListEnd::Right, ListEnd::Left))
+    }
+}
+
+impl TryFrom<RespArray> for ListRightPopLeftPush {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["RPOPLPUSH"], Some(2))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(src)), Some(RespFrame::BulkString(dst))) => {
+                Ok(ListRightPopLeftPush {
+                    src: src.try_into()?,
+                    dst: dst.try_into()?,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid source or destination".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for ListPos {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let count = self.count.unwrap_or(1);
+        let indexes =
+            backend.list_positions(&self.key, &self.element, self.rank, count, self.max_len);
+        match self.count {
+            Some(_) => {
+                let indexes: Vec<RespFrame> = indexes
+                    .into_iter()
+                    .map(|i| RespFrame::Integer(i as i64))
+                    .collect();
+                RespArray::new(indexes).into()
+            }
+            None => match indexes.first() {
+                Some(i) => RespFrame::Integer(*i as i64),
+                None => RespFrame::Null(RespNull),
+            },
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ListPos {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["LPOS"], None)?;
+
+        // Parse the key and the element.
+        let mut args = extract_args(value, 1)?.into_iter();
+        let mut pos = match (args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(BulkString::Normal(element))),
+            ) => ListPos {
+                key: key.try_into()?,
+                element: element.into(),
+                rank: 1,
+                count: None,
+                max_len: 0,
+            },
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid key or element".to_string(),
+                ))
+            }
+        };
+
+        // Parse the options, each of them takes a value.
+        while let Some(arg) = args.next() {
+            let (option, value) = match (arg, args.next()) {
+                (RespFrame::BulkString(option), Some(RespFrame::BulkString(value))) => {
+                    (option.to_ascii_uppercase(), value)
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                b"RANK" => {
+                    pos.rank = parse_number(value, "rank")?;
+                    if pos.rank == 0 {
+                        return Err(CommandError::InvalidArgument(
+                            "RANK can't be zero".to_string(),
+                        ));
+                    }
+                }
+                b"COUNT" => pos.count = Some(parse_number(value, "count")?),
+                b"MAXLEN" => pos.max_len = parse_number(value, "maxlen")?,
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        Ok(pos)
+    }
+}
+
+fn elements_reply(elements: Vec<Bytes>) -> RespFrame {
+    let elements: Vec<RespFrame> = elements
+        .into_iter()
+        .map(|element| BulkString::from(element).into())
+        .collect();
+    RespArray::new(elements).into()
+}
+
+// A single element without a count, an array of up to `count` elements otherwise, which is
+// null if the key does not exist.
+fn pop(backend: &crate::Backend, key: &str, end: ListEnd, count: Option<usize>) -> RespFrame {
+    match count {
+        None => element_reply(backend.list_pop(key, end)),
+        Some(count) => match backend.list_pop_many(key, end, count) {
+            Some(elements) => elements_reply(elements),
+            None => RespArray::Null.into(),
+        },
+    }
+}
+
+// Pops from the first of the lists that exists, replying its key along with the element.
+fn blocking_pop(backend: &crate::Backend, keys: &[String], end: ListEnd) -> Option<RespFrame> {
+    keys.iter().find_map(|key| {
+        let element = backend.list_pop(key, end)?;
+        Some(
+            RespArray::new(vec![
+                BulkString::new(key.as_str()).into(),
+                BulkString::from(element).into(),
+            ])
+            .into(),
+        )
+    })
+}
+
+// Pops up to `count` elements from the first of the lists that exists, replying its key
+// along with the elements.
+fn multi_pop(
+    backend: &crate::Backend,
+    keys: &[String],
+    end: ListEnd,
+    count: usize,
+) -> Option<RespFrame> {
+    keys.iter().find_map(|key| {
+        let elements = backend.list_pop_many(key, end, count)?;
+        Some(
+            RespArray::new(vec![
+                BulkString::new(key.as_str()).into(),
+                elements_reply(elements),
+            ])
+            .into(),
+        )
+    })
+}
+
+// Null if there is no element.
+fn element_reply(element: Option<Bytes>) -> RespFrame {
+    match element {
+        Some(element) => BulkString::from(element).into(),
+        None => RespFrame::Null(RespNull),
+    }
+}
+
+// Parses a "LEFT | RIGHT" argument.
+fn parse_list_end(value: &BulkString) -> Result<ListEnd, CommandError> {
+    match value.to_ascii_uppercase().as_slice() {
+        b"LEFT" => Ok(ListEnd::Left),
+        b"RIGHT" => Ok(ListEnd::Right),
+        _ => Err(CommandError::InvalidArgument(
+            "Direction must be LEFT or RIGHT".to_string(),
+        )),
+    }
+}
+
+// Parses "<key> <start> <stop>" shared by LRANGE and LTRIM.
+fn extract_key_and_range(value: RespArray) -> Result<(String, i64, i64), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    match (args.next(), args.next(), args.next()) {
+        (
+            Some(RespFrame::BulkString(key)),
+            Some(RespFrame::BulkString(start)),
+            Some(RespFrame::BulkString(end)),
+        ) => Ok((
+            key.try_into()?,
+            parse_number(start, "start")?,
+            parse_number(end, "end")?,
+        )),
+        _ => Err(CommandError::InvalidArgument(
+            "Invalid key, start or end".to_string(),
+        )),
+    }
+}
+
+// Parses "<numkeys> <key> [<key> ...] <LEFT | RIGHT> [COUNT <count>]" shared by LMPOP and
+// BLMPOP. The count defaults to 1.
+fn extract_multi_pop(
+    mut args: impl Iterator<Item = RespFrame>,
+) -> Result<(Vec<String>, ListEnd, usize), CommandError> {
+    let num_keys: usize = match args.next() {
+        Some(RespFrame::BulkString(num_keys)) => parse_number(num_keys, "numkeys")?,
+        _ => return Err(CommandError::InvalidArgument("Invalid numkeys".to_string())),
+    };
+    if num_keys == 0 {
+        return Err(CommandError::InvalidArgument(
+            "numkeys should be greater than 0".to_string(),
+        ));
+    }
+
+    let mut keys = Vec::new();
+    for _ in 0..num_keys {
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => keys.push(key.try_into()?),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+    let end = match args.next() {
+        Some(RespFrame::BulkString(end)) => parse_list_end(&end)?,
+        _ => {
+            return Err(CommandError::InvalidArgument(
+                "Invalid direction".to_string(),
+            ))
+        }
+    };
+
+    let count = match (args.next(), args.next()) {
+        (None, None) => 1,
+        (Some(RespFrame::BulkString(option)), Some(RespFrame::BulkString(count)))
+            if option.eq_ignore_ascii_case(b"COUNT") =>
+        {
+            match parse_number(count, "count")? {
+                0 => {
+                    return Err(CommandError::InvalidArgument(
+                        "count should be greater than 0".to_string(),
+                    ))
+                }
+                count => count,
+            }
+        }
+        _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+    };
+    if args.next().is_some() {
+        return Err(CommandError::InvalidArgument("Invalid option".to_string()));
+    }
+    Ok((keys, end, count))
+}
+
+// Parses "<key> <element> [element ...]" shared by LPUSH and RPUSH.
+fn extract_key_and_elements(value: RespArray) -> Result<(String, Vec<Bytes>), CommandError> {
+    // Parse the key.
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = match args.next() {
+        Some(RespFrame::BulkString(key)) => key.try_into()?,
+        _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    };
+
+    // Parse the elements, which may be any binary data.
+    let mut elements = Vec::new();
+    for arg in args {
+        match arg {
+            RespFrame::BulkString(BulkString::Normal(element)) => elements.push(element.into()),
+            _ => return Err(CommandError::InvalidArgument("Invalid element".to_string())),
+        }
+    }
+
+    if elements.is_empty() {
+        return Err(CommandError::InvalidArgument(
+            "At least one element is required".to_string(),
+        ));
+    }
+
+    Ok((key, elements))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{BackendError, BulkString, ListEnd, RespArray, RespDecode, RespFrame, RespNull};
+
+    use super::{
+        BlockingCommand, CommandExecutor, DelayPush, ListBlockingLeftPop, ListBlockingMultiPop,
+        ListBlockingRightPop, ListIndex, ListInsert, ListLeftPop, ListLeftPush, ListLen, ListMove,
+        ListMultiPop, ListPos, ListRange, ListRemove, ListRightPop, ListRightPopLeftPush,
+        ListRightPush, ListSet, ListTrim, RESP_OK,
+    };
+
+    #[test]
+    fn test_lpush_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nlpush\r\n$4\r\nlist\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ListLeftPush = frame.try_into()?;
+        assert_eq!(result.key, "list");
+        assert_eq!(result.elements, ["a", "b"]);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$5\r\nrpush\r\n$4\r\nlist\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ListRightPush::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lpop_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\nrpop\r\n$4\r\nlist\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ListRightPop = frame.try_into()?;
+        assert_eq!(result.key, "list");
+        assert_eq!(result.count, None);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$4\r\nlpop\r\n$4\r\nlist\r\n$1\r\n2\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: ListLeftPop = frame.try_into()?;
+        assert_eq!(result.count, Some(2));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$4\r\nlpop\r\n$4\r\nlist\r\n$2\r\n-1\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ListLeftPop::try_from(frame).is_err());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$4\r\nllen\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ListLen::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_commands() {
+        let backend = crate::Backend::new();
+        let elements = |elements: &[&str]| {
+            elements
+                .iter()
+                .map(|e| e.as_bytes().to_vec().into())
+                .collect()
+        };
+        let llen = |key: &str| {
+            ListLen {
+                key: key.to_string(),
+            }
+            .execute(&backend)
+        };
+        let lpop = |key: &str| {
+            ListLeftPop {
+                key: key.to_string(),
+                count: None,
+            }
+            .execute(&backend)
+        };
+        let rpop = |key: &str| {
+            ListRightPop {
+                key: key.to_string(),
+                count: None,
+            }
+            .execute(&backend)
+        };
+
+        // Elements pushed to the left end up in reverse order.
+        let cmd = ListLeftPush {
+            key: "list".to_string(),
+            elements: elements(&["b", "a"]),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        let cmd = ListRightPush {
+            key: "list".to_string(),
+            elements: elements(&["c", "d"]),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(4));
+        assert_eq!(llen("list"), RespFrame::Integer(4));
+
+        assert_eq!(lpop("list"), BulkString::from("a").into());
+        assert_eq!(rpop("list"), BulkString::from("d").into());
+        assert_eq!(lpop("list"), BulkString::from("b").into());
+        backend.expire_at("list", u64::MAX, Default::default());
+
+        // The list is deleted along with its last element.
+        assert_eq!(rpop("list"), BulkString::from("c").into());
+        assert!(!backend.exists("list"));
+        assert_eq!(backend.expire_time("list"), None);
+        assert_eq!(lpop("list"), RespFrame::Null(RespNull));
+        assert_eq!(llen("list"), RespFrame::Integer(0));
+
+        // With a count, the elements are popped at once and replied as an array.
+        backend.list_push(
+            "list".to_string(),
+            elements(&["a", "b", "c"]),
+            ListEnd::Right,
+        );
+        let cmd = ListRightPop {
+            key: "list".to_string(),
+            count: Some(2),
+        };
+        let expected = RespArray::new(vec![
+            BulkString::from("c").into(),
+            BulkString::from("b").into(),
+        ]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+        let cmd = ListLeftPop {
+            key: "list".to_string(),
+            count: Some(5),
+        };
+        let expected = RespArray::new(vec![BulkString::from("a").into()]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+        assert!(!backend.exists("list"));
+        let cmd = ListLeftPop {
+            key: "list".to_string(),
+            count: Some(1),
+        };
+        assert_eq!(cmd.execute(&backend), RespArray::Null.into());
+    }
+
+    #[test]
+    fn test_blpop_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nblpop\r\n$1\r\na\r\n$1\r\nb\r\n$3\r\n0.5\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ListBlockingLeftPop = frame.try_into()?;
+        assert_eq!(result.keys, ["a", "b"]);
+        assert_eq!(result.timeout, Some(Duration::from_millis(500)));
+
+        // A timeout of 0 blocks forever.
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\nbrpop\r\n$1\r\na\r\n$1\r\n0\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: ListBlockingRightPop = frame.try_into()?;
+        assert_eq!(result.timeout, None);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\nblpop\r\n$1\r\na\r\n$2\r\n-1\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ListBlockingLeftPop::try_from(frame).is_err());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\nblpop\r\n$1\r\na\r\n$1\r\nx\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ListBlockingLeftPop::try_from(frame).is_err());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$5\r\nbrpop\r\n$1\r\n0\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ListBlockingRightPop::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blpop_commands() {
+        let backend = crate::Backend::new();
+        let elements = ["a", "b", "c"].map(|e| e.into()).to_vec();
+        backend.list_push("l2".to_string(), elements, ListEnd::Right);
+        let keys = vec!["l1".to_string(), "l2".to_string()];
+        let reply = |key: &str, element: &str| -> RespFrame {
+            RespArray::new(vec![
+                BulkString::from(key).into(),
+                BulkString::from(element).into(),
+            ])
+            .into()
+        };
+
+        // The first list that exists is popped from.
+        let cmd = ListBlockingLeftPop {
+            keys: keys.clone(),
+            timeout: None,
+        };
+        assert_eq!(cmd.keys(), keys);
+        assert_eq!(cmd.try_execute(&backend), Some(reply("l2", "a")));
+        let cmd = ListBlockingRightPop {
+            keys: keys.clone(),
+            timeout: None,
+        };
+        assert_eq!(cmd.execute(&backend), reply("l2", "c"));
+        backend.list_push("l1".to_string(), vec!["d".into()], ListEnd::Left);
+        let cmd = ListBlockingRightPop {
+            keys: keys.clone(),
+            timeout: None,
+        };
+        assert_eq!(cmd.execute(&backend), reply("l1", "d"));
+        let cmd = ListBlockingLeftPop {
+            keys: keys.clone(),
+            timeout: None,
+        };
+        assert_eq!(cmd.execute(&backend), reply("l2", "b"));
+
+        // Without any list, executing it directly replies as if the timeout elapsed.
+        let cmd = ListBlockingLeftPop {
+            keys,
+            timeout: Some(Duration::from_secs(1)),
+        };
+        assert_eq!(cmd.try_execute(&backend), None);
+        assert_eq!(cmd.execute(&backend), RespArray::Null.into());
+    }
+
+    #[test]
+    fn test_lmpop_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*7\r\n$5\r\nlmpop\r\n$1\r\n2\r\n$1\r\na\r\n$1\r\nb\r\n$5\r\nRIGHT\r\n$5\r\ncount\r\n$1\r\n3\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ListMultiPop = frame.try_into()?;
+        assert_eq!(result.keys, ["a", "b"]);
+        assert_eq!(result.end, ListEnd::Right);
+        assert_eq!(result.count, 3);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$6\r\nblmpop\r\n$3\r\n1.5\r\n$1\r\n1\r\n$1\r\na\r\n$4\r\nleft\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: ListBlockingMultiPop = frame.try_into()?;
+        assert_eq!(result.keys, ["a"]);
+        assert_eq!(result.end, ListEnd::Left);
+        assert_eq!(result.count, 1);
+        assert_eq!(result.timeout, Some(Duration::from_millis(1500)));
+
+        // numkeys must match the keys given, and the count be positive.
+        for args in [
+            &b"*4\r\n$5\r\nlmpop\r\n$1\r\n2\r\n$1\r\na\r\n$4\r\nleft\r\n"[..],
+            b"*4\r\n$5\r\nlmpop\r\n$1\r\n0\r\n$1\r\na\r\n$4\r\nleft\r\n",
+            b"*4\r\n$5\r\nlmpop\r\n$1\r\n1\r\n$1\r\na\r\n$2\r\nup\r\n",
+            b"*6\r\n$5\r\nlmpop\r\n$1\r\n1\r\n$1\r\na\r\n$4\r\nleft\r\n$5\r\ncount\r\n$1\r\n0\r\n",
+            b"*5\r\n$5\r\nlmpop\r\n$1\r\n1\r\n$1\r\na\r\n$4\r\nleft\r\n$5\r\ncount\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(ListMultiPop::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lmpop_commands() {
+        let backend = crate::Backend::new();
+        let elements = ["a", "b", "c"].map(|e| e.into()).to_vec();
+        backend.list_push("l2".to_string(), elements, ListEnd::Right);
+        let keys = vec!["l1".to_string(), "l2".to_string()];
+        let reply = |key: &str, elements: &[&str]| -> RespFrame {
+            let elements: Vec<RespFrame> = elements
+                .iter()
+                .map(|e| BulkString::from(*e).into())
+                .collect();
+            RespArray::new(vec![
+                BulkString::from(key).into(),
+                RespArray::new(elements).into(),
+            ])
+            .into()
+        };
+
+        let cmd = ListMultiPop {
+            keys: keys.clone(),
+            end: ListEnd::Right,
+            count: 2,
+        };
+        assert_eq!(cmd.execute(&backend), reply("l2", &["c", "b"]));
+
+        // The count is capped at the length of the list, which is then deleted.
+        let cmd = ListBlockingMultiPop {
+            keys: keys.clone(),
+            end: ListEnd::Left,
+            count: 5,
+            timeout: None,
+        };
+        assert_eq!(cmd.try_execute(&backend), Some(reply("l2", &["a"])));
+        assert!(!backend.exists("l2"));
+
+        let cmd = ListMultiPop {
+            keys: keys.clone(),
+            end: ListEnd::Left,
+            count: 1,
+        };
+        assert_eq!(cmd.execute(&backend), RespArray::Null.into());
+        let cmd = ListBlockingMultiPop {
+            keys,
+            end: ListEnd::Left,
+            count: 1,
+            timeout: None,
+        };
+        assert_eq!(cmd.execute(&backend), RespArray::Null.into());
+    }
+
+    #[test]
+    fn test_lrange_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$6\r\nlrange\r\n$4\r\nlist\r\n$1\r\n0\r\n$2\r\n-1\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ListRange = frame.try_into()?;
+        assert_eq!(result.key, "list");
+        assert_eq!(result.start, 0);
+        assert_eq!(result.end, -1);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$6\r\nlrange\r\n$4\r\nlist\r\n$1\r\nx\r\n$2\r\n-1\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ListRange::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lrange_command() {
+        let backend = crate::Backend::new();
+        let elements = ["a", "b", "c", "d"].map(|e| e.into()).to_vec();
+        backend.list_push("list".to_string(), elements, crate::ListEnd::Right);
+        let lrange = |key: &str, start: i64, end: i64| {
+            let RespFrame::Array(items) = (ListRange {
+                key: key.to_string(),
+                start,
+                end,
+            })
+            .execute(&backend) else {
+                panic!("unexpected reply");
+            };
+            items.to_vec()
+        };
+        let expected = |elements: &[&str]| -> Vec<RespFrame> {
+            elements
+                .iter()
+                .map(|e| BulkString::from(*e).into())
+                .collect()
+        };
+
+        assert_eq!(lrange("list", 0, -1), expected(&["a", "b", "c", "d"]));
+        assert_eq!(lrange("list", 1, 2), expected(&["b", "c"]));
+        assert_eq!(lrange("list", -3, -2), expected(&["b", "c"]));
+        // Out of range indexes are clamped, an empty range replies an empty array.
+        assert_eq!(lrange("list", -100, 100), expected(&["a", "b", "c", "d"]));
+        assert_eq!(lrange("list", 2, 1), expected(&[]));
+        assert_eq!(lrange("list", 4, 10), expected(&[]));
+        assert_eq!(lrange("list", 0, -5), expected(&[]));
+        assert_eq!(lrange("missing", 0, -1), expected(&[]));
+    }
+
+    #[test]
+    fn test_lindex_lset_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nlindex\r\n$4\r\nlist\r\n$2\r\n-1\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ListIndex = frame.try_into()?;
+        assert_eq!(result.key, "list");
+        assert_eq!(result.index, -1);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$4\r\nlset\r\n$4\r\nlist\r\n$1\r\n2\r\n$1\r\nx\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: ListSet = frame.try_into()?;
+        assert_eq!(result.key, "list");
+        assert_eq!(result.index, 2);
+        assert_eq!(result.element, "x");
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$4\r\nlset\r\n$4\r\nlist\r\n$1\r\n2\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ListSet::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lindex_lset_commands() {
+        let backend = crate::Backend::new();
+        let elements = ["a", "b", "c"].map(|e| e.into()).to_vec();
+        backend.list_push("list".to_string(), elements, crate::ListEnd::Right);
+        let lindex = |key: &str, index: i64| {
+            ListIndex {
+                key: key.to_string(),
+                index,
+            }
+            .execute(&backend)
+        };
+        let lset = |key: &str, index: i64, element: &str| {
+            ListSet {
+                key: key.to_string(),
+                index,
+                element: element.as_bytes().to_vec().into(),
+            }
+            .execute(&backend)
+        };
+
+        assert_eq!(lindex("list", 0), BulkString::from("a").into());
+        assert_eq!(lindex("list", -1), BulkString::from("c").into());
+        assert_eq!(lindex("list", 3), RespFrame::Null(RespNull));
+        assert_eq!(lindex("list", -4), RespFrame::Null(RespNull));
+        assert_eq!(lindex("missing", 0), RespFrame::Null(RespNull));
+
+        assert_eq!(lset("list", 1, "x"), RESP_OK.clone());
+        assert_eq!(lset("list", -1, "y"), RESP_OK.clone());
+        assert_eq!(lindex("list", 1), BulkString::from("x").into());
+        assert_eq!(lindex("list", 2), BulkString::from("y").into());
+        assert_eq!(lset("list", 3, "z"), BackendError::IndexOutOfRange.into());
+        assert_eq!(lset("list", -4, "z"), BackendError::IndexOutOfRange.into());
+        assert_eq!(lset("missing", 0, "z"), BackendError::NoSuchKey.into());
+        assert!(!backend.exists("missing"));
+    }
+
+    #[test]
+    fn test_linsert_lrem_ltrim_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$7\r\nlinsert\r\n$4\r\nlist\r\n$6\r\nbefore\r\n$1\r\na\r\n$1\r\nx\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ListInsert = frame.try_into()?;
+        assert_eq!(result.key, "list");
+        assert!(result.before);
+        assert_eq!(result.pivot, "a");
+        assert_eq!(result.element, "x");
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$7\r\nlinsert\r\n$4\r\nlist\r\n$6\r\nbeside\r\n$1\r\na\r\n$1\r\nx\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ListInsert::try_from(frame).is_err());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$4\r\nlrem\r\n$4\r\nlist\r\n$2\r\n-2\r\n$1\r\na\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: ListRemove = frame.try_into()?;
+        assert_eq!(result.count, -2);
+        assert_eq!(result.element, "a");
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nltrim\r\n$4\r\nlist\r\n$1\r\n1\r\n$2\r\n-1\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: ListTrim = frame.try_into()?;
+        assert_eq!((result.start, result.end), (1, -1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_linsert_lrem_ltrim_commands() {
+        let backend = crate::Backend::new();
+        let elements = ["a", "b", "a", "c", "a"].map(|e| e.into()).to_vec();
+        backend.list_push("list".to_string(), elements, crate::ListEnd::Right);
+        let contents = |key: &str| -> Vec<String> {
+            backend
+                .list_range(key, 0, -1)
+                .into_iter()
+                .map(|e| String::from_utf8(e.to_vec()).unwrap())
+                .collect()
+        };
+        let linsert = |key: &str, before: bool, pivot: &str, element: &str| {
+            ListInsert {
+                key: key.to_string(),
+                before,
+                pivot: pivot.as_bytes().to_vec().into(),
+                element: element.as_bytes().to_vec().into(),
+            }
+            .execute(&backend)
+        };
+        let lrem = |key: &str, count: i64, element: &str| {
+            ListRemove {
+                key: key.to_string(),
+                count,
+                element: element.as_bytes().to_vec().into(),
+            }
+            .execute(&backend)
+        };
+        let ltrim = |key: &str, start: i64, end: i64| {
+            ListTrim {
+                key: key.to_string(),
+                start,
+                end,
+            }
+            .execute(&backend)
+        };
+
+        // The first occurrence of the pivot is used.
+        assert_eq!(linsert("list", true, "a", "x"), RespFrame::Integer(6));
+        assert_eq!(linsert("list", false, "c", "y"), RespFrame::Integer(7));
+        assert_eq!(contents("list"), ["x", "a", "b", "a", "c", "y", "a"]);
+        assert_eq!(linsert("list", true, "z", "x"), RespFrame::Integer(-1));
+        assert_eq!(linsert("missing", true, "a", "x"), RespFrame::Integer(0));
+        assert!(!backend.exists("missing"));
+
+        // From the tail with a negative count, from the head otherwise.
+        assert_eq!(lrem("list", -2, "a"), RespFrame::Integer(2));
+        assert_eq!(contents("list"), ["x", "a", "b", "c", "y"]);
+        assert_eq!(lrem("list", 0, "a"), RespFrame::Integer(1));
+        assert_eq!(lrem("list", 1, "z"), RespFrame::Integer(0));
+        assert_eq!(contents("list"), ["x", "b", "c", "y"]);
+
+        assert_eq!(ltrim("list", 1, -2), RESP_OK.clone());
+        assert_eq!(contents("list"), ["b", "c"]);
+        assert_eq!(ltrim("list", 0, 10), RESP_OK.clone());
+        assert_eq!(contents("list"), ["b", "c"]);
+
+        // The list is deleted once empty.
+        assert_eq!(ltrim("list", 5, 10), RESP_OK.clone());
+        assert!(!backend.exists("list"));
+        backend.list_push("list".to_string(), vec!["a".into()], crate::ListEnd::Left);
+        assert_eq!(lrem("list", 0, "a"), RespFrame::Integer(1));
+        assert!(!backend.exists("list"));
+    }
+
+    #[test]
+    fn test_lmove_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$5\r\nlmove\r\n$3\r\nsrc\r\n$3\r\ndst\r\n$4\r\nleft\r\n$5\r\nRIGHT\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ListMove = frame.try_into()?;
+        assert_eq!(result.src, "src");
+        assert_eq!(result.dst, "dst");
+        assert_eq!(result.from, ListEnd::Left);
+        assert_eq!(result.to, ListEnd::Right);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$5\r\nlmove\r\n$3\r\nsrc\r\n$3\r\ndst\r\n$4\r\nleft\r\n$2\r\nup\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ListMove::try_from(frame).is_err());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$9\r\nrpoplpush\r\n$3\r\nsrc\r\n$3\r\ndst\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: ListRightPopLeftPush = frame.try_into()?;
+        assert_eq!(result.src, "src");
+        assert_eq!(result.dst, "dst");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lmove_rpoplpush_commands() {
+        let backend = crate::Backend::new();
+        let elements = ["a", "b", "c"].map(|e| e.into()).to_vec();
+        backend.list_push("src".to_string(), elements, ListEnd::Right);
+        backend.expire_at("src", u64::MAX, Default::default());
+        let contents = |key: &str| -> Vec<String> {
+            backend
+                .list_range(key, 0, -1)
+                .into_iter()
+                .map(|e| String::from_utf8(e.to_vec()).unwrap())
+                .collect()
+        };
+        let lmove = |src: &str, dst: &str, from: ListEnd, to: ListEnd| {
+            ListMove {
+                src: src.to_string(),
+                dst: dst.to_string(),
+                from,
+                to,
+            }
+            .execute(&backend)
+        };
+
+        // The destination is created if needed.
+        assert_eq!(
+            lmove("src", "dst", ListEnd::Left, ListEnd::Right),
+            BulkString::from("a").into()
+        );
+        assert_eq!(
+            lmove("src", "dst", ListEnd::Left, ListEnd::Left),
+            BulkString::from("b").into()
+        );
+        assert_eq!(contents("dst"), ["b", "a"]);
+
+        // The same list is rotated.
+        assert_eq!(
+            lmove("dst", "dst", ListEnd::Left, ListEnd::Right),
+            BulkString::from("b").into()
+        );
+        assert_eq!(contents("dst"), ["a", "b"]);
+        assert_eq!(
+            lmove("missing", "dst", ListEnd::Left, ListEnd::Right),
+            RespFrame::Null(RespNull)
+        );
+        assert!(!backend.exists("missing"));
+
+        // The source is deleted along with its last element.
+        let cmd = ListRightPopLeftPush {
+            src: "src".to_string(),
+            dst: "dst".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), BulkString::from("c").into());
+        assert_eq!(contents("dst"), ["c", "a", "b"]);
+        assert!(!backend.exists("src"));
+        assert_eq!(backend.expire_time("src"), None);
+    }
+
+    #[test]
+    fn test_lpos_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*9\r\n$4\r\nlpos\r\n$4\r\nlist\r\n$1\r\na\r\n$4\r\nrank\r\n$2\r\n-2\r\n$5\r\ncount\r\n$1\r\n0\r\n$6\r\nmaxlen\r\n$2\r\n10\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ListPos = frame.try_into()?;
+        assert_eq!(result.key, "list");
+        assert_eq!(result.element, "a");
+        assert_eq!(result.rank, -2);
+        assert_eq!(result.count, Some(0));
+        assert_eq!(result.max_len, 10);
+
+        for invalid in [
+            &b"*5\r\n$4\r\nlpos\r\n$4\r\nlist\r\n$1\r\na\r\n$4\r\nrank\r\n$1\r\n0\r\n"[..],
+            b"*5\r\n$4\r\nlpos\r\n$4\r\nlist\r\n$1\r\na\r\n$5\r\ncount\r\n$2\r\n-1\r\n",
+            b"*4\r\n$4\r\nlpos\r\n$4\r\nlist\r\n$1\r\na\r\n$6\r\nmaxlen\r\n",
+        ] {
+            let mut buf = BytesMut::from(invalid);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(ListPos::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lpos_command() {
+        let backend = crate::Backend::new();
+        let elements = ["a", "b", "c", "a", "b", "a"].map(|e| e.into()).to_vec();
+        backend.list_push("list".to_string(), elements, ListEnd::Right);
+        let lpos = |element: &str, rank: i64, count: Option<usize>, max_len: usize| {
+            ListPos {
+                key: "list".to_string(),
+                element: element.as_bytes().to_vec().into(),
+                rank,
+                count,
+                max_len,
+            }
+            .execute(&backend)
+        };
+        let indexes = |indexes: &[i64]| -> RespFrame {
+            let indexes: Vec<RespFrame> = indexes.iter().map(|i| RespFrame::Integer(*i)).collect();
+            RespArray::new(indexes).into()
+        };
+
+        assert_eq!(lpos("b", 1, None, 0), RespFrame::Integer(1));
+        assert_eq!(lpos("b", 2, None, 0), RespFrame::Integer(4));
+        assert_eq!(lpos("b", 3, None, 0), RespFrame::Null(RespNull));
+        assert_eq!(lpos("x", 1, None, 0), RespFrame::Null(RespNull));
+
+        // Indexes are counted from the head, even when searching from the tail.
+        assert_eq!(lpos("a", -1, None, 0), RespFrame::Integer(5));
+        assert_eq!(lpos("a", 1, Some(0), 0), indexes(&[0, 3, 5]));
+        assert_eq!(lpos("a", 2, Some(1), 0), indexes(&[3]));
+        assert_eq!(lpos("a", -1, Some(2), 0), indexes(&[5, 3]));
+        assert_eq!(lpos("x", 1, Some(0), 0), indexes(&[]));
+
+        // MAXLEN limits the number of elements compared.
+        assert_eq!(lpos("a", 1, Some(0), 4), indexes(&[0, 3]));
+        assert_eq!(lpos("c", -1, None, 3), RespFrame::Null(RespNull));
+    }
+
+    #[test]
+    fn test_delay_push_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$10\r\ndelay.push\r\n$4\r\njobs\r\n$3\r\njob\r\n$3\r\n100\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: DelayPush = frame.try_into()?;
+        assert_eq!(result.key, "jobs");
+        assert_eq!(result.payload, "job");
+        assert_eq!(result.delay_ms, 100);
+
+        for invalid in [
+            &b"*4\r\n$10\r\ndelay.push\r\n$4\r\njobs\r\n$3\r\njob\r\n$2\r\n-1\r\n"[..],
+            b"*3\r\n$10\r\ndelay.push\r\n$4\r\njobs\r\n$3\r\njob\r\n",
+        ] {
+            let mut buf = BytesMut::from(invalid);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(DelayPush::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_delay_push_command() {
+        let backend = crate::Backend::new();
+        let push = |payload: &str, delay_ms| {
+            DelayPush {
+                key: "jobs".to_string(),
+                payload: payload.as_bytes().to_vec().into(),
+                delay_ms,
+            }
+            .execute(&backend)
+        };
+        assert_eq!(push("later", 60_000), RespFrame::Integer(1));
+        assert_eq!(push("now", 0), RespFrame::Integer(2));
+
+        // Only the due job is moved onto the list.
+        assert_eq!(backend.delay_move_due(), 1);
+        assert_eq!(backend.list_range("jobs", 0, -1), vec!["now"]);
+    }
+}
@@ -1,112 +1,1263 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use bytes::Bytes;
 use enum_dispatch::enum_dispatch;
 use lazy_static::lazy_static;
 use thiserror::Error;
 
-use crate::{Backend, RespArray, RespError, RespFrame, SimpleString};
+use crate::{
+    now_ms, Aggregate, Backend, BulkString, ExpireCondition, Expiry, LexRange, ListEnd, RangeBy,
+    RespArray, RespError, RespFrame, ScoreCondition, ScoreRange, SetCondition, SimpleError,
+    SimpleString, StreamAddId, StreamClaimOptions, StreamFields, StreamId, StreamTrimOptions,
+};
+
+mod bloom;
+mod counter;
+mod debug;
+mod echo;
+mod hmap;
+mod key;
+mod list;
+mod ratelimit;
+mod server;
+mod set;
+mod sort;
+#[cfg(feature = "str-ext")]
+mod str_ext;
+mod stream;
+mod string;
+mod zset;
+
+lazy_static! {
+    static ref RESP_OK: RespFrame = SimpleString::new("OK").into();
+    static ref RESP_UNSUPPORTED: RespFrame = SimpleError::new("ERR unknown command").into();
+}
+
+// The COUNT of SCAN, HSCAN and ZSCAN when not given, same default as Redis.
+const SCAN_DEFAULT_COUNT: usize = 10;
+
+#[derive(Error, Debug)]
+pub enum CommandError {
+    #[error("Invalid command: {0}")]
+    InvalidCommand(String),
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("{0}")]
+    RespError(#[from] RespError),
+    #[error("Utf8 error: {0}")]
+    Utf8Error(#[from] std::string::FromUtf8Error),
+}
+
+#[enum_dispatch]
+pub trait CommandExecutor {
+    fn execute(self, backend: &Backend) -> RespFrame;
+}
+
+// Commands that block the client until they can be served, like BLPOP. The connection blocks
+// the client on the keys and retries `try_execute` each time one of them is written to, until
+// it succeeds or the timeout elapses. Executing the command directly only tries once.
+pub trait BlockingCommand {
+    fn keys(&self) -> Vec<String>;
+
+    // None to block forever.
+    fn timeout(&self) -> Option<Duration>;
+
+    // None if the client has to keep waiting.
+    fn try_execute(&self, backend: &Backend) -> Option<RespFrame>;
+
+    // The reply once the timeout elapsed.
+    fn timeout_reply(&self) -> RespFrame {
+        RespArray::Null.into()
+    }
+}
+
+#[enum_dispatch(CommandExecutor)]
+#[derive(Debug)]
+pub enum Command {
+    Echo(Echo),
+    KeyDelete(KeyDelete),
+    KeyUnlink(KeyUnlink),
+    KeyExists(KeyExists),
+    KeyTouch(KeyTouch),
+    KeyRename(KeyRename),
+    KeyRenameNx(KeyRenameNx),
+    KeyExpire(KeyExpire),
+    KeyPExpire(KeyPExpire),
+    KeyExpireAt(KeyExpireAt),
+    KeyPExpireAt(KeyPExpireAt),
+    KeyPersist(KeyPersist),
+    KeyTtl(KeyTtl),
+    KeyPTtl(KeyPTtl),
+    KeyExpireTime(KeyExpireTime),
+    KeyPExpireTime(KeyPExpireTime),
+    KeyDump(KeyDump),
+    KeyRestore(KeyRestore),
+    Keys(Keys),
+    Scan(Scan),
+    RandomKey(RandomKey),
+    DbSize(DbSize),
+    FlushDb(FlushDb),
+    FlushAll(FlushAll),
+    ObjectEncoding(ObjectEncoding),
+    ObjectRefCount(ObjectRefCount),
+    ObjectIdleTime(ObjectIdleTime),
+    ObjectFreq(ObjectFreq),
+    StringGet(StringGet),
+    StringSet(StringSet),
+    StringSetNx(StringSetNx),
+    StringSetEx(StringSetEx),
+    StringPSetEx(StringPSetEx),
+    StringMultiSetNx(StringMultiSetNx),
+    StringIncr(StringIncr),
+    StringDecr(StringDecr),
+    StringIncrBy(StringIncrBy),
+    StringDecrBy(StringDecrBy),
+    StringIncrByFloat(StringIncrByFloat),
+    StringAppend(StringAppend),
+    StringLen(StringLen),
+    StringGetRange(StringGetRange),
+    StringSetRange(StringSetRange),
+    StringGetBit(StringGetBit),
+    StringSetBit(StringSetBit),
+    #[cfg(feature = "str-ext")]
+    StringCharLen(StringCharLen),
+    #[cfg(feature = "str-ext")]
+    StringCharRange(StringCharRange),
+    HashGet(HashGet),
+    HashSet(HashSet),
+    HashGetAll(HashGetAll),
+    HashMultiGet(HashMultiGet),
+    HashMultiSet(HashMultiSet),
+    HashDelete(HashDelete),
+    HashExists(HashExists),
+    HashLen(HashLen),
+    HashKeys(HashKeys),
+    HashValues(HashValues),
+    HashIncrBy(HashIncrBy),
+    HashIncrByFloat(HashIncrByFloat),
+    HashScan(HashScan),
+    HashRandField(HashRandField),
+    HashStrLen(HashStrLen),
+    HashExpire(HashExpire),
+    HashPExpire(HashPExpire),
+    HashTtl(HashTtl),
+    HashPersist(HashPersist),
+    SetAdd(SetAdd),
+    SetIsMember(SetIsMember),
+    SetMembers(SetMembers),
+    SetRemove(SetRemove),
+    SetRandMember(SetRandMember),
+    SetInter(SetInter),
+    SetUnion(SetUnion),
+    SetDiff(SetDiff),
+    SetInterStore(SetInterStore),
+    SetUnionStore(SetUnionStore),
+    SetDiffStore(SetDiffStore),
+    SetMove(SetMove),
+    ListLeftPush(ListLeftPush),
+    ListRightPush(ListRightPush),
+    ListLeftPop(ListLeftPop),
+    ListRightPop(ListRightPop),
+    ListBlockingLeftPop(ListBlockingLeftPop),
+    ListBlockingRightPop(ListBlockingRightPop),
+    ListMultiPop(ListMultiPop),
+    ListBlockingMultiPop(ListBlockingMultiPop),
+    ListLen(ListLen),
+    ListRange(ListRange),
+    ListIndex(ListIndex),
+    ListSet(ListSet),
+    ListInsert(ListInsert),
+    ListRemove(ListRemove),
+    ListTrim(ListTrim),
+    ListMove(ListMove),
+    ListRightPopLeftPush(ListRightPopLeftPush),
+    ListPos(ListPos),
+    DelayPush(DelayPush),
+    SortedSetAdd(SortedSetAdd),
+    SortedSetScore(SortedSetScore),
+    SortedSetMultiScore(SortedSetMultiScore),
+    SortedSetIncrBy(SortedSetIncrBy),
+    SortedSetCard(SortedSetCard),
+    SortedSetCount(SortedSetCount),
+    SortedSetLexCount(SortedSetLexCount),
+    SortedSetRange(SortedSetRange),
+    SortedSetRangeStore(SortedSetRangeStore),
+    SortedSetRevRange(SortedSetRevRange),
+    SortedSetRank(SortedSetRank),
+    SortedSetRevRank(SortedSetRevRank),
+    SortedSetPopMin(SortedSetPopMin),
+    SortedSetPopMax(SortedSetPopMax),
+    SortedSetBlockingPopMin(SortedSetBlockingPopMin),
+    SortedSetBlockingPopMax(SortedSetBlockingPopMax),
+    SortedSetScan(SortedSetScan),
+    SortedSetUnionStore(SortedSetUnionStore),
+    SortedSetInterStore(SortedSetInterStore),
+    SortedSetDiff(SortedSetDiff),
+    SortedSetRemove(SortedSetRemove),
+    SortedSetRemoveRangeByRank(SortedSetRemoveRangeByRank),
+    SortedSetRemoveRangeByScore(SortedSetRemoveRangeByScore),
+    SortedSetRemoveRangeByLex(SortedSetRemoveRangeByLex),
+    StreamAdd(StreamAdd),
+    StreamLen(StreamLen),
+    StreamRange(StreamRange),
+    StreamRevRange(StreamRevRange),
+    StreamRead(StreamRead),
+    StreamGroupCreate(StreamGroupCreate),
+    StreamGroupDestroy(StreamGroupDestroy),
+    StreamGroupCreateConsumer(StreamGroupCreateConsumer),
+    StreamGroupDeleteConsumer(StreamGroupDeleteConsumer),
+    StreamGroupSetId(StreamGroupSetId),
+    StreamReadGroup(StreamReadGroup),
+    StreamAck(StreamAck),
+    StreamPending(StreamPending),
+    StreamClaim(StreamClaim),
+    StreamAutoClaim(StreamAutoClaim),
+    StreamTrim(StreamTrim),
+    StreamDelete(StreamDelete),
+    StreamInfoStream(StreamInfoStream),
+    StreamInfoGroups(StreamInfoGroups),
+    StreamInfoConsumers(StreamInfoConsumers),
+    Sort(Sort),
+    BloomReserve(BloomReserve),
+    BloomAdd(BloomAdd),
+    BloomMultiAdd(BloomMultiAdd),
+    BloomExists(BloomExists),
+    BloomMultiExists(BloomMultiExists),
+    RateLimitCheck(RateLimitCheck),
+    CounterIncr(CounterIncr),
+    CounterGet(CounterGet),
+    DebugSetTtl(DebugSetTtl),
+    DebugExpireScan(DebugExpireScan),
+    #[cfg(feature = "chaos")]
+    DebugChaos(DebugChaos),
+
+    Unsupported(Unsupported),
+}
+
+#[derive(Debug)]
+pub struct Echo {
+    message: String,
+}
+
+#[derive(Debug)]
+pub struct KeyDelete {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct KeyUnlink {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct KeyExists {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct KeyTouch {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct KeyRename {
+    src: String,
+    dst: String,
+}
+
+#[derive(Debug)]
+pub struct KeyRenameNx {
+    src: String,
+    dst: String,
+}
+
+// The expire commands store the absolute unix time in milliseconds.
+#[derive(Debug)]
+pub struct KeyExpire {
+    key: String,
+    at: u64,
+    condition: ExpireCondition,
+}
+
+#[derive(Debug)]
+pub struct KeyPExpire {
+    key: String,
+    at: u64,
+    condition: ExpireCondition,
+}
+
+#[derive(Debug)]
+pub struct KeyExpireAt {
+    key: String,
+    at: u64,
+    condition: ExpireCondition,
+}
+
+#[derive(Debug)]
+pub struct KeyPExpireAt {
+    key: String,
+    at: u64,
+    condition: ExpireCondition,
+}
+
+#[derive(Debug)]
+pub struct KeyPersist {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct KeyTtl {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct KeyPTtl {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct KeyExpireTime {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct KeyPExpireTime {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct KeyDump {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct KeyRestore {
+    key: String,
+    // In milliseconds, 0 for none.
+    ttl: u64,
+    payload: Vec<u8>,
+    replace: bool,
+    // Whether the ttl is a unix time rather than relative to now.
+    absolute: bool,
+}
+
+#[derive(Debug)]
+pub struct Keys {
+    pattern: String,
+}
+
+#[derive(Debug)]
+pub struct Scan {
+    cursor: u64,
+    pattern: Option<String>,
+    count: usize,
+    key_type: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct RandomKey;
+
+#[derive(Debug)]
+pub struct DbSize;
+
+#[derive(Debug)]
+pub struct FlushDb {
+    asynchronous: bool,
+}
+
+#[derive(Debug)]
+pub struct FlushAll {
+    asynchronous: bool,
+}
+
+#[derive(Debug)]
+pub struct ObjectEncoding {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct ObjectRefCount {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct ObjectIdleTime {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct ObjectFreq {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct StringGet {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct StringSet {
+    key: String,
+    value: Vec<u8>,
+    condition: SetCondition,
+    expiry: Expiry,
+    get: bool,
+}
+
+#[derive(Debug)]
+pub struct StringSetNx {
+    key: String,
+    value: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct StringSetEx {
+    key: String,
+    value: Vec<u8>,
+    expiry: Expiry,
+}
+
+#[derive(Debug)]
+pub struct StringPSetEx {
+    key: String,
+    value: Vec<u8>,
+    expiry: Expiry,
+}
+
+#[derive(Debug)]
+pub struct StringMultiSetNx {
+    pairs: Vec<(String, Vec<u8>)>,
+}
+
+#[derive(Debug)]
+pub struct StringIncr {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct StringDecr {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct StringIncrBy {
+    key: String,
+    delta: i64,
+}
+
+#[derive(Debug)]
+pub struct StringDecrBy {
+    key: String,
+    delta: i64,
+}
+
+#[derive(Debug)]
+pub struct StringIncrByFloat {
+    key: String,
+    delta: f64,
+}
+
+#[derive(Debug)]
+pub struct StringAppend {
+    key: String,
+    value: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct StringLen {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct StringGetRange {
+    key: String,
+    start: i64,
+    end: i64,
+}
+
+#[derive(Debug)]
+pub struct StringSetRange {
+    key: String,
+    offset: usize,
+    value: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct StringGetBit {
+    key: String,
+    offset: usize,
+}
+
+#[derive(Debug)]
+pub struct StringSetBit {
+    key: String,
+    offset: usize,
+    bit: bool,
+}
+
+#[cfg(feature = "str-ext")]
+#[derive(Debug)]
+pub struct StringCharLen {
+    key: String,
+}
+
+#[cfg(feature = "str-ext")]
+#[derive(Debug)]
+pub struct StringCharRange {
+    key: String,
+    start: i64,
+    end: i64,
+}
+
+#[derive(Debug)]
+pub struct HashGet {
+    key: String,
+    field: String,
+}
+
+#[derive(Debug)]
+pub struct HashSet {
+    key: String,
+    fields: Vec<(String, RespFrame)>,
+}
+
+#[derive(Debug)]
+pub struct HashMultiSet {
+    key: String,
+    fields: Vec<(String, RespFrame)>,
+}
+
+#[derive(Debug)]
+pub struct HashGetAll {
+    key: String,
+    sort: bool,
+}
+
+#[derive(Debug)]
+pub struct HashMultiGet {
+    key: String,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HashDelete {
+    key: String,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HashExists {
+    key: String,
+    field: String,
+}
+
+#[derive(Debug)]
+pub struct HashLen {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct HashKeys {
+    key: String,
+    sort: bool,
+}
+
+#[derive(Debug)]
+pub struct HashValues {
+    key: String,
+    sort: bool,
+}
+
+#[derive(Debug)]
+pub struct HashIncrBy {
+    key: String,
+    field: String,
+    delta: i64,
+}
+
+#[derive(Debug)]
+pub struct HashIncrByFloat {
+    key: String,
+    field: String,
+    delta: f64,
+}
+
+#[derive(Debug)]
+pub struct HashScan {
+    key: String,
+    cursor: u64,
+    pattern: Option<String>,
+    count: usize,
+    no_values: bool,
+}
+
+#[derive(Debug)]
+pub struct HashRandField {
+    key: String,
+    count: Option<i64>,
+    with_values: bool,
+}
+
+#[derive(Debug)]
+pub struct HashStrLen {
+    key: String,
+    field: String,
+}
+
+// Like the key expire commands, the absolute unix time in milliseconds.
+#[derive(Debug)]
+pub struct HashExpire {
+    key: String,
+    at: u64,
+    condition: ExpireCondition,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HashPExpire {
+    key: String,
+    at: u64,
+    condition: ExpireCondition,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HashTtl {
+    key: String,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HashPersist {
+    key: String,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SetAdd {
+    key: String,
+    members: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SetIsMember {
+    key: String,
+    member: String,
+}
+
+#[derive(Debug)]
+pub struct SetMembers {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct SetRemove {
+    key: String,
+    members: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SetRandMember {
+    key: String,
+    count: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct SetInter {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SetUnion {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SetDiff {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SetInterStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SetUnionStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SetDiffStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SetMove {
+    src: String,
+    dst: String,
+    member: String,
+}
+
+#[derive(Debug)]
+pub struct ListLeftPush {
+    key: String,
+    elements: Vec<Bytes>,
+}
+
+#[derive(Debug)]
+pub struct ListRightPush {
+    key: String,
+    elements: Vec<Bytes>,
+}
+
+#[derive(Debug)]
+pub struct ListLeftPop {
+    key: String,
+    // None to reply a single element rather than an array.
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct ListRightPop {
+    key: String,
+    // None to reply a single element rather than an array.
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct ListLen {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct ListRange {
+    key: String,
+    start: i64,
+    end: i64,
+}
+
+#[derive(Debug)]
+pub struct ListIndex {
+    key: String,
+    index: i64,
+}
+
+#[derive(Debug)]
+pub struct ListSet {
+    key: String,
+    index: i64,
+    element: Bytes,
+}
+
+#[derive(Debug)]
+pub struct ListInsert {
+    key: String,
+    // Whether to insert before the pivot rather than after it.
+    before: bool,
+    pivot: Bytes,
+    element: Bytes,
+}
+
+#[derive(Debug)]
+pub struct ListRemove {
+    key: String,
+    count: i64,
+    element: Bytes,
+}
+
+#[derive(Debug)]
+pub struct ListTrim {
+    key: String,
+    start: i64,
+    end: i64,
+}
+
+#[derive(Debug)]
+pub struct ListBlockingLeftPop {
+    keys: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct ListBlockingRightPop {
+    keys: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct ListMultiPop {
+    keys: Vec<String>,
+    end: ListEnd,
+    count: usize,
+}
+
+#[derive(Debug)]
+pub struct ListBlockingMultiPop {
+    keys: Vec<String>,
+    end: ListEnd,
+    count: usize,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct ListMove {
+    src: String,
+    dst: String,
+    from: ListEnd,
+    to: ListEnd,
+}
+
+#[derive(Debug)]
+pub struct ListRightPopLeftPush {
+    src: String,
+    dst: String,
+}
+
+#[derive(Debug)]
+pub struct ListPos {
+    key: String,
+    element: Bytes,
+    // Never 0, negative to search from the tail.
+    rank: i64,
+    // None to reply a single index rather than an array, Some(0) for all the matches.
+    count: Option<usize>,
+    // 0 for no limit.
+    max_len: usize,
+}
 
-mod echo;
-mod hmap;
-mod set;
-mod string;
+#[derive(Debug)]
+pub struct DelayPush {
+    key: String,
+    payload: Bytes,
+    delay_ms: u64,
+}
 
-lazy_static! {
-    static ref RESP_OK: RespFrame = SimpleString::new("OK").into();
-    static ref RESP_UNSUPPORTED: RespFrame = SimpleString::new("UNSUPPORTED").into();
+#[derive(Debug)]
+pub struct SortedSetAdd {
+    key: String,
+    members: Vec<(f64, String)>,
+    condition: ScoreCondition,
+    // Whether to reply the number of members changed rather than added, as the CH option.
+    changed: bool,
+    // Whether to add the score to the only member's one and reply the result, as the INCR
+    // option.
+    incr: bool,
 }
 
-#[derive(Error, Debug)]
-pub enum CommandError {
-    #[error("Invalid command: {0}")]
-    InvalidCommand(String),
-    #[error("Invalid argument: {0}")]
-    InvalidArgument(String),
+#[derive(Debug)]
+pub struct SortedSetScore {
+    key: String,
+    member: String,
+}
 
-    #[error("{0}")]
-    RespError(#[from] RespError),
-    #[error("Utf8 error: {0}")]
-    Utf8Error(#[from] std::string::FromUtf8Error),
+#[derive(Debug)]
+pub struct SortedSetMultiScore {
+    key: String,
+    members: Vec<String>,
 }
 
-#[enum_dispatch]
-pub trait CommandExecutor {
-    fn execute(self, backend: &Backend) -> RespFrame;
+#[derive(Debug)]
+pub struct SortedSetIncrBy {
+    key: String,
+    delta: f64,
+    member: String,
 }
 
-#[enum_dispatch(CommandExecutor)]
 #[derive(Debug)]
-pub enum Command {
-    Echo(Echo),
-    StringGet(StringGet),
-    StringSet(StringSet),
-    HashGet(HashGet),
-    HashSet(HashSet),
-    HashGetAll(HashGetAll),
-    HashMultiGet(HashMultiGet),
-    SetAdd(SetAdd),
-    SetIsMember(SetIsMember),
-    SetMembers(SetMembers),
+pub struct SortedSetCard {
+    key: String,
+}
 
-    Unsupported(Unsupported),
+#[derive(Debug)]
+pub struct SortedSetCount {
+    key: String,
+    range: ScoreRange,
 }
 
 #[derive(Debug)]
-pub struct Echo {
-    message: String,
+pub struct SortedSetLexCount {
+    key: String,
+    range: LexRange,
 }
 
 #[derive(Debug)]
-pub struct StringGet {
+pub struct SortedSetRange {
     key: String,
+    start: i64,
+    end: i64,
+    // Whether to rank members from the highest score, as the REV option.
+    rev: bool,
+    with_scores: bool,
 }
 
 #[derive(Debug)]
-pub struct StringSet {
+pub struct SortedSetRangeStore {
+    destination: String,
+    source: String,
+    by: RangeBy,
+    // Whether to select from the highest score, as the REV option.
+    rev: bool,
+    // Offset and count.
+    limit: Option<(i64, i64)>,
+}
+
+#[derive(Debug)]
+pub struct SortedSetRevRange {
     key: String,
-    value: RespFrame,
+    start: i64,
+    end: i64,
+    with_scores: bool,
 }
 
 #[derive(Debug)]
-pub struct HashGet {
+pub struct SortedSetRank {
     key: String,
-    field: String,
+    member: String,
+    with_score: bool,
 }
 
 #[derive(Debug)]
-pub struct HashSet {
+pub struct SortedSetRevRank {
     key: String,
-    field: String,
-    value: RespFrame,
+    member: String,
+    with_score: bool,
 }
 
 #[derive(Debug)]
-pub struct HashGetAll {
+pub struct SortedSetPopMin {
     key: String,
-    sort: bool,
+    count: Option<usize>,
 }
 
 #[derive(Debug)]
-pub struct HashMultiGet {
+pub struct SortedSetPopMax {
     key: String,
-    fields: Vec<String>,
+    count: Option<usize>,
 }
 
 #[derive(Debug)]
-pub struct SetAdd {
+pub struct SortedSetBlockingPopMin {
+    keys: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct SortedSetBlockingPopMax {
+    keys: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct SortedSetScan {
+    key: String,
+    cursor: u64,
+    pattern: Option<String>,
+    count: usize,
+}
+
+#[derive(Debug)]
+pub struct SortedSetUnionStore {
+    destination: String,
+    keys: Vec<String>,
+    // One per key.
+    weights: Vec<f64>,
+    aggregate: Aggregate,
+}
+
+#[derive(Debug)]
+pub struct SortedSetInterStore {
+    destination: String,
+    keys: Vec<String>,
+    // One per key.
+    weights: Vec<f64>,
+    aggregate: Aggregate,
+}
+
+#[derive(Debug)]
+pub struct SortedSetDiff {
+    keys: Vec<String>,
+    with_scores: bool,
+}
+
+#[derive(Debug)]
+pub struct SortedSetRemove {
     key: String,
     members: Vec<String>,
 }
 
 #[derive(Debug)]
-pub struct SetIsMember {
+pub struct SortedSetRemoveRangeByRank {
     key: String,
-    member: String,
+    start: i64,
+    end: i64,
 }
 
 #[derive(Debug)]
-pub struct SetMembers {
+pub struct SortedSetRemoveRangeByScore {
+    key: String,
+    range: ScoreRange,
+}
+
+#[derive(Debug)]
+pub struct SortedSetRemoveRangeByLex {
+    key: String,
+    range: LexRange,
+}
+
+#[derive(Debug)]
+pub struct StreamAdd {
+    key: String,
+    id: StreamAddId,
+    fields: StreamFields,
+    // NOMKSTREAM.
+    no_create: bool,
+    trim: Option<StreamTrimOptions>,
+}
+
+#[derive(Debug)]
+pub struct StreamLen {
+    key: String,
+}
+
+// Both bounds are inclusive, exclusive ones are converted when parsing.
+#[derive(Debug)]
+pub struct StreamRange {
+    key: String,
+    start: StreamId,
+    end: StreamId,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct StreamRevRange {
+    key: String,
+    start: StreamId,
+    end: StreamId,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct StreamRead {
+    keys: Vec<String>,
+    // Entries are read after these IDs, one per key. None stands for "$", the last ID of
+    // the stream when the command is first tried.
+    ids: Vec<Option<StreamId>>,
+    count: Option<usize>,
+    // Whether BLOCK was given, the timeout being None to block forever.
+    block: bool,
+    timeout: Option<Duration>,
+    // The IDs with "$" resolved, set on the first try.
+    after: OnceLock<Vec<StreamId>>,
+}
+
+#[derive(Debug)]
+pub struct StreamGroupCreate {
+    key: String,
+    group: String,
+    // None stands for "$", the last ID of the stream.
+    last_id: Option<StreamId>,
+    // MKSTREAM.
+    create: bool,
+}
+
+#[derive(Debug)]
+pub struct StreamGroupDestroy {
+    key: String,
+    group: String,
+}
+
+#[derive(Debug)]
+pub struct StreamGroupCreateConsumer {
+    key: String,
+    group: String,
+    consumer: String,
+}
+
+#[derive(Debug)]
+pub struct StreamGroupDeleteConsumer {
+    key: String,
+    group: String,
+    consumer: String,
+}
+
+#[derive(Debug)]
+pub struct StreamGroupSetId {
+    key: String,
+    group: String,
+    // None stands for "$", the last ID of the stream.
+    last_id: Option<StreamId>,
+}
+
+#[derive(Debug)]
+pub struct StreamReadGroup {
+    group: String,
+    consumer: String,
+    keys: Vec<String>,
+    // One per key. None stands for ">", the entries never delivered to the group, otherwise
+    // the consumer's pending entries after the ID are read again.
+    ids: Vec<Option<StreamId>>,
+    count: Option<usize>,
+    no_ack: bool,
+    // Whether BLOCK was given, the timeout being None to block forever.
+    block: bool,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct StreamAck {
+    key: String,
+    group: String,
+    ids: Vec<StreamId>,
+}
+
+#[derive(Debug)]
+pub struct StreamPending {
+    key: String,
+    group: String,
+    // The start, end and count of the extended form, otherwise the summary is replied.
+    range: Option<(StreamId, StreamId, usize)>,
+    // IDLE, only with a range.
+    min_idle: u64,
+    consumer: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct StreamClaim {
+    key: String,
+    group: String,
+    consumer: String,
+    ids: Vec<StreamId>,
+    options: StreamClaimOptions,
+    // IDLE, turned into the time of the last delivery when executed unless TIME is given.
+    idle: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct StreamAutoClaim {
+    key: String,
+    group: String,
+    consumer: String,
+    start: StreamId,
+    count: usize,
+    options: StreamClaimOptions,
+}
+
+#[derive(Debug)]
+pub struct StreamTrim {
+    key: String,
+    trim: StreamTrimOptions,
+}
+
+#[derive(Debug)]
+pub struct StreamDelete {
+    key: String,
+    ids: Vec<StreamId>,
+}
+
+#[derive(Debug)]
+pub struct StreamInfoStream {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct StreamInfoGroups {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct StreamInfoConsumers {
+    key: String,
+    group: String,
+}
+
+#[derive(Debug)]
+pub struct Sort {
+    key: String,
+    by: Option<String>,
+    // Offset and count.
+    limit: Option<(i64, i64)>,
+    get: Vec<String>,
+    desc: bool,
+    alpha: bool,
+    store: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct BloomReserve {
+    key: String,
+    error_rate: f64,
+    capacity: u64,
+    expansion: u32,
+}
+
+#[derive(Debug)]
+pub struct BloomAdd {
+    key: String,
+    item: String,
+}
+
+#[derive(Debug)]
+pub struct BloomMultiAdd {
+    key: String,
+    items: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct BloomExists {
+    key: String,
+    item: String,
+}
+
+#[derive(Debug)]
+pub struct BloomMultiExists {
+    key: String,
+    items: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct RateLimitCheck {
+    key: String,
+    max_tokens: u64,
+    refill_rate: f64,
+    cost: u64,
+}
+
+#[derive(Debug)]
+pub struct CounterIncr {
+    key: String,
+    delta: i64,
+}
+
+#[derive(Debug)]
+pub struct CounterGet {
     key: String,
 }
 
+#[derive(Debug)]
+pub struct DebugSetTtl {
+    pattern: String,
+    seconds: u64,
+    condition: ExpireCondition,
+}
+
+#[derive(Debug)]
+pub struct DebugExpireScan {
+    pattern: String,
+}
+
+#[cfg(feature = "chaos")]
+#[derive(Debug)]
+pub struct DebugChaos {
+    // The fault and its probability, None to turn all of them off.
+    fault: Option<(crate::chaos::Fault, f64)>,
+}
+
 #[derive(Debug)]
 pub struct Unsupported;
 
@@ -128,15 +1279,185 @@ impl TryFrom<RespArray> for Command {
         match v.first() {
             Some(RespFrame::BulkString(ref cmd)) => match cmd.to_ascii_uppercase().as_ref() {
                 b"ECHO" => Ok(Echo::try_from(v)?.into()),
+                b"DEL" => Ok(KeyDelete::try_from(v)?.into()),
+                b"UNLINK" => Ok(KeyUnlink::try_from(v)?.into()),
+                b"EXISTS" => Ok(KeyExists::try_from(v)?.into()),
+                b"TOUCH" => Ok(KeyTouch::try_from(v)?.into()),
+                b"RENAME" => Ok(KeyRename::try_from(v)?.into()),
+                b"RENAMENX" => Ok(KeyRenameNx::try_from(v)?.into()),
+                b"EXPIRE" => Ok(KeyExpire::try_from(v)?.into()),
+                b"PEXPIRE" => Ok(KeyPExpire::try_from(v)?.into()),
+                b"EXPIREAT" => Ok(KeyExpireAt::try_from(v)?.into()),
+                b"PEXPIREAT" => Ok(KeyPExpireAt::try_from(v)?.into()),
+                b"PERSIST" => Ok(KeyPersist::try_from(v)?.into()),
+                b"TTL" => Ok(KeyTtl::try_from(v)?.into()),
+                b"PTTL" => Ok(KeyPTtl::try_from(v)?.into()),
+                b"EXPIRETIME" => Ok(KeyExpireTime::try_from(v)?.into()),
+                b"PEXPIRETIME" => Ok(KeyPExpireTime::try_from(v)?.into()),
+                b"DUMP" => Ok(KeyDump::try_from(v)?.into()),
+                b"RESTORE" => Ok(KeyRestore::try_from(v)?.into()),
+                b"KEYS" => Ok(Keys::try_from(v)?.into()),
+                b"SCAN" => Ok(Scan::try_from(v)?.into()),
+                b"RANDOMKEY" => Ok(RandomKey::try_from(v)?.into()),
+                b"DBSIZE" => Ok(DbSize::try_from(v)?.into()),
+                b"FLUSHDB" => Ok(FlushDb::try_from(v)?.into()),
+                b"FLUSHALL" => Ok(FlushAll::try_from(v)?.into()),
+                b"OBJECT" => match v.get(1) {
+                    Some(RespFrame::BulkString(sub)) => match sub.to_ascii_uppercase().as_ref() {
+                        b"ENCODING" => Ok(ObjectEncoding::try_from(v)?.into()),
+                        b"REFCOUNT" => Ok(ObjectRefCount::try_from(v)?.into()),
+                        b"IDLETIME" => Ok(ObjectIdleTime::try_from(v)?.into()),
+                        b"FREQ" => Ok(ObjectFreq::try_from(v)?.into()),
+                        _ => Ok(Unsupported.into()),
+                    },
+                    _ => Ok(Unsupported.into()),
+                },
                 b"GET" => Ok(StringGet::try_from(v)?.into()),
                 b"SET" => Ok(StringSet::try_from(v)?.into()),
+                b"SETNX" => Ok(StringSetNx::try_from(v)?.into()),
+                b"SETEX" => Ok(StringSetEx::try_from(v)?.into()),
+                b"PSETEX" => Ok(StringPSetEx::try_from(v)?.into()),
+                b"MSETNX" => Ok(StringMultiSetNx::try_from(v)?.into()),
+                b"INCR" => Ok(StringIncr::try_from(v)?.into()),
+                b"DECR" => Ok(StringDecr::try_from(v)?.into()),
+                b"INCRBY" => Ok(StringIncrBy::try_from(v)?.into()),
+                b"DECRBY" => Ok(StringDecrBy::try_from(v)?.into()),
+                b"INCRBYFLOAT" => Ok(StringIncrByFloat::try_from(v)?.into()),
+                b"APPEND" => Ok(StringAppend::try_from(v)?.into()),
+                b"STRLEN" => Ok(StringLen::try_from(v)?.into()),
+                b"GETRANGE" => Ok(StringGetRange::try_from(v)?.into()),
+                b"SETRANGE" => Ok(StringSetRange::try_from(v)?.into()),
+                b"GETBIT" => Ok(StringGetBit::try_from(v)?.into()),
+                b"SETBIT" => Ok(StringSetBit::try_from(v)?.into()),
+                #[cfg(feature = "str-ext")]
+                b"STR.LEN" => Ok(StringCharLen::try_from(v)?.into()),
+                #[cfg(feature = "str-ext")]
+                b"STR.SUBSTR" => Ok(StringCharRange::try_from(v)?.into()),
                 b"HGET" => Ok(HashGet::try_from(v)?.into()),
                 b"HSET" => Ok(HashSet::try_from(v)?.into()),
+                b"HMSET" => Ok(HashMultiSet::try_from(v)?.into()),
                 b"HGETALL" => Ok(HashGetAll::try_from(v)?.into()),
                 b"HMGET" => Ok(HashMultiGet::try_from(v)?.into()),
+                b"HDEL" => Ok(HashDelete::try_from(v)?.into()),
+                b"HEXISTS" => Ok(HashExists::try_from(v)?.into()),
+                b"HLEN" => Ok(HashLen::try_from(v)?.into()),
+                b"HKEYS" => Ok(HashKeys::try_from(v)?.into()),
+                b"HVALS" => Ok(HashValues::try_from(v)?.into()),
+                b"HINCRBY" => Ok(HashIncrBy::try_from(v)?.into()),
+                b"HINCRBYFLOAT" => Ok(HashIncrByFloat::try_from(v)?.into()),
+                b"HSCAN" => Ok(HashScan::try_from(v)?.into()),
+                b"HRANDFIELD" => Ok(HashRandField::try_from(v)?.into()),
+                b"HSTRLEN" => Ok(HashStrLen::try_from(v)?.into()),
+                b"HEXPIRE" => Ok(HashExpire::try_from(v)?.into()),
+                b"HPEXPIRE" => Ok(HashPExpire::try_from(v)?.into()),
+                b"HTTL" => Ok(HashTtl::try_from(v)?.into()),
+                b"HPERSIST" => Ok(HashPersist::try_from(v)?.into()),
                 b"SADD" => Ok(SetAdd::try_from(v)?.into()),
                 b"SISMEMBER" => Ok(SetIsMember::try_from(v)?.into()),
                 b"SMEMBERS" => Ok(SetMembers::try_from(v)?.into()),
+                b"SREM" => Ok(SetRemove::try_from(v)?.into()),
+                b"SRANDMEMBER" => Ok(SetRandMember::try_from(v)?.into()),
+                b"SINTER" => Ok(SetInter::try_from(v)?.into()),
+                b"SUNION" => Ok(SetUnion::try_from(v)?.into()),
+                b"SDIFF" => Ok(SetDiff::try_from(v)?.into()),
+                b"SINTERSTORE" => Ok(SetInterStore::try_from(v)?.into()),
+                b"SUNIONSTORE" => Ok(SetUnionStore::try_from(v)?.into()),
+                b"SDIFFSTORE" => Ok(SetDiffStore::try_from(v)?.into()),
+                b"SMOVE" => Ok(SetMove::try_from(v)?.into()),
+                b"LPUSH" => Ok(ListLeftPush::try_from(v)?.into()),
+                b"RPUSH" => Ok(ListRightPush::try_from(v)?.into()),
+                b"LPOP" => Ok(ListLeftPop::try_from(v)?.into()),
+                b"RPOP" => Ok(ListRightPop::try_from(v)?.into()),
+                b"BLPOP" => Ok(ListBlockingLeftPop::try_from(v)?.into()),
+                b"BRPOP" => Ok(ListBlockingRightPop::try_from(v)?.into()),
+                b"LMPOP" => Ok(ListMultiPop::try_from(v)?.into()),
+                b"BLMPOP" => Ok(ListBlockingMultiPop::try_from(v)?.into()),
+                b"LLEN" => Ok(ListLen::try_from(v)?.into()),
+                b"LRANGE" => Ok(ListRange::try_from(v)?.into()),
+                b"LINDEX" => Ok(ListIndex::try_from(v)?.into()),
+                b"LSET" => Ok(ListSet::try_from(v)?.into()),
+                b"LINSERT" => Ok(ListInsert::try_from(v)?.into()),
+                b"LREM" => Ok(ListRemove::try_from(v)?.into()),
+                b"LTRIM" => Ok(ListTrim::try_from(v)?.into()),
+                b"LMOVE" => Ok(ListMove::try_from(v)?.into()),
+                b"RPOPLPUSH" => Ok(ListRightPopLeftPush::try_from(v)?.into()),
+                b"LPOS" => Ok(ListPos::try_from(v)?.into()),
+                b"DELAY.PUSH" => Ok(DelayPush::try_from(v)?.into()),
+                b"ZADD" => Ok(SortedSetAdd::try_from(v)?.into()),
+                b"ZSCORE" => Ok(SortedSetScore::try_from(v)?.into()),
+                b"ZMSCORE" => Ok(SortedSetMultiScore::try_from(v)?.into()),
+                b"ZINCRBY" => Ok(SortedSetIncrBy::try_from(v)?.into()),
+                b"ZCARD" => Ok(SortedSetCard::try_from(v)?.into()),
+                b"ZCOUNT" => Ok(SortedSetCount::try_from(v)?.into()),
+                b"ZLEXCOUNT" => Ok(SortedSetLexCount::try_from(v)?.into()),
+                b"ZRANGE" => Ok(SortedSetRange::try_from(v)?.into()),
+                b"ZRANGESTORE" => Ok(SortedSetRangeStore::try_from(v)?.into()),
+                b"ZREVRANGE" => Ok(SortedSetRevRange::try_from(v)?.into()),
+                b"ZRANK" => Ok(SortedSetRank::try_from(v)?.into()),
+                b"ZREVRANK" => Ok(SortedSetRevRank::try_from(v)?.into()),
+                b"ZPOPMIN" => Ok(SortedSetPopMin::try_from(v)?.into()),
+                b"ZPOPMAX" => Ok(SortedSetPopMax::try_from(v)?.into()),
+                b"BZPOPMIN" => Ok(SortedSetBlockingPopMin::try_from(v)?.into()),
+                b"BZPOPMAX" => Ok(SortedSetBlockingPopMax::try_from(v)?.into()),
+                b"ZSCAN" => Ok(SortedSetScan::try_from(v)?.into()),
+                b"ZUNIONSTORE" => Ok(SortedSetUnionStore::try_from(v)?.into()),
+                b"ZINTERSTORE" => Ok(SortedSetInterStore::try_from(v)?.into()),
+                b"ZDIFF" => Ok(SortedSetDiff::try_from(v)?.into()),
+                b"ZREM" => Ok(SortedSetRemove::try_from(v)?.into()),
+                b"ZREMRANGEBYRANK" => Ok(SortedSetRemoveRangeByRank::try_from(v)?.into()),
+                b"ZREMRANGEBYSCORE" => Ok(SortedSetRemoveRangeByScore::try_from(v)?.into()),
+                b"ZREMRANGEBYLEX" => Ok(SortedSetRemoveRangeByLex::try_from(v)?.into()),
+                b"XADD" => Ok(StreamAdd::try_from(v)?.into()),
+                b"XLEN" => Ok(StreamLen::try_from(v)?.into()),
+                b"XRANGE" => Ok(StreamRange::try_from(v)?.into()),
+                b"XREVRANGE" => Ok(StreamRevRange::try_from(v)?.into()),
+                b"XREAD" => Ok(StreamRead::try_from(v)?.into()),
+                b"XGROUP" => match v.get(1) {
+                    Some(RespFrame::BulkString(sub)) => match sub.to_ascii_uppercase().as_ref() {
+                        b"CREATE" => Ok(StreamGroupCreate::try_from(v)?.into()),
+                        b"DESTROY" => Ok(StreamGroupDestroy::try_from(v)?.into()),
+                        b"CREATECONSUMER" => Ok(StreamGroupCreateConsumer::try_from(v)?.into()),
+                        b"DELCONSUMER" => Ok(StreamGroupDeleteConsumer::try_from(v)?.into()),
+                        b"SETID" => Ok(StreamGroupSetId::try_from(v)?.into()),
+                        _ => Ok(Unsupported.into()),
+                    },
+                    _ => Ok(Unsupported.into()),
+                },
+                b"XREADGROUP" => Ok(StreamReadGroup::try_from(v)?.into()),
+                b"XACK" => Ok(StreamAck::try_from(v)?.into()),
+                b"XPENDING" => Ok(StreamPending::try_from(v)?.into()),
+                b"XCLAIM" => Ok(StreamClaim::try_from(v)?.into()),
+                b"XAUTOCLAIM" => Ok(StreamAutoClaim::try_from(v)?.into()),
+                b"XTRIM" => Ok(StreamTrim::try_from(v)?.into()),
+                b"XDEL" => Ok(StreamDelete::try_from(v)?.into()),
+                b"XINFO" => match v.get(1) {
+                    Some(RespFrame::BulkString(sub)) => match sub.to_ascii_uppercase().as_ref() {
+                        b"STREAM" => Ok(StreamInfoStream::try_from(v)?.into()),
+                        b"GROUPS" => Ok(StreamInfoGroups::try_from(v)?.into()),
+                        b"CONSUMERS" => Ok(StreamInfoConsumers::try_from(v)?.into()),
+                        _ => Ok(Unsupported.into()),
+                    },
+                    _ => Ok(Unsupported.into()),
+                },
+                b"SORT" => Ok(Sort::try_from(v)?.into()),
+                b"BF.RESERVE" => Ok(BloomReserve::try_from(v)?.into()),
+                b"BF.ADD" => Ok(BloomAdd::try_from(v)?.into()),
+                b"BF.MADD" => Ok(BloomMultiAdd::try_from(v)?.into()),
+                b"BF.EXISTS" => Ok(BloomExists::try_from(v)?.into()),
+                b"BF.MEXISTS" => Ok(BloomMultiExists::try_from(v)?.into()),
+                b"RATELIMIT.CHECK" => Ok(RateLimitCheck::try_from(v)?.into()),
+                b"COUNTER.INCR" => Ok(CounterIncr::try_from(v)?.into()),
+                b"COUNTER.GET" => Ok(CounterGet::try_from(v)?.into()),
+                b"DEBUG" => match v.get(1) {
+                    Some(RespFrame::BulkString(sub)) => match sub.to_ascii_uppercase().as_ref() {
+                        b"SET-TTL" => Ok(DebugSetTtl::try_from(v)?.into()),
+                        b"EXPIRE-SCAN" => Ok(DebugExpireScan::try_from(v)?.into()),
+                        #[cfg(feature = "chaos")]
+                        b"CHAOS" => Ok(DebugChaos::try_from(v)?.into()),
+                        _ => Ok(Unsupported.into()),
+                    },
+                    _ => Ok(Unsupported.into()),
+                },
                 _ => Ok(Unsupported.into()),
             },
             _ => Err(CommandError::InvalidCommand(
@@ -158,6 +1479,12 @@ fn validate_command(
     names: &[&'static str],
     n_args: Option<usize>,
 ) -> Result<(), CommandError> {
+    if value.len() < names.len() {
+        return Err(CommandError::InvalidCommand(format!(
+            "Invalid command: expected {}",
+            names.join(" ")
+        )));
+    }
     if let Some(n_args) = n_args {
         if value.len() != n_args + names.len() {
             return Err(CommandError::InvalidArgument(format!(
@@ -197,6 +1524,136 @@ fn extract_args(value: RespArray, start: usize) -> Result<Vec<RespFrame>, Comman
     Ok(args)
 }
 
+// Parses the "<key>" of the single key commands.
+fn extract_key(value: RespArray) -> Result<String, CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    match args.next() {
+        Some(RespFrame::BulkString(key)) => Ok(key.try_into()?),
+        _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    }
+}
+
+// Parses "<key> [<key> ...]" shared by the variadic key commands.
+fn extract_keys(value: RespArray) -> Result<Vec<String>, CommandError> {
+    let mut keys = Vec::new();
+    for arg in extract_args(value, 1)? {
+        match arg {
+            RespFrame::BulkString(key) => keys.push(key.try_into()?),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+    if keys.is_empty() {
+        return Err(CommandError::InvalidArgument(
+            "At least one key is required".to_string(),
+        ));
+    }
+    Ok(keys)
+}
+
+fn parse_number<T: std::str::FromStr>(value: BulkString, name: &str) -> Result<T, CommandError> {
+    let s: String = value.try_into()?;
+    s.parse()
+        .map_err(|_| CommandError::InvalidArgument(format!("Invalid {}", name)))
+}
+
+// Parses the timeout in seconds of the blocking commands, 0 to block forever.
+fn parse_timeout(value: BulkString) -> Result<Option<Duration>, CommandError> {
+    let secs: f64 = parse_number(value, "timeout")?;
+    if secs < 0.0 {
+        return Err(CommandError::InvalidArgument(
+            "timeout is negative".to_string(),
+        ));
+    }
+    match Duration::try_from_secs_f64(secs) {
+        Ok(timeout) if timeout.is_zero() => Ok(None),
+        Ok(timeout) => Ok(Some(timeout)),
+        Err(_) => Err(CommandError::InvalidArgument("Invalid timeout".to_string())),
+    }
+}
+
+// Parses "<key> [<count>]" shared by the pop commands.
+fn extract_key_and_count(value: RespArray) -> Result<(String, Option<usize>), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    match (args.next(), args.next(), args.next()) {
+        (Some(RespFrame::BulkString(key)), None, None) => Ok((key.try_into()?, None)),
+        (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(count)), None) => {
+            Ok((key.try_into()?, Some(parse_number(count, "count")?)))
+        }
+        _ => Err(CommandError::InvalidArgument(
+            "Invalid key or count".to_string(),
+        )),
+    }
+}
+
+// Parses "<key> [<key> ...] <timeout>" shared by the blocking pop commands.
+fn extract_keys_and_timeout(
+    value: RespArray,
+) -> Result<(Vec<String>, Option<Duration>), CommandError> {
+    let mut args = extract_args(value, 1)?;
+    let timeout = match args.pop() {
+        Some(RespFrame::BulkString(timeout)) => parse_timeout(timeout)?,
+        _ => return Err(CommandError::InvalidArgument("Invalid timeout".to_string())),
+    };
+
+    let mut keys = Vec::with_capacity(args.len());
+    for arg in args {
+        match arg {
+            RespFrame::BulkString(key) => keys.push(key.try_into()?),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+    if keys.is_empty() {
+        return Err(CommandError::InvalidArgument(
+            "At least one key is required".to_string(),
+        ));
+    }
+    Ok((keys, timeout))
+}
+
+// Converts a time counted in units of `unit_ms` milliseconds, from now if `relative` or from
+// the unix epoch otherwise, into an absolute unix time in milliseconds, as taken by the expire
+// commands. Times in the past are kept as 0.
+fn parse_expire_time(n: i64, unit_ms: i64, relative: bool) -> Result<u64, CommandError> {
+    let base = if relative { now_ms() as i64 } else { 0 };
+    match n.checked_mul(unit_ms).and_then(|ms| ms.checked_add(base)) {
+        Some(at) => Ok(at.max(0) as u64),
+        None => Err(CommandError::InvalidArgument(
+            "Invalid expire time".to_string(),
+        )),
+    }
+}
+
+// Parses the "[NX | XX | GT | LT]" options of the expire commands.
+fn parse_expire_condition(
+    options: impl IntoIterator<Item = RespFrame>,
+) -> Result<ExpireCondition, CommandError> {
+    let mut condition = ExpireCondition::default();
+    for arg in options {
+        let option = match arg {
+            RespFrame::BulkString(option) => option.to_ascii_uppercase(),
+            _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+        };
+        match option.as_slice() {
+            b"NX" => condition.if_none = true,
+            b"XX" => condition.if_some = true,
+            b"GT" => condition.if_greater = true,
+            b"LT" => condition.if_less = true,
+            _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+        }
+    }
+    if condition.if_none && (condition.if_some || condition.if_greater || condition.if_less) {
+        return Err(CommandError::InvalidArgument(
+            "NX and XX, GT or LT options at the same time are not compatible".to_string(),
+        ));
+    }
+    if condition.if_greater && condition.if_less {
+        return Err(CommandError::InvalidArgument(
+            "GT and LT options at the same time are not compatible".to_string(),
+        ));
+    }
+    Ok(condition)
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
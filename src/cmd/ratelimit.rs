@@ -0,0 +1,130 @@
+use crate::{cmd::CommandError, RespArray, RespFrame};
+
+use super::{extract_args, parse_number, validate_command, CommandExecutor, RateLimitCheck};
+
+// Replies with [allowed, remaining, retry_after_ms], retry_after_ms is -1 if the request
+// was allowed or if it can never be (cost is larger than max_tokens).
+impl CommandExecutor for RateLimitCheck {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let ret = backend.ratelimit_check(self.key, self.max_tokens, self.refill_rate, self.cost);
+        let retry_after = ret
+            .retry_after
+            .map(|d| i64::try_from(d.as_micros().div_ceil(1000)).unwrap_or(i64::MAX))
+            .unwrap_or(-1);
+        RespArray::new([
+            RespFrame::Integer(ret.allowed as i64),
+            RespFrame::Integer(ret.remaining as i64),
+            RespFrame::Integer(retry_after),
+        ])
+        .into()
+    }
+}
+
+impl TryFrom<RespArray> for RateLimitCheck {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["RATELIMIT.CHECK"], None)?;
+        if !(4..=5).contains(&value.len()) {
+            return Err(CommandError::InvalidArgument(
+                "RATELIMIT.CHECK command must have 3 or 4 arguments".to_string(),
+            ));
+        }
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, max_tokens, refill_rate) = match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(max_tokens)),
+                Some(RespFrame::BulkString(refill_rate)),
+            ) => (
+                key.try_into()?,
+                parse_number::<u64>(max_tokens, "max tokens")?,
+                parse_number::<f64>(refill_rate, "refill rate")?,
+            ),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid key, max tokens or refill rate".to_string(),
+                ))
+            }
+        };
+        let cost = match args.next() {
+            Some(RespFrame::BulkString(cost)) => parse_number(cost, "cost")?,
+            Some(_) => return Err(CommandError::InvalidArgument("Invalid cost".to_string())),
+            None => 1,
+        };
+
+        if max_tokens == 0 {
+            return Err(CommandError::InvalidArgument(
+                "max tokens should be larger than 0".to_string(),
+            ));
+        }
+        if !(refill_rate.is_finite() && refill_rate > 0.0) {
+            return Err(CommandError::InvalidArgument(
+                "refill rate should be larger than 0".to_string(),
+            ));
+        }
+
+        Ok(RateLimitCheck {
+            key,
+            max_tokens,
+            refill_rate,
+            cost,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{Backend, RespArray, RespDecode, RespFrame};
+
+    use super::{CommandExecutor, RateLimitCheck};
+
+    #[test]
+    fn test_ratelimit_check_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$15\r\nratelimit.check\r\n$4\r\nuser\r\n$2\r\n10\r\n$3\r\n0.5\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: RateLimitCheck = frame.try_into()?;
+        assert_eq!(result.key, "user");
+        assert_eq!(result.max_tokens, 10);
+        assert_eq!(result.refill_rate, 0.5);
+        assert_eq!(result.cost, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratelimit_check_command() -> Result<()> {
+        let backend = Backend::new();
+        let check = |cost| RateLimitCheck {
+            key: "user".to_string(),
+            max_tokens: 2,
+            refill_rate: 0.001,
+            cost,
+        };
+
+        let ret = check(2).execute(&backend);
+        let RespFrame::Array(ret) = ret else {
+            panic!("expect an array reply");
+        };
+        assert_eq!(ret[0], RespFrame::Integer(1));
+        assert_eq!(ret[1], RespFrame::Integer(0));
+        assert_eq!(ret[2], RespFrame::Integer(-1));
+
+        let ret = check(1).execute(&backend);
+        let RespFrame::Array(ret) = ret else {
+            panic!("expect an array reply");
+        };
+        assert_eq!(ret[0], RespFrame::Integer(0));
+        assert!(matches!(ret[2], RespFrame::Integer(ms) if ms > 0));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{cmd::CommandError, RespArray, RespFrame};
+
+use super::{extract_args, validate_command, CommandExecutor, DbSize, FlushAll, FlushDb, RESP_OK};
+
+impl CommandExecutor for DbSize {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.size() as i64)
+    }
+}
+
+impl TryFrom<RespArray> for DbSize {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["DBSIZE"], Some(0))?;
+        Ok(DbSize)
+    }
+}
+
+impl CommandExecutor for FlushDb {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        backend.flush(self.asynchronous);
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for FlushDb {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["FLUSHDB"], None)?;
+
+        Ok(FlushDb {
+            asynchronous: extract_flush_mode(value)?,
+        })
+    }
+}
+
+// There is a single database, so FLUSHALL is the same as FLUSHDB.
+impl CommandExecutor for FlushAll {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        backend.flush(self.asynchronous);
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for FlushAll {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["FLUSHALL"], None)?;
+
+        Ok(FlushAll {
+            asynchronous: extract_flush_mode(value)?,
+        })
+    }
+}
+
+// Parses the optional "ASYNC | SYNC" argument, returns whether the flush is asynchronous.
+fn extract_flush_mode(value: RespArray) -> Result<bool, CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    let asynchronous = match args.next() {
+        None => false,
+        Some(RespFrame::BulkString(mode)) => match mode.to_ascii_uppercase().as_slice() {
+            b"ASYNC" => true,
+            b"SYNC" => false,
+            _ => return Err(CommandError::InvalidArgument("Invalid mode".to_string())),
+        },
+        _ => return Err(CommandError::InvalidArgument("Invalid mode".to_string())),
+    };
+    if args.next().is_some() {
+        return Err(CommandError::InvalidArgument(
+            "Too many arguments".to_string(),
+        ));
+    }
+    Ok(asynchronous)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{Backend, RespArray, RespDecode, RespFrame};
+
+    use super::{CommandExecutor, DbSize, FlushAll, FlushDb, RESP_OK};
+
+    #[test]
+    fn test_flushall_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$8\r\nflushall\r\n$5\r\nasync\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: FlushAll = frame.try_into()?;
+        assert!(result.asynchronous);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$7\r\nflushdb\r\n$4\r\nlazy\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(FlushDb::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dbsize_flush_commands() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("string".to_string(), b"value".to_vec());
+        backend.set_add("set".to_string(), vec!["member".to_string()]);
+        backend.bloom_add("bloom".to_string(), vec!["item".to_string()]);
+        assert_eq!(DbSize.execute(&backend), RespFrame::Integer(3));
+
+        let cmd = FlushDb {
+            asynchronous: false,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(DbSize.execute(&backend), RespFrame::Integer(0));
+
+        backend.string_set("string".to_string(), b"value".to_vec());
+        let cmd = FlushAll { asynchronous: true };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(DbSize.execute(&backend), RespFrame::Integer(0));
+        assert_eq!(backend.string_get("string"), None);
+
+        Ok(())
+    }
+}
@@ -1,6 +1,10 @@
-use crate::{cmd::CommandError, RespArray, RespFrame};
+use crate::{cmd::CommandError, BulkString, RespArray, RespFrame, SetOperation};
 
-use super::{extract_args, validate_command, CommandExecutor, SetAdd, SetIsMember, SetMembers};
+use super::{
+    extract_args, extract_keys, parse_number, validate_command, CommandExecutor, SetAdd, SetDiff,
+    SetDiffStore, SetInter, SetInterStore, SetIsMember, SetMembers, SetMove, SetRandMember,
+    SetRemove, SetUnion, SetUnionStore,
+};
 
 impl CommandExecutor for SetAdd {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
@@ -13,22 +17,7 @@ impl TryFrom<RespArray> for SetAdd {
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
         validate_command(&value, &["SADD"], None)?;
 
-        // Parse the key.
-        let mut args = extract_args(value, 1)?.into_iter();
-        let key = match args.next() {
-            Some(RespFrame::BulkString(key)) => key.try_into()?,
-            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
-        };
-
-        // Parse the members.
-        let mut members = Vec::new();
-        for arg in args {
-            match arg {
-                RespFrame::BulkString(member) => members.push(member.try_into()?),
-                _ => return Err(CommandError::InvalidArgument("Invalid member".to_string())),
-            }
-        }
-
+        let (key, members) = extract_key_and_members(value)?;
         Ok(SetAdd { key, members })
     }
 }
@@ -61,8 +50,7 @@ impl TryFrom<RespArray> for SetIsMember {
 
 impl CommandExecutor for SetMembers {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        let set = backend.set.get(&self.key);
-        match set {
+        match backend.set_members(&self.key) {
             Some(set) => {
                 let members: Vec<RespFrame> = set
                     .iter()
@@ -89,3 +77,574 @@ impl TryFrom<RespArray> for SetMembers {
         }
     }
 }
+
+impl CommandExecutor for SetRemove {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.set_remove(&self.key, &self.members) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SetRemove {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SREM"], None)?;
+
+        let (key, members) = extract_key_and_members(value)?;
+        Ok(SetRemove { key, members })
+    }
+}
+
+// Like HRANDFIELD: without a count, replies with a single member or null. A positive count
+// picks distinct members, up to the whole set, a negative one picks as many members as its
+// absolute value, possibly repeated.
+impl CommandExecutor for SetRandMember {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let Some(count) = self.count else {
+            return match backend.set_random_members(&self.key, 1, false).pop() {
+                Some(member) => BulkString::from(member).into(),
+                None => RespFrame::Null(crate::RespNull),
+            };
+        };
+        members_reply(backend.set_random_members(
+            &self.key,
+            count.unsigned_abs() as usize,
+            count < 0,
+        ))
+    }
+}
+
+impl TryFrom<RespArray> for SetRandMember {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SRANDMEMBER"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = match args.next() {
+            Some(RespFrame::BulkString(key)) => key.try_into()?,
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+        let count = match args.next() {
+            Some(RespFrame::BulkString(count)) => Some(parse_number(count, "count")?),
+            None => None,
+            _ => return Err(CommandError::InvalidArgument("Invalid count".to_string())),
+        };
+        if args.next().is_some() {
+            return Err(CommandError::InvalidArgument(
+                "Too many arguments".to_string(),
+            ));
+        }
+
+        Ok(SetRandMember { key, count })
+    }
+}
+
+impl CommandExecutor for SetInter {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        members_reply(backend.set_combine(SetOperation::Inter, &self.keys))
+    }
+}
+
+impl TryFrom<RespArray> for SetInter {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SINTER"], None)?;
+
+        Ok(SetInter {
+            keys: extract_keys(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for SetUnion {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        members_reply(backend.set_combine(SetOperation::Union, &self.keys))
+    }
+}
+
+impl TryFrom<RespArray> for SetUnion {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SUNION"], None)?;
+
+        Ok(SetUnion {
+            keys: extract_keys(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for SetDiff {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        members_reply(backend.set_combine(SetOperation::Diff, &self.keys))
+    }
+}
+
+impl TryFrom<RespArray> for SetDiff {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SDIFF"], None)?;
+
+        Ok(SetDiff {
+            keys: extract_keys(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for SetInterStore {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let len = backend.set_combine_store(SetOperation::Inter, &self.destination, &self.keys);
+        RespFrame::Integer(len as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SetInterStore {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SINTERSTORE"], None)?;
+
+        let (destination, keys) = extract_destination_and_keys(value)?;
+        Ok(SetInterStore { destination, keys })
+    }
+}
+
+impl CommandExecutor for SetUnionStore {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let len = backend.set_combine_store(SetOperation::Union, &self.destination, &self.keys);
+        RespFrame::Integer(len as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SetUnionStore {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SUNIONSTORE"], None)?;
+
+        let (destination, keys) = extract_destination_and_keys(value)?;
+        Ok(SetUnionStore { destination, keys })
+    }
+}
+
+impl CommandExecutor for SetDiffStore {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let len = backend.set_combine_store(SetOperation::Diff, &self.destination, &self.keys);
+        RespFrame::Integer(len as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SetDiffStore {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SDIFFSTORE"], None)?;
+
+        let (destination, keys) = extract_destination_and_keys(value)?;
+        Ok(SetDiffStore { destination, keys })
+    }
+}
+
+impl CommandExecutor for SetMove {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.set_move(&self.src, &self.dst, &self.member) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SetMove {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SMOVE"], Some(3))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(src)),
+                Some(RespFrame::BulkString(dst)),
+                Some(RespFrame::BulkString(member)),
+            ) => Ok(SetMove {
+                src: src.try_into()?,
+                dst: dst.try_into()?,
+                member: member.try_into()?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid source, destination or member".to_string(),
+            )),
+        }
+    }
+}
+
+fn members_reply(members: Vec<String>) -> RespFrame {
+    let members: Vec<RespFrame> = members
+        .into_iter()
+        .map(|member| BulkString::from(member).into())
+        .collect();
+    RespArray::new(members).into()
+}
+
+// Parses "<key> <member> [member ...]" shared by SADD and SREM.
+fn extract_key_and_members(value: RespArray) -> Result<(String, Vec<String>), CommandError> {
+    // Parse the key.
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = match args.next() {
+        Some(RespFrame::BulkString(key)) => key.try_into()?,
+        _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    };
+
+    // Parse the members.
+    let mut members = Vec::new();
+    for arg in args {
+        match arg {
+            RespFrame::BulkString(member) => members.push(member.try_into()?),
+            _ => return Err(CommandError::InvalidArgument("Invalid member".to_string())),
+        }
+    }
+
+    if members.is_empty() {
+        return Err(CommandError::InvalidArgument(
+            "At least one member is required".to_string(),
+        ));
+    }
+
+    Ok((key, members))
+}
+
+// Parses "<destination> <key> [key ...]" shared by the STORE variants.
+fn extract_destination_and_keys(value: RespArray) -> Result<(String, Vec<String>), CommandError> {
+    let mut keys = extract_keys(value)?;
+    if keys.len() < 2 {
+        return Err(CommandError::InvalidArgument(
+            "At least one key is required".to_string(),
+        ));
+    }
+    let destination = keys.remove(0);
+    Ok((destination, keys))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{BulkString, RespArray, RespDecode, RespFrame};
+
+    use super::{
+        CommandExecutor, SetDiff, SetDiffStore, SetInter, SetInterStore, SetMove, SetRandMember,
+        SetRemove, SetUnion, SetUnionStore,
+    };
+
+    #[test]
+    fn test_srem_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$4\r\nsrem\r\n$3\r\nset\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SetRemove = frame.try_into()?;
+        assert_eq!(result.key, "set");
+        assert_eq!(result.members, ["a", "b"]);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\nsrem\r\n$3\r\nset\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(SetRemove::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_srem_command() {
+        let backend = crate::Backend::new();
+        backend.set_add(
+            "set".to_string(),
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        );
+        backend.expire_at("set", u64::MAX, Default::default());
+
+        let cmd = SetRemove {
+            key: "set".to_string(),
+            members: vec!["a".to_string(), "b".to_string(), "x".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(backend.set_members("set").map(|s| s.len()), Some(1));
+
+        // The set is deleted along with its last member.
+        let cmd = SetRemove {
+            key: "set".to_string(),
+            members: vec!["c".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(!backend.exists("set"));
+        assert_eq!(backend.expire_time("set"), None);
+
+        let cmd = SetRemove {
+            key: "missing".to_string(),
+            members: vec!["a".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+    }
+
+    #[test]
+    fn test_srandmember_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$11\r\nsrandmember\r\n$3\r\nset\r\n$2\r\n-5\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SetRandMember = frame.try_into()?;
+        assert_eq!(result.key, "set");
+        assert_eq!(result.count, Some(-5));
+
+        for invalid in [
+            &b"*3\r\n$11\r\nsrandmember\r\n$3\r\nset\r\n$1\r\nx\r\n"[..],
+            b"*4\r\n$11\r\nsrandmember\r\n$3\r\nset\r\n$1\r\n1\r\n$1\r\n1\r\n",
+            b"*1\r\n$11\r\nsrandmember\r\n",
+        ] {
+            let mut buf = BytesMut::from(invalid);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(SetRandMember::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_srandmember_command() {
+        let backend = crate::Backend::new();
+        backend.set_add(
+            "set".to_string(),
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        );
+
+        let rand_member = |key: &str, count: Option<i64>| match (SetRandMember {
+            key: key.to_string(),
+            count,
+        })
+        .execute(&backend)
+        {
+            RespFrame::Array(items) => items.to_vec(),
+            frame => vec![frame],
+        };
+        let abc: Vec<RespFrame> = ["a", "b", "c"]
+            .into_iter()
+            .map(|m| BulkString::from(m).into())
+            .collect();
+
+        let member = rand_member("set", None);
+        assert!(member.len() == 1 && abc.contains(&member[0]));
+        assert_eq!(
+            rand_member("missing", None),
+            [RespFrame::Null(crate::RespNull)]
+        );
+
+        // A positive count returns distinct members, at most all of them.
+        let mut members = rand_member("set", Some(10));
+        members.sort_by_key(|m| format!("{:?}", m));
+        assert_eq!(members, abc);
+        assert_eq!(rand_member("set", Some(2)).len(), 2);
+        assert!(rand_member("set", Some(0)).is_empty());
+
+        // A negative count may repeat members.
+        let members = rand_member("set", Some(-10));
+        assert_eq!(members.len(), 10);
+        assert!(members.iter().all(|m| abc.contains(m)));
+        assert!(rand_member("missing", Some(-3)).is_empty());
+
+        // The set is left as is.
+        assert_eq!(backend.set_members("set").map(|s| s.len()), Some(3));
+    }
+
+    #[test]
+    fn test_sinter_sunion_sdiff_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nsinter\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SetInter = frame.try_into()?;
+        assert_eq!(result.keys, ["a", "b"]);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$6\r\nsunion\r\n$1\r\na\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SetUnion = frame.try_into()?;
+        assert_eq!(result.keys, ["a"]);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$5\r\nsdiff\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(SetDiff::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sinter_sunion_sdiff_commands() {
+        let backend = crate::Backend::new();
+        let members = |members: &[&str]| members.iter().map(|m| m.to_string()).collect();
+        backend.set_add("a".to_string(), members(&["1", "2", "3"]));
+        backend.set_add("b".to_string(), members(&["2", "3", "4"]));
+        backend.set_add("c".to_string(), members(&["3", "5"]));
+        let keys = |keys: &[&str]| keys.iter().map(|k| k.to_string()).collect();
+        let sorted = |frame: RespFrame| {
+            let RespFrame::Array(items) = frame else {
+                panic!("unexpected reply {:?}", frame);
+            };
+            let mut items = items.to_vec();
+            items.sort_by_key(|m| format!("{:?}", m));
+            items
+        };
+        let expected = |members: &[&str]| -> Vec<RespFrame> {
+            members
+                .iter()
+                .map(|m| BulkString::from(*m).into())
+                .collect()
+        };
+
+        let cmd = SetInter {
+            keys: keys(&["a", "b", "c"]),
+        };
+        assert_eq!(sorted(cmd.execute(&backend)), expected(&["3"]));
+        let cmd = SetInter {
+            keys: keys(&["a", "missing"]),
+        };
+        assert_eq!(sorted(cmd.execute(&backend)), expected(&[]));
+
+        let cmd = SetUnion {
+            keys: keys(&["a", "c", "missing"]),
+        };
+        assert_eq!(
+            sorted(cmd.execute(&backend)),
+            expected(&["1", "2", "3", "5"])
+        );
+
+        let cmd = SetDiff {
+            keys: keys(&["a", "b", "missing"]),
+        };
+        assert_eq!(sorted(cmd.execute(&backend)), expected(&["1"]));
+        let cmd = SetDiff {
+            keys: keys(&["missing", "a"]),
+        };
+        assert_eq!(sorted(cmd.execute(&backend)), expected(&[]));
+        let cmd = SetDiff { keys: keys(&["a"]) };
+        assert_eq!(sorted(cmd.execute(&backend)), expected(&["1", "2", "3"]));
+    }
+
+    #[test]
+    fn test_sinterstore_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$11\r\nsinterstore\r\n$3\r\ndst\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SetInterStore = frame.try_into()?;
+        assert_eq!(result.destination, "dst");
+        assert_eq!(result.keys, ["a", "b"]);
+
+        // A destination alone is not enough.
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$11\r\nsunionstore\r\n$3\r\ndst\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(SetUnionStore::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sinterstore_sunionstore_sdiffstore_commands() {
+        let backend = crate::Backend::new();
+        let members = |members: &[&str]| members.iter().map(|m| m.to_string()).collect();
+        backend.set_add("a".to_string(), members(&["1", "2", "3"]));
+        backend.set_add("b".to_string(), members(&["2", "3", "4"]));
+        let keys = |keys: &[&str]| keys.iter().map(|k| k.to_string()).collect();
+        let stored = |key: &str| {
+            let mut members: Vec<String> = backend
+                .set_members(key)
+                .map(|set| set.into_iter().collect())
+                .unwrap_or_default();
+            members.sort();
+            members
+        };
+
+        // Any existing value is replaced, along with its time to live.
+        backend.string_set("dst".to_string(), b"value".to_vec());
+        backend.expire_at("dst", u64::MAX, Default::default());
+        let cmd = SetInterStore {
+            destination: "dst".to_string(),
+            keys: keys(&["a", "b"]),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(stored("dst"), ["2", "3"]);
+        assert_eq!(backend.string_get("dst"), None);
+        assert_eq!(backend.expire_time("dst"), Some(None));
+
+        let cmd = SetUnionStore {
+            destination: "dst".to_string(),
+            keys: keys(&["a", "b", "missing"]),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(4));
+        assert_eq!(stored("dst"), ["1", "2", "3", "4"]);
+
+        // The destination may be one of the sources.
+        let cmd = SetDiffStore {
+            destination: "a".to_string(),
+            keys: keys(&["a", "b"]),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(stored("a"), ["1"]);
+
+        // An empty result deletes the destination.
+        let cmd = SetInterStore {
+            destination: "dst".to_string(),
+            keys: keys(&["a", "missing"]),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert!(!backend.exists("dst"));
+    }
+
+    #[test]
+    fn test_smove_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nsmove\r\n$3\r\nsrc\r\n$3\r\ndst\r\n$1\r\na\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SetMove = frame.try_into()?;
+        assert_eq!(result.src, "src");
+        assert_eq!(result.dst, "dst");
+        assert_eq!(result.member, "a");
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\nsmove\r\n$3\r\nsrc\r\n$3\r\ndst\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(SetMove::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_smove_command() {
+        let backend = crate::Backend::new();
+        backend.set_add("src".to_string(), vec!["a".to_string(), "b".to_string()]);
+        backend.expire_at("src", u64::MAX, Default::default());
+        let smove = |src: &str, dst: &str, member: &str| {
+            SetMove {
+                src: src.to_string(),
+                dst: dst.to_string(),
+                member: member.to_string(),
+            }
+            .execute(&backend)
+        };
+
+        // The destination is created if needed.
+        assert_eq!(smove("src", "dst", "a"), RespFrame::Integer(1));
+        assert_eq!(backend.set_is_member("dst", "a"), RespFrame::Integer(1));
+        assert_eq!(backend.set_is_member("src", "a"), RespFrame::Integer(0));
+
+        assert_eq!(smove("src", "dst", "x"), RespFrame::Integer(0));
+        assert_eq!(smove("missing", "dst", "a"), RespFrame::Integer(0));
+        assert_eq!(smove("src", "src", "b"), RespFrame::Integer(1));
+        assert_eq!(smove("src", "src", "x"), RespFrame::Integer(0));
+
+        // The source is deleted along with its last member.
+        assert_eq!(smove("src", "dst", "b"), RespFrame::Integer(1));
+        assert!(!backend.exists("src"));
+        assert_eq!(backend.expire_time("src"), None);
+        assert_eq!(backend.set_members("dst").map(|s| s.len()), Some(2));
+    }
+}
@@ -0,0 +1,402 @@
+use bytes::Bytes;
+
+use crate::{
+    cmd::CommandError, parse_score, Backend, BulkString, RespArray, RespFrame, RespNull,
+    SimpleError,
+};
+
+use super::{extract_args, parse_number, validate_command, CommandExecutor, Sort};
+
+// The elements are read first and the BY and GET keys looked up afterward, so unlike in
+// Redis, concurrent writes to those keys may be seen halfway through.
+impl CommandExecutor for Sort {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let mut elements: Vec<Bytes> = match backend.set_members(&self.key) {
+            Some(set) => set.into_iter().map(Bytes::from).collect(),
+            None => backend.list_range(&self.key, 0, -1),
+        };
+
+        // A BY pattern without `*` looks up the same key for every element, so there is
+        // nothing to sort by.
+        if self.by.as_deref().is_none_or(|by| by.contains('*')) {
+            elements = match self.sort_elements(backend, elements) {
+                Ok(elements) => elements,
+                Err(e) => return e.into(),
+            };
+        }
+
+        // Same clamping as Redis: a negative offset counts as 0, a negative count as all.
+        let (offset, count) = self.limit.unwrap_or((0, -1));
+        let start = (offset.max(0) as usize).min(elements.len());
+        let end = if count < 0 {
+            elements.len()
+        } else {
+            start.saturating_add(count as usize).min(elements.len())
+        };
+
+        let mut values = Vec::new();
+        for element in &elements[start..end] {
+            if self.get.is_empty() {
+                values.push(Some(element.to_vec()));
+            }
+            for pattern in &self.get {
+                values.push(lookup(backend, pattern, element));
+            }
+        }
+
+        // Missing values are stored as empty strings, like Redis does.
+        if let Some(dst) = &self.store {
+            let elements = values
+                .into_iter()
+                .map(|value| Bytes::from(value.unwrap_or_default()))
+                .collect();
+            return RespFrame::Integer(backend.list_store(dst, elements) as i64);
+        }
+        let ret = values
+            .into_iter()
+            .map(|value| match value {
+                Some(value) => BulkString::new(value).into(),
+                None => RespFrame::Null(RespNull),
+            })
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(ret).into()
+    }
+}
+
+impl Sort {
+    // Ties are broken by comparing the elements themselves, so the order is deterministic.
+    fn sort_elements(
+        &self,
+        backend: &Backend,
+        elements: Vec<Bytes>,
+    ) -> Result<Vec<Bytes>, SimpleError> {
+        let weighted = elements.into_iter().map(|element| {
+            let weight = match &self.by {
+                Some(by) => lookup(backend, by, &element),
+                None => Some(element.to_vec()),
+            };
+            (element, weight)
+        });
+        let mut elements: Vec<Bytes> = if self.alpha {
+            // Missing weights sort first.
+            let mut weighted: Vec<_> = weighted.collect();
+            weighted.sort_by(|(a, wa), (b, wb)| wa.cmp(wb).then_with(|| a.cmp(b)));
+            weighted.into_iter().map(|(element, _)| element).collect()
+        } else {
+            // Missing weights count as 0.
+            let mut scored = weighted
+                .map(|(element, weight)| {
+                    let score = match weight {
+                        Some(weight) => parse_score(&weight).map_err(|_| {
+                            SimpleError::new(
+                                "ERR One or more scores can't be converted into double",
+                            )
+                        })?,
+                        None => 0.0,
+                    };
+                    Ok((element, score))
+                })
+                .collect::<Result<Vec<_>, SimpleError>>()?;
+            scored.sort_by(|(a, sa), (b, sb)| sa.total_cmp(sb).then_with(|| a.cmp(b)));
+            scored.into_iter().map(|(element, _)| element).collect()
+        };
+        if self.desc {
+            elements.reverse();
+        }
+        Ok(elements)
+    }
+}
+
+// SORT key [BY pattern] [LIMIT offset count] [GET pattern ...] [ASC | DESC] [ALPHA]
+//   [STORE destination]
+impl TryFrom<RespArray> for Sort {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SORT"], None)?;
+
+        // Parse the key.
+        let mut args = extract_args(value, 1)?.into_iter();
+        let mut sort = match args.next() {
+            Some(RespFrame::BulkString(key)) => Sort {
+                key: key.try_into()?,
+                by: None,
+                limit: None,
+                get: Vec::new(),
+                desc: false,
+                alpha: false,
+                store: None,
+            },
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+
+        // Parse the options.
+        while let Some(arg) = args.next() {
+            let option = match arg {
+                RespFrame::BulkString(option) => option.to_ascii_uppercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                b"ASC" => sort.desc = false,
+                b"DESC" => sort.desc = true,
+                b"ALPHA" => sort.alpha = true,
+                b"BY" => match args.next() {
+                    Some(RespFrame::BulkString(pattern)) => sort.by = Some(pattern.try_into()?),
+                    _ => return Err(CommandError::InvalidArgument("Invalid BY".to_string())),
+                },
+                b"GET" => match args.next() {
+                    Some(RespFrame::BulkString(pattern)) => sort.get.push(pattern.try_into()?),
+                    _ => return Err(CommandError::InvalidArgument("Invalid GET".to_string())),
+                },
+                b"LIMIT" => match (args.next(), args.next()) {
+                    (Some(RespFrame::BulkString(offset)), Some(RespFrame::BulkString(count))) => {
+                        sort.limit = Some((
+                            parse_number(offset, "offset")?,
+                            parse_number(count, "count")?,
+                        ));
+                    }
+                    _ => return Err(CommandError::InvalidArgument("Invalid LIMIT".to_string())),
+                },
+                b"STORE" => match args.next() {
+                    Some(RespFrame::BulkString(dst)) => sort.store = Some(dst.try_into()?),
+                    _ => return Err(CommandError::InvalidArgument("Invalid STORE".to_string())),
+                },
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        Ok(sort)
+    }
+}
+
+// Resolves a BY or GET pattern for the element: `#` is the element itself, otherwise the
+// first `*` is replaced with it to get a string key, or a hash key and field if followed by
+// `->field`. None if the pattern has no `*`, or the key or field does not exist. Keys are
+// strings, so elements that are not valid UTF-8 are converted lossily to build them.
+fn lookup(backend: &Backend, pattern: &str, element: &[u8]) -> Option<Vec<u8>> {
+    if pattern == "#" {
+        return Some(element.to_vec());
+    }
+    let element = String::from_utf8_lossy(element);
+    let star = pattern.find('*')?;
+    let (prefix, rest) = (&pattern[..star], &pattern[star + 1..]);
+    match rest.find("->") {
+        Some(arrow) if arrow + 2 < rest.len() => {
+            let key = format!("{}{}{}", prefix, element, &rest[..arrow]);
+            match backend.hash_get(&key, &rest[arrow + 2..])? {
+                RespFrame::BulkString(BulkString::Normal(value)) => Some(value),
+                RespFrame::Integer(n) => Some(n.to_string().into_bytes()),
+                _ => None,
+            }
+        }
+        _ => backend.string_get(&format!("{}{}{}", prefix, element, rest)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::{Bytes, BytesMut};
+
+    use crate::{
+        Backend, BulkString, ListEnd, RespArray, RespDecode, RespFrame, RespNull, SimpleError,
+    };
+
+    use super::{CommandExecutor, Sort};
+
+    fn sort(key: &str) -> Sort {
+        Sort {
+            key: key.to_string(),
+            by: None,
+            limit: None,
+            get: Vec::new(),
+            desc: false,
+            alpha: false,
+            store: None,
+        }
+    }
+
+    fn bulk_strings<'a>(values: impl IntoIterator<Item = &'a str>) -> RespFrame {
+        RespArray::new(
+            values
+                .into_iter()
+                .map(|v| BulkString::new(v).into())
+                .collect::<Vec<RespFrame>>(),
+        )
+        .into()
+    }
+
+    #[test]
+    fn test_sort_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*12\r\n$4\r\nsort\r\n$1\r\ns\r\n$2\r\nby\r\n$3\r\nw_*\r\n$5\r\nlimit\r\n$1\r\n1\r\n$1\r\n2\r\n$3\r\nget\r\n$1\r\n#\r\n$4\r\ndesc\r\n$5\r\nalpha\r\n$3\r\nGET\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        // The second GET has no pattern.
+        assert!(Sort::try_from(frame).is_err());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*11\r\n$4\r\nsort\r\n$1\r\ns\r\n$2\r\nby\r\n$3\r\nw_*\r\n$5\r\nlimit\r\n$1\r\n1\r\n$1\r\n2\r\n$3\r\nget\r\n$1\r\n#\r\n$4\r\ndesc\r\n$5\r\nalpha\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Sort = frame.try_into()?;
+        assert_eq!(result.key, "s");
+        assert_eq!(result.by.as_deref(), Some("w_*"));
+        assert_eq!(result.limit, Some((1, 2)));
+        assert_eq!(result.get, vec!["#".to_string()]);
+        assert!(result.desc);
+        assert!(result.alpha);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$4\r\nsort\r\n$1\r\ns\r\n$5\r\nstore\r\n$1\r\nd\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Sort = frame.try_into()?;
+        assert_eq!(result.store.as_deref(), Some("d"));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$4\r\nsort\r\n$1\r\ns\r\n$5\r\nstore\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Sort::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_command() {
+        let backend = Backend::new();
+        let members = ["3", "10", "1", "2.5"].map(String::from).to_vec();
+        backend.set_add("s".to_string(), members);
+
+        assert_eq!(
+            sort("s").execute(&backend),
+            bulk_strings(["1", "2.5", "3", "10"])
+        );
+        assert_eq!(sort("missing").execute(&backend), bulk_strings([]));
+
+        let cmd = Sort {
+            alpha: true,
+            desc: true,
+            ..sort("s")
+        };
+        assert_eq!(cmd.execute(&backend), bulk_strings(["3", "2.5", "10", "1"]));
+
+        let cmd = Sort {
+            limit: Some((1, 2)),
+            ..sort("s")
+        };
+        assert_eq!(cmd.execute(&backend), bulk_strings(["2.5", "3"]));
+        let cmd = Sort {
+            limit: Some((-5, -1)),
+            ..sort("s")
+        };
+        assert_eq!(cmd.execute(&backend), bulk_strings(["1", "2.5", "3", "10"]));
+        let cmd = Sort {
+            limit: Some((10, 1)),
+            ..sort("s")
+        };
+        assert_eq!(cmd.execute(&backend), bulk_strings([]));
+
+        backend.set_add("words".to_string(), vec!["b".to_string(), "a".to_string()]);
+        assert_eq!(
+            sort("words").execute(&backend),
+            SimpleError::new("ERR One or more scores can't be converted into double").into()
+        );
+        let cmd = Sort {
+            alpha: true,
+            ..sort("words")
+        };
+        assert_eq!(cmd.execute(&backend), bulk_strings(["a", "b"]));
+    }
+
+    #[test]
+    fn test_sort_by_and_get() {
+        let backend = Backend::new();
+        let members = ["a", "b", "c"].map(String::from).to_vec();
+        backend.set_add("s".to_string(), members);
+        backend.string_set("w_a".to_string(), b"3".to_vec());
+        backend.string_set("w_b".to_string(), b"1".to_vec());
+        backend.hash_set(
+            "h_a".to_string(),
+            "name".to_string(),
+            BulkString::new("alice").into(),
+        );
+        backend.hash_set(
+            "h_b".to_string(),
+            "name".to_string(),
+            BulkString::new("bob").into(),
+        );
+
+        // c has no weight, which counts as 0.
+        let cmd = Sort {
+            by: Some("w_*".to_string()),
+            ..sort("s")
+        };
+        assert_eq!(cmd.execute(&backend), bulk_strings(["c", "b", "a"]));
+
+        let cmd = Sort {
+            by: Some("h_*->name".to_string()),
+            alpha: true,
+            desc: true,
+            get: vec!["#".to_string(), "h_*->name".to_string(), "w_*".to_string()],
+            ..sort("s")
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new(vec![
+                BulkString::new("b").into(),
+                BulkString::new("bob").into(),
+                BulkString::new("1").into(),
+                BulkString::new("a").into(),
+                BulkString::new("alice").into(),
+                BulkString::new("3").into(),
+                BulkString::new("c").into(),
+                RespFrame::Null(RespNull),
+                RespFrame::Null(RespNull),
+            ])
+            .into()
+        );
+
+        // Without `*` there is nothing to sort by, the elements are only counted.
+        let cmd = Sort {
+            by: Some("nosort".to_string()),
+            ..sort("s")
+        };
+        match cmd.execute(&backend) {
+            RespFrame::Array(array) => assert_eq!(array.len(), 3),
+            frame => panic!("unexpected reply {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn test_sort_list_and_store() {
+        let backend = Backend::new();
+        let elements = ["3", "1", "2"].map(Bytes::from).to_vec();
+        backend.list_push("l".to_string(), elements, ListEnd::Right);
+        assert_eq!(sort("l").execute(&backend), bulk_strings(["1", "2", "3"]));
+
+        // dst is replaced whatever its type, and missing GET values are stored as empty strings.
+        backend.string_set("w_1".to_string(), b"10".to_vec());
+        backend.set_add("dst".to_string(), vec!["x".to_string()]);
+        let cmd = Sort {
+            get: vec!["w_*".to_string()],
+            desc: true,
+            store: Some("dst".to_string()),
+            ..sort("l")
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(3));
+        assert!(backend.set_members("dst").is_none());
+        assert_eq!(
+            backend.list_range("dst", 0, -1),
+            vec![Bytes::new(), Bytes::new(), Bytes::from("10")]
+        );
+
+        // An empty result deletes dst.
+        let cmd = Sort {
+            store: Some("dst".to_string()),
+            ..sort("missing")
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert!(!backend.exists("dst"));
+    }
+}
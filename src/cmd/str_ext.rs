@@ -0,0 +1,122 @@
+use crate::{cmd::CommandError, BulkString, RespArray, RespFrame};
+
+use super::{
+    extract_args, parse_number, validate_command, CommandExecutor, StringCharLen, StringCharRange,
+};
+
+impl CommandExecutor for StringCharLen {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.string_char_len(&self.key) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StringCharLen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["STR.LEN"], Some(1))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(StringCharLen {
+                key: key.try_into()?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl CommandExecutor for StringCharRange {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.string_get_char_range(&self.key, self.start, self.end) {
+            Ok(s) => BulkString::new(s).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StringCharRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["STR.SUBSTR"], Some(3))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(start)),
+                Some(RespFrame::BulkString(end)),
+            ) => Ok(StringCharRange {
+                key: key.try_into()?,
+                start: parse_number(start, "start")?,
+                end: parse_number(end, "end")?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, start or end".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{Backend, BackendError, RespArray, RespDecode, RespFrame};
+
+    use super::{CommandExecutor, StringCharLen, StringCharRange};
+
+    #[test]
+    fn test_str_substr_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$10\r\nstr.substr\r\n$5\r\nhello\r\n$1\r\n1\r\n$2\r\n-1\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StringCharRange = frame.try_into()?;
+        assert_eq!(result.key, "hello");
+        assert_eq!(result.start, 1);
+        assert_eq!(result.end, -1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_str_len_substr_commands() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("hello".to_string(), "héllo wörld".as_bytes().to_vec());
+
+        let cmd = StringCharLen {
+            key: "hello".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(11));
+
+        for (start, end, expected) in [
+            (1, 4, "éllo"),
+            (-5, -1, "wörld"),
+            (-100, 0, "h"),
+            (3, 1, ""),
+        ] {
+            let cmd = StringCharRange {
+                key: "hello".to_string(),
+                start,
+                end,
+            };
+            assert_eq!(
+                cmd.execute(&backend),
+                RespFrame::BulkString(expected.into())
+            );
+        }
+
+        backend.string_set("binary".to_string(), vec![0xff, 0xfe]);
+        let cmd = StringCharLen {
+            key: "binary".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), BackendError::NotUtf8.into());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,2248 @@
+use std::iter::Peekable;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::{
+    cmd::CommandError, now_ms, BulkString, RespArray, RespFrame, RespNull, SimpleString,
+    StreamAddId, StreamClaimOptions, StreamFields, StreamId, StreamTrimOptions, StreamTrimStrategy,
+};
+
+use super::{
+    extract_args, extract_key, parse_number, validate_command, BlockingCommand, CommandExecutor,
+    StreamAck, StreamAdd, StreamAutoClaim, StreamClaim, StreamDelete, StreamGroupCreate,
+    StreamGroupCreateConsumer, StreamGroupDeleteConsumer, StreamGroupDestroy, StreamGroupSetId,
+    StreamInfoConsumers, StreamInfoGroups, StreamInfoStream, StreamLen, StreamPending, StreamRange,
+    StreamRead, StreamReadGroup, StreamRevRange, StreamTrim,
+};
+
+// The COUNT of XAUTOCLAIM when not given, same default as Redis.
+const AUTO_CLAIM_DEFAULT_COUNT: usize = 100;
+
+// The LIMIT of approximate trimming when not given, 100 nodes of entries like in Redis.
+const TRIM_DEFAULT_LIMIT: usize = 10_000;
+
+impl CommandExecutor for StreamAdd {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.stream_add(
+            self.key,
+            self.id,
+            self.fields,
+            self.no_create,
+            self.trim.as_ref(),
+        ) {
+            Ok(Some(id)) => BulkString::new(id.to_string()).into(),
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StreamAdd {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XADD"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+        let key = match args.next() {
+            Some(RespFrame::BulkString(key)) => key.try_into()?,
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+
+        // The options come first, up to the ID.
+        let (mut no_create, mut strategy, mut limit) = (false, None, None);
+        let id = loop {
+            match args.next() {
+                Some(RespFrame::BulkString(arg)) => match arg.to_ascii_uppercase().as_slice() {
+                    b"NOMKSTREAM" => no_create = true,
+                    option @ (b"MAXLEN" | b"MINID") => {
+                        strategy = Some(parse_trim_strategy(option, &mut args)?)
+                    }
+                    b"LIMIT" => limit = Some(parse_trim_limit(args.next())?),
+                    _ => break parse_add_id(&arg)?,
+                },
+                _ => return Err(CommandError::InvalidArgument("Invalid ID".to_string())),
+            }
+        };
+        let trim = match strategy {
+            Some(strategy) => Some(trim_options(strategy, limit)?),
+            None if limit.is_some() => return Err(missing_trim_strategy()),
+            None => None,
+        };
+
+        // Fields and values may be any binary data.
+        let mut fields = Vec::new();
+        while let Some(field) = args.next() {
+            match (field, args.next()) {
+                (
+                    RespFrame::BulkString(BulkString::Normal(field)),
+                    Some(RespFrame::BulkString(BulkString::Normal(value))),
+                ) => fields.push((field.into(), value.into())),
+                _ => {
+                    return Err(CommandError::InvalidArgument(
+                        "Invalid field or value".to_string(),
+                    ))
+                }
+            }
+        }
+        if fields.is_empty() {
+            return Err(CommandError::InvalidArgument(
+                "At least one field is required".to_string(),
+            ));
+        }
+
+        Ok(StreamAdd {
+            key,
+            id,
+            fields,
+            no_create,
+            trim,
+        })
+    }
+}
+
+impl CommandExecutor for StreamLen {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.stream_len(&self.key) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for StreamLen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XLEN"], Some(1))?;
+
+        Ok(StreamLen {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for StreamRange {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let entries = backend.stream_range(&self.key, self.start, self.end, false, self.count);
+        entries_reply(entries)
+    }
+}
+
+impl TryFrom<RespArray> for StreamRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XRANGE"], None)?;
+
+        let (key, start, end, count) = extract_range_args(value)?;
+        Ok(StreamRange {
+            key,
+            start: parse_range_bound(&start, true)?,
+            end: parse_range_bound(&end, false)?,
+            count,
+        })
+    }
+}
+
+impl CommandExecutor for StreamRevRange {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let entries = backend.stream_range(&self.key, self.start, self.end, true, self.count);
+        entries_reply(entries)
+    }
+}
+
+impl TryFrom<RespArray> for StreamRevRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XREVRANGE"], None)?;
+
+        // The end comes first.
+        let (key, end, start, count) = extract_range_args(value)?;
+        Ok(StreamRevRange {
+            key,
+            start: parse_range_bound(&start, true)?,
+            end: parse_range_bound(&end, false)?,
+            count,
+        })
+    }
+}
+
+impl StreamRead {
+    // Without BLOCK, XREAD is a plain command replying right away.
+    pub fn is_blocking(&self) -> bool {
+        self.block
+    }
+}
+
+impl BlockingCommand for StreamRead {
+    fn keys(&self) -> Vec<String> {
+        self.keys.clone()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    // Replies with the keys that have entries after their ID, along with the entries.
+    fn try_execute(&self, backend: &crate::Backend) -> Option<RespFrame> {
+        let after = self.after.get_or_init(|| {
+            self.keys
+                .iter()
+                .zip(&self.ids)
+                .map(|(key, id)| id.unwrap_or_else(|| backend.stream_last_id(key)))
+                .collect()
+        });
+
+        let mut streams = Vec::new();
+        for (key, after) in self.keys.iter().zip(after) {
+            let Some(start) = after.next() else {
+                continue;
+            };
+            let entries = backend.stream_range(key, start, StreamId::MAX, false, self.count);
+            if !entries.is_empty() {
+                let stream = vec![BulkString::new(key.as_str()).into(), entries_reply(entries)];
+                streams.push(RespArray::new(stream).into());
+            }
+        }
+        (!streams.is_empty()).then(|| RespArray::new(streams).into())
+    }
+}
+
+impl CommandExecutor for StreamRead {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        self.try_execute(backend)
+            .unwrap_or_else(|| self.timeout_reply())
+    }
+}
+
+impl TryFrom<RespArray> for StreamRead {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XREAD"], None)?;
+
+        // The options come first, up to STREAMS.
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (mut count, mut block, mut timeout) = (None, false, None);
+        loop {
+            let option = match args.next() {
+                Some(RespFrame::BulkString(option)) => option.to_ascii_uppercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            if option.as_slice() == b"STREAMS" {
+                break;
+            }
+            match (option.as_slice(), args.next()) {
+                (b"COUNT", Some(RespFrame::BulkString(n))) => count = parse_read_count(n)?,
+                (b"BLOCK", Some(RespFrame::BulkString(ms))) => {
+                    block = true;
+                    timeout = parse_block_timeout(ms)?;
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        let (keys, ids) = extract_streams(args.collect(), "XREAD", "$")?;
+
+        Ok(StreamRead {
+            keys,
+            ids,
+            count,
+            block,
+            timeout,
+            after: OnceLock::new(),
+        })
+    }
+}
+
+impl CommandExecutor for StreamGroupCreate {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.stream_group_create(self.key, self.group, self.last_id, self.create) {
+            Ok(()) => SimpleString::new("OK").into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StreamGroupCreate {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = extract_group_args(value, "CREATE")?;
+        let (key, group) = (
+            next_string(&mut args, "key")?,
+            next_string(&mut args, "group")?,
+        );
+        let last_id = match args.next() {
+            Some(RespFrame::BulkString(id)) => parse_group_id(&id)?,
+            _ => return Err(invalid_id()),
+        };
+
+        let mut create = false;
+        for arg in args {
+            match arg {
+                RespFrame::BulkString(option) if option.eq_ignore_ascii_case(b"MKSTREAM") => {
+                    create = true
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        Ok(StreamGroupCreate {
+            key,
+            group,
+            last_id,
+            create,
+        })
+    }
+}
+
+impl CommandExecutor for StreamGroupDestroy {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.stream_group_destroy(&self.key, &self.group) {
+            Ok(destroyed) => RespFrame::Integer(destroyed as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StreamGroupDestroy {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = extract_group_args(value, "DESTROY")?;
+        let (key, group) = (
+            next_string(&mut args, "key")?,
+            next_string(&mut args, "group")?,
+        );
+        no_more_args(args)?;
+
+        Ok(StreamGroupDestroy { key, group })
+    }
+}
+
+impl CommandExecutor for StreamGroupCreateConsumer {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.stream_group_create_consumer(&self.key, &self.group, &self.consumer) {
+            Ok(created) => RespFrame::Integer(created as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StreamGroupCreateConsumer {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, group, consumer) = extract_consumer_args(value, "CREATECONSUMER")?;
+        Ok(StreamGroupCreateConsumer {
+            key,
+            group,
+            consumer,
+        })
+    }
+}
+
+impl CommandExecutor for StreamGroupDeleteConsumer {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.stream_group_delete_consumer(&self.key, &self.group, &self.consumer) {
+            Ok(pending) => RespFrame::Integer(pending as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StreamGroupDeleteConsumer {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, group, consumer) = extract_consumer_args(value, "DELCONSUMER")?;
+        Ok(StreamGroupDeleteConsumer {
+            key,
+            group,
+            consumer,
+        })
+    }
+}
+
+impl CommandExecutor for StreamGroupSetId {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.stream_group_set_id(&self.key, &self.group, self.last_id) {
+            Ok(()) => SimpleString::new("OK").into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StreamGroupSetId {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = extract_group_args(value, "SETID")?;
+        let (key, group) = (
+            next_string(&mut args, "key")?,
+            next_string(&mut args, "group")?,
+        );
+        let last_id = match args.next() {
+            Some(RespFrame::BulkString(id)) => parse_group_id(&id)?,
+            _ => return Err(invalid_id()),
+        };
+        no_more_args(args)?;
+
+        Ok(StreamGroupSetId {
+            key,
+            group,
+            last_id,
+        })
+    }
+}
+
+impl StreamReadGroup {
+    // Only reading new entries blocks, reading the pending ones replies right away.
+    pub fn is_blocking(&self) -> bool {
+        self.block && self.ids.iter().all(Option::is_none)
+    }
+}
+
+impl BlockingCommand for StreamReadGroup {
+    fn keys(&self) -> Vec<String> {
+        self.keys.clone()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    // Replies with the keys that have new entries, along with the entries, and with every key
+    // whose pending entries are read. Fails right away if a group does not exist.
+    fn try_execute(&self, backend: &crate::Backend) -> Option<RespFrame> {
+        let mut streams = Vec::new();
+        for (key, after) in self.keys.iter().zip(&self.ids) {
+            let entries = match backend.stream_read_group(
+                key,
+                &self.group,
+                &self.consumer,
+                *after,
+                self.count,
+                self.no_ack,
+            ) {
+                Ok(entries) => entries,
+                Err(e) => return Some(e.into()),
+            };
+            if after.is_none() && entries.is_empty() {
+                continue;
+            }
+            let entries: Vec<RespFrame> = entries
+                .into_iter()
+                .map(|(id, fields)| entry_reply(id, fields))
+                .collect();
+            let stream = vec![
+                BulkString::new(key.as_str()).into(),
+                RespArray::new(entries).into(),
+            ];
+            streams.push(RespArray::new(stream).into());
+        }
+        (!streams.is_empty()).then(|| RespArray::new(streams).into())
+    }
+}
+
+impl CommandExecutor for StreamReadGroup {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        self.try_execute(backend)
+            .unwrap_or_else(|| self.timeout_reply())
+    }
+}
+
+impl TryFrom<RespArray> for StreamReadGroup {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XREADGROUP"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(option)) if option.eq_ignore_ascii_case(b"GROUP") => {}
+            _ => return Err(CommandError::InvalidArgument("Missing GROUP".to_string())),
+        }
+        let group = next_string(&mut args, "group")?;
+        let consumer = next_string(&mut args, "consumer")?;
+
+        // The options come first, up to STREAMS.
+        let (mut count, mut no_ack, mut block, mut timeout) = (None, false, false, None);
+        loop {
+            let option = match args.next() {
+                Some(RespFrame::BulkString(option)) => option.to_ascii_uppercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                b"STREAMS" => break,
+                b"NOACK" => {
+                    no_ack = true;
+                    continue;
+                }
+                _ => {}
+            }
+            match (option.as_slice(), args.next()) {
+                (b"COUNT", Some(RespFrame::BulkString(n))) => count = parse_read_count(n)?,
+                (b"BLOCK", Some(RespFrame::BulkString(ms))) => {
+                    block = true;
+                    timeout = parse_block_timeout(ms)?;
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        let (keys, ids) = extract_streams(args.collect(), "XREADGROUP", ">")?;
+
+        Ok(StreamReadGroup {
+            group,
+            consumer,
+            keys,
+            ids,
+            count,
+            no_ack,
+            block,
+            timeout,
+        })
+    }
+}
+
+impl CommandExecutor for StreamAck {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.stream_ack(&self.key, &self.group, &self.ids) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for StreamAck {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XACK"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, group) = (
+            next_string(&mut args, "key")?,
+            next_string(&mut args, "group")?,
+        );
+        let ids = args
+            .map(|id| match id {
+                RespFrame::BulkString(id) => parse_full_id(&id),
+                _ => Err(invalid_id()),
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        if ids.is_empty() {
+            return Err(CommandError::InvalidArgument(
+                "At least one ID is required".to_string(),
+            ));
+        }
+
+        Ok(StreamAck { key, group, ids })
+    }
+}
+
+impl CommandExecutor for StreamPending {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let Some((start, end, count)) = self.range else {
+            let summary = match backend.stream_pending_summary(&self.key, &self.group) {
+                Ok(summary) => summary,
+                Err(e) => return e.into(),
+            };
+            let (first, last) = match summary.range {
+                Some((first, last)) => (
+                    BulkString::new(first.to_string()).into(),
+                    BulkString::new(last.to_string()).into(),
+                ),
+                None => (RespFrame::Null(RespNull), RespFrame::Null(RespNull)),
+            };
+            // The counts of the consumers are replied as strings, like in Redis.
+            let consumers: Vec<RespFrame> = summary
+                .consumers
+                .into_iter()
+                .map(|(name, count)| {
+                    RespArray::new([
+                        BulkString::new(name).into(),
+                        BulkString::new(count.to_string()).into(),
+                    ])
+                    .into()
+                })
+                .collect();
+            let consumers = match consumers.is_empty() {
+                true => RespArray::Null,
+                false => RespArray::new(consumers),
+            };
+            return RespArray::new([
+                RespFrame::Integer(summary.count as i64),
+                first,
+                last,
+                consumers.into(),
+            ])
+            .into();
+        };
+
+        match backend.stream_pending(
+            &self.key,
+            &self.group,
+            start,
+            end,
+            count,
+            self.consumer.as_deref(),
+            self.min_idle,
+        ) {
+            Ok(entries) => {
+                let entries: Vec<RespFrame> = entries
+                    .into_iter()
+                    .map(|entry| {
+                        RespArray::new([
+                            BulkString::new(entry.id.to_string()).into(),
+                            BulkString::new(entry.consumer).into(),
+                            RespFrame::Integer(entry.idle as i64),
+                            RespFrame::Integer(entry.delivery_count as i64),
+                        ])
+                        .into()
+                    })
+                    .collect();
+                RespArray::new(entries).into()
+            }
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StreamPending {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XPENDING"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+        let (key, group) = (
+            next_string(&mut args, "key")?,
+            next_string(&mut args, "group")?,
+        );
+        if args.peek().is_none() {
+            return Ok(StreamPending {
+                key,
+                group,
+                range: None,
+                min_idle: 0,
+                consumer: None,
+            });
+        }
+
+        let mut min_idle = 0;
+        let has_idle = match args.peek() {
+            Some(RespFrame::BulkString(option)) => option.eq_ignore_ascii_case(b"IDLE"),
+            _ => false,
+        };
+        if has_idle {
+            args.next();
+            min_idle = match args.next() {
+                Some(RespFrame::BulkString(ms)) => parse_clamped(ms, "min-idle-time")?,
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+        }
+        let range = match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(start)),
+                Some(RespFrame::BulkString(end)),
+                Some(RespFrame::BulkString(count)),
+            ) => (
+                parse_range_bound(&start, true)?,
+                parse_range_bound(&end, false)?,
+                parse_clamped(count, "count")? as usize,
+            ),
+            _ => return Err(CommandError::InvalidArgument("Invalid range".to_string())),
+        };
+        let consumer = match args.next() {
+            Some(RespFrame::BulkString(consumer)) => Some(consumer.try_into()?),
+            Some(_) => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid consumer".to_string(),
+                ))
+            }
+            None => None,
+        };
+        no_more_args(args)?;
+
+        Ok(StreamPending {
+            key,
+            group,
+            range: Some(range),
+            min_idle,
+            consumer,
+        })
+    }
+}
+
+impl CommandExecutor for StreamClaim {
+    fn execute(mut self, backend: &crate::Backend) -> RespFrame {
+        if let (None, Some(idle)) = (self.options.delivered_at, self.idle) {
+            self.options.delivered_at = Some(now_ms().saturating_sub(idle));
+        }
+        match backend.stream_claim(
+            &self.key,
+            &self.group,
+            &self.consumer,
+            &self.ids,
+            &self.options,
+        ) {
+            Ok(entries) => claimed_reply(entries, self.options.just_id),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StreamClaim {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XCLAIM"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+        let key = next_string(&mut args, "key")?;
+        let group = next_string(&mut args, "group")?;
+        let consumer = next_string(&mut args, "consumer")?;
+        let mut options = StreamClaimOptions {
+            min_idle: match args.next() {
+                Some(RespFrame::BulkString(ms)) => parse_clamped(ms, "min-idle-time")?,
+                _ => {
+                    return Err(CommandError::InvalidArgument(
+                        "Invalid min-idle-time".to_string(),
+                    ))
+                }
+            },
+            ..Default::default()
+        };
+
+        // The IDs, up to the first option.
+        let mut ids = Vec::new();
+        while let Some(RespFrame::BulkString(id)) = args.peek() {
+            match parse_full_id(id) {
+                Ok(id) => ids.push(id),
+                Err(_) => break,
+            }
+            args.next();
+        }
+        if ids.is_empty() {
+            return Err(invalid_id());
+        }
+
+        let mut idle = None;
+        while let Some(option) = args.next() {
+            let option = match option {
+                RespFrame::BulkString(option) => option.to_ascii_uppercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                b"FORCE" => {
+                    options.force = true;
+                    continue;
+                }
+                b"JUSTID" => {
+                    options.just_id = true;
+                    continue;
+                }
+                _ => {}
+            }
+            match (option.as_slice(), args.next()) {
+                (b"IDLE", Some(RespFrame::BulkString(ms))) => {
+                    idle = Some(parse_clamped(ms, "IDLE")?)
+                }
+                (b"TIME", Some(RespFrame::BulkString(ms))) => {
+                    options.delivered_at = Some(parse_clamped(ms, "TIME")?)
+                }
+                (b"RETRYCOUNT", Some(RespFrame::BulkString(n))) => {
+                    options.retry_count = Some(parse_clamped(n, "RETRYCOUNT")?)
+                }
+                (b"LASTID", Some(RespFrame::BulkString(id))) => {
+                    options.last_id = Some(parse_full_id(&id)?)
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        Ok(StreamClaim {
+            key,
+            group,
+            consumer,
+            ids,
+            options,
+            idle,
+        })
+    }
+}
+
+impl CommandExecutor for StreamAutoClaim {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.stream_auto_claim(
+            &self.key,
+            &self.group,
+            &self.consumer,
+            self.start,
+            self.count,
+            &self.options,
+        ) {
+            Ok(result) => {
+                let deleted: Vec<RespFrame> = result
+                    .deleted
+                    .into_iter()
+                    .map(|id| BulkString::new(id.to_string()).into())
+                    .collect();
+                RespArray::new([
+                    BulkString::new(result.next.to_string()).into(),
+                    claimed_reply(result.claimed, self.options.just_id),
+                    RespArray::new(deleted).into(),
+                ])
+                .into()
+            }
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StreamAutoClaim {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XAUTOCLAIM"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = next_string(&mut args, "key")?;
+        let group = next_string(&mut args, "group")?;
+        let consumer = next_string(&mut args, "consumer")?;
+        let mut options = StreamClaimOptions::default();
+        let start = match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(ms)), Some(RespFrame::BulkString(start))) => {
+                options.min_idle = parse_clamped(ms, "min-idle-time")?;
+                parse_range_bound(&start, true)?
+            }
+            _ => return Err(invalid_id()),
+        };
+
+        let mut count = AUTO_CLAIM_DEFAULT_COUNT;
+        while let Some(option) = args.next() {
+            let option = match option {
+                RespFrame::BulkString(option) => option.to_ascii_uppercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                b"JUSTID" => options.just_id = true,
+                b"COUNT" => {
+                    let n: i64 = match args.next() {
+                        Some(RespFrame::BulkString(n)) => parse_number(n, "count")?,
+                        _ => {
+                            return Err(CommandError::InvalidArgument("Invalid count".to_string()))
+                        }
+                    };
+                    if n <= 0 {
+                        return Err(CommandError::InvalidArgument(
+                            "COUNT must be > 0".to_string(),
+                        ));
+                    }
+                    count = n as usize;
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        Ok(StreamAutoClaim {
+            key,
+            group,
+            consumer,
+            start,
+            count,
+            options,
+        })
+    }
+}
+
+impl CommandExecutor for StreamTrim {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.stream_trim(&self.key, &self.trim) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for StreamTrim {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XTRIM"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+        let key = next_string(&mut args, "key")?;
+        let (mut strategy, mut limit) = (None, None);
+        while let Some(option) = args.next() {
+            let option = match option {
+                RespFrame::BulkString(option) => option.to_ascii_uppercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                option @ (b"MAXLEN" | b"MINID") => {
+                    strategy = Some(parse_trim_strategy(option, &mut args)?)
+                }
+                b"LIMIT" => limit = Some(parse_trim_limit(args.next())?),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+        let strategy = strategy.ok_or_else(missing_trim_strategy)?;
+
+        Ok(StreamTrim {
+            key,
+            trim: trim_options(strategy, limit)?,
+        })
+    }
+}
+
+impl CommandExecutor for StreamDelete {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.stream_delete(&self.key, &self.ids) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for StreamDelete {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XDEL"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = next_string(&mut args, "key")?;
+        let ids = args
+            .map(|id| match id {
+                RespFrame::BulkString(id) => parse_full_id(&id),
+                _ => Err(invalid_id()),
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        if ids.is_empty() {
+            return Err(CommandError::InvalidArgument(
+                "At least one ID is required".to_string(),
+            ));
+        }
+
+        Ok(StreamDelete { key, ids })
+    }
+}
+
+impl CommandExecutor for StreamInfoStream {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let info = match backend.stream_info(&self.key) {
+            Ok(info) => info,
+            Err(e) => return e.into(),
+        };
+        let edge_entry = |entry: Option<(StreamId, StreamFields)>| match entry {
+            Some((id, fields)) => entry_reply(id, Some(fields)),
+            None => RespFrame::Null(RespNull),
+        };
+        info_reply([
+            ("length", RespFrame::Integer(info.length as i64)),
+            ("last-generated-id", id_reply(info.last_id)),
+            ("max-deleted-entry-id", id_reply(info.max_deleted_id)),
+            (
+                "entries-added",
+                RespFrame::Integer(info.entries_added as i64),
+            ),
+            ("recorded-first-entry-id", id_reply(info.first_id)),
+            ("groups", RespFrame::Integer(info.groups as i64)),
+            ("first-entry", edge_entry(info.first_entry)),
+            ("last-entry", edge_entry(info.last_entry)),
+        ])
+    }
+}
+
+impl TryFrom<RespArray> for StreamInfoStream {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // FULL is not supported.
+        validate_command(&value, &["XINFO", "STREAM"], Some(1))?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        Ok(StreamInfoStream {
+            key: next_string(&mut args, "key")?,
+        })
+    }
+}
+
+impl CommandExecutor for StreamInfoGroups {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let groups = match backend.stream_group_infos(&self.key) {
+            Ok(groups) => groups,
+            Err(e) => return e.into(),
+        };
+        let groups: Vec<RespFrame> = groups
+            .into_iter()
+            .map(|group| {
+                info_reply([
+                    ("name", BulkString::new(group.name).into()),
+                    ("consumers", RespFrame::Integer(group.consumers as i64)),
+                    ("pending", RespFrame::Integer(group.pending as i64)),
+                    ("last-delivered-id", id_reply(group.last_delivered_id)),
+                    ("entries-read", optional_integer(group.entries_read)),
+                    ("lag", optional_integer(group.lag)),
+                ])
+            })
+            .collect();
+        RespArray::new(groups).into()
+    }
+}
+
+impl TryFrom<RespArray> for StreamInfoGroups {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XINFO", "GROUPS"], Some(1))?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        Ok(StreamInfoGroups {
+            key: next_string(&mut args, "key")?,
+        })
+    }
+}
+
+impl CommandExecutor for StreamInfoConsumers {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let consumers = match backend.stream_consumer_infos(&self.key, &self.group) {
+            Ok(consumers) => consumers,
+            Err(e) => return e.into(),
+        };
+        let consumers: Vec<RespFrame> = consumers
+            .into_iter()
+            .map(|consumer| {
+                // -1 if the consumer has never been delivered entries, like in Redis.
+                let inactive = consumer.inactive.map_or(-1, |ms| ms as i64);
+                info_reply([
+                    ("name", BulkString::new(consumer.name).into()),
+                    ("pending", RespFrame::Integer(consumer.pending as i64)),
+                    ("idle", RespFrame::Integer(consumer.idle as i64)),
+                    ("inactive", RespFrame::Integer(inactive)),
+                ])
+            })
+            .collect();
+        RespArray::new(consumers).into()
+    }
+}
+
+impl TryFrom<RespArray> for StreamInfoConsumers {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["XINFO", "CONSUMERS"], Some(2))?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        Ok(StreamInfoConsumers {
+            key: next_string(&mut args, "key")?,
+            group: next_string(&mut args, "group")?,
+        })
+    }
+}
+
+// XINFO replies with the names of the fields followed by their values, in a flat array like
+// Redis does over RESP2.
+fn info_reply<const N: usize>(fields: [(&str, RespFrame); N]) -> RespFrame {
+    let fields: Vec<RespFrame> = fields
+        .into_iter()
+        .flat_map(|(name, value)| [BulkString::new(name).into(), value])
+        .collect();
+    RespArray::new(fields).into()
+}
+
+fn id_reply(id: StreamId) -> RespFrame {
+    BulkString::new(id.to_string()).into()
+}
+
+fn optional_integer(n: Option<u64>) -> RespFrame {
+    match n {
+        Some(n) => RespFrame::Integer(n as i64),
+        None => RespFrame::Null(RespNull),
+    }
+}
+
+// The claimed entries, or only their IDs with JUSTID.
+fn claimed_reply(entries: Vec<(StreamId, StreamFields)>, just_id: bool) -> RespFrame {
+    if !just_id {
+        return entries_reply(entries);
+    }
+    let ids: Vec<RespFrame> = entries
+        .into_iter()
+        .map(|(id, _)| BulkString::new(id.to_string()).into())
+        .collect();
+    RespArray::new(ids).into()
+}
+
+fn entries_reply(entries: Vec<(StreamId, StreamFields)>) -> RespFrame {
+    let entries: Vec<RespFrame> = entries
+        .into_iter()
+        .map(|(id, fields)| entry_reply(id, Some(fields)))
+        .collect();
+    RespArray::new(entries).into()
+}
+
+// An entry is replied as its ID and the list of its fields and values, null if it has been
+// deleted.
+fn entry_reply(id: StreamId, fields: Option<StreamFields>) -> RespFrame {
+    let fields = match fields {
+        Some(fields) => {
+            let fields: Vec<RespFrame> = fields
+                .into_iter()
+                .flat_map(|(field, value)| [BulkString::from(field), BulkString::from(value)])
+                .map(RespFrame::from)
+                .collect();
+            RespArray::new(fields).into()
+        }
+        None => RespArray::Null.into(),
+    };
+    RespArray::new([BulkString::new(id.to_string()).into(), fields]).into()
+}
+
+// Parses the keys following STREAMS in XREAD and XREADGROUP, then as many IDs. The
+// `special` ID, "$" or ">", is returned as None. A missing sequence number is 0.
+fn extract_streams(
+    mut keys: Vec<RespFrame>,
+    name: &str,
+    special: &str,
+) -> Result<(Vec<String>, Vec<Option<StreamId>>), CommandError> {
+    if keys.is_empty() || !keys.len().is_multiple_of(2) {
+        return Err(CommandError::InvalidArgument(format!(
+            "Unbalanced {} list of streams: for each stream key an ID or '{}' must be specified",
+            name, special
+        )));
+    }
+    let ids = keys.split_off(keys.len() / 2);
+    let keys = keys
+        .into_iter()
+        .map(|key| match key {
+            RespFrame::BulkString(key) => Ok(key.try_into()?),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        })
+        .collect::<Result<Vec<String>, CommandError>>()?;
+    let ids = ids
+        .into_iter()
+        .map(|id| match id {
+            RespFrame::BulkString(id) if id.as_slice() == special.as_bytes() => Ok(None),
+            RespFrame::BulkString(id) => parse_full_id(&id).map(Some),
+            _ => Err(invalid_id()),
+        })
+        .collect::<Result<Vec<_>, CommandError>>()?;
+    Ok((keys, ids))
+}
+
+// Parses "[=|~] <threshold>" following MAXLEN or MINID in XADD and XTRIM, along with whether
+// trimming is approximate.
+fn parse_trim_strategy(
+    option: &[u8],
+    args: &mut Peekable<impl Iterator<Item = RespFrame>>,
+) -> Result<(StreamTrimStrategy, bool), CommandError> {
+    let mut approximate = false;
+    if let Some(RespFrame::BulkString(operator)) = args.peek() {
+        match operator.as_slice() {
+            b"=" => {
+                args.next();
+            }
+            b"~" => {
+                approximate = true;
+                args.next();
+            }
+            _ => {}
+        }
+    }
+    let threshold = match args.next() {
+        Some(RespFrame::BulkString(threshold)) => threshold,
+        _ => {
+            return Err(CommandError::InvalidArgument(
+                "Invalid threshold".to_string(),
+            ))
+        }
+    };
+    let strategy = match option {
+        b"MAXLEN" => {
+            let len: i64 = parse_number(threshold, "MAXLEN")?;
+            if len < 0 {
+                return Err(CommandError::InvalidArgument(
+                    "The MAXLEN argument must be >= 0".to_string(),
+                ));
+            }
+            StreamTrimStrategy::MaxLen(len as usize)
+        }
+        _ => StreamTrimStrategy::MinId(parse_full_id(&threshold)?),
+    };
+    Ok((strategy, approximate))
+}
+
+fn parse_trim_limit(value: Option<RespFrame>) -> Result<usize, CommandError> {
+    let limit: i64 = match value {
+        Some(RespFrame::BulkString(limit)) => parse_number(limit, "LIMIT")?,
+        _ => return Err(CommandError::InvalidArgument("Invalid LIMIT".to_string())),
+    };
+    if limit < 0 {
+        return Err(CommandError::InvalidArgument(
+            "The LIMIT argument must be >= 0".to_string(),
+        ));
+    }
+    Ok(limit as usize)
+}
+
+// LIMIT only goes with approximate trimming, where 0 means no limit.
+fn trim_options(
+    (strategy, approximate): (StreamTrimStrategy, bool),
+    limit: Option<usize>,
+) -> Result<StreamTrimOptions, CommandError> {
+    let limit = match limit {
+        Some(_) if !approximate => {
+            return Err(CommandError::InvalidArgument(
+                "LIMIT cannot be used without the special ~ option".to_string(),
+            ))
+        }
+        Some(0) => None,
+        Some(limit) => Some(limit),
+        None if approximate => Some(TRIM_DEFAULT_LIMIT),
+        None => None,
+    };
+    Ok(StreamTrimOptions {
+        strategy,
+        approximate,
+        limit,
+    })
+}
+
+fn missing_trim_strategy() -> CommandError {
+    CommandError::InvalidArgument("MAXLEN or MINID is required".to_string())
+}
+
+// Returns the arguments following "XGROUP <subcommand>".
+fn extract_group_args(
+    value: RespArray,
+    subcommand: &'static str,
+) -> Result<std::vec::IntoIter<RespFrame>, CommandError> {
+    validate_command(&value, &["XGROUP", subcommand], None)?;
+    Ok(extract_args(value, 2)?.into_iter())
+}
+
+// Parses "XGROUP <subcommand> <key> <group> <consumer>".
+fn extract_consumer_args(
+    value: RespArray,
+    subcommand: &'static str,
+) -> Result<(String, String, String), CommandError> {
+    let mut args = extract_group_args(value, subcommand)?;
+    let key = next_string(&mut args, "key")?;
+    let group = next_string(&mut args, "group")?;
+    let consumer = next_string(&mut args, "consumer")?;
+    no_more_args(args)?;
+    Ok((key, group, consumer))
+}
+
+fn next_string(
+    args: &mut impl Iterator<Item = RespFrame>,
+    name: &str,
+) -> Result<String, CommandError> {
+    match args.next() {
+        Some(RespFrame::BulkString(arg)) => Ok(arg.try_into()?),
+        _ => Err(CommandError::InvalidArgument(format!("Invalid {}", name))),
+    }
+}
+
+fn no_more_args(mut args: impl Iterator<Item = RespFrame>) -> Result<(), CommandError> {
+    match args.next() {
+        None => Ok(()),
+        Some(_) => Err(CommandError::InvalidArgument(
+            "Too many arguments".to_string(),
+        )),
+    }
+}
+
+// The last ID delivered to a group, None for "$".
+fn parse_group_id(value: &[u8]) -> Result<Option<StreamId>, CommandError> {
+    match value {
+        b"$" => Ok(None),
+        _ => parse_full_id(value).map(Some),
+    }
+}
+
+// A time in milliseconds or a count of XPENDING and XCLAIM, negative ones being taken as 0
+// like in Redis.
+fn parse_clamped(value: BulkString, name: &str) -> Result<u64, CommandError> {
+    let ms: i64 = parse_number(value, name)?;
+    Ok(ms.max(0) as u64)
+}
+
+// The COUNT of XREAD and XREADGROUP, where 0 means no limit unlike for XRANGE.
+fn parse_read_count(value: BulkString) -> Result<Option<usize>, CommandError> {
+    let n: i64 = parse_number(value, "count")?;
+    Ok((n > 0).then_some(n as usize))
+}
+
+// The BLOCK timeout of XREAD and XREADGROUP in milliseconds, 0 to block forever.
+fn parse_block_timeout(value: BulkString) -> Result<Option<Duration>, CommandError> {
+    let ms: i64 = parse_number(value, "timeout")?;
+    if ms < 0 {
+        return Err(CommandError::InvalidArgument(
+            "timeout is negative".to_string(),
+        ));
+    }
+    Ok((ms > 0).then(|| Duration::from_millis(ms as u64)))
+}
+
+// Parses "<key> <first> <second> [COUNT <count>]" shared by XRANGE and XREVRANGE. A negative
+// count is taken as 0, like in Redis.
+fn extract_range_args(
+    value: RespArray,
+) -> Result<(String, BulkString, BulkString, Option<usize>), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    let (key, first, second) = match (args.next(), args.next(), args.next()) {
+        (
+            Some(RespFrame::BulkString(key)),
+            Some(RespFrame::BulkString(first)),
+            Some(RespFrame::BulkString(second)),
+        ) => (key.try_into()?, first, second),
+        _ => {
+            return Err(CommandError::InvalidArgument(
+                "Invalid key or range".to_string(),
+            ))
+        }
+    };
+
+    let count = match (args.next(), args.next(), args.next()) {
+        (None, _, _) => None,
+        (Some(RespFrame::BulkString(option)), Some(RespFrame::BulkString(count)), None)
+            if option.eq_ignore_ascii_case(b"COUNT") =>
+        {
+            let count: i64 = parse_number(count, "count")?;
+            Some(count.max(0) as usize)
+        }
+        _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+    };
+    Ok((key, first, second, count))
+}
+
+// "*" generates the whole ID, "<ms>-*" only the sequence number. A missing sequence number
+// is 0.
+fn parse_add_id(value: &[u8]) -> Result<StreamAddId, CommandError> {
+    let id = match value {
+        b"*" => Some(StreamAddId::Auto),
+        _ => match value.strip_suffix(b"-*") {
+            Some(ms) => parse_u64(ms).map(StreamAddId::AutoSeq),
+            None => return parse_full_id(value).map(StreamAddId::Explicit),
+        },
+    };
+    id.ok_or_else(invalid_id)
+}
+
+// Parses "<ms>" or "<ms>-<seq>", a missing sequence number being 0.
+fn parse_full_id(value: &[u8]) -> Result<StreamId, CommandError> {
+    parse_id(value)
+        .map(|(ms, seq)| StreamId::new(ms, seq.unwrap_or(0)))
+        .ok_or_else(invalid_id)
+}
+
+// "-" and "+" stand for the smallest and greatest IDs, and a bound prefixed with "(" is
+// exclusive. A missing sequence number spans the whole millisecond, so it's 0 for the start
+// and the greatest one for the end.
+fn parse_range_bound(value: &[u8], start: bool) -> Result<StreamId, CommandError> {
+    let (value, exclusive) = match value.strip_prefix(b"(") {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let missing_seq = if start { 0 } else { u64::MAX };
+    let id = match value {
+        b"-" if !exclusive => StreamId::MIN,
+        b"+" if !exclusive => StreamId::MAX,
+        _ => match parse_id(value) {
+            Some((ms, seq)) => StreamId::new(ms, seq.unwrap_or(missing_seq)),
+            None => return Err(invalid_id()),
+        },
+    };
+    if !exclusive {
+        return Ok(id);
+    }
+    let id = if start { id.next() } else { id.prev() };
+    id.ok_or_else(|| CommandError::InvalidArgument("Invalid interval".to_string()))
+}
+
+// Parses "<ms>" or "<ms>-<seq>", None if malformed.
+fn parse_id(value: &[u8]) -> Option<(u64, Option<u64>)> {
+    let mut parts = value.splitn(2, |&b| b == b'-');
+    let ms = parse_u64(parts.next()?)?;
+    match parts.next() {
+        Some(seq) => Some((ms, Some(parse_u64(seq)?))),
+        None => Some((ms, None)),
+    }
+}
+
+fn parse_u64(value: &[u8]) -> Option<u64> {
+    std::str::from_utf8(value).ok()?.parse().ok()
+}
+
+fn invalid_id() -> CommandError {
+    CommandError::InvalidArgument(
+        "Invalid stream ID specified as stream command argument".to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        BackendError, BulkString, RespArray, RespDecode, RespFrame, RespNull, SimpleString,
+        StreamAddId, StreamClaimOptions, StreamId, StreamTrimOptions, StreamTrimStrategy,
+    };
+
+    use super::{
+        BlockingCommand, CommandExecutor, StreamAck, StreamAdd, StreamAutoClaim, StreamClaim,
+        StreamDelete, StreamGroupCreate, StreamGroupDestroy, StreamGroupSetId, StreamInfoConsumers,
+        StreamInfoGroups, StreamInfoStream, StreamLen, StreamPending, StreamRange, StreamRead,
+        StreamReadGroup, StreamRevRange, StreamTrim,
+    };
+
+    #[test]
+    fn test_xadd_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$4\r\nxadd\r\n$6\r\nstream\r\n$10\r\nnomkstream\r\n$3\r\n5-*\r\n\
+            $1\r\na\r\n$0\r\n\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StreamAdd = frame.try_into()?;
+        assert_eq!(result.key, "stream");
+        assert_eq!(result.id, StreamAddId::AutoSeq(5));
+        assert!(result.no_create);
+        // An empty string is a value like any other.
+        let fields: Vec<_> = result
+            .fields
+            .iter()
+            .map(|(field, value)| (&field[..], &value[..]))
+            .collect();
+        assert_eq!(fields, [(&b"a"[..], &b""[..])]);
+
+        for (id, expected) in [
+            (&b"*"[..], StreamAddId::Auto),
+            (b"7", StreamAddId::Explicit(StreamId::new(7, 0))),
+            (b"7-3", StreamAddId::Explicit(StreamId::new(7, 3))),
+        ] {
+            assert_eq!(super::parse_add_id(id)?, expected);
+        }
+
+        for args in [
+            &b"*5\r\n$4\r\nxadd\r\n$6\r\nstream\r\n$3\r\n1-x\r\n$1\r\na\r\n$1\r\n1\r\n"[..],
+            b"*5\r\n$4\r\nxadd\r\n$6\r\nstream\r\n$2\r\n-1\r\n$1\r\na\r\n$1\r\n1\r\n",
+            b"*6\r\n$4\r\nxadd\r\n$6\r\nstream\r\n$1\r\n*\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n",
+            b"*3\r\n$4\r\nxadd\r\n$6\r\nstream\r\n$1\r\n*\r\n",
+            b"*2\r\n$4\r\nxadd\r\n$6\r\nstream\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xadd_command() {
+        let backend = crate::Backend::new();
+        let xadd = |id, no_create| {
+            StreamAdd {
+                key: "stream".to_string(),
+                id,
+                fields: vec![("f".into(), "v".into())],
+                no_create,
+                trim: None,
+            }
+            .execute(&backend)
+        };
+
+        assert_eq!(xadd(StreamAddId::Auto, true), RespFrame::Null(RespNull));
+        assert!(!backend.exists("stream"));
+        // A failed XADD doesn't create the stream.
+        let zero = StreamAddId::Explicit(StreamId::new(0, 0));
+        assert_eq!(xadd(zero, false), BackendError::StreamIdZero.into());
+        assert!(!backend.exists("stream"));
+
+        let id = StreamAddId::Explicit(StreamId::new(5, 1));
+        assert_eq!(xadd(id, false), BulkString::new("5-1").into());
+        assert_eq!(xadd(id, false), BackendError::StreamIdTooSmall.into());
+        assert_eq!(
+            xadd(StreamAddId::AutoSeq(5), true),
+            BulkString::new("5-2").into()
+        );
+        assert_eq!(backend.stream_len("stream"), 2);
+
+        let cmd = StreamLen {
+            key: "stream".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+    }
+
+    #[test]
+    fn test_xrange_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$6\r\nxrange\r\n$6\r\nstream\r\n$4\r\n(1-5\r\n$1\r\n3\r\n\
+            $5\r\ncount\r\n$2\r\n-2\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StreamRange = frame.try_into()?;
+        assert_eq!(result.key, "stream");
+        assert_eq!(result.start, StreamId::new(1, 6));
+        assert_eq!(result.end, StreamId::new(3, u64::MAX));
+        assert_eq!(result.count, Some(0));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$9\r\nxrevrange\r\n$6\r\nstream\r\n$1\r\n+\r\n$4\r\n(2-0\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: StreamRevRange = frame.try_into()?;
+        assert_eq!(result.start, StreamId::new(2, 1));
+        assert_eq!(result.end, StreamId::MAX);
+        assert_eq!(result.count, None);
+
+        for args in [
+            &b"*4\r\n$6\r\nxrange\r\n$6\r\nstream\r\n$2\r\n(-\r\n$1\r\n+\r\n"[..],
+            b"*4\r\n$6\r\nxrange\r\n$6\r\nstream\r\n$1\r\n-\r\n$4\r\n(0-0\r\n",
+            b"*4\r\n$6\r\nxrange\r\n$6\r\nstream\r\n$1\r\nx\r\n$1\r\n+\r\n",
+            b"*5\r\n$6\r\nxrange\r\n$6\r\nstream\r\n$1\r\n-\r\n$1\r\n+\r\n$5\r\ncount\r\n",
+            b"*3\r\n$6\r\nxrange\r\n$6\r\nstream\r\n$1\r\n-\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xrange_command() {
+        let backend = crate::Backend::new();
+        for (ms, value) in [(1, "a"), (2, "b"), (3, "c")] {
+            let id = StreamAddId::Explicit(StreamId::new(ms, 0));
+            let fields = vec![("f".into(), value.into())];
+            backend
+                .stream_add("stream".to_string(), id, fields, false, None)
+                .unwrap();
+        }
+        let entry = |id: &str, value: &str| -> RespFrame {
+            let fields = vec![BulkString::new("f").into(), BulkString::new(value).into()];
+            RespArray::new([BulkString::new(id).into(), RespArray::new(fields).into()]).into()
+        };
+
+        let cmd = StreamRange {
+            key: "stream".to_string(),
+            start: StreamId::new(2, 0),
+            end: StreamId::MAX,
+            count: None,
+        };
+        let expected = RespArray::new([entry("2-0", "b"), entry("3-0", "c")]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = StreamRevRange {
+            key: "stream".to_string(),
+            start: StreamId::MIN,
+            end: StreamId::MAX,
+            count: Some(2),
+        };
+        let expected = RespArray::new([entry("3-0", "c"), entry("2-0", "b")]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = StreamRange {
+            key: "missing".to_string(),
+            start: StreamId::MIN,
+            end: StreamId::MAX,
+            count: None,
+        };
+        assert_eq!(cmd.execute(&backend), RespArray::new([]).into());
+    }
+
+    #[test]
+    fn test_xread_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*10\r\n$5\r\nxread\r\n$5\r\ncount\r\n$1\r\n0\r\n$5\r\nblock\r\n$3\r\n100\r\n\
+            $7\r\nstreams\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\n$\r\n$3\r\n5-1\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StreamRead = frame.try_into()?;
+        assert_eq!(result.keys, ["a", "b"]);
+        assert_eq!(result.ids, [None, Some(StreamId::new(5, 1))]);
+        assert_eq!(result.count, None);
+        assert!(result.is_blocking());
+        assert_eq!(result.timeout, Some(Duration::from_millis(100)));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$5\r\nxread\r\n$5\r\nblock\r\n$1\r\n0\r\n$7\r\nstreams\r\n$1\r\na\r\n$1\r\n5\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: StreamRead = frame.try_into()?;
+        assert_eq!(result.ids, [Some(StreamId::new(5, 0))]);
+        assert!(result.is_blocking());
+        assert_eq!(result.timeout, None);
+
+        for args in [
+            &b"*5\r\n$5\r\nxread\r\n$7\r\nstreams\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\n$\r\n"[..],
+            b"*4\r\n$5\r\nxread\r\n$7\r\nstreams\r\n$1\r\na\r\n$1\r\nx\r\n",
+            b"*6\r\n$5\r\nxread\r\n$5\r\nblock\r\n$2\r\n-1\r\n$7\r\nstreams\r\n$1\r\na\r\n$1\r\n$\r\n",
+            b"*4\r\n$5\r\nxread\r\n$5\r\ncount\r\n$1\r\na\r\n$1\r\n$\r\n",
+            b"*2\r\n$5\r\nxread\r\n$7\r\nstreams\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xread_command() {
+        let backend = crate::Backend::new();
+        let xadd = |key: &str, ms: u64| {
+            let id = StreamAddId::Explicit(StreamId::new(ms, 0));
+            let fields = vec![("f".into(), "v".into())];
+            backend
+                .stream_add(key.to_string(), id, fields, false, None)
+                .unwrap();
+        };
+        xadd("a", 1);
+        xadd("a", 2);
+        xadd("b", 3);
+        let xread = |ids: Vec<Option<StreamId>>, count| StreamRead {
+            keys: vec!["a".to_string(), "b".to_string()],
+            ids,
+            count,
+            block: true,
+            timeout: None,
+            after: OnceLock::new(),
+        };
+        let entry = |id: &str| -> RespFrame {
+            let fields = vec![BulkString::new("f").into(), BulkString::new("v").into()];
+            RespArray::new([BulkString::new(id).into(), RespArray::new(fields).into()]).into()
+        };
+        let stream = |key: &str, entries: Vec<RespFrame>| -> RespFrame {
+            RespArray::new([BulkString::new(key).into(), RespArray::new(entries).into()]).into()
+        };
+
+        // Only the keys with entries after their ID are replied.
+        let cmd = xread(
+            vec![Some(StreamId::new(1, 0)), Some(StreamId::new(3, 0))],
+            None,
+        );
+        let expected = RespArray::new([stream("a", vec![entry("2-0")])]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = xread(vec![Some(StreamId::MIN), Some(StreamId::MIN)], Some(1));
+        let expected = RespArray::new([
+            stream("a", vec![entry("1-0")]),
+            stream("b", vec![entry("3-0")]),
+        ]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        // "$" is resolved on the first try, later tries get the entries appended since.
+        let cmd = xread(vec![None, None], None);
+        assert_eq!(cmd.try_execute(&backend), None);
+        xadd("b", 4);
+        let expected = RespArray::new([stream("b", vec![entry("4-0")])]);
+        assert_eq!(cmd.try_execute(&backend), Some(expected.into()));
+
+        // Executed once, "$" never has entries after it.
+        let cmd = xread(vec![None, None], None);
+        assert_eq!(cmd.execute(&backend), RespArray::Null.into());
+    }
+
+    #[test]
+    fn test_xgroup_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$6\r\nxgroup\r\n$6\r\ncreate\r\n$6\r\nstream\r\n$1\r\ng\r\n$1\r\n$\r\n\
+            $8\r\nmkstream\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StreamGroupCreate = frame.try_into()?;
+        assert_eq!(result.key, "stream");
+        assert_eq!(result.group, "g");
+        assert_eq!(result.last_id, None);
+        assert!(result.create);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$6\r\nxgroup\r\n$5\r\nsetid\r\n$6\r\nstream\r\n$1\r\ng\r\n$3\r\n5-1\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: StreamGroupSetId = frame.try_into()?;
+        assert_eq!(result.last_id, Some(StreamId::new(5, 1)));
+
+        for args in [
+            &b"*5\r\n$6\r\nxgroup\r\n$6\r\ncreate\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\n>\r\n"[..],
+            b"*6\r\n$6\r\nxgroup\r\n$6\r\ncreate\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\n0\r\n$1\r\nx\r\n",
+            b"*5\r\n$6\r\nxgroup\r\n$7\r\ndestroy\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\nx\r\n",
+            b"*4\r\n$6\r\nxgroup\r\n$11\r\ndelconsumer\r\n$1\r\ns\r\n$1\r\ng\r\n",
+            b"*3\r\n$4\r\nxack\r\n$1\r\ns\r\n$1\r\ng\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xreadgroup_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*13\r\n$10\r\nxreadgroup\r\n$5\r\ngroup\r\n$1\r\ng\r\n$1\r\nc\r\n\
+            $5\r\ncount\r\n$1\r\n2\r\n$5\r\nnoack\r\n$5\r\nblock\r\n$1\r\n0\r\n\
+            $7\r\nstreams\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\n>\r\n",
+        );
+
+        // One ID short.
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(crate::cmd::Command::try_from(frame).is_err());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*14\r\n$10\r\nxreadgroup\r\n$5\r\ngroup\r\n$1\r\ng\r\n$1\r\nc\r\n\
+            $5\r\ncount\r\n$1\r\n2\r\n$5\r\nnoack\r\n$5\r\nblock\r\n$1\r\n0\r\n\
+            $7\r\nstreams\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\n>\r\n$1\r\n>\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: StreamReadGroup = frame.try_into()?;
+        assert_eq!(result.group, "g");
+        assert_eq!(result.consumer, "c");
+        assert_eq!(result.keys, ["a", "b"]);
+        assert_eq!(result.ids, [None, None]);
+        assert_eq!(result.count, Some(2));
+        assert!(result.no_ack);
+        assert!(result.is_blocking());
+        assert_eq!(result.timeout, None);
+
+        // Reading the pending entries never blocks.
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*9\r\n$10\r\nxreadgroup\r\n$5\r\ngroup\r\n$1\r\ng\r\n$1\r\nc\r\n\
+            $5\r\nblock\r\n$2\r\n10\r\n$7\r\nstreams\r\n$1\r\na\r\n$1\r\n0\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: StreamReadGroup = frame.try_into()?;
+        assert_eq!(result.ids, [Some(StreamId::MIN)]);
+        assert!(!result.is_blocking());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xreadgroup_command() {
+        let backend = crate::Backend::new();
+        let xreadgroup = |consumer: &str, ids: Vec<Option<StreamId>>| {
+            StreamReadGroup {
+                group: "g".to_string(),
+                consumer: consumer.to_string(),
+                keys: vec!["stream".to_string()],
+                ids,
+                count: None,
+                no_ack: false,
+                block: false,
+                timeout: None,
+            }
+            .execute(&backend)
+        };
+        let entry = |id: &str| -> RespFrame {
+            let fields = vec![BulkString::new("f").into(), BulkString::new("v").into()];
+            RespArray::new([BulkString::new(id).into(), RespArray::new(fields).into()]).into()
+        };
+        let stream = |entries: Vec<RespFrame>| -> RespFrame {
+            RespArray::new([RespArray::new([
+                BulkString::new("stream").into(),
+                RespArray::new(entries).into(),
+            ])
+            .into()])
+            .into()
+        };
+
+        let create = |create| StreamGroupCreate {
+            key: "stream".to_string(),
+            group: "g".to_string(),
+            last_id: None,
+            create,
+        };
+        assert_eq!(
+            create(false).execute(&backend),
+            BackendError::NoStream.into()
+        );
+        assert_eq!(
+            xreadgroup("alice", vec![None]),
+            BackendError::NoGroup {
+                key: "stream".to_string(),
+                group: "g".to_string()
+            }
+            .into()
+        );
+        assert_eq!(
+            create(true).execute(&backend),
+            SimpleString::new("OK").into()
+        );
+        assert_eq!(
+            create(true).execute(&backend),
+            BackendError::BusyGroup.into()
+        );
+
+        for ms in 1..=2 {
+            let id = StreamAddId::Explicit(StreamId::new(ms, 0));
+            let fields = vec![("f".into(), "v".into())];
+            backend
+                .stream_add("stream".to_string(), id, fields, false, None)
+                .unwrap();
+        }
+        assert_eq!(
+            xreadgroup("alice", vec![None]),
+            stream(vec![entry("1-0"), entry("2-0")])
+        );
+        assert_eq!(xreadgroup("alice", vec![None]), RespArray::Null.into());
+        // The pending entries are replied even if there are none.
+        assert_eq!(xreadgroup("bob", vec![Some(StreamId::MIN)]), stream(vec![]));
+
+        let cmd = StreamAck {
+            key: "stream".to_string(),
+            group: "g".to_string(),
+            ids: vec![StreamId::new(1, 0), StreamId::new(3, 0)],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(
+            xreadgroup("alice", vec![Some(StreamId::MIN)]),
+            stream(vec![entry("2-0")])
+        );
+
+        let cmd = StreamGroupSetId {
+            key: "stream".to_string(),
+            group: "g".to_string(),
+            last_id: Some(StreamId::MIN),
+        };
+        assert_eq!(cmd.execute(&backend), SimpleString::new("OK").into());
+        assert_eq!(
+            xreadgroup("bob", vec![None]),
+            stream(vec![entry("1-0"), entry("2-0")])
+        );
+
+        let destroy = || StreamGroupDestroy {
+            key: "stream".to_string(),
+            group: "g".to_string(),
+        };
+        assert_eq!(destroy().execute(&backend), RespFrame::Integer(1));
+        assert_eq!(destroy().execute(&backend), RespFrame::Integer(0));
+    }
+
+    #[test]
+    fn test_xpending_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*9\r\n$8\r\nxpending\r\n$6\r\nstream\r\n$1\r\ng\r\n$4\r\nidle\r\n$3\r\n100\r\n\
+            $1\r\n-\r\n$2\r\n(5\r\n$2\r\n-1\r\n$5\r\nalice\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StreamPending = frame.try_into()?;
+        assert_eq!(result.key, "stream");
+        assert_eq!(result.group, "g");
+        let end = StreamId::new(5, u64::MAX - 1);
+        assert_eq!(result.range, Some((StreamId::MIN, end, 0)));
+        assert_eq!(result.min_idle, 100);
+        assert_eq!(result.consumer.as_deref(), Some("alice"));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$8\r\nxpending\r\n$6\r\nstream\r\n$1\r\ng\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: StreamPending = frame.try_into()?;
+        assert_eq!(result.range, None);
+
+        for args in [
+            &b"*4\r\n$8\r\nxpending\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\n-\r\n"[..],
+            b"*6\r\n$8\r\nxpending\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\n-\r\n$1\r\n+\r\n$1\r\nx\r\n",
+            b"*5\r\n$8\r\nxpending\r\n$1\r\ns\r\n$1\r\ng\r\n$4\r\nidle\r\n$1\r\nx\r\n",
+            b"*2\r\n$8\r\nxpending\r\n$1\r\ns\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xclaim_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*15\r\n$6\r\nxclaim\r\n$6\r\nstream\r\n$1\r\ng\r\n$1\r\nc\r\n$2\r\n-5\r\n\
+            $3\r\n1-1\r\n$1\r\n2\r\n$4\r\nidle\r\n$2\r\n10\r\n$10\r\nretrycount\r\n$1\r\n3\r\n\
+            $5\r\nforce\r\n$6\r\njustid\r\n$6\r\nlastid\r\n$1\r\n9\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StreamClaim = frame.try_into()?;
+        assert_eq!(result.consumer, "c");
+        assert_eq!(result.ids, [StreamId::new(1, 1), StreamId::new(2, 0)]);
+        assert_eq!(result.idle, Some(10));
+        let options = StreamClaimOptions {
+            min_idle: 0,
+            delivered_at: None,
+            retry_count: Some(3),
+            force: true,
+            just_id: true,
+            last_id: Some(StreamId::new(9, 0)),
+        };
+        assert_eq!(result.options, options);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*9\r\n$10\r\nxautoclaim\r\n$6\r\nstream\r\n$1\r\ng\r\n$1\r\nc\r\n$2\r\n10\r\n\
+            $2\r\n(5\r\n$6\r\njustid\r\n$5\r\ncount\r\n$1\r\n2\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: StreamAutoClaim = frame.try_into()?;
+        assert_eq!(result.start, StreamId::new(5, 1));
+        assert_eq!(result.count, 2);
+        assert_eq!(result.options.min_idle, 10);
+        assert!(result.options.just_id);
+
+        for args in [
+            &b"*5\r\n$6\r\nxclaim\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\nc\r\n$1\r\n0\r\n"[..],
+            b"*6\r\n$6\r\nxclaim\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\nc\r\n$1\r\n0\r\n$1\r\nx\r\n",
+            b"*7\r\n$6\r\nxclaim\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\nc\r\n$1\r\n0\r\n$1\r\n1\r\n\
+            $4\r\nidle\r\n",
+            b"*8\r\n$10\r\nxautoclaim\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\nc\r\n$1\r\n0\r\n$1\r\n0\r\n\
+            $5\r\ncount\r\n$1\r\n0\r\n",
+            b"*5\r\n$10\r\nxautoclaim\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\nc\r\n$1\r\n0\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xpending_and_xclaim_commands() {
+        let backend = crate::Backend::new();
+        for ms in 1..=2 {
+            let id = StreamAddId::Explicit(StreamId::new(ms, 0));
+            let fields = vec![("f".into(), "v".into())];
+            backend
+                .stream_add("stream".to_string(), id, fields, false, None)
+                .unwrap();
+        }
+        backend
+            .stream_group_create(
+                "stream".to_string(),
+                "g".to_string(),
+                Some(StreamId::MIN),
+                false,
+            )
+            .unwrap();
+        let pending = || StreamPending {
+            key: "stream".to_string(),
+            group: "g".to_string(),
+            range: None,
+            min_idle: 0,
+            consumer: None,
+        };
+        let expected = RespArray::new([
+            RespFrame::Integer(0),
+            RespFrame::Null(RespNull),
+            RespFrame::Null(RespNull),
+            RespArray::Null.into(),
+        ]);
+        assert_eq!(pending().execute(&backend), expected.into());
+
+        backend
+            .stream_read_group("stream", "g", "alice", None, None, false)
+            .unwrap();
+        let expected = RespArray::new([
+            RespFrame::Integer(2),
+            BulkString::new("1-0").into(),
+            BulkString::new("2-0").into(),
+            RespArray::new([RespArray::new([
+                BulkString::new("alice").into(),
+                BulkString::new("2").into(),
+            ])
+            .into()])
+            .into(),
+        ]);
+        assert_eq!(pending().execute(&backend), expected.into());
+
+        let cmd = StreamPending {
+            range: Some((StreamId::MIN, StreamId::MAX, 1)),
+            consumer: Some("alice".to_string()),
+            ..pending()
+        };
+        let RespFrame::Array(RespArray::Normal(entries)) = cmd.execute(&backend) else {
+            panic!("expected an array");
+        };
+        let RespFrame::Array(RespArray::Normal(entry)) = &entries[0] else {
+            panic!("expected an array");
+        };
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entry[0], BulkString::new("1-0").into());
+        assert_eq!(entry[1], BulkString::new("alice").into());
+        assert_eq!(entry[3], RespFrame::Integer(1));
+
+        let cmd = StreamClaim {
+            key: "stream".to_string(),
+            group: "g".to_string(),
+            consumer: "bob".to_string(),
+            ids: vec![StreamId::new(2, 0)],
+            options: StreamClaimOptions {
+                just_id: true,
+                ..Default::default()
+            },
+            idle: Some(1000),
+        };
+        let expected = RespArray::new([BulkString::new("2-0").into()]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = StreamAutoClaim {
+            key: "stream".to_string(),
+            group: "g".to_string(),
+            consumer: "bob".to_string(),
+            start: StreamId::MIN,
+            count: 10,
+            options: StreamClaimOptions {
+                min_idle: 500,
+                ..Default::default()
+            },
+        };
+        // Only 2-0 has been idle for long enough, since claimed as delivered a second ago.
+        let entry = RespArray::new([
+            BulkString::new("2-0").into(),
+            RespArray::new([BulkString::new("f").into(), BulkString::new("v").into()]).into(),
+        ]);
+        let expected = RespArray::new([
+            BulkString::new("0-0").into(),
+            RespArray::new([entry.into()]).into(),
+            RespArray::new([]).into(),
+        ]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = StreamPending {
+            group: "nope".to_string(),
+            ..pending()
+        };
+        let expected = BackendError::NoGroup {
+            key: "stream".to_string(),
+            group: "nope".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), expected.into());
+    }
+
+    #[test]
+    fn test_xtrim_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*7\r\n$5\r\nxtrim\r\n$6\r\nstream\r\n$6\r\nmaxlen\r\n$1\r\n~\r\n$2\r\n10\r\n\
+            $5\r\nlimit\r\n$1\r\n0\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StreamTrim = frame.try_into()?;
+        assert_eq!(result.key, "stream");
+        let trim = StreamTrimOptions {
+            strategy: StreamTrimStrategy::MaxLen(10),
+            approximate: true,
+            limit: None,
+        };
+        assert_eq!(result.trim, trim);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*8\r\n$4\r\nxadd\r\n$6\r\nstream\r\n$5\r\nminid\r\n$1\r\n=\r\n$3\r\n5-1\r\n\
+            $1\r\n*\r\n$1\r\na\r\n$1\r\n1\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: StreamAdd = frame.try_into()?;
+        let trim = StreamTrimOptions {
+            strategy: StreamTrimStrategy::MinId(StreamId::new(5, 1)),
+            approximate: false,
+            limit: None,
+        };
+        assert_eq!(result.trim, Some(trim));
+        assert_eq!(result.id, StreamAddId::Auto);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$4\r\nxdel\r\n$6\r\nstream\r\n$1\r\n1\r\n$3\r\n2-3\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: StreamDelete = frame.try_into()?;
+        assert_eq!(result.ids, [StreamId::new(1, 0), StreamId::new(2, 3)]);
+
+        for args in [
+            &b"*4\r\n$5\r\nxtrim\r\n$1\r\ns\r\n$6\r\nmaxlen\r\n$2\r\n-1\r\n"[..],
+            b"*6\r\n$5\r\nxtrim\r\n$1\r\ns\r\n$6\r\nmaxlen\r\n$1\r\n1\r\n$5\r\nlimit\r\n$1\r\n5\r\n",
+            b"*4\r\n$5\r\nxtrim\r\n$1\r\ns\r\n$5\r\nlimit\r\n$1\r\n5\r\n",
+            b"*3\r\n$5\r\nxtrim\r\n$1\r\ns\r\n$5\r\nminid\r\n",
+            b"*2\r\n$4\r\nxdel\r\n$1\r\ns\r\n",
+            b"*5\r\n$4\r\nxadd\r\n$1\r\ns\r\n$6\r\nmaxlen\r\n$1\r\nx\r\n$1\r\n*\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xtrim_command() {
+        let backend = crate::Backend::new();
+        let trim = StreamTrimOptions {
+            strategy: StreamTrimStrategy::MaxLen(2),
+            approximate: false,
+            limit: None,
+        };
+        for ms in 1..=4 {
+            StreamAdd {
+                key: "stream".to_string(),
+                id: StreamAddId::Explicit(StreamId::new(ms, 0)),
+                fields: vec![("f".into(), "v".into())],
+                no_create: false,
+                trim: Some(trim),
+            }
+            .execute(&backend);
+        }
+        assert_eq!(backend.stream_len("stream"), 2);
+
+        let cmd = StreamDelete {
+            key: "stream".to_string(),
+            ids: vec![
+                StreamId::new(3, 0),
+                StreamId::new(3, 0),
+                StreamId::new(1, 0),
+            ],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let xtrim = |strategy| StreamTrim {
+            key: "stream".to_string(),
+            trim: StreamTrimOptions {
+                strategy,
+                approximate: false,
+                limit: None,
+            },
+        };
+        let cmd = xtrim(StreamTrimStrategy::MinId(StreamId::new(5, 0)));
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        // The stream is kept once empty.
+        assert!(backend.exists("stream"));
+        let cmd = xtrim(StreamTrimStrategy::MaxLen(0));
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+    }
+
+    #[test]
+    fn test_xinfo_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$5\r\nxinfo\r\n$9\r\nconsumers\r\n$6\r\nstream\r\n$1\r\ng\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StreamInfoConsumers = frame.try_into()?;
+        assert_eq!(result.key, "stream");
+        assert_eq!(result.group, "g");
+
+        for args in [
+            &b"*4\r\n$5\r\nxinfo\r\n$6\r\nstream\r\n$1\r\ns\r\n$4\r\nfull\r\n"[..],
+            b"*2\r\n$5\r\nxinfo\r\n$6\r\ngroups\r\n",
+            b"*3\r\n$5\r\nxinfo\r\n$9\r\nconsumers\r\n$1\r\ns\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xinfo_command() {
+        let backend = crate::Backend::new();
+        let cmd = StreamInfoStream {
+            key: "stream".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), BackendError::NoSuchKey.into());
+
+        let id = StreamAddId::Explicit(StreamId::new(1, 0));
+        let fields = vec![("f".into(), "v".into())];
+        backend
+            .stream_add("stream".to_string(), id, fields, false, None)
+            .unwrap();
+        backend
+            .stream_group_create("stream".to_string(), "g".to_string(), None, false)
+            .unwrap();
+        let entry = RespArray::new([
+            BulkString::new("1-0").into(),
+            RespArray::new([BulkString::new("f").into(), BulkString::new("v").into()]).into(),
+        ]);
+        let cmd = StreamInfoStream {
+            key: "stream".to_string(),
+        };
+        let expected = RespArray::new([
+            BulkString::new("length").into(),
+            RespFrame::Integer(1),
+            BulkString::new("last-generated-id").into(),
+            BulkString::new("1-0").into(),
+            BulkString::new("max-deleted-entry-id").into(),
+            BulkString::new("0-0").into(),
+            BulkString::new("entries-added").into(),
+            RespFrame::Integer(1),
+            BulkString::new("recorded-first-entry-id").into(),
+            BulkString::new("1-0").into(),
+            BulkString::new("groups").into(),
+            RespFrame::Integer(1),
+            BulkString::new("first-entry").into(),
+            entry.clone().into(),
+            BulkString::new("last-entry").into(),
+            entry.into(),
+        ]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = StreamInfoGroups {
+            key: "stream".to_string(),
+        };
+        let expected = RespArray::new([RespArray::new([
+            BulkString::new("name").into(),
+            BulkString::new("g").into(),
+            BulkString::new("consumers").into(),
+            RespFrame::Integer(0),
+            BulkString::new("pending").into(),
+            RespFrame::Integer(0),
+            BulkString::new("last-delivered-id").into(),
+            BulkString::new("1-0").into(),
+            BulkString::new("entries-read").into(),
+            RespFrame::Null(RespNull),
+            BulkString::new("lag").into(),
+            RespFrame::Integer(0),
+        ])
+        .into()]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        backend
+            .stream_group_create_consumer("stream", "g", "alice")
+            .unwrap();
+        let cmd = StreamInfoConsumers {
+            key: "stream".to_string(),
+            group: "g".to_string(),
+        };
+        let RespFrame::Array(RespArray::Normal(consumers)) = cmd.execute(&backend) else {
+            panic!("expected an array");
+        };
+        let RespFrame::Array(RespArray::Normal(consumer)) = &consumers[0] else {
+            panic!("expected an array");
+        };
+        assert_eq!(consumer[1], BulkString::new("alice").into());
+        assert_eq!(consumer[3], RespFrame::Integer(0));
+        assert_eq!(consumer[7], RespFrame::Integer(-1));
+
+        let cmd = StreamInfoConsumers {
+            key: "stream".to_string(),
+            group: "nope".to_string(),
+        };
+        let expected = BackendError::NoGroup {
+            key: "stream".to_string(),
+            group: "nope".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), expected.into());
+    }
+}
@@ -1,15 +1,21 @@
 use crate::{
     cmd::{CommandError, StringGet},
-    RespArray, RespFrame, RespNull,
+    now_ms, BulkString, Expiry, RespArray, RespFrame, RespNull, SetCondition,
 };
 
-use super::{extract_args, validate_command, CommandExecutor, StringSet, RESP_OK};
+use super::{
+    extract_args, parse_number, validate_command, CommandExecutor, StringAppend, StringDecr,
+    StringDecrBy, StringGetBit, StringGetRange, StringIncr, StringIncrBy, StringIncrByFloat,
+    StringLen, StringMultiSetNx, StringPSetEx, StringSet, StringSetBit, StringSetEx, StringSetNx,
+    StringSetRange, RESP_OK,
+};
 
 impl CommandExecutor for StringGet {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        backend
-            .string_get(&self.key)
-            .unwrap_or(RespFrame::Null(RespNull))
+        match backend.string_get(&self.key) {
+            Some(value) => BulkString::new(value).into(),
+            None => RespFrame::Null(RespNull),
+        }
     }
 }
 
@@ -30,22 +36,450 @@ impl TryFrom<RespArray> for StringGet {
 
 impl CommandExecutor for StringSet {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        backend.string_set(self.key, self.value);
-        RESP_OK.clone()
+        let (written, old) =
+            backend.string_set_with(self.key, self.value, self.condition, self.expiry);
+        if self.get {
+            match old {
+                Some(old) => BulkString::new(old).into(),
+                None => RespFrame::Null(RespNull),
+            }
+        } else if written {
+            RESP_OK.clone()
+        } else {
+            RespFrame::Null(RespNull)
+        }
     }
 }
 
 impl TryFrom<RespArray> for StringSet {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["SET"], Some(2))?;
+        validate_command(&value, &["SET"], None)?;
+
+        // Parse the key and value.
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, value) = match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(value))) => {
+                (key.try_into()?, value.to_vec())
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid key or value".to_string(),
+                ))
+            }
+        };
+
+        // Parse the options.
+        let mut condition = None;
+        let mut expiry = None;
+        let mut get = false;
+        while let Some(arg) = args.next() {
+            let option = match arg {
+                RespFrame::BulkString(option) => option.to_ascii_uppercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            // NX and XX are mutually exclusive, so are KEEPTTL and the expire time options.
+            match option.as_slice() {
+                b"NX" if condition.is_none() => condition = Some(SetCondition::IfAbsent),
+                b"XX" if condition.is_none() => condition = Some(SetCondition::IfExists),
+                b"GET" => get = true,
+                b"KEEPTTL" if expiry.is_none() => expiry = Some(Expiry::Keep),
+                b"EX" | b"PX" | b"EXAT" | b"PXAT" if expiry.is_none() => {
+                    let n = match args.next() {
+                        Some(RespFrame::BulkString(n)) => parse_number::<i64>(n, "expire time")?,
+                        _ => {
+                            return Err(CommandError::InvalidArgument(
+                                "Invalid expire time".to_string(),
+                            ))
+                        }
+                    };
+                    expiry = Some(parse_expiry(&option, n)?);
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        Ok(StringSet {
+            key,
+            value,
+            condition: condition.unwrap_or_default(),
+            expiry: expiry.unwrap_or_default(),
+            get,
+        })
+    }
+}
+
+impl CommandExecutor for StringSetEx {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        backend.string_set_with(self.key, self.value, SetCondition::Always, self.expiry);
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for StringSetEx {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SETEX"], Some(3))?;
+
+        let (key, expiry, value) = extract_key_expiry_and_value(value, b"EX")?;
+        Ok(StringSetEx { key, value, expiry })
+    }
+}
+
+impl CommandExecutor for StringPSetEx {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        backend.string_set_with(self.key, self.value, SetCondition::Always, self.expiry);
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for StringPSetEx {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["PSETEX"], Some(3))?;
+
+        let (key, expiry, value) = extract_key_expiry_and_value(value, b"PX")?;
+        Ok(StringPSetEx { key, value, expiry })
+    }
+}
+
+impl CommandExecutor for StringIncr {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.string_incr_by(self.key, 1) {
+            Ok(v) => RespFrame::Integer(v),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StringIncr {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["INCR"], Some(1))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(StringIncr {
+                key: key.try_into()?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl CommandExecutor for StringDecr {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.string_incr_by(self.key, -1) {
+            Ok(v) => RespFrame::Integer(v),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StringDecr {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["DECR"], Some(1))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(StringDecr {
+                key: key.try_into()?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl CommandExecutor for StringIncrBy {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.string_incr_by(self.key, self.delta) {
+            Ok(v) => RespFrame::Integer(v),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StringIncrBy {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["INCRBY"], Some(2))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(delta))) => {
+                Ok(StringIncrBy {
+                    key: key.try_into()?,
+                    delta: parse_number(delta, "increment")?,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or increment".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for StringDecrBy {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match self.delta.checked_neg() {
+            Some(delta) => match backend.string_incr_by(self.key, delta) {
+                Ok(v) => RespFrame::Integer(v),
+                Err(e) => e.into(),
+            },
+            None => crate::BackendError::Overflow.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StringDecrBy {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["DECRBY"], Some(2))?;
 
         let mut args = extract_args(value, 1)?.into_iter();
         match (args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(value)) => Ok(StringSet {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(delta))) => {
+                Ok(StringDecrBy {
+                    key: key.try_into()?,
+                    delta: parse_number(delta, "decrement")?,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or decrement".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for StringIncrByFloat {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.string_incr_by_float(self.key, self.delta) {
+            Ok(v) => BulkString::new(v).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StringIncrByFloat {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["INCRBYFLOAT"], Some(2))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, delta) = match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(delta))) => {
+                (key.try_into()?, parse_number::<f64>(delta, "increment")?)
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid key or increment".to_string(),
+                ))
+            }
+        };
+        if !delta.is_finite() {
+            return Err(CommandError::InvalidArgument(
+                "Invalid increment".to_string(),
+            ));
+        }
+
+        Ok(StringIncrByFloat { key, delta })
+    }
+}
+
+impl CommandExecutor for StringAppend {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.string_append(self.key, &self.value) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for StringAppend {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["APPEND"], Some(2))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(value))) => {
+                Ok(StringAppend {
+                    key: key.try_into()?,
+                    value: value.to_vec(),
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or value".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for StringLen {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.string_len(&self.key) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for StringLen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["STRLEN"], Some(1))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(StringLen {
                 key: key.try_into()?,
-                value,
             }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl CommandExecutor for StringGetRange {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        BulkString::new(backend.string_get_range(&self.key, self.start, self.end)).into()
+    }
+}
+
+impl TryFrom<RespArray> for StringGetRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["GETRANGE"], Some(3))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(start)),
+                Some(RespFrame::BulkString(end)),
+            ) => Ok(StringGetRange {
+                key: key.try_into()?,
+                start: parse_number(start, "start")?,
+                end: parse_number(end, "end")?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, start or end".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for StringSetRange {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.string_set_range(self.key, self.offset, &self.value) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StringSetRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SETRANGE"], Some(3))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(offset)),
+                Some(RespFrame::BulkString(value)),
+            ) => Ok(StringSetRange {
+                key: key.try_into()?,
+                offset: parse_number(offset, "offset")?,
+                value: value.to_vec(),
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, offset or value".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for StringGetBit {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.string_get_bit(&self.key, self.offset) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for StringGetBit {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["GETBIT"], Some(2))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(offset))) => {
+                Ok(StringGetBit {
+                    key: key.try_into()?,
+                    offset: parse_number(offset, "offset")?,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or offset".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for StringSetBit {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.string_set_bit(self.key, self.offset, self.bit) {
+            Ok(bit) => RespFrame::Integer(bit as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StringSetBit {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SETBIT"], Some(3))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(offset)),
+                Some(RespFrame::BulkString(bit)),
+            ) => Ok(StringSetBit {
+                key: key.try_into()?,
+                offset: parse_number(offset, "offset")?,
+                bit: match bit.as_ref() {
+                    b"0" => false,
+                    b"1" => true,
+                    _ => {
+                        return Err(CommandError::InvalidArgument(
+                            "bit is not 0 or 1".to_string(),
+                        ))
+                    }
+                },
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, offset or bit".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for StringSetNx {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.string_set_if_absent(self.key, self.value) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for StringSetNx {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["SETNX"], Some(2))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(value))) => {
+                Ok(StringSetNx {
+                    key: key.try_into()?,
+                    value: value.to_vec(),
+                })
+            }
             _ => Err(CommandError::InvalidArgument(
                 "Invalid key or value".to_string(),
             )),
@@ -53,14 +487,98 @@ impl TryFrom<RespArray> for StringSet {
     }
 }
 
+impl CommandExecutor for StringMultiSetNx {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.string_multi_set_if_absent(self.pairs) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for StringMultiSetNx {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["MSETNX"], None)?;
+
+        let args = extract_args(value, 1)?;
+        if args.is_empty() || args.len() % 2 != 0 {
+            return Err(CommandError::InvalidArgument(
+                "MSETNX command must have key value pairs".to_string(),
+            ));
+        }
+
+        let mut pairs = Vec::with_capacity(args.len() / 2);
+        let mut args = args.into_iter();
+        while let (Some(key), Some(value)) = (args.next(), args.next()) {
+            match (key, value) {
+                (RespFrame::BulkString(key), RespFrame::BulkString(value)) => {
+                    pairs.push((key.try_into()?, value.to_vec()))
+                }
+                _ => {
+                    return Err(CommandError::InvalidArgument(
+                        "Invalid key or value".to_string(),
+                    ))
+                }
+            }
+        }
+
+        Ok(StringMultiSetNx { pairs })
+    }
+}
+
+// Parses "<key> <expire time> <value>" shared by SETEX and PSETEX, the expire time is
+// interpreted like the given SET option.
+fn extract_key_expiry_and_value(
+    value: RespArray,
+    option: &[u8],
+) -> Result<(String, Expiry, Vec<u8>), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    match (args.next(), args.next(), args.next()) {
+        (
+            Some(RespFrame::BulkString(key)),
+            Some(RespFrame::BulkString(n)),
+            Some(RespFrame::BulkString(value)),
+        ) => Ok((
+            key.try_into()?,
+            parse_expiry(option, parse_number(n, "expire time")?)?,
+            value.to_vec(),
+        )),
+        _ => Err(CommandError::InvalidArgument(
+            "Invalid key, expire time or value".to_string(),
+        )),
+    }
+}
+
+// Converts a relative or absolute expire time option into an absolute unix time in milliseconds.
+fn parse_expiry(option: &[u8], n: i64) -> Result<Expiry, CommandError> {
+    let now = now_ms() as i64;
+    let at = match option {
+        b"EX" => n.checked_mul(1000).and_then(|ms| ms.checked_add(now)),
+        b"PX" => n.checked_add(now),
+        b"EXAT" => n.checked_mul(1000),
+        _ => Some(n),
+    };
+    match at {
+        Some(at) if n > 0 => Ok(Expiry::At(at as u64)),
+        _ => Err(CommandError::InvalidArgument(
+            "Invalid expire time".to_string(),
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
-    use bytes::BytesMut;
+    use bytes::{Bytes, BytesMut};
 
-    use crate::{Backend, RespArray, RespDecode, RespFrame};
+    use crate::{
+        now_ms, Backend, BulkString, ExpireCondition, Expiry, ListEnd, RespArray, RespDecode,
+        RespFrame, RespNull, SetCondition, SimpleError,
+    };
 
-    use super::{CommandExecutor, StringGet, StringSet, RESP_OK};
+    use super::{
+        CommandExecutor, StringAppend, StringDecr, StringDecrBy, StringGet, StringGetBit,
+        StringGetRange, StringIncr, StringIncrBy, StringIncrByFloat, StringLen, StringMultiSetNx,
+        StringPSetEx, StringSet, StringSetBit, StringSetEx, StringSetNx, StringSetRange, RESP_OK,
+    };
 
     #[test]
     fn test_get_from_resp_array() -> Result<()> {
@@ -84,7 +602,7 @@ mod tests {
 
         let result: StringSet = frame.try_into()?;
         assert_eq!(result.key, "hello");
-        assert_eq!(result.value, RespFrame::BulkString(b"world".into()));
+        assert_eq!(result.value, b"world");
 
         Ok(())
     }
@@ -94,7 +612,10 @@ mod tests {
         let backend = Backend::new();
         let cmd = StringSet {
             key: "hello".to_string(),
-            value: RespFrame::BulkString(b"world".into()),
+            value: b"world".to_vec(),
+            condition: SetCondition::Always,
+            expiry: Expiry::Persist,
+            get: false,
         };
         let result = cmd.execute(&backend);
         assert_eq!(result, RESP_OK.clone());
@@ -107,4 +628,513 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_set_options_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*7\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n$2\r\nxx\r\n$3\r\nget\r\n$4\r\nexat\r\n$10\r\n2000000000\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StringSet = frame.try_into()?;
+        assert_eq!(result.condition, SetCondition::IfExists);
+        assert_eq!(result.expiry, Expiry::At(2_000_000_000_000));
+        assert!(result.get);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n$2\r\nnx\r\n$2\r\nxx\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(StringSet::try_from(frame).is_err());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n$2\r\nex\r\n$1\r\n0\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(StringSet::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_options_command() -> Result<()> {
+        let backend = Backend::new();
+        let set = |value: &[u8], condition, expiry, get| StringSet {
+            key: "hello".to_string(),
+            value: value.to_vec(),
+            condition,
+            expiry,
+            get,
+        };
+
+        let cmd = set(b"a", SetCondition::IfExists, Expiry::Persist, false);
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+        let cmd = set(b"a", SetCondition::IfAbsent, Expiry::Persist, false);
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        let cmd = set(b"b", SetCondition::IfAbsent, Expiry::Persist, true);
+        assert_eq!(cmd.execute(&backend), RespFrame::BulkString(b"a".into()));
+        assert_eq!(backend.string_get("hello"), Some(b"a".to_vec()));
+
+        // An expire time in the past makes the key disappear right away.
+        let cmd = set(b"b", SetCondition::IfExists, Expiry::At(1), true);
+        assert_eq!(cmd.execute(&backend), RespFrame::BulkString(b"a".into()));
+        assert_eq!(backend.string_get("hello"), None);
+
+        // KEEPTTL keeps the expire time, while a plain SET discards it.
+        let cmd = set(b"c", SetCondition::Always, Expiry::At(u64::MAX), false);
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        let cmd = set(b"d", SetCondition::Always, Expiry::Keep, false);
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.expire.get("hello").map(|at| *at), Some(u64::MAX));
+        let cmd = set(b"e", SetCondition::Always, Expiry::Persist, false);
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert!(backend.expire.get("hello").is_none());
+
+        // A value of another type counts for NX and XX, and is replaced.
+        backend.delete("hello");
+        backend.list_push("hello".to_string(), vec![Bytes::from("x")], ListEnd::Right);
+        let cmd = set(b"f", SetCondition::IfAbsent, Expiry::Persist, false);
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+        let cmd = set(b"f", SetCondition::IfExists, Expiry::Persist, false);
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.list_len("hello"), 0);
+        assert_eq!(backend.string_get("hello"), Some(b"f".to_vec()));
+        assert_eq!(backend.size(), 1);
+
+        // Along with its access data and field expiries, while KEEPTTL keeps its time to live.
+        backend.delete("hello");
+        backend.hash_set(
+            "hello".to_string(),
+            "f".to_string(),
+            BulkString::from("v").into(),
+        );
+        backend.hash_get("hello", "f");
+        let at = now_ms() + 100_000;
+        backend.hash_expire_at("hello", &["f".to_string()], at, ExpireCondition::default());
+        backend.expire_at("hello", at, ExpireCondition::default());
+        let cmd = set(b"f", SetCondition::Always, Expiry::Keep, false);
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.object_freq("hello"), Some(5));
+        assert!(!backend.hash_field_expire.contains_key("hello"));
+        assert_eq!(backend.expire.get("hello").map(|at| *at), Some(at));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_psetex_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$6\r\npsetex\r\n$5\r\nhello\r\n$4\r\n1500\r\n$5\r\nworld\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let before = now_ms();
+        let result: StringPSetEx = frame.try_into()?;
+        assert_eq!(result.key, "hello");
+        assert_eq!(result.value, b"world");
+        match result.expiry {
+            Expiry::At(at) => assert!(at >= before + 1500 && at <= now_ms() + 1500),
+            _ => panic!("expected an expire time"),
+        }
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nsetex\r\n$5\r\nhello\r\n$2\r\n-1\r\n$5\r\nworld\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(StringSetEx::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_setex_command() -> Result<()> {
+        let backend = Backend::new();
+        let cmd = StringSetEx {
+            key: "hello".to_string(),
+            value: b"world".to_vec(),
+            expiry: Expiry::At(u64::MAX),
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.string_get("hello"), Some(b"world".to_vec()));
+        assert_eq!(backend.expire.get("hello").map(|at| *at), Some(u64::MAX));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_incrby_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nincrby\r\n$7\r\ncounter\r\n$2\r\n-5\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StringIncrBy = frame.try_into()?;
+        assert_eq!(result.key, "counter");
+        assert_eq!(result.delta, -5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_incr_decr_commands() -> Result<()> {
+        let backend = Backend::new();
+        let key = "counter".to_string();
+
+        let cmd = StringIncr { key: key.clone() };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = StringIncrBy {
+            key: key.clone(),
+            delta: 10,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(11));
+
+        let cmd = StringDecrBy {
+            key: key.clone(),
+            delta: 20,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(-9));
+
+        let cmd = StringDecr { key: key.clone() };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(-10));
+
+        let cmd = StringGet { key };
+        assert_eq!(cmd.execute(&backend), RespFrame::BulkString(b"-10".into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_incr_invalid_value() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("hello".to_string(), b"world".to_vec());
+
+        let cmd = StringIncr {
+            key: "hello".to_string(),
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR value is not an integer or out of range").into()
+        );
+
+        backend.string_set("max".to_string(), b"9223372036854775807".to_vec());
+        let cmd = StringIncr {
+            key: "max".to_string(),
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR increment or decrement would overflow").into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_incrbyfloat_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("float".to_string(), b"10.50".to_vec());
+
+        let cmd = StringIncrByFloat {
+            key: "float".to_string(),
+            delta: 0.1,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::BulkString(b"10.6".into()));
+
+        let cmd = StringIncrByFloat {
+            key: "float".to_string(),
+            delta: -5.6,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::BulkString(b"5".into()));
+
+        let cmd = StringGet {
+            key: "float".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::BulkString(b"5".into()));
+
+        // The rounding error of the addition is not shown, like Redis.
+        backend.string_set("sum".to_string(), b"0.1".to_vec());
+        let cmd = StringIncrByFloat {
+            key: "sum".to_string(),
+            delta: 0.2,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::BulkString(b"0.3".into()));
+        assert_eq!(backend.string_get("sum"), Some(b"0.3".to_vec()));
+
+        let cmd = StringIncrByFloat {
+            key: "float".to_string(),
+            delta: f64::MAX,
+        };
+        cmd.execute(&backend);
+        let cmd = StringIncrByFloat {
+            key: "float".to_string(),
+            delta: f64::MAX,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR increment would produce NaN or Infinity").into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_strlen_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        let cmd = StringLen {
+            key: "hello".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        let cmd = StringAppend {
+            key: "hello".to_string(),
+            value: b"hello".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(5));
+
+        let cmd = StringAppend {
+            key: "hello".to_string(),
+            value: b" world".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(11));
+
+        let cmd = StringGet {
+            key: "hello".to_string(),
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::BulkString(b"hello world".into())
+        );
+
+        let cmd = StringLen {
+            key: "hello".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(11));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_getrange_setrange_commands() -> Result<()> {
+        let backend = Backend::new();
+        backend.string_set("hello".to_string(), b"This is a string".to_vec());
+
+        for (start, end, expected) in [
+            (0, 3, &b"This"[..]),
+            (-3, -1, b"ing"),
+            (0, -1, b"This is a string"),
+            (10, 100, b"string"),
+            (-100, -15, b"Th"),
+            (5, 3, b""),
+        ] {
+            let cmd = StringGetRange {
+                key: "hello".to_string(),
+                start,
+                end,
+            };
+            assert_eq!(
+                cmd.execute(&backend),
+                RespFrame::BulkString(expected.into())
+            );
+        }
+
+        let cmd = StringSetRange {
+            key: "hello".to_string(),
+            offset: 10,
+            value: b"thing".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(16));
+        assert_eq!(
+            backend.string_get("hello"),
+            Some(b"This is a thingg".to_vec())
+        );
+
+        let cmd = StringSetRange {
+            key: "padded".to_string(),
+            offset: 3,
+            value: b"abc".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(6));
+        assert_eq!(backend.string_get("padded"), Some(b"\0\0\0abc".to_vec()));
+
+        let cmd = StringSetRange {
+            key: "missing".to_string(),
+            offset: 3,
+            value: Vec::new(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert_eq!(backend.string_get("missing"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_msetnx_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$6\r\nmsetnx\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n2\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StringMultiSetNx = frame.try_into()?;
+        assert_eq!(
+            result.pairs,
+            vec![
+                ("a".to_string(), b"1".to_vec()),
+                ("b".to_string(), b"2".to_vec())
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_setnx_msetnx_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        let cmd = StringSetNx {
+            key: "a".to_string(),
+            value: b"1".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = StringSetNx {
+            key: "a".to_string(),
+            value: b"2".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert_eq!(backend.string_get("a"), Some(b"1".to_vec()));
+
+        let cmd = StringMultiSetNx {
+            pairs: vec![
+                ("a".to_string(), b"3".to_vec()),
+                ("b".to_string(), b"3".to_vec()),
+            ],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert_eq!(backend.string_get("b"), None);
+
+        let cmd = StringMultiSetNx {
+            pairs: vec![
+                ("b".to_string(), b"4".to_vec()),
+                ("c".to_string(), b"4".to_vec()),
+            ],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.string_get("b"), Some(b"4".to_vec()));
+        assert_eq!(backend.string_get("c"), Some(b"4".to_vec()));
+
+        // Keys of other types exist too.
+        backend.hash_set(
+            "h".to_string(),
+            "f".to_string(),
+            BulkString::new("v").into(),
+        );
+        let cmd = StringSetNx {
+            key: "h".to_string(),
+            value: b"1".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        let cmd = StringMultiSetNx {
+            pairs: vec![
+                ("d".to_string(), b"5".to_vec()),
+                ("h".to_string(), b"5".to_vec()),
+            ],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert_eq!(backend.string_get("h"), None);
+        assert_eq!(backend.string_get("d"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_setbit_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$6\r\nsetbit\r\n$1\r\nk\r\n$2\r\n10\r\n$1\r\n1\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: StringSetBit = frame.try_into()?;
+        assert_eq!(result.key, "k");
+        assert_eq!(result.offset, 10);
+        assert!(result.bit);
+
+        for invalid in [
+            &b"*4\r\n$6\r\nsetbit\r\n$1\r\nk\r\n$2\r\n-1\r\n$1\r\n1\r\n"[..],
+            b"*4\r\n$6\r\nsetbit\r\n$1\r\nk\r\n$1\r\n0\r\n$1\r\n2\r\n",
+            b"*3\r\n$6\r\nsetbit\r\n$1\r\nk\r\n$1\r\n0\r\n",
+            b"*3\r\n$6\r\ngetbit\r\n$1\r\nk\r\n$1\r\nx\r\n",
+        ] {
+            let mut buf = BytesMut::from(invalid);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_setbit_getbit_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        let cmd = StringSetBit {
+            key: "k".to_string(),
+            offset: 9,
+            bit: true,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert_eq!(backend.string_get("k"), Some(b"\0\x40".to_vec()));
+
+        let cmd = StringSetBit {
+            key: "k".to_string(),
+            offset: 9,
+            bit: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.string_get("k"), Some(b"\0\0".to_vec()));
+
+        // "1" is 0x31, bits 2, 3 and 7 are set.
+        backend.string_set("n".to_string(), b"1".to_vec());
+        for (offset, expected) in [(0, 0), (2, 1), (3, 1), (6, 0), (7, 1), (100, 0)] {
+            let cmd = StringGetBit {
+                key: "n".to_string(),
+                offset,
+            };
+            assert_eq!(cmd.execute(&backend), RespFrame::Integer(expected));
+        }
+        let cmd = StringGetBit {
+            key: "missing".to_string(),
+            offset: 0,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        // Clearing a bit past the end still grows the string.
+        let cmd = StringSetBit {
+            key: "n".to_string(),
+            offset: 23,
+            bit: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert_eq!(backend.string_get("n"), Some(b"1\0\0".to_vec()));
+
+        let cmd = StringSetBit {
+            key: "big".to_string(),
+            offset: 1 << 32,
+            bit: true,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR string exceeds maximum allowed size (proto-max-bulk-len)").into()
+        );
+        assert_eq!(backend.string_get("big"), None);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,2069 @@
+use std::time::Duration;
+
+use crate::{
+    cmd::CommandError, format_score, parse_score, Aggregate, BulkString, LexBound, LexRange,
+    RangeBy, RespArray, RespFrame, RespNull, ScoreCondition, ScoreRange, SetOperation,
+};
+
+use super::{
+    extract_args, extract_key, extract_key_and_count, extract_keys_and_timeout, parse_number,
+    validate_command, BlockingCommand, CommandExecutor, SortedSetAdd, SortedSetBlockingPopMax,
+    SortedSetBlockingPopMin, SortedSetCard, SortedSetCount, SortedSetDiff, SortedSetIncrBy,
+    SortedSetInterStore, SortedSetLexCount, SortedSetMultiScore, SortedSetPopMax, SortedSetPopMin,
+    SortedSetRange, SortedSetRangeStore, SortedSetRank, SortedSetRemove, SortedSetRemoveRangeByLex,
+    SortedSetRemoveRangeByRank, SortedSetRemoveRangeByScore, SortedSetRevRange, SortedSetRevRank,
+    SortedSetScan, SortedSetScore, SortedSetUnionStore, SCAN_DEFAULT_COUNT,
+};
+
+impl CommandExecutor for SortedSetAdd {
+    fn execute(mut self, backend: &crate::Backend) -> RespFrame {
+        if self.incr {
+            let (delta, member) = self.members.pop().expect("one member");
+            return match backend.zset_incr_by_with(self.key, member, delta, self.condition) {
+                Ok(Some(score)) => score_reply(score),
+                Ok(None) => RespFrame::Null(RespNull),
+                Err(e) => e.into(),
+            };
+        }
+        let (added, updated) = backend.zset_add_with(self.key, self.members, self.condition);
+        let count = if self.changed { added + updated } else { added };
+        RespFrame::Integer(count as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetAdd {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZADD"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+        let key = match args.next() {
+            Some(RespFrame::BulkString(key)) => key.try_into()?,
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+
+        // The options come first, up to the first score.
+        let mut condition = ScoreCondition::default();
+        let (mut changed, mut incr) = (false, false);
+        while let Some(RespFrame::BulkString(option)) = args.peek() {
+            match option.to_ascii_uppercase().as_slice() {
+                b"NX" => condition.if_absent = true,
+                b"XX" => condition.if_exists = true,
+                b"GT" => condition.if_greater = true,
+                b"LT" => condition.if_less = true,
+                b"CH" => changed = true,
+                b"INCR" => incr = true,
+                _ => break,
+            }
+            args.next();
+        }
+        if condition.if_absent && condition.if_exists {
+            return Err(CommandError::InvalidArgument(
+                "XX and NX options at the same time are not compatible".to_string(),
+            ));
+        }
+        if [condition.if_absent, condition.if_greater, condition.if_less]
+            .iter()
+            .filter(|&&set| set)
+            .count()
+            > 1
+        {
+            return Err(CommandError::InvalidArgument(
+                "GT, LT, and/or NX options at the same time are not compatible".to_string(),
+            ));
+        }
+
+        let mut members = Vec::new();
+        while let Some(score) = args.next() {
+            match (score, args.next()) {
+                (RespFrame::BulkString(score), Some(RespFrame::BulkString(member))) => {
+                    members.push((extract_score(&score)?, member.try_into()?));
+                }
+                _ => {
+                    return Err(CommandError::InvalidArgument(
+                        "Invalid score or member".to_string(),
+                    ))
+                }
+            }
+        }
+        if members.is_empty() {
+            return Err(CommandError::InvalidArgument(
+                "At least one member is required".to_string(),
+            ));
+        }
+        if incr && members.len() > 1 {
+            return Err(CommandError::InvalidArgument(
+                "INCR option supports a single increment-element pair".to_string(),
+            ));
+        }
+        Ok(SortedSetAdd {
+            key,
+            members,
+            condition,
+            changed,
+            incr,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetScore {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.zset_score(&self.key, &self.member) {
+            Some(score) => score_reply(score),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetScore {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZSCORE"], Some(2))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(member))) => {
+                Ok(SortedSetScore {
+                    key: key.try_into()?,
+                    member: member.try_into()?,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or member".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for SortedSetMultiScore {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let scores: Vec<RespFrame> = backend
+            .zset_scores(&self.key, &self.members)
+            .into_iter()
+            .map(|score| score.map_or(RespFrame::Null(RespNull), score_reply))
+            .collect();
+        RespArray::new(scores).into()
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetMultiScore {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZMSCORE"], None)?;
+
+        let (key, members) = extract_key_and_members(value)?;
+        Ok(SortedSetMultiScore { key, members })
+    }
+}
+
+impl CommandExecutor for SortedSetIncrBy {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match backend.zset_incr_by(self.key, self.member, self.delta) {
+            Ok(score) => score_reply(score),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetIncrBy {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZINCRBY"], Some(3))?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(delta)),
+                Some(RespFrame::BulkString(member)),
+            ) => Ok(SortedSetIncrBy {
+                key: key.try_into()?,
+                delta: extract_score(&delta)?,
+                member: member.try_into()?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, increment or member".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for SortedSetCard {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.zset_card(&self.key) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetCard {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZCARD"], Some(1))?;
+
+        Ok(SortedSetCard {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetCount {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.zset_count_by_score(&self.key, &self.range) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetCount {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZCOUNT"], Some(3))?;
+
+        let (key, min, max) = extract_key_and_bounds(value)?;
+        Ok(SortedSetCount {
+            key,
+            range: parse_score_range(&min, &max)?,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetLexCount {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.zset_count_by_lex(&self.key, &self.range) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetLexCount {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZLEXCOUNT"], Some(3))?;
+
+        let (key, min, max) = extract_key_and_bounds(value)?;
+        Ok(SortedSetLexCount {
+            key,
+            range: parse_lex_range(&min, &max)?,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetRange {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let members = backend.zset_range(&self.key, self.start, self.end, self.rev);
+        members_reply(members, self.with_scores)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZRANGE"], None)?;
+
+        let (key, start, end, options) = extract_key_and_range(value)?;
+        let (mut rev, mut with_scores) = (false, false);
+        for option in options {
+            match option.to_ascii_uppercase().as_slice() {
+                b"REV" => rev = true,
+                b"WITHSCORES" => with_scores = true,
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+        Ok(SortedSetRange {
+            key,
+            start,
+            end,
+            rev,
+            with_scores,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetRangeStore {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let len = backend.zset_range_store(
+            &self.destination,
+            &self.source,
+            &self.by,
+            self.rev,
+            self.limit,
+        );
+        RespFrame::Integer(len as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetRangeStore {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZRANGESTORE"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (destination, source, min, max) =
+            match (args.next(), args.next(), args.next(), args.next()) {
+                (
+                    Some(RespFrame::BulkString(destination)),
+                    Some(RespFrame::BulkString(source)),
+                    Some(RespFrame::BulkString(min)),
+                    Some(RespFrame::BulkString(max)),
+                ) => (destination.try_into()?, source.try_into()?, min, max),
+                _ => {
+                    return Err(CommandError::InvalidArgument(
+                        "Invalid destination, source or range".to_string(),
+                    ))
+                }
+            };
+
+        let (mut by_score, mut by_lex, mut rev, mut limit) = (false, false, false, None);
+        while let Some(option) = args.next() {
+            let RespFrame::BulkString(option) = option else {
+                return Err(CommandError::InvalidArgument("Invalid option".to_string()));
+            };
+            match option.to_ascii_uppercase().as_slice() {
+                b"BYSCORE" => by_score = true,
+                b"BYLEX" => by_lex = true,
+                b"REV" => rev = true,
+                b"LIMIT" => match (args.next(), args.next()) {
+                    (Some(RespFrame::BulkString(offset)), Some(RespFrame::BulkString(count))) => {
+                        limit = Some((
+                            parse_number(offset, "offset")?,
+                            parse_number(count, "count")?,
+                        ));
+                    }
+                    _ => return Err(CommandError::InvalidArgument("Invalid LIMIT".to_string())),
+                },
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        // With REV, score and lex ranges are given from the highest bound, unlike ranks.
+        let (min, max) = if rev && (by_score || by_lex) {
+            (max, min)
+        } else {
+            (min, max)
+        };
+        let by = match (by_score, by_lex) {
+            (false, false) if limit.is_some() => {
+                return Err(CommandError::InvalidArgument(
+                    "LIMIT is only supported in combination with either BYSCORE or BYLEX"
+                        .to_string(),
+                ))
+            }
+            (false, false) => {
+                RangeBy::Rank(parse_number(min, "start")?, parse_number(max, "stop")?)
+            }
+            (true, false) => RangeBy::Score(parse_score_range(&min, &max)?),
+            (false, true) => RangeBy::Lex(parse_lex_range(&min, &max)?),
+            (true, true) => {
+                return Err(CommandError::InvalidArgument(
+                    "BYSCORE and BYLEX options at the same time are not compatible".to_string(),
+                ))
+            }
+        };
+        Ok(SortedSetRangeStore {
+            destination,
+            source,
+            by,
+            rev,
+            limit,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetRevRange {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let members = backend.zset_range(&self.key, self.start, self.end, true);
+        members_reply(members, self.with_scores)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetRevRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZREVRANGE"], None)?;
+
+        let (key, start, end, options) = extract_key_and_range(value)?;
+        let with_scores = match options.as_slice() {
+            [] => false,
+            [option] if option.eq_ignore_ascii_case(b"WITHSCORES") => true,
+            _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+        };
+        Ok(SortedSetRevRange {
+            key,
+            start,
+            end,
+            with_scores,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetRank {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        rank_reply(
+            backend.zset_rank(&self.key, &self.member, false),
+            self.with_score,
+        )
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetRank {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZRANK"], None)?;
+
+        let (key, member, with_score) = extract_rank_args(value)?;
+        Ok(SortedSetRank {
+            key,
+            member,
+            with_score,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetRevRank {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        rank_reply(
+            backend.zset_rank(&self.key, &self.member, true),
+            self.with_score,
+        )
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetRevRank {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZREVRANK"], None)?;
+
+        let (key, member, with_score) = extract_rank_args(value)?;
+        Ok(SortedSetRevRank {
+            key,
+            member,
+            with_score,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetPopMin {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        pop(backend, &self.key, self.count, false)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetPopMin {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZPOPMIN"], None)?;
+
+        let (key, count) = extract_key_and_count(value)?;
+        Ok(SortedSetPopMin { key, count })
+    }
+}
+
+impl CommandExecutor for SortedSetPopMax {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        pop(backend, &self.key, self.count, true)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetPopMax {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZPOPMAX"], None)?;
+
+        let (key, count) = extract_key_and_count(value)?;
+        Ok(SortedSetPopMax { key, count })
+    }
+}
+
+impl BlockingCommand for SortedSetBlockingPopMin {
+    fn keys(&self) -> Vec<String> {
+        self.keys.clone()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn try_execute(&self, backend: &crate::Backend) -> Option<RespFrame> {
+        blocking_pop(backend, &self.keys, false)
+    }
+}
+
+impl CommandExecutor for SortedSetBlockingPopMin {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        self.try_execute(backend)
+            .unwrap_or_else(|| self.timeout_reply())
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetBlockingPopMin {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["BZPOPMIN"], None)?;
+
+        let (keys, timeout) = extract_keys_and_timeout(value)?;
+        Ok(SortedSetBlockingPopMin { keys, timeout })
+    }
+}
+
+impl BlockingCommand for SortedSetBlockingPopMax {
+    fn keys(&self) -> Vec<String> {
+        self.keys.clone()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn try_execute(&self, backend: &crate::Backend) -> Option<RespFrame> {
+        blocking_pop(backend, &self.keys, true)
+    }
+}
+
+impl CommandExecutor for SortedSetBlockingPopMax {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        self.try_execute(backend)
+            .unwrap_or_else(|| self.timeout_reply())
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetBlockingPopMax {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["BZPOPMAX"], None)?;
+
+        let (keys, timeout) = extract_keys_and_timeout(value)?;
+        Ok(SortedSetBlockingPopMax { keys, timeout })
+    }
+}
+
+impl CommandExecutor for SortedSetScan {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let (cursor, members) = backend.zset_scan(
+            &self.key,
+            self.cursor,
+            self.pattern.as_deref().map(str::as_bytes),
+            self.count,
+        );
+        RespArray::new(vec![
+            BulkString::new(cursor.to_string()).into(),
+            members_reply(members, true),
+        ])
+        .into()
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetScan {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZSCAN"], None)?;
+
+        // Parse the key and the cursor.
+        let mut args = extract_args(value, 1)?.into_iter();
+        let mut scan = match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(cursor))) => {
+                SortedSetScan {
+                    key: key.try_into()?,
+                    cursor: parse_number(cursor, "cursor")?,
+                    pattern: None,
+                    count: SCAN_DEFAULT_COUNT,
+                }
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid key or cursor".to_string(),
+                ))
+            }
+        };
+
+        // Parse the options, MATCH and COUNT take a value.
+        while let Some(arg) = args.next() {
+            let option = match arg {
+                RespFrame::BulkString(option) => option.to_ascii_uppercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                b"MATCH" => match args.next() {
+                    Some(RespFrame::BulkString(pattern)) => {
+                        scan.pattern = Some(pattern.try_into()?)
+                    }
+                    _ => return Err(CommandError::InvalidArgument("Invalid pattern".to_string())),
+                },
+                b"COUNT" => {
+                    scan.count = match args.next() {
+                        Some(RespFrame::BulkString(count)) => parse_number(count, "count")?,
+                        _ => {
+                            return Err(CommandError::InvalidArgument("Invalid count".to_string()))
+                        }
+                    };
+                    if scan.count == 0 {
+                        return Err(CommandError::InvalidArgument(
+                            "count should be larger than 0".to_string(),
+                        ));
+                    }
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            }
+        }
+
+        Ok(scan)
+    }
+}
+
+impl CommandExecutor for SortedSetUnionStore {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let len = backend.zset_combine_store(
+            SetOperation::Union,
+            &self.destination,
+            &self.keys,
+            &self.weights,
+            self.aggregate,
+        );
+        RespFrame::Integer(len as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetUnionStore {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZUNIONSTORE"], None)?;
+
+        let (destination, keys, weights, aggregate) = extract_store_args(value)?;
+        Ok(SortedSetUnionStore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetInterStore {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let len = backend.zset_combine_store(
+            SetOperation::Inter,
+            &self.destination,
+            &self.keys,
+            &self.weights,
+            self.aggregate,
+        );
+        RespFrame::Integer(len as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetInterStore {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZINTERSTORE"], None)?;
+
+        let (destination, keys, weights, aggregate) = extract_store_args(value)?;
+        Ok(SortedSetInterStore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetDiff {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let weights = vec![1.0; self.keys.len()];
+        let members =
+            backend.zset_combine(SetOperation::Diff, &self.keys, &weights, Aggregate::Sum);
+        members_reply(members, self.with_scores)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetDiff {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZDIFF"], None)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let keys = extract_num_keys(&mut args)?;
+        let with_scores = match (args.next(), args.next()) {
+            (None, None) => false,
+            (Some(RespFrame::BulkString(option)), None)
+                if option.eq_ignore_ascii_case(b"WITHSCORES") =>
+            {
+                true
+            }
+            _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+        };
+        Ok(SortedSetDiff { keys, with_scores })
+    }
+}
+
+impl CommandExecutor for SortedSetRemove {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.zset_remove(&self.key, &self.members) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetRemove {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZREM"], None)?;
+
+        let (key, members) = extract_key_and_members(value)?;
+        Ok(SortedSetRemove { key, members })
+    }
+}
+
+impl CommandExecutor for SortedSetRemoveRangeByRank {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let removed = backend.zset_remove_range_by_rank(&self.key, self.start, self.end);
+        RespFrame::Integer(removed as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetRemoveRangeByRank {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZREMRANGEBYRANK"], Some(3))?;
+
+        let (key, start, end, _) = extract_key_and_range(value)?;
+        Ok(SortedSetRemoveRangeByRank { key, start, end })
+    }
+}
+
+impl CommandExecutor for SortedSetRemoveRangeByScore {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let removed = backend.zset_remove_range_by_score(&self.key, &self.range);
+        RespFrame::Integer(removed as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetRemoveRangeByScore {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZREMRANGEBYSCORE"], Some(3))?;
+
+        let (key, min, max) = extract_key_and_bounds(value)?;
+        Ok(SortedSetRemoveRangeByScore {
+            key,
+            range: parse_score_range(&min, &max)?,
+        })
+    }
+}
+
+impl CommandExecutor for SortedSetRemoveRangeByLex {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let removed = backend.zset_remove_range_by_lex(&self.key, &self.range);
+        RespFrame::Integer(removed as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SortedSetRemoveRangeByLex {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ZREMRANGEBYLEX"], Some(3))?;
+
+        let (key, min, max) = extract_key_and_bounds(value)?;
+        Ok(SortedSetRemoveRangeByLex {
+            key,
+            range: parse_lex_range(&min, &max)?,
+        })
+    }
+}
+
+// Pops the members with the lowest scores, or the highest with `rev`, replying them each followed
+// by its score. Without a count, at most one member is popped. Empty if the key does not exist.
+fn pop(backend: &crate::Backend, key: &str, count: Option<usize>, rev: bool) -> RespFrame {
+    let members = backend.zset_pop(key, count.unwrap_or(1), rev);
+    members_reply(members, true)
+}
+
+// Pops from the first of the sorted sets that exists, replying its key along with the member
+// and its score.
+fn blocking_pop(backend: &crate::Backend, keys: &[String], rev: bool) -> Option<RespFrame> {
+    keys.iter().find_map(|key| {
+        let (score, member) = backend.zset_pop(key, 1, rev).pop()?;
+        Some(
+            RespArray::new(vec![
+                BulkString::new(key.as_str()).into(),
+                BulkString::new(member).into(),
+                score_reply(score),
+            ])
+            .into(),
+        )
+    })
+}
+
+// The rank, along with the score as an array with WITHSCORE. Null if there is no such member.
+fn rank_reply(rank: Option<(usize, f64)>, with_score: bool) -> RespFrame {
+    match rank {
+        Some((rank, score)) if with_score => {
+            RespArray::new(vec![RespFrame::Integer(rank as i64), score_reply(score)]).into()
+        }
+        Some((rank, _)) => RespFrame::Integer(rank as i64),
+        None => RespFrame::Null(RespNull),
+    }
+}
+
+// The members in order, each followed by its score with WITHSCORES.
+fn members_reply(members: Vec<(f64, String)>, with_scores: bool) -> RespFrame {
+    let mut frames = Vec::with_capacity(members.len() * (1 + with_scores as usize));
+    for (score, member) in members {
+        frames.push(BulkString::new(member).into());
+        if with_scores {
+            frames.push(score_reply(score));
+        }
+    }
+    RespArray::new(frames).into()
+}
+
+// Scores are replied as bulk strings, formatted like Redis does.
+fn score_reply(score: f64) -> RespFrame {
+    BulkString::new(format_score(score)).into()
+}
+
+// Parses "<key> <start> <stop> [option ...]" shared by ZRANGE and ZREVRANGE.
+fn extract_key_and_range(
+    value: RespArray,
+) -> Result<(String, i64, i64, Vec<BulkString>), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    let (key, start, end) = match (args.next(), args.next(), args.next()) {
+        (
+            Some(RespFrame::BulkString(key)),
+            Some(RespFrame::BulkString(start)),
+            Some(RespFrame::BulkString(end)),
+        ) => (
+            key.try_into()?,
+            parse_number(start, "start")?,
+            parse_number(end, "stop")?,
+        ),
+        _ => {
+            return Err(CommandError::InvalidArgument(
+                "Invalid key or range".to_string(),
+            ))
+        }
+    };
+
+    let mut options = Vec::new();
+    for arg in args {
+        match arg {
+            RespFrame::BulkString(option) => options.push(option),
+            _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+        }
+    }
+    Ok((key, start, end, options))
+}
+
+// Parses "<key> <member> [WITHSCORE]" shared by ZRANK and ZREVRANK.
+fn extract_rank_args(value: RespArray) -> Result<(String, String, bool), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    match (args.next(), args.next(), args.next(), args.next()) {
+        (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(member)), None, None) => {
+            Ok((key.try_into()?, member.try_into()?, false))
+        }
+        (
+            Some(RespFrame::BulkString(key)),
+            Some(RespFrame::BulkString(member)),
+            Some(RespFrame::BulkString(option)),
+            None,
+        ) if option.eq_ignore_ascii_case(b"WITHSCORE") => {
+            Ok((key.try_into()?, member.try_into()?, true))
+        }
+        _ => Err(CommandError::InvalidArgument(
+            "Invalid key, member or option".to_string(),
+        )),
+    }
+}
+
+// Parses "<destination> <numkeys> <key> [<key> ...] [WEIGHTS <weight> ...]
+// [AGGREGATE <SUM | MIN | MAX>]" shared by ZUNIONSTORE and ZINTERSTORE. Weights default to 1.
+fn extract_store_args(
+    value: RespArray,
+) -> Result<(String, Vec<String>, Vec<f64>, Aggregate), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    let destination = match args.next() {
+        Some(RespFrame::BulkString(destination)) => destination.try_into()?,
+        _ => {
+            return Err(CommandError::InvalidArgument(
+                "Invalid destination".to_string(),
+            ))
+        }
+    };
+    let keys = extract_num_keys(&mut args)?;
+
+    let mut weights = vec![1.0; keys.len()];
+    let mut aggregate = Aggregate::default();
+    while let Some(option) = args.next() {
+        let RespFrame::BulkString(option) = option else {
+            return Err(CommandError::InvalidArgument("Invalid option".to_string()));
+        };
+        match option.to_ascii_uppercase().as_slice() {
+            b"WEIGHTS" => {
+                for weight in weights.iter_mut() {
+                    *weight = match args.next() {
+                        Some(RespFrame::BulkString(value)) => parse_score(&value).ok(),
+                        _ => None,
+                    }
+                    .ok_or_else(|| {
+                        CommandError::InvalidArgument("weight value is not a float".to_string())
+                    })?;
+                }
+            }
+            b"AGGREGATE" => {
+                aggregate = match args.next() {
+                    Some(RespFrame::BulkString(value)) if value.eq_ignore_ascii_case(b"SUM") => {
+                        Aggregate::Sum
+                    }
+                    Some(RespFrame::BulkString(value)) if value.eq_ignore_ascii_case(b"MIN") => {
+                        Aggregate::Min
+                    }
+                    Some(RespFrame::BulkString(value)) if value.eq_ignore_ascii_case(b"MAX") => {
+                        Aggregate::Max
+                    }
+                    _ => {
+                        return Err(CommandError::InvalidArgument(
+                            "Invalid aggregate".to_string(),
+                        ))
+                    }
+                };
+            }
+            _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+        }
+    }
+    Ok((destination, keys, weights, aggregate))
+}
+
+// Parses "<numkeys> <key> [<key> ...]", leaving the arguments that follow.
+fn extract_num_keys(
+    args: &mut impl Iterator<Item = RespFrame>,
+) -> Result<Vec<String>, CommandError> {
+    let num_keys: usize = match args.next() {
+        Some(RespFrame::BulkString(num_keys)) => parse_number(num_keys, "numkeys")?,
+        _ => return Err(CommandError::InvalidArgument("Invalid numkeys".to_string())),
+    };
+    if num_keys == 0 {
+        return Err(CommandError::InvalidArgument(
+            "at least 1 input key is needed".to_string(),
+        ));
+    }
+
+    let mut keys = Vec::new();
+    for _ in 0..num_keys {
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => keys.push(key.try_into()?),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+    Ok(keys)
+}
+
+// Parses "<key> <member> [<member> ...]" shared by ZMSCORE and ZREM.
+fn extract_key_and_members(value: RespArray) -> Result<(String, Vec<String>), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = match args.next() {
+        Some(RespFrame::BulkString(key)) => key.try_into()?,
+        _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    };
+
+    let mut members = Vec::new();
+    for arg in args {
+        match arg {
+            RespFrame::BulkString(member) => members.push(member.try_into()?),
+            _ => return Err(CommandError::InvalidArgument("Invalid member".to_string())),
+        }
+    }
+    if members.is_empty() {
+        return Err(CommandError::InvalidArgument(
+            "At least one member is required".to_string(),
+        ));
+    }
+    Ok((key, members))
+}
+
+// Parses "<key> <min> <max>" shared by the commands taking a score or lex range.
+fn extract_key_and_bounds(
+    value: RespArray,
+) -> Result<(String, BulkString, BulkString), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    match (args.next(), args.next(), args.next()) {
+        (
+            Some(RespFrame::BulkString(key)),
+            Some(RespFrame::BulkString(min)),
+            Some(RespFrame::BulkString(max)),
+        ) => Ok((key.try_into()?, min, max)),
+        _ => Err(CommandError::InvalidArgument(
+            "Invalid key or range".to_string(),
+        )),
+    }
+}
+
+// Each bound is a score, exclusive when prefixed with "(", like "(1.5" or "-inf".
+fn parse_score_range(min: &[u8], max: &[u8]) -> Result<ScoreRange, CommandError> {
+    let bound = |value: &[u8]| {
+        let (value, exclusive) = match value.strip_prefix(b"(") {
+            Some(value) => (value, true),
+            None => (value, false),
+        };
+        parse_score(value)
+            .map(|score| (score, exclusive))
+            .map_err(|_| CommandError::InvalidArgument("min or max is not a float".to_string()))
+    };
+    let ((min, min_exclusive), (max, max_exclusive)) = (bound(min)?, bound(max)?);
+    Ok(ScoreRange {
+        min,
+        min_exclusive,
+        max,
+        max_exclusive,
+    })
+}
+
+// Each bound is "-", "+", or a member prefixed with "[" when inclusive or "(" when exclusive.
+fn parse_lex_range(min: &[u8], max: &[u8]) -> Result<LexRange, CommandError> {
+    let bound = |value: &[u8]| {
+        let member = |member: &[u8]| String::from_utf8(member.to_vec()).ok();
+        match value {
+            b"-" => Some(LexBound::NegInfinity),
+            b"+" => Some(LexBound::PosInfinity),
+            [b'[', rest @ ..] => member(rest).map(LexBound::Inclusive),
+            [b'(', rest @ ..] => member(rest).map(LexBound::Exclusive),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            CommandError::InvalidArgument("min or max not valid string range item".to_string())
+        })
+    };
+    Ok(LexRange {
+        min: bound(min)?,
+        max: bound(max)?,
+    })
+}
+
+// NaN is rejected, infinities are accepted.
+fn extract_score(value: &BulkString) -> Result<f64, CommandError> {
+    parse_score(value).map_err(|_| CommandError::InvalidArgument("Invalid score".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        Aggregate, BackendError, BulkString, LexBound, LexRange, RangeBy, RespArray, RespDecode,
+        RespFrame, RespNull, ScoreCondition, ScoreRange,
+    };
+
+    use super::{
+        CommandExecutor, SortedSetAdd, SortedSetBlockingPopMax, SortedSetBlockingPopMin,
+        SortedSetCard, SortedSetCount, SortedSetDiff, SortedSetIncrBy, SortedSetInterStore,
+        SortedSetLexCount, SortedSetMultiScore, SortedSetPopMax, SortedSetPopMin, SortedSetRange,
+        SortedSetRangeStore, SortedSetRank, SortedSetRemove, SortedSetRemoveRangeByLex,
+        SortedSetRemoveRangeByRank, SortedSetRemoveRangeByScore, SortedSetRevRange,
+        SortedSetRevRank, SortedSetScan, SortedSetScore, SortedSetUnionStore,
+    };
+
+    #[test]
+    fn test_zadd_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$4\r\nzadd\r\n$4\r\nzset\r\n$3\r\n1.5\r\n$1\r\na\r\n$4\r\n-inf\r\n$1\r\nb\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetAdd = frame.try_into()?;
+        assert_eq!(result.key, "zset");
+        assert_eq!(
+            result.members,
+            [(1.5, "a".to_string()), (f64::NEG_INFINITY, "b".to_string())]
+        );
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*7\r\n$4\r\nzadd\r\n$4\r\nzset\r\n$2\r\nxx\r\n$2\r\ngt\r\n$2\r\nch\r\n\
+            $1\r\n1\r\n$2\r\nnx\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SortedSetAdd = frame.try_into()?;
+        let expected = ScoreCondition {
+            if_exists: true,
+            if_greater: true,
+            ..Default::default()
+        };
+        assert_eq!(result.condition, expected);
+        assert!(result.changed && !result.incr);
+        // Options come before the scores, so a member may be named like one.
+        assert_eq!(result.members, [(1.0, "nx".to_string())]);
+
+        for args in [
+            &b"*4\r\n$4\r\nzadd\r\n$4\r\nzset\r\n$3\r\nnan\r\n$1\r\na\r\n"[..],
+            b"*6\r\n$4\r\nzadd\r\n$4\r\nzset\r\n$2\r\nnx\r\n$2\r\nxx\r\n$1\r\n1\r\n$1\r\na\r\n",
+            b"*6\r\n$4\r\nzadd\r\n$4\r\nzset\r\n$2\r\nnx\r\n$2\r\ngt\r\n$1\r\n1\r\n$1\r\na\r\n",
+            b"*6\r\n$4\r\nzadd\r\n$4\r\nzset\r\n$2\r\nlt\r\n$2\r\ngt\r\n$1\r\n1\r\n$1\r\na\r\n",
+            b"*7\r\n$4\r\nzadd\r\n$4\r\nzset\r\n$4\r\nincr\r\n$1\r\n1\r\n$1\r\na\r\n$1\r\n2\r\n$1\r\nb\r\n",
+            b"*4\r\n$4\r\nzadd\r\n$4\r\nzset\r\n$1\r\nx\r\n$1\r\na\r\n",
+            b"*3\r\n$4\r\nzadd\r\n$4\r\nzset\r\n$1\r\n1\r\n",
+            b"*2\r\n$4\r\nzadd\r\n$4\r\nzset\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(SortedSetAdd::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zscore_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nzscore\r\n$4\r\nzset\r\n$1\r\na\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetScore = frame.try_into()?;
+        assert_eq!(result.key, "zset");
+        assert_eq!(result.member, "a");
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$5\r\nzcard\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(SortedSetCard::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zmscore_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$7\r\nzmscore\r\n$4\r\nzset\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetMultiScore = frame.try_into()?;
+        assert_eq!(result.key, "zset");
+        assert_eq!(result.members, ["a", "b"]);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$7\r\nzmscore\r\n$4\r\nzset\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(SortedSetMultiScore::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zmscore_command() {
+        let backend = crate::Backend::new();
+        backend.zset_add("zset".to_string(), vec![(1.5, "a".to_string())]);
+        let zmscore = |key: &str| {
+            SortedSetMultiScore {
+                key: key.to_string(),
+                members: vec!["a".to_string(), "missing".to_string()],
+            }
+            .execute(&backend)
+        };
+
+        let expected = RespArray::new(vec![
+            BulkString::new("1.5").into(),
+            RespFrame::Null(RespNull),
+        ]);
+        assert_eq!(zmscore("zset"), expected.into());
+        let expected = RespArray::new(vec![RespFrame::Null(RespNull), RespFrame::Null(RespNull)]);
+        assert_eq!(zmscore("missing"), expected.into());
+    }
+
+    #[test]
+    fn test_zincrby_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$7\r\nzincrby\r\n$4\r\nzset\r\n$2\r\n-2\r\n$1\r\na\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetIncrBy = frame.try_into()?;
+        assert_eq!(result.key, "zset");
+        assert_eq!(result.delta, -2.0);
+        assert_eq!(result.member, "a");
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$7\r\nzincrby\r\n$4\r\nzset\r\n$3\r\nnan\r\n$1\r\na\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(SortedSetIncrBy::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zincrby_command() {
+        let backend = crate::Backend::new();
+        let zincrby = |delta: f64| {
+            SortedSetIncrBy {
+                key: "zset".to_string(),
+                delta,
+                member: "a".to_string(),
+            }
+            .execute(&backend)
+        };
+
+        // A missing member is added with the increment as its score.
+        assert_eq!(zincrby(1.5), BulkString::new("1.5").into());
+        assert_eq!(zincrby(-3.0), BulkString::new("-1.5").into());
+        assert_eq!(backend.zset_score("zset", "a"), Some(-1.5));
+
+        assert_eq!(zincrby(f64::INFINITY), BulkString::new("inf").into());
+        assert_eq!(zincrby(f64::NEG_INFINITY), BackendError::ScoreNan.into());
+        assert_eq!(backend.zset_score("zset", "a"), Some(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_zcount_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$6\r\nzcount\r\n$4\r\nzset\r\n$4\r\n-inf\r\n$2\r\n(2\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetCount = frame.try_into()?;
+        assert_eq!(result.key, "zset");
+        let expected = ScoreRange {
+            min: f64::NEG_INFINITY,
+            min_exclusive: false,
+            max: 2.0,
+            max_exclusive: true,
+        };
+        assert_eq!(result.range, expected);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$9\r\nzlexcount\r\n$4\r\nzset\r\n$2\r\n[b\r\n$1\r\n+\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SortedSetLexCount = frame.try_into()?;
+        let expected = LexRange {
+            min: LexBound::Inclusive("b".to_string()),
+            max: LexBound::PosInfinity,
+        };
+        assert_eq!(result.range, expected);
+
+        for args in [
+            &b"*4\r\n$6\r\nzcount\r\n$4\r\nzset\r\n$1\r\nx\r\n$1\r\n1\r\n"[..],
+            b"*4\r\n$9\r\nzlexcount\r\n$4\r\nzset\r\n$1\r\nb\r\n$1\r\n+\r\n",
+            b"*3\r\n$6\r\nzcount\r\n$4\r\nzset\r\n$1\r\n1\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zcount_commands() {
+        let backend = crate::Backend::new();
+        let members = [(1.0, "a"), (2.0, "b"), (2.0, "c"), (3.0, "d")]
+            .map(|(score, member)| (score, member.to_string()))
+            .to_vec();
+        backend.zset_add("zset".to_string(), members);
+        let zcount = |key: &str, min, min_exclusive, max, max_exclusive| {
+            SortedSetCount {
+                key: key.to_string(),
+                range: ScoreRange {
+                    min,
+                    min_exclusive,
+                    max,
+                    max_exclusive,
+                },
+            }
+            .execute(&backend)
+        };
+        assert_eq!(
+            zcount("zset", 2.0, false, 3.0, false),
+            RespFrame::Integer(3)
+        );
+        assert_eq!(zcount("zset", 1.0, true, 3.0, true), RespFrame::Integer(2));
+        assert_eq!(
+            zcount("zset", 3.0, false, 1.0, false),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            zcount("missing", 0.0, false, 9.0, false),
+            RespFrame::Integer(0)
+        );
+
+        let backend = crate::Backend::new();
+        let members = ["a", "b", "c", "d"]
+            .map(|member| (0.0, member.to_string()))
+            .to_vec();
+        backend.zset_add("zset".to_string(), members);
+        let zlexcount = |min, max| {
+            SortedSetLexCount {
+                key: "zset".to_string(),
+                range: LexRange { min, max },
+            }
+            .execute(&backend)
+        };
+        assert_eq!(
+            zlexcount(LexBound::NegInfinity, LexBound::PosInfinity),
+            RespFrame::Integer(4)
+        );
+        assert_eq!(
+            zlexcount(
+                LexBound::Exclusive("a".to_string()),
+                LexBound::Inclusive("c".to_string())
+            ),
+            RespFrame::Integer(2)
+        );
+    }
+
+    #[test]
+    fn test_zrange_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$6\r\nzrange\r\n$4\r\nzset\r\n$1\r\n0\r\n$2\r\n-1\r\n$10\r\nwithscores\r\n$3\r\nREV\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetRange = frame.try_into()?;
+        assert_eq!(result.key, "zset");
+        assert_eq!((result.start, result.end), (0, -1));
+        assert!(result.rev);
+        assert!(result.with_scores);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$9\r\nzrevrange\r\n$4\r\nzset\r\n$1\r\n1\r\n$1\r\n2\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SortedSetRevRange = frame.try_into()?;
+        assert_eq!((result.start, result.end), (1, 2));
+        assert!(!result.with_scores);
+
+        for args in [
+            &b"*5\r\n$9\r\nzrevrange\r\n$4\r\nzset\r\n$1\r\n0\r\n$1\r\n1\r\n$3\r\nrev\r\n"[..],
+            b"*4\r\n$6\r\nzrange\r\n$4\r\nzset\r\n$1\r\nx\r\n$1\r\n1\r\n",
+            b"*3\r\n$6\r\nzrange\r\n$4\r\nzset\r\n$1\r\n0\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zrange_commands() {
+        let backend = crate::Backend::new();
+        let members = [(1.0, "a"), (2.0, "b"), (3.0, "c")]
+            .map(|(score, member)| (score, member.to_string()))
+            .to_vec();
+        backend.zset_add("zset".to_string(), members);
+        let expected = |items: &[&str]| -> RespFrame {
+            let items: Vec<RespFrame> = items.iter().map(|i| BulkString::new(*i).into()).collect();
+            RespArray::new(items).into()
+        };
+
+        let cmd = SortedSetRange {
+            key: "zset".to_string(),
+            start: 0,
+            end: -1,
+            rev: false,
+            with_scores: false,
+        };
+        assert_eq!(cmd.execute(&backend), expected(&["a", "b", "c"]));
+        let cmd = SortedSetRange {
+            key: "zset".to_string(),
+            start: 1,
+            end: 5,
+            rev: false,
+            with_scores: true,
+        };
+        assert_eq!(cmd.execute(&backend), expected(&["b", "2", "c", "3"]));
+
+        // Ranks count from the highest score in reverse.
+        let cmd = SortedSetRange {
+            key: "zset".to_string(),
+            start: 0,
+            end: 1,
+            rev: true,
+            with_scores: false,
+        };
+        assert_eq!(cmd.execute(&backend), expected(&["c", "b"]));
+        let cmd = SortedSetRevRange {
+            key: "zset".to_string(),
+            start: -1,
+            end: -1,
+            with_scores: true,
+        };
+        assert_eq!(cmd.execute(&backend), expected(&["a", "1"]));
+
+        let cmd = SortedSetRevRange {
+            key: "missing".to_string(),
+            start: 0,
+            end: -1,
+            with_scores: false,
+        };
+        assert_eq!(cmd.execute(&backend), expected(&[]));
+    }
+
+    #[test]
+    fn test_zrangestore_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*10\r\n$11\r\nzrangestore\r\n$3\r\ndst\r\n$3\r\nsrc\r\n$1\r\n5\r\n$2\r\n(1\r\n\
+            $7\r\nbyscore\r\n$3\r\nrev\r\n$5\r\nlimit\r\n$1\r\n1\r\n$1\r\n2\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetRangeStore = frame.try_into()?;
+        assert_eq!(result.destination, "dst");
+        assert_eq!(result.source, "src");
+        // With REV, the score range is given from the highest bound.
+        let expected = RangeBy::Score(ScoreRange {
+            min: 1.0,
+            min_exclusive: true,
+            max: 5.0,
+            max_exclusive: false,
+        });
+        assert_eq!(result.by, expected);
+        assert!(result.rev);
+        assert_eq!(result.limit, Some((1, 2)));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$11\r\nzrangestore\r\n$3\r\ndst\r\n$3\r\nsrc\r\n$1\r\n0\r\n$2\r\n-1\r\n$3\r\nrev\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SortedSetRangeStore = frame.try_into()?;
+        assert_eq!(result.by, RangeBy::Rank(0, -1));
+
+        for args in [
+            &b"*8\r\n$11\r\nzrangestore\r\n$3\r\ndst\r\n$3\r\nsrc\r\n$1\r\n0\r\n$2\r\n-1\r\n$5\r\nlimit\r\n$1\r\n0\r\n$1\r\n1\r\n"[..],
+            b"*7\r\n$11\r\nzrangestore\r\n$3\r\ndst\r\n$3\r\nsrc\r\n$1\r\n-\r\n$1\r\n+\r\n$7\r\nbyscore\r\n$5\r\nbylex\r\n",
+            b"*6\r\n$11\r\nzrangestore\r\n$3\r\ndst\r\n$3\r\nsrc\r\n$1\r\na\r\n$1\r\n+\r\n$5\r\nbylex\r\n",
+            b"*4\r\n$11\r\nzrangestore\r\n$3\r\ndst\r\n$3\r\nsrc\r\n$1\r\n0\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zrangestore_command() {
+        let backend = crate::Backend::new();
+        let members = [(1.0, "a"), (2.0, "b"), (3.0, "c")]
+            .map(|(score, member)| (score, member.to_string()))
+            .to_vec();
+        backend.zset_add("src".to_string(), members);
+        backend.string_set("dst".to_string(), b"value".to_vec());
+        let zrangestore = |by: RangeBy, rev: bool| {
+            SortedSetRangeStore {
+                destination: "dst".to_string(),
+                source: "src".to_string(),
+                by,
+                rev,
+                limit: None,
+            }
+            .execute(&backend)
+        };
+
+        // The destination is replaced whatever its type.
+        assert_eq!(
+            zrangestore(RangeBy::Rank(0, 1), true),
+            RespFrame::Integer(2)
+        );
+        assert_eq!(backend.string_get("dst"), None);
+        let expected = vec![(2.0, "b".to_string()), (3.0, "c".to_string())];
+        assert_eq!(backend.zset_range("dst", 0, -1, false), expected);
+
+        // An empty selection deletes it.
+        assert_eq!(
+            zrangestore(RangeBy::Rank(5, 9), false),
+            RespFrame::Integer(0)
+        );
+        assert!(!backend.exists("dst"));
+
+        // The source may be the destination.
+        let cmd = SortedSetRangeStore {
+            destination: "src".to_string(),
+            source: "src".to_string(),
+            by: RangeBy::Rank(0, 0),
+            rev: false,
+            limit: None,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.zset_card("src"), 1);
+    }
+
+    #[test]
+    fn test_zrank_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\nzrank\r\n$4\r\nzset\r\n$1\r\na\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetRank = frame.try_into()?;
+        assert_eq!(result.key, "zset");
+        assert_eq!(result.member, "a");
+        assert!(!result.with_score);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$8\r\nzrevrank\r\n$4\r\nzset\r\n$1\r\na\r\n$9\r\nwithscore\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SortedSetRevRank = frame.try_into()?;
+        assert!(result.with_score);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$5\r\nzrank\r\n$4\r\nzset\r\n$1\r\na\r\n$10\r\nwithscores\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(SortedSetRank::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zrank_commands() {
+        let backend = crate::Backend::new();
+        let members = [(1.0, "a"), (2.0, "b"), (3.0, "c")]
+            .map(|(score, member)| (score, member.to_string()))
+            .to_vec();
+        backend.zset_add("zset".to_string(), members);
+        let zrank = |member: &str, with_score: bool| {
+            SortedSetRank {
+                key: "zset".to_string(),
+                member: member.to_string(),
+                with_score,
+            }
+            .execute(&backend)
+        };
+        let zrevrank = |member: &str, with_score: bool| {
+            SortedSetRevRank {
+                key: "zset".to_string(),
+                member: member.to_string(),
+                with_score,
+            }
+            .execute(&backend)
+        };
+
+        assert_eq!(zrank("a", false), RespFrame::Integer(0));
+        assert_eq!(zrank("c", false), RespFrame::Integer(2));
+        assert_eq!(zrevrank("c", false), RespFrame::Integer(0));
+        let expected = RespArray::new(vec![RespFrame::Integer(2), BulkString::new("1").into()]);
+        assert_eq!(zrevrank("a", true), expected.into());
+        assert_eq!(zrank("missing", false), RespFrame::Null(RespNull));
+        assert_eq!(zrevrank("missing", true), RespFrame::Null(RespNull));
+    }
+
+    #[test]
+    fn test_zpopmin_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$7\r\nzpopmin\r\n$4\r\nzset\r\n$1\r\n2\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetPopMin = frame.try_into()?;
+        assert_eq!(result.key, "zset");
+        assert_eq!(result.count, Some(2));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$8\r\nbzpopmax\r\n$1\r\na\r\n$1\r\nb\r\n$3\r\n0.5\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SortedSetBlockingPopMax = frame.try_into()?;
+        assert_eq!(result.keys, ["a", "b"]);
+        assert_eq!(result.timeout, Some(Duration::from_millis(500)));
+
+        for args in [
+            &b"*3\r\n$7\r\nzpopmax\r\n$4\r\nzset\r\n$2\r\n-1\r\n"[..],
+            b"*4\r\n$7\r\nzpopmin\r\n$4\r\nzset\r\n$1\r\n1\r\n$1\r\n1\r\n",
+            b"*2\r\n$8\r\nbzpopmin\r\n$1\r\n0\r\n",
+            b"*3\r\n$8\r\nbzpopmin\r\n$1\r\na\r\n$2\r\n-1\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zpop_commands() {
+        let backend = crate::Backend::new();
+        let members = [(1.0, "a"), (2.0, "b"), (3.0, "c"), (4.0, "d")]
+            .map(|(score, member)| (score, member.to_string()))
+            .to_vec();
+        backend.zset_add("zset".to_string(), members);
+        let reply = |frames: &[&str]| {
+            let frames: Vec<RespFrame> =
+                frames.iter().map(|&f| BulkString::new(f).into()).collect();
+            RespFrame::from(RespArray::new(frames))
+        };
+
+        let cmd = SortedSetPopMin {
+            key: "zset".to_string(),
+            count: None,
+        };
+        assert_eq!(cmd.execute(&backend), reply(&["a", "1"]));
+        let cmd = SortedSetPopMax {
+            key: "zset".to_string(),
+            count: Some(2),
+        };
+        assert_eq!(cmd.execute(&backend), reply(&["d", "4", "c", "3"]));
+
+        let cmd = SortedSetBlockingPopMin {
+            keys: vec!["missing".to_string(), "zset".to_string()],
+            timeout: None,
+        };
+        assert_eq!(cmd.execute(&backend), reply(&["zset", "b", "2"]));
+        assert!(!backend.exists("zset"));
+
+        // Popping from missing keys replies an empty array, or null when blocking.
+        let cmd = SortedSetPopMin {
+            key: "zset".to_string(),
+            count: Some(1),
+        };
+        assert_eq!(cmd.execute(&backend), reply(&[]));
+        let cmd = SortedSetBlockingPopMax {
+            keys: vec!["zset".to_string()],
+            timeout: None,
+        };
+        assert_eq!(cmd.execute(&backend), RespArray::Null.into());
+    }
+
+    #[test]
+    fn test_zscan_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*7\r\n$5\r\nzscan\r\n$4\r\nzset\r\n$2\r\n42\r\n$5\r\nmatch\r\n$2\r\na*\r\n\
+            $5\r\ncount\r\n$3\r\n100\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetScan = frame.try_into()?;
+        assert_eq!(result.key, "zset");
+        assert_eq!(result.cursor, 42);
+        assert_eq!(result.pattern.as_deref(), Some("a*"));
+        assert_eq!(result.count, 100);
+
+        for args in [
+            &b"*2\r\n$5\r\nzscan\r\n$4\r\nzset\r\n"[..],
+            b"*5\r\n$5\r\nzscan\r\n$4\r\nzset\r\n$1\r\n0\r\n$5\r\ncount\r\n$1\r\n0\r\n",
+            b"*4\r\n$5\r\nzscan\r\n$4\r\nzset\r\n$1\r\n0\r\n$8\r\nnovalues\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zscan_command() {
+        let backend = crate::Backend::new();
+        let members = (0..50)
+            .map(|i| {
+                (
+                    i as f64,
+                    format!("{}{}", if i % 2 == 0 { "a" } else { "b" }, i),
+                )
+            })
+            .collect();
+        backend.zset_add("zset".to_string(), members);
+
+        // Iterate until the cursor is back to 0, every matching member is returned along with
+        // its score.
+        let scan = |cursor: u64| {
+            let cmd = SortedSetScan {
+                key: "zset".to_string(),
+                cursor,
+                pattern: Some("a*".to_string()),
+                count: 10,
+            };
+            match cmd.execute(&backend) {
+                RespFrame::Array(reply) => match (&reply[0], &reply[1]) {
+                    (RespFrame::BulkString(cursor), RespFrame::Array(items)) => (
+                        String::from_utf8_lossy(cursor).parse::<u64>().unwrap(),
+                        items.to_vec(),
+                    ),
+                    _ => panic!("unexpected reply"),
+                },
+                _ => panic!("unexpected reply"),
+            }
+        };
+        let mut items = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (next, batch) = scan(cursor);
+            items.extend(batch);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        let mut pairs: Vec<(String, String)> = items
+            .chunks(2)
+            .map(|pair| match pair {
+                [RespFrame::BulkString(member), RespFrame::BulkString(score)] => (
+                    String::from_utf8_lossy(member).to_string(),
+                    String::from_utf8_lossy(score).to_string(),
+                ),
+                _ => panic!("unexpected reply"),
+            })
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+        assert_eq!(pairs.len(), 25);
+        assert!(pairs
+            .iter()
+            .all(|(member, score)| member.starts_with('a') && member[1..] == *score));
+
+        let cmd = SortedSetScan {
+            key: "missing".to_string(),
+            cursor: 0,
+            pattern: None,
+            count: 10,
+        };
+        let expected = RespArray::new(vec![
+            BulkString::new("0").into(),
+            RespArray::new(vec![]).into(),
+        ]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+    }
+
+    #[test]
+    fn test_zunionstore_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*10\r\n$11\r\nzunionstore\r\n$3\r\ndst\r\n$1\r\n2\r\n$1\r\na\r\n$1\r\nb\r\n\
+            $7\r\nweights\r\n$1\r\n2\r\n$4\r\n-inf\r\n$9\r\naggregate\r\n$3\r\nmax\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetUnionStore = frame.try_into()?;
+        assert_eq!(result.destination, "dst");
+        assert_eq!(result.keys, ["a", "b"]);
+        assert_eq!(result.weights, [2.0, f64::NEG_INFINITY]);
+        assert_eq!(result.aggregate, Aggregate::Max);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$11\r\nzinterstore\r\n$3\r\ndst\r\n$1\r\n1\r\n$1\r\na\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SortedSetInterStore = frame.try_into()?;
+        assert_eq!(result.weights, [1.0]);
+        assert_eq!(result.aggregate, Aggregate::Sum);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$5\r\nzdiff\r\n$1\r\n2\r\n$1\r\na\r\n$1\r\nb\r\n$10\r\nwithscores\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SortedSetDiff = frame.try_into()?;
+        assert_eq!(result.keys, ["a", "b"]);
+        assert!(result.with_scores);
+
+        for args in [
+            &b"*4\r\n$11\r\nzunionstore\r\n$3\r\ndst\r\n$1\r\n0\r\n$1\r\na\r\n"[..],
+            b"*4\r\n$11\r\nzunionstore\r\n$3\r\ndst\r\n$1\r\n2\r\n$1\r\na\r\n",
+            b"*6\r\n$11\r\nzunionstore\r\n$3\r\ndst\r\n$1\r\n2\r\n$1\r\na\r\n$1\r\nb\r\n$7\r\nweights\r\n",
+            b"*8\r\n$11\r\nzinterstore\r\n$3\r\ndst\r\n$1\r\n2\r\n$1\r\na\r\n$1\r\nb\r\n$7\r\nweights\r\n$1\r\n1\r\n$1\r\nx\r\n",
+            b"*6\r\n$11\r\nzinterstore\r\n$3\r\ndst\r\n$1\r\n1\r\n$1\r\na\r\n$9\r\naggregate\r\n$3\r\navg\r\n",
+            b"*4\r\n$5\r\nzdiff\r\n$1\r\n1\r\n$1\r\na\r\n$1\r\nb\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zunionstore_commands() {
+        let backend = crate::Backend::new();
+        let members = [(1.0, "a"), (2.0, "b"), (3.0, "c")]
+            .map(|(score, member)| (score, member.to_string()))
+            .to_vec();
+        backend.zset_add("zset".to_string(), members);
+        backend.set_add("set".to_string(), vec!["b".to_string(), "d".to_string()]);
+        let keys = vec!["zset".to_string(), "set".to_string()];
+        let range = |key: &str| backend.zset_range(key, 0, -1, false);
+        let expected = |members: &[(f64, &str)]| {
+            members
+                .iter()
+                .map(|&(score, member)| (score, member.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        // Plain sets count as sorted sets with all scores 1.
+        let cmd = SortedSetUnionStore {
+            destination: "dst".to_string(),
+            keys: keys.clone(),
+            weights: vec![2.0, 1.0],
+            aggregate: Aggregate::Sum,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(4));
+        assert_eq!(
+            range("dst"),
+            expected(&[(1.0, "d"), (2.0, "a"), (5.0, "b"), (6.0, "c")])
+        );
+
+        let cmd = SortedSetInterStore {
+            destination: "dst".to_string(),
+            keys: keys.clone(),
+            weights: vec![1.0, 1.0],
+            aggregate: Aggregate::Min,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(range("dst"), expected(&[(1.0, "b")]));
+
+        let cmd = SortedSetDiff {
+            keys: keys.clone(),
+            with_scores: true,
+        };
+        let reply: Vec<RespFrame> = ["a", "1", "c", "3"]
+            .iter()
+            .map(|&f| BulkString::new(f).into())
+            .collect();
+        assert_eq!(cmd.execute(&backend), RespArray::new(reply).into());
+
+        // The destination may be an input, even a plain set, and is replaced by the result.
+        let cmd = SortedSetUnionStore {
+            destination: "set".to_string(),
+            keys: vec!["set".to_string()],
+            weights: vec![3.0],
+            aggregate: Aggregate::Sum,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(range("set"), expected(&[(3.0, "b"), (3.0, "d")]));
+        assert!(backend.set_members("set").is_none());
+
+        // An empty result deletes the destination.
+        let cmd = SortedSetInterStore {
+            destination: "dst".to_string(),
+            keys: vec!["zset".to_string(), "missing".to_string()],
+            weights: vec![1.0, 1.0],
+            aggregate: Aggregate::Sum,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert!(!backend.exists("dst"));
+    }
+
+    #[test]
+    fn test_zrem_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$4\r\nzrem\r\n$4\r\nzset\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SortedSetRemove = frame.try_into()?;
+        assert_eq!(result.key, "zset");
+        assert_eq!(result.members, ["a", "b"]);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$15\r\nzremrangebyrank\r\n$4\r\nzset\r\n$1\r\n0\r\n$2\r\n-2\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SortedSetRemoveRangeByRank = frame.try_into()?;
+        assert_eq!((result.start, result.end), (0, -2));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$16\r\nzremrangebyscore\r\n$4\r\nzset\r\n$4\r\n(1.5\r\n$3\r\ninf\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SortedSetRemoveRangeByScore = frame.try_into()?;
+        let expected = ScoreRange {
+            min: 1.5,
+            min_exclusive: true,
+            max: f64::INFINITY,
+            max_exclusive: false,
+        };
+        assert_eq!(result.range, expected);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$14\r\nzremrangebylex\r\n$4\r\nzset\r\n$2\r\n(a\r\n$1\r\n+\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: SortedSetRemoveRangeByLex = frame.try_into()?;
+        let expected = LexRange {
+            min: LexBound::Exclusive("a".to_string()),
+            max: LexBound::PosInfinity,
+        };
+        assert_eq!(result.range, expected);
+
+        for args in [
+            &b"*2\r\n$4\r\nzrem\r\n$4\r\nzset\r\n"[..],
+            b"*4\r\n$15\r\nzremrangebyrank\r\n$4\r\nzset\r\n$1\r\n0\r\n$1\r\nx\r\n",
+            b"*4\r\n$16\r\nzremrangebyscore\r\n$4\r\nzset\r\n$2\r\n(x\r\n$1\r\n1\r\n",
+            b"*4\r\n$16\r\nzremrangebyscore\r\n$4\r\nzset\r\n$1\r\n0\r\n$3\r\nnan\r\n",
+            b"*4\r\n$14\r\nzremrangebylex\r\n$4\r\nzset\r\n$1\r\na\r\n$1\r\n+\r\n",
+            b"*4\r\n$14\r\nzremrangebylex\r\n$4\r\nzset\r\n$1\r\n-\r\n$2\r\n+a\r\n",
+            b"*3\r\n$14\r\nzremrangebylex\r\n$4\r\nzset\r\n$1\r\n-\r\n",
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            assert!(crate::cmd::Command::try_from(frame).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zrem_commands() {
+        let backend = crate::Backend::new();
+        let add = |members: &[(f64, &str)]| {
+            let members = members
+                .iter()
+                .map(|&(score, member)| (score, member.to_string()))
+                .collect();
+            backend.zset_add("zset".to_string(), members);
+        };
+        add(&[(1.0, "a"), (2.0, "b"), (3.0, "c"), (4.0, "d")]);
+
+        let cmd = SortedSetRemove {
+            key: "zset".to_string(),
+            members: vec!["a".to_string(), "missing".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = SortedSetRemoveRangeByRank {
+            key: "zset".to_string(),
+            start: -1,
+            end: -1,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.zset_score("zset", "d"), None);
+
+        let cmd = SortedSetRemoveRangeByScore {
+            key: "zset".to_string(),
+            range: ScoreRange {
+                min: 2.0,
+                min_exclusive: true,
+                max: f64::INFINITY,
+                max_exclusive: false,
+            },
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.zset_card("zset"), 1);
+
+        // Lex ranges apply to members with the same score. Removing the last one drops the key.
+        add(&[(2.0, "x"), (2.0, "y")]);
+        let cmd = SortedSetRemoveRangeByLex {
+            key: "zset".to_string(),
+            range: LexRange {
+                min: LexBound::NegInfinity,
+                max: LexBound::Inclusive("x".to_string()),
+            },
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        let cmd = SortedSetRemoveRangeByScore {
+            key: "zset".to_string(),
+            range: ScoreRange {
+                min: f64::NEG_INFINITY,
+                min_exclusive: false,
+                max: f64::INFINITY,
+                max_exclusive: false,
+            },
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(!backend.exists("zset"));
+    }
+
+    #[test]
+    fn test_zset_commands() {
+        let backend = crate::Backend::new();
+        let zscore = |member: &str| {
+            SortedSetScore {
+                key: "zset".to_string(),
+                member: member.to_string(),
+            }
+            .execute(&backend)
+        };
+        let zcard = |key: &str| {
+            SortedSetCard {
+                key: key.to_string(),
+            }
+            .execute(&backend)
+        };
+
+        let cmd = SortedSetAdd {
+            key: "zset".to_string(),
+            members: vec![(1.0, "a".to_string()), (2.5, "b".to_string())],
+            condition: ScoreCondition::default(),
+            changed: false,
+            incr: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+
+        // Existing members only get their score updated.
+        let cmd = SortedSetAdd {
+            key: "zset".to_string(),
+            members: vec![(0.1, "a".to_string()), (3.0, "c".to_string())],
+            condition: ScoreCondition::default(),
+            changed: false,
+            incr: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(zcard("zset"), RespFrame::Integer(3));
+        assert_eq!(zcard("missing"), RespFrame::Integer(0));
+
+        assert_eq!(zscore("a"), BulkString::new("0.10000000000000001").into());
+        assert_eq!(zscore("b"), BulkString::new("2.5").into());
+        assert_eq!(zscore("missing"), RespFrame::Null(RespNull));
+    }
+
+    #[test]
+    fn test_zadd_options_command() {
+        let backend = crate::Backend::new();
+        let zadd = |members: &[(f64, &str)], options: &str| {
+            let members = members
+                .iter()
+                .map(|&(score, member)| (score, member.to_string()))
+                .collect();
+            SortedSetAdd {
+                key: "zset".to_string(),
+                members,
+                condition: ScoreCondition {
+                    if_absent: options.contains("NX"),
+                    if_exists: options.contains("XX"),
+                    if_greater: options.contains("GT"),
+                    if_less: options.contains("LT"),
+                },
+                changed: options.contains("CH"),
+                incr: options.contains("INCR"),
+            }
+            .execute(&backend)
+        };
+
+        // XX never creates the key.
+        assert_eq!(zadd(&[(1.0, "a")], "XX"), RespFrame::Integer(0));
+        assert_eq!(zadd(&[(1.0, "a")], "XX INCR"), RespFrame::Null(RespNull));
+        assert!(!backend.exists("zset"));
+
+        assert_eq!(zadd(&[(1.0, "a"), (2.0, "b")], "NX"), RespFrame::Integer(2));
+        assert_eq!(zadd(&[(5.0, "a"), (3.0, "c")], "NX"), RespFrame::Integer(1));
+        assert_eq!(backend.zset_score("zset", "a"), Some(1.0));
+
+        // GT and LT only update scores in their direction, but still add new members.
+        let members = [(0.5, "a"), (3.0, "b"), (1.0, "d")];
+        assert_eq!(zadd(&members, "GT CH"), RespFrame::Integer(2));
+        assert_eq!(backend.zset_score("zset", "a"), Some(1.0));
+        assert_eq!(backend.zset_score("zset", "b"), Some(3.0));
+        assert_eq!(
+            zadd(&[(0.5, "a"), (4.0, "b")], "LT XX CH"),
+            RespFrame::Integer(1)
+        );
+        assert_eq!(backend.zset_score("zset", "a"), Some(0.5));
+
+        // CH doesn't count members written with the same score.
+        assert_eq!(zadd(&[(0.5, "a"), (9.0, "e")], "CH"), RespFrame::Integer(1));
+
+        assert_eq!(zadd(&[(2.0, "a")], "INCR"), BulkString::new("2.5").into());
+        assert_eq!(zadd(&[(-1.0, "a")], "GT INCR"), RespFrame::Null(RespNull));
+        assert_eq!(zadd(&[(1.0, "a")], "NX INCR"), RespFrame::Null(RespNull));
+        assert_eq!(zadd(&[(1.0, "f")], "NX INCR"), BulkString::new("1").into());
+        assert_eq!(backend.zset_score("zset", "a"), Some(2.5));
+    }
+}
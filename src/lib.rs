@@ -4,5 +4,13 @@ pub use resp::*;
 mod backend;
 mod resp;
 
+#[cfg(feature = "chaos")]
+pub mod chaos;
+// Their items embedders need are exported by the prelude.
+#[doc(hidden)]
 pub mod cmd;
+#[doc(hidden)]
 pub mod network;
+pub mod prelude;
+pub mod server;
+pub mod shutdown;
@@ -1,31 +1,40 @@
+use std::{backtrace::Backtrace, str::FromStr, time::Duration};
+
 use anyhow::Result;
-use tokio::net::TcpListener;
-use tracing::{info, warn};
+use tracing::error;
 
-use simple_redis::{network, Backend};
+use simple_redis::{server::ServerBuilder, shutdown::shutdown_signal, Backend};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
+    // Request handlers recover from panics, so log them with a backtrace instead of to stderr.
+    std::panic::set_hook(Box::new(|info| {
+        error!("{}\n{}", info, Backtrace::force_capture());
+    }));
 
-    let addr = "0.0.0.0:6500";
-    info!("Simple-Redis-Server is listening on {}", addr);
-    let listener = TcpListener::bind(addr).await?;
-
-    let backend = Backend::new();
-    loop {
-        let (stream, raddr) = listener.accept().await?;
-        info!("Accepted connection from: {}", raddr);
-        let cloned_backend = backend.clone();
-        tokio::spawn(async move {
-            match network::stream_handler(stream, cloned_backend).await {
-                Ok(_) => {
-                    info!("Connection from {} exited", raddr);
-                }
-                Err(e) => {
-                    warn!("handle error for {}: {:?}", raddr, e);
-                }
-            }
-        });
+    let mut builder = ServerBuilder::new();
+    if let Some(secs) = env_var("SHUTDOWN_GRACE_PERIOD_SECS") {
+        builder = builder.grace_period(Duration::from_secs(secs));
+    }
+    if let Some(commands_per_turn) = env_var("COMMANDS_PER_TURN") {
+        builder = builder.commands_per_turn(commands_per_turn);
+    }
+    if let Some(ms) = env_var("ACTIVE_EXPIRE_INTERVAL_MS") {
+        builder = builder.active_expire_interval(Duration::from_millis(ms));
+    }
+    if let Some(effort) = env_var("ACTIVE_EXPIRE_EFFORT") {
+        builder = builder.active_expire_effort(effort);
     }
+    if env_var("ACTIVE_EXPIRE_INDEX") == Some(true) {
+        builder = builder.backend(Backend::with_expire_index());
+    }
+
+    let server = builder.bind("0.0.0.0:6500").await?;
+    server.run(shutdown_signal()).await
+}
+
+// Unset and invalid values both fall back to the default.
+fn env_var<T: FromStr>(name: &str) -> Option<T> {
+    std::env::var(name).ok().and_then(|v| v.parse().ok())
 }
@@ -1,13 +1,19 @@
+use std::future::{self, Future};
+use std::panic::{self, AssertUnwindSafe};
+
 use anyhow::Result;
 use futures::SinkExt;
-use tokio::net::TcpStream;
+use tokio::{net::TcpStream, time::Instant};
 use tokio_stream::StreamExt;
 use tokio_util::codec::{Decoder, Encoder, Framed};
-use tracing::info;
+use tracing::{error, info, warn};
 
+#[cfg(feature = "chaos")]
+use crate::chaos::Fault;
 use crate::{
-    cmd::{Command, CommandExecutor},
-    Backend, RespDecode, RespEncode, RespError, RespFrame,
+    cmd::{BlockingCommand, Command, CommandExecutor},
+    shutdown::ShutdownListener,
+    Backend, RespDecode, RespEncode, RespError, RespFrame, SimpleError,
 };
 
 #[derive(Debug)]
@@ -17,25 +23,83 @@ struct RespFrameCodec;
 struct RedisRequest {
     frame: RespFrame,
     backend: Backend,
+    shutdown: ShutdownListener,
 }
 
 #[derive(Debug)]
 struct RedisResponse {
     frame: RespFrame,
+    // Injected when sending the reply.
+    #[cfg(feature = "chaos")]
+    fault: Option<Fault>,
 }
 
-pub async fn stream_handler(stream: TcpStream, backend: Backend) -> Result<()> {
+// Commands a connection may execute before yielding to the other connections.
+pub const DEFAULT_COMMANDS_PER_TURN: usize = 128;
+
+// A pipeline of requests is executed without waiting on the socket, so without yielding
+// regularly a single connection could keep a worker thread busy for its whole pipeline.
+// Yielding every `commands_per_turn` commands keeps the other connections responsive,
+// 0 disables it.
+pub async fn stream_handler(
+    stream: TcpStream,
+    backend: Backend,
+    mut shutdown: ShutdownListener,
+    commands_per_turn: usize,
+) -> Result<()> {
     // how to get a frame from the stream?
     let mut framed = Framed::new(stream, RespFrameCodec);
+    let mut executed = 0;
     loop {
-        match framed.next().await {
+        // Only wait for the next request while the server is running,
+        // the in-flight request (if any) has already been answered at this point.
+        let frame = tokio::select! {
+            frame = framed.next() => frame,
+            _ = shutdown.recv() => {
+                info!("Closing connection for server shutdown");
+                return Ok(());
+            }
+        };
+        match frame {
             Some(Ok(frame)) => {
                 info!("Received frame: {:?}", frame);
                 let request = RedisRequest {
                     frame,
                     backend: backend.clone(),
+                    shutdown: shutdown.clone(),
                 };
-                let response = request_handler(request).await?;
+                let response = request_handler(request, client_closed(framed.get_ref())).await?;
+                executed += 1;
+                if executed == commands_per_turn {
+                    executed = 0;
+                    tokio::task::yield_now().await;
+                }
+
+                #[cfg(feature = "chaos")]
+                match response.fault {
+                    Some(Fault::Delay(delay)) => tokio::time::sleep(delay).await,
+                    Some(Fault::Drop) => {
+                        warn!("Chaos: dropping response {:?}", response.frame);
+                        continue;
+                    }
+                    Some(Fault::PartialWrite) => {
+                        warn!("Chaos: truncating response {:?}", response.frame);
+                        let encoded = response.frame.encode();
+                        framed
+                            .write_buffer_mut()
+                            .extend_from_slice(&encoded[..encoded.len() / 2]);
+                        framed.flush().await?;
+                        return Ok(());
+                    }
+                    Some(Fault::Disconnect) => {
+                        warn!(
+                            "Chaos: disconnecting instead of sending {:?}",
+                            response.frame
+                        );
+                        return Ok(());
+                    }
+                    None => {}
+                }
                 info!("Sending response: {:?}", response.frame);
                 framed.send(response.frame).await?;
             }
@@ -45,12 +109,116 @@ pub async fn stream_handler(stream: TcpStream, backend: Backend) -> Result<()> {
     }
 }
 
-async fn request_handler(request: RedisRequest) -> Result<RedisResponse> {
+// `closed` resolves if the client disconnects, which only matters to blocking commands.
+async fn request_handler(
+    request: RedisRequest,
+    closed: impl Future<Output = ()>,
+) -> Result<RedisResponse> {
     let (frame, backend) = (request.frame, request.backend);
-    let cmd = Command::try_from(frame)?;
-    info!("Executing command: {:?}", cmd);
-    let frame = cmd.execute(&backend);
-    Ok(RedisResponse { frame })
+    // Replies to DEBUG CHAOS itself are never hit, so faults can always be turned off.
+    #[cfg(feature = "chaos")]
+    let mut fault = None;
+    // A malformed command is answered with an error reply, the connection stays usable.
+    let frame = match Command::try_from(frame) {
+        Ok(cmd) => {
+            info!("Executing command: {:?}", cmd);
+            #[cfg(feature = "chaos")]
+            if !matches!(cmd, Command::DebugChaos(_)) {
+                fault = backend.chaos.roll();
+            }
+            match cmd {
+                Command::ListBlockingLeftPop(cmd) => {
+                    execute_blocking(cmd, &backend, request.shutdown, closed).await
+                }
+                Command::ListBlockingRightPop(cmd) => {
+                    execute_blocking(cmd, &backend, request.shutdown, closed).await
+                }
+                Command::ListBlockingMultiPop(cmd) => {
+                    execute_blocking(cmd, &backend, request.shutdown, closed).await
+                }
+                Command::SortedSetBlockingPopMin(cmd) => {
+                    execute_blocking(cmd, &backend, request.shutdown, closed).await
+                }
+                Command::SortedSetBlockingPopMax(cmd) => {
+                    execute_blocking(cmd, &backend, request.shutdown, closed).await
+                }
+                Command::StreamRead(cmd) if cmd.is_blocking() => {
+                    execute_blocking(cmd, &backend, request.shutdown, closed).await
+                }
+                Command::StreamReadGroup(cmd) if cmd.is_blocking() => {
+                    execute_blocking(cmd, &backend, request.shutdown, closed).await
+                }
+                cmd => execute_command(cmd, &backend),
+            }
+        }
+        Err(e) => {
+            warn!("Invalid command: {:?}", e);
+            SimpleError::new(format!("ERR {}", e)).into()
+        }
+    };
+    Ok(RedisResponse {
+        frame,
+        #[cfg(feature = "chaos")]
+        fault,
+    })
+}
+
+// A panicking command only fails its own request, the connection and the server keep going.
+// DashMap locks are released on unwind, so the backend stays usable afterward.
+fn execute_command(cmd: impl CommandExecutor, backend: &Backend) -> RespFrame {
+    match panic::catch_unwind(AssertUnwindSafe(|| cmd.execute(backend))) {
+        Ok(frame) => frame,
+        Err(_) => panicked(),
+    }
+}
+
+// The client is blocked on the command's keys before the first try, so no write is missed
+// in between. It stops waiting with the timeout reply once the timeout elapses, the server
+// starts draining or the client disconnects, the latter so that it's not served an element
+// it would never receive.
+async fn execute_blocking(
+    cmd: impl BlockingCommand,
+    backend: &Backend,
+    mut shutdown: ShutdownListener,
+    closed: impl Future<Output = ()>,
+) -> RespFrame {
+    let deadline = cmd.timeout().map(|timeout| Instant::now() + timeout);
+    let blocked = backend.block(cmd.keys());
+    tokio::pin!(closed);
+    loop {
+        match panic::catch_unwind(AssertUnwindSafe(|| cmd.try_execute(backend))) {
+            Ok(Some(frame)) => return frame,
+            Ok(None) => {}
+            Err(_) => return panicked(),
+        }
+        let timeout = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = blocked.woken() => {}
+            _ = timeout => return cmd.timeout_reply(),
+            _ = shutdown.recv() => return cmd.timeout_reply(),
+            _ = &mut closed => return cmd.timeout_reply(),
+        }
+    }
+}
+
+fn panicked() -> RespFrame {
+    error!("Command panicked, see the panic log for the backtrace");
+    SimpleError::new("ERR internal error").into()
+}
+
+// Resolves once the client closed the connection. Requests pipelined after the current one
+// are left unread, so while some are pending a disconnect goes unnoticed.
+async fn client_closed(stream: &TcpStream) {
+    let mut buf = [0; 1];
+    match stream.peek(&mut buf).await {
+        Ok(0) | Err(_) => {}
+        Ok(_) => future::pending().await,
+    }
 }
 
 impl Encoder<RespFrame> for RespFrameCodec {
@@ -75,3 +243,289 @@ impl Decoder for RespFrameCodec {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use anyhow::Result;
+    use bytes::BytesMut;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        sync::oneshot,
+    };
+
+    use crate::{
+        cmd::{Command, CommandExecutor},
+        shutdown::Shutdown,
+        Backend, BulkString, RespArray, RespDecode, RespEncode, RespFrame, SimpleError,
+    };
+
+    use super::{execute_command, stream_handler};
+
+    fn command(args: &[&str]) -> Vec<u8> {
+        let args: Vec<RespFrame> = args.iter().map(|a| BulkString::new(*a).into()).collect();
+        RespFrame::from(RespArray::new(args)).encode()
+    }
+
+    const PIPELINE_LEN: usize = 100_000;
+
+    struct PanicCommand;
+
+    impl CommandExecutor for PanicCommand {
+        fn execute(self, _: &Backend) -> RespFrame {
+            panic!("deliberate panic");
+        }
+    }
+
+    #[test]
+    fn test_execute_command_catches_panic() -> Result<()> {
+        let backend = Backend::new();
+        assert_eq!(
+            execute_command(PanicCommand, &backend),
+            SimpleError::new("ERR internal error").into()
+        );
+
+        // The backend keeps serving requests afterward.
+        let cmd = Command::try_from(RespArray::new(vec![
+            BulkString::new("echo").into(),
+            BulkString::new("hello").into(),
+        ]))?;
+        assert_eq!(
+            execute_command(cmd, &backend),
+            BulkString::new("hello").into()
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "chaos")]
+    #[tokio::test]
+    async fn test_chaos_faults() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let shutdown = Shutdown::new(Duration::from_secs(1));
+        let backend = Backend::new();
+        let listener_shutdown = shutdown.subscribe();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (backend, shutdown) = (backend.clone(), listener_shutdown.clone());
+                tokio::spawn(stream_handler(stream, backend, shutdown, 16));
+            }
+        });
+        let ok = RespFrame::from(crate::SimpleString::new("OK")).encode();
+        let echo = command(&["echo", "hello"]);
+
+        // The dropped reply is skipped, the reply to DEBUG CHAOS is never dropped.
+        let mut stream = TcpStream::connect(addr).await?;
+        stream
+            .write_all(&command(&["debug", "chaos", "drop", "1"]))
+            .await?;
+        stream.write_all(&echo).await?;
+        stream
+            .write_all(&command(&["debug", "chaos", "off"]))
+            .await?;
+        let mut buf = vec![0; ok.len() * 2];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(buf, ok.repeat(2));
+
+        // Half of the reply is sent, then the connection is closed.
+        stream
+            .write_all(&command(&["debug", "chaos", "partial", "1"]))
+            .await?;
+        stream.write_all(&echo).await?;
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await?;
+        let reply = RespFrame::from(BulkString::new("hello")).encode();
+        assert_eq!(buf, [&ok[..], &reply[..reply.len() / 2]].concat());
+
+        // The faults are shared by all connections, so this one replaces the partial write.
+        let mut stream = TcpStream::connect(addr).await?;
+        stream
+            .write_all(&command(&["debug", "chaos", "off"]))
+            .await?;
+        stream
+            .write_all(&command(&["debug", "chaos", "disconnect", "1"]))
+            .await?;
+        stream.write_all(&echo).await?;
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await?;
+        assert_eq!(buf, ok.repeat(2));
+
+        Ok(())
+    }
+
+    // The second client is answered while the first one's pipeline is still being executed.
+    #[tokio::test]
+    async fn test_pipeline_does_not_starve_other_connections() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let shutdown = Shutdown::new(Duration::from_secs(1));
+        let backend = Backend::new();
+        let listener_shutdown = shutdown.subscribe();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (backend, shutdown) = (backend.clone(), listener_shutdown.clone());
+                tokio::spawn(stream_handler(stream, backend, shutdown, 16));
+            }
+        });
+
+        let echo = RespFrame::from(RespArray::new(vec![
+            BulkString::new("echo").into(),
+            BulkString::new("hello").into(),
+        ]))
+        .encode();
+
+        // The first client sends a whole pipeline at once, then counts the replies.
+        let (mut reader, mut writer) = TcpStream::connect(addr).await?.into_split();
+        let pipeline = echo.repeat(PIPELINE_LEN);
+        tokio::spawn(async move { writer.write_all(&pipeline).await });
+        let (started_tx, started_rx) = oneshot::channel();
+        let first = tokio::spawn(async move {
+            let mut started_tx = Some(started_tx);
+            let (mut buf, mut replies) = (BytesMut::new(), 0);
+            while replies < PIPELINE_LEN {
+                reader.read_buf(&mut buf).await?;
+                while RespFrame::decode(&mut buf).is_ok() {
+                    replies += 1;
+                }
+                if let Some(tx) = started_tx.take() {
+                    let _ = tx.send(());
+                }
+            }
+            anyhow::Ok(Instant::now())
+        });
+
+        // The second client sends a single command once the pipeline is being executed.
+        started_rx.await?;
+        let sent_at = Instant::now();
+        let mut stream = TcpStream::connect(addr).await?;
+        stream.write_all(&echo).await?;
+        let mut buf = BytesMut::new();
+        while RespFrame::decode(&mut buf.clone()).is_err() {
+            stream.read_buf(&mut buf).await?;
+        }
+        let answered_at = Instant::now();
+
+        let pipeline_done_at = first.await??;
+        assert!(
+            answered_at < pipeline_done_at,
+            "answered after {:?}, pipeline done after {:?}",
+            answered_at - sent_at,
+            pipeline_done_at - sent_at
+        );
+
+        Ok(())
+    }
+
+    // A blocked client is served by a push from another connection, unless it disconnected.
+    #[tokio::test]
+    async fn test_blocking_pop() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let shutdown = Shutdown::new(Duration::from_secs(1));
+        let backend = Backend::new();
+        let listener_shutdown = shutdown.subscribe();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (backend, shutdown) = (backend.clone(), listener_shutdown.clone());
+                tokio::spawn(stream_handler(stream, backend, shutdown, 16));
+            }
+        });
+        let reply = |frame: RespFrame| frame.encode();
+
+        let mut blocked = TcpStream::connect(addr).await?;
+        blocked.write_all(&command(&["blpop", "list", "0"])).await?;
+        let mut stream = TcpStream::connect(addr).await?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        stream
+            .write_all(&command(&["rpush", "list", "a", "b"]))
+            .await?;
+        let mut buf = vec![0; reply(RespFrame::Integer(2)).len()];
+        stream.read_exact(&mut buf).await?;
+        let expected = reply(
+            RespArray::new(vec![
+                BulkString::new("list").into(),
+                BulkString::new("a").into(),
+            ])
+            .into(),
+        );
+        let mut buf = vec![0; expected.len()];
+        blocked.read_exact(&mut buf).await?;
+        assert_eq!(buf, expected);
+
+        // The element stays in the list for the clients still connected.
+        blocked
+            .write_all(&command(&["blpop", "other", "0"]))
+            .await?;
+        drop(blocked);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        stream.write_all(&command(&["rpush", "other", "c"])).await?;
+        stream.write_all(&command(&["lpop", "other"])).await?;
+        let expected = [
+            reply(RespFrame::Integer(1)),
+            reply(BulkString::new("c").into()),
+        ]
+        .concat();
+        let mut buf = vec![0; expected.len()];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(buf, expected);
+
+        // Sorted sets block the same way.
+        let mut blocked = TcpStream::connect(addr).await?;
+        blocked
+            .write_all(&command(&["bzpopmin", "zset", "0"]))
+            .await?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        stream
+            .write_all(&command(&["zadd", "zset", "2", "b", "1", "a"]))
+            .await?;
+        let expected = reply(
+            RespArray::new(vec![
+                BulkString::new("zset").into(),
+                BulkString::new("a").into(),
+                BulkString::new("1").into(),
+            ])
+            .into(),
+        );
+        let mut buf = vec![0; expected.len()];
+        blocked.read_exact(&mut buf).await?;
+        assert_eq!(buf, expected);
+
+        // So does XREAD, for the entries appended after it blocked.
+        stream
+            .write_all(&command(&["xadd", "events", "1-1", "a", "1"]))
+            .await?;
+        let mut buf = vec![0; reply(BulkString::new("1-1").into()).len()];
+        stream.read_exact(&mut buf).await?;
+        blocked
+            .write_all(&command(&["xread", "block", "0", "streams", "events", "$"]))
+            .await?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        stream
+            .write_all(&command(&["xadd", "events", "2-1", "b", "2"]))
+            .await?;
+        let entry = RespArray::new(vec![
+            BulkString::new("2-1").into(),
+            RespArray::new(vec![
+                BulkString::new("b").into(),
+                BulkString::new("2").into(),
+            ])
+            .into(),
+        ]);
+        let expected = reply(
+            RespArray::new(vec![RespArray::new(vec![
+                BulkString::new("events").into(),
+                RespArray::new(vec![entry.into()]).into(),
+            ])
+            .into()])
+            .into(),
+        );
+        let mut buf = vec![0; expected.len()];
+        blocked.read_exact(&mut buf).await?;
+        assert_eq!(buf, expected);
+
+        Ok(())
+    }
+}
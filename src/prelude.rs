@@ -0,0 +1,21 @@
+// The types most embedders need, importable at once with `use simple_redis::prelude::*`.
+// Everything here is covered by semver. Experimental types are only exported with the
+// `unstable` feature, and may change in any release.
+pub use crate::{
+    cmd::{BlockingCommand, Command, CommandError, CommandExecutor},
+    network::{stream_handler, DEFAULT_COMMANDS_PER_TURN},
+    server::{Server, ServerBuilder},
+    shutdown::{Shutdown, ShutdownListener, ShutdownState},
+    Aggregate, Backend, BackendError, Blocked, BulkString, ExpireCondition, Expiry,
+    FieldExpireResult, LexBound, LexRange, ListEnd, RangeBy, RateLimitResult, RespArray,
+    RespDecode, RespEncode, RespError, RespFrame, RespMap, RespNull, RespSet, ScoreCondition,
+    ScoreRange, SetCondition, SetOperation, SimpleError, SimpleString, StringValue, StripedCounter,
+};
+
+#[cfg(feature = "unstable")]
+pub use crate::{
+    cmp_score_member, format_score, parse_score, BloomFilter, SkipList, StreamAddId,
+    StreamAutoClaimResult, StreamClaimOptions, StreamConsumerInfo, StreamFields, StreamGroupInfo,
+    StreamId, StreamInfo, StreamPendingEntry, StreamPendingSummary, StreamTrimOptions,
+    StreamTrimStrategy,
+};
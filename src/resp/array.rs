@@ -158,7 +158,11 @@ mod tests {
         let ret = RespArray::decode(&mut buf);
         assert_eq!(ret.unwrap_err(), RespError::NotComplete);
 
-        buf.extend_from_slice(b"$5\r\nhello\r\n");
+        buf.extend_from_slice(b"$5\r\nhel");
+        let ret = RespArray::decode(&mut buf);
+        assert_eq!(ret.unwrap_err(), RespError::NotComplete);
+
+        buf.extend_from_slice(b"lo\r\n");
         let frame = RespArray::decode(&mut buf)?;
         assert_eq!(frame, RespArray::new([b"set".into(), b"hello".into()]));
 
@@ -120,7 +120,8 @@ fn calc_total_length(buf: &[u8], end: usize, len: usize, prefix: &str) -> Result
             // find nth CRLF in the buffer, for array and set, we need to find 1 CRLF for each element
             for _ in 0..len {
                 let len = RespFrame::expect_length(data)?;
-                data = &data[len..];
+                // A bulk string only announces its length, its data may not be there yet.
+                data = data.get(len..).ok_or(RespError::NotComplete)?;
                 total += len;
             }
             Ok(total)
@@ -134,7 +135,7 @@ fn calc_total_length(buf: &[u8], end: usize, len: usize, prefix: &str) -> Result
                 total += len;
 
                 let len = RespFrame::expect_length(data)?;
-                data = &data[len..];
+                data = data.get(len..).ok_or(RespError::NotComplete)?;
                 total += len;
             }
             Ok(total)
@@ -0,0 +1,233 @@
+use std::{future::Future, io, net::SocketAddr, time::Duration};
+
+use anyhow::Result;
+use tokio::{
+    net::{TcpListener, ToSocketAddrs},
+    task::JoinSet,
+};
+use tracing::{info, warn};
+
+use crate::{
+    network::{self, DEFAULT_COMMANDS_PER_TURN},
+    shutdown::Shutdown,
+    Backend,
+};
+
+pub const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(10);
+// Ten runs per second checking up to 200 keys each, close to Redis's defaults.
+pub const DEFAULT_ACTIVE_EXPIRE_INTERVAL: Duration = Duration::from_millis(100);
+pub const DEFAULT_ACTIVE_EXPIRE_EFFORT: usize = 200;
+const RATELIMIT_CLEANUP_INTERVAL: Duration = Duration::from_secs(10);
+
+// Configures a server, which is then bound with `bind` and served with `Server::run`. It can
+// be run in-process, tests and embedders bind it to port 0 and read the port picked from
+// `Server::local_addr`.
+#[derive(Debug)]
+pub struct ServerBuilder {
+    backend: Backend,
+    grace_period: Duration,
+    commands_per_turn: usize,
+    active_expire_interval: Duration,
+    active_expire_effort: usize,
+}
+
+#[derive(Debug)]
+pub struct Server {
+    listener: TcpListener,
+    backend: Backend,
+    shutdown: Shutdown,
+    commands_per_turn: usize,
+    active_expire_interval: Duration,
+    active_expire_effort: usize,
+}
+
+impl Default for ServerBuilder {
+    fn default() -> Self {
+        Self {
+            backend: Backend::new(),
+            grace_period: DEFAULT_SHUTDOWN_GRACE_PERIOD,
+            commands_per_turn: DEFAULT_COMMANDS_PER_TURN,
+            active_expire_interval: DEFAULT_ACTIVE_EXPIRE_INTERVAL,
+            active_expire_effort: DEFAULT_ACTIVE_EXPIRE_EFFORT,
+        }
+    }
+}
+
+impl ServerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Serves an existing backend, so its data can be read and written directly as well.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    // How long connections have to finish their in-flight request once shutting down.
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    // See `network::stream_handler`, 0 disables yielding.
+    pub fn commands_per_turn(mut self, commands_per_turn: usize) -> Self {
+        self.commands_per_turn = commands_per_turn;
+        self
+    }
+
+    // How often expired keys never accessed again are looked for, zero to disable it.
+    pub fn active_expire_interval(mut self, interval: Duration) -> Self {
+        self.active_expire_interval = interval;
+        self
+    }
+
+    // How many keys, and as many hashes, are checked each time.
+    pub fn active_expire_effort(mut self, effort: usize) -> Self {
+        self.active_expire_effort = effort;
+        self
+    }
+
+    pub async fn bind(self, addr: impl ToSocketAddrs) -> io::Result<Server> {
+        let listener = TcpListener::bind(addr).await?;
+        Ok(Server {
+            listener,
+            backend: self.backend,
+            shutdown: Shutdown::new(self.grace_period),
+            commands_per_turn: self.commands_per_turn,
+            active_expire_interval: self.active_expire_interval,
+            active_expire_effort: self.active_expire_effort,
+        })
+    }
+}
+
+impl Server {
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn backend(&self) -> &Backend {
+        &self.backend
+    }
+
+    // Serves connections until `signal` resolves, then stops accepting new ones and drains the
+    // existing ones. The background tasks are stopped along with it.
+    pub async fn run(self, signal: impl Future<Output = ()>) -> Result<()> {
+        let Self {
+            listener,
+            backend,
+            shutdown,
+            commands_per_turn,
+            active_expire_interval,
+            active_expire_effort,
+        } = self;
+        info!(
+            "Simple-Redis-Server is listening on {}",
+            listener.local_addr()?
+        );
+        tokio::pin!(signal);
+
+        let mut tasks = JoinSet::new();
+        // Idle rate limit buckets are full again, dropping them is the same as keeping them.
+        let cloned_backend = backend.clone();
+        tasks.spawn(async move {
+            let mut interval = tokio::time::interval(RATELIMIT_CLEANUP_INTERVAL);
+            loop {
+                interval.tick().await;
+                cloned_backend.ratelimit_cleanup();
+            }
+        });
+        // Expired keys and hash fields are removed on access, this reclaims the memory of the
+        // ones never accessed again.
+        if !active_expire_interval.is_zero() {
+            let cloned_backend = backend.clone();
+            tasks.spawn(async move {
+                let mut interval = tokio::time::interval(active_expire_interval);
+                loop {
+                    interval.tick().await;
+                    cloned_backend.active_expire(active_expire_effort);
+                    cloned_backend.active_expire_fields(active_expire_effort);
+                }
+            });
+        }
+
+        // The jobs of DELAY.PUSH are moved onto their lists once due.
+        let cloned_backend = backend.clone();
+        tasks.spawn(async move { cloned_backend.delay_mover().await });
+
+        let mut connections = JoinSet::new();
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (stream, raddr) = accepted?;
+                    info!("Accepted connection from: {}", raddr);
+                    let cloned_backend = backend.clone();
+                    let listener = shutdown.subscribe();
+                    connections.spawn(async move {
+                        match network::stream_handler(stream, cloned_backend, listener, commands_per_turn).await {
+                            Ok(_) => {
+                                info!("Connection from {} exited", raddr);
+                            }
+                            Err(e) => {
+                                warn!("handle error for {}: {:?}", raddr, e);
+                            }
+                        }
+                    });
+                }
+                // Reap finished connections so the set does not grow unbounded.
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                _ = &mut signal => break,
+            }
+        }
+
+        // Stop accepting new connections before draining the existing ones.
+        drop(listener);
+        shutdown.drain(&mut connections).await;
+        tasks.shutdown().await;
+        info!("Simple-Redis-Server has shut down");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+        sync::oneshot,
+    };
+
+    use super::ServerBuilder;
+    use crate::Backend;
+
+    #[tokio::test]
+    async fn test_server_run() {
+        let backend = Backend::new();
+        let server = ServerBuilder::new()
+            .backend(backend.clone())
+            .bind("127.0.0.1:0")
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        let (stop, stopped) = oneshot::channel::<()>();
+        let handle = tokio::spawn(server.run(async {
+            let _ = stopped.await;
+        }));
+
+        // The backend given is the one served.
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n")
+            .await
+            .unwrap();
+        let mut buf = [0; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"+OK\r\n");
+        assert_eq!(backend.string_get("k"), Some(b"v".to_vec()));
+
+        // Once the signal resolves, the idle connection is closed and the server returns.
+        stop.send(()).unwrap();
+        handle.await.unwrap().unwrap();
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use tokio::{sync::watch, task::JoinSet};
+use tracing::{info, warn};
+
+// The server goes through these states strictly in order:
+// - Running: accepting new connections and serving requests.
+// - Draining: no new connections are accepted, connections close once their in-flight request is done.
+// - Terminated: the grace period is over, any connection still alive has been force-closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ShutdownState {
+    Running,
+    Draining,
+    Terminated,
+}
+
+#[derive(Debug)]
+pub struct Shutdown {
+    state: watch::Sender<ShutdownState>,
+    grace_period: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct ShutdownListener(watch::Receiver<ShutdownState>);
+
+impl Shutdown {
+    pub fn new(grace_period: Duration) -> Self {
+        let (state, _) = watch::channel(ShutdownState::Running);
+        Self {
+            state,
+            grace_period,
+        }
+    }
+
+    pub fn state(&self) -> ShutdownState {
+        *self.state.borrow()
+    }
+
+    pub fn subscribe(&self) -> ShutdownListener {
+        ShutdownListener(self.state.subscribe())
+    }
+
+    // Notify all connections to stop, then wait for them to exit within the grace period.
+    // Connections that are still alive afterward get aborted.
+    pub async fn drain(&self, connections: &mut JoinSet<()>) {
+        self.transition(ShutdownState::Draining);
+        info!(
+            "Draining {} connection(s), grace period {:?}",
+            connections.len(),
+            self.grace_period
+        );
+
+        let drained = tokio::time::timeout(self.grace_period, async {
+            while connections.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            warn!(
+                "Grace period elapsed, force-closing {} connection(s)",
+                connections.len()
+            );
+            connections.shutdown().await;
+        }
+
+        self.transition(ShutdownState::Terminated);
+    }
+
+    // States only move forward, so a late transition never revives the server.
+    fn transition(&self, next: ShutdownState) {
+        self.state.send_if_modified(|state| {
+            if *state < next {
+                *state = next;
+                true
+            } else {
+                false
+            }
+        });
+    }
+}
+
+impl ShutdownListener {
+    pub fn is_shutdown(&self) -> bool {
+        *self.0.borrow() != ShutdownState::Running
+    }
+
+    // Resolves once the server leaves the Running state.
+    pub async fn recv(&mut self) {
+        // An error means the Shutdown was dropped, which also ends the server.
+        let _ = self.0.wait_for(|s| *s != ShutdownState::Running).await;
+    }
+}
+
+// Resolves on Ctrl-C, or on SIGTERM for unix platforms.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl-C: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {:?}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl-C"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::task::JoinSet;
+
+    use super::{Shutdown, ShutdownState};
+
+    #[tokio::test]
+    async fn test_drain_waits_for_connections() {
+        let shutdown = Shutdown::new(Duration::from_secs(5));
+        let mut connections = JoinSet::new();
+        for _ in 0..3 {
+            let mut listener = shutdown.subscribe();
+            connections.spawn(async move { listener.recv().await });
+        }
+        assert_eq!(shutdown.state(), ShutdownState::Running);
+
+        shutdown.drain(&mut connections).await;
+        assert_eq!(shutdown.state(), ShutdownState::Terminated);
+        assert!(connections.is_empty());
+        assert!(shutdown.subscribe().is_shutdown());
+    }
+
+    #[tokio::test]
+    async fn test_drain_force_closes_after_grace_period() {
+        let shutdown = Shutdown::new(Duration::from_millis(10));
+        let mut connections = JoinSet::new();
+        // A connection that ignores the shutdown notification.
+        connections.spawn(std::future::pending::<()>());
+
+        shutdown.drain(&mut connections).await;
+        assert_eq!(shutdown.state(), ShutdownState::Terminated);
+        assert!(connections.is_empty());
+    }
+}
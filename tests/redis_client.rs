@@ -0,0 +1,1440 @@
+// Drives the server with the redis crate, to check protocol compatibility with a mainstream
+// client. Each test runs its own server in-process, on a random port.
+use std::{
+    collections::HashMap, future, net::SocketAddr, num::NonZeroUsize, sync::mpsc, time::Duration,
+};
+
+use redis::{Commands, Connection, RedisResult, Value};
+
+use simple_redis::prelude::*;
+
+// The server lives on its own runtime thread for as long as the test process does.
+fn start_server() -> SocketAddr {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("failed to build the runtime");
+        runtime.block_on(async move {
+            let server = ServerBuilder::new()
+                .grace_period(Duration::from_secs(1))
+                .bind("127.0.0.1:0")
+                .await
+                .unwrap();
+            tx.send(server.local_addr().unwrap()).unwrap();
+            server.run(future::pending()).await.unwrap();
+        });
+    });
+    rx.recv().expect("the server failed to start")
+}
+
+fn connect() -> Connection {
+    let addr = start_server();
+    redis::Client::open(format!("redis://{}/", addr))
+        .and_then(|client| client.get_connection())
+        .expect("failed to connect")
+}
+
+// Asserts the command is answered with an error reply, and the connection stays usable.
+fn assert_error(con: &mut Connection, cmd: &mut redis::Cmd) {
+    let ret: RedisResult<Value> = cmd.query(con);
+    assert!(ret.is_err(), "expected an error reply, got {:?}", ret);
+    let pong: String = redis::cmd("ECHO").arg("ping").query(con).unwrap();
+    assert_eq!(pong, "ping");
+}
+
+#[test]
+fn test_echo_and_unknown_command() {
+    let mut con = connect();
+    let ret: String = redis::cmd("ECHO").arg("hello").query(&mut con).unwrap();
+    assert_eq!(ret, "hello");
+
+    assert_error(&mut con, &mut redis::cmd("ECHO"));
+    assert_error(&mut con, redis::cmd("NOSUCHCOMMAND").arg("a"));
+}
+
+#[test]
+fn test_string_commands() {
+    let mut con = connect();
+    let () = con.set("a", "hello").unwrap();
+    let ret: Option<String> = con.get("a").unwrap();
+    assert_eq!(ret.as_deref(), Some("hello"));
+    let ret: Option<String> = con.get("missing").unwrap();
+    assert_eq!(ret, None);
+
+    let ret: Option<String> = redis::cmd("SET")
+        .arg("a")
+        .arg("world")
+        .arg("NX")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, None);
+    let ret: Option<String> = redis::cmd("SET")
+        .arg("a")
+        .arg("world")
+        .arg("GET")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret.as_deref(), Some("hello"));
+    assert_error(
+        &mut con,
+        redis::cmd("SET").arg("a").arg("b").arg("EX").arg(0),
+    );
+
+    let ret: bool = con.set_nx("a", "x").unwrap();
+    assert!(!ret);
+    let ret: bool = redis::cmd("MSETNX")
+        .arg("b")
+        .arg("1")
+        .arg("c")
+        .arg("2")
+        .query(&mut con)
+        .unwrap();
+    assert!(ret);
+    assert_error(&mut con, redis::cmd("MSETNX").arg("b"));
+
+    let () = con.set_ex("ex", "v", 100).unwrap();
+    let () = con.pset_ex("px", "v", 100_000).unwrap();
+    let ret: i64 = con.ttl("ex").unwrap();
+    assert_eq!(ret, 100);
+    assert_error(&mut con, redis::cmd("SETEX").arg("ex").arg(-1).arg("v"));
+    assert_error(&mut con, redis::cmd("PSETEX").arg("px").arg("v"));
+
+    let ret: i64 = con.incr("n", 5).unwrap();
+    assert_eq!(ret, 5);
+    let ret: i64 = con.decr("n", 2).unwrap();
+    assert_eq!(ret, 3);
+    let ret: i64 = redis::cmd("INCR").arg("n").query(&mut con).unwrap();
+    assert_eq!(ret, 4);
+    let ret: i64 = redis::cmd("DECR").arg("n").query(&mut con).unwrap();
+    assert_eq!(ret, 3);
+    let ret: f64 = con.incr("f", 1.5).unwrap();
+    assert_eq!(ret, 1.5);
+    assert_error(&mut con, redis::cmd("INCR").arg("a"));
+    assert_error(&mut con, redis::cmd("INCRBY").arg("n").arg("x"));
+    assert_error(&mut con, redis::cmd("INCRBYFLOAT").arg("a").arg(1));
+    assert_error(&mut con, redis::cmd("DECRBY").arg("n"));
+
+    let ret: usize = con.append("a", "!").unwrap();
+    assert_eq!(ret, 6);
+    let ret: usize = con.strlen("a").unwrap();
+    assert_eq!(ret, 6);
+    let ret: String = con.getrange("a", 0, 4).unwrap();
+    assert_eq!(ret, "world");
+    let ret: usize = con.setrange("a", 6, "?").unwrap();
+    assert_eq!(ret, 7);
+    assert_error(&mut con, redis::cmd("APPEND").arg("a"));
+    assert_error(&mut con, &mut redis::cmd("STRLEN"));
+    assert_error(&mut con, redis::cmd("GETRANGE").arg("a").arg("x").arg(1));
+    assert_error(&mut con, redis::cmd("SETRANGE").arg("a").arg(-1).arg("v"));
+
+    let ret: u8 = con.setbit("bits", 7, true).unwrap();
+    assert_eq!(ret, 0);
+    let ret: u8 = con.getbit("bits", 7).unwrap();
+    assert_eq!(ret, 1);
+    let ret: String = con.get("bits").unwrap();
+    assert_eq!(ret, "\u{1}");
+    assert_error(&mut con, redis::cmd("SETBIT").arg("bits").arg(0).arg(2));
+    assert_error(&mut con, redis::cmd("GETBIT").arg("bits").arg(-1));
+    assert_error(&mut con, &mut redis::cmd("GET"));
+}
+
+#[cfg(feature = "str-ext")]
+#[test]
+fn test_str_ext_commands() {
+    let mut con = connect();
+    let () = con.set("a", "héllo").unwrap();
+    let ret: usize = redis::cmd("STR.LEN").arg("a").query(&mut con).unwrap();
+    assert_eq!(ret, 5);
+    let ret: String = redis::cmd("STR.SUBSTR")
+        .arg("a")
+        .arg(1)
+        .arg(2)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, "él");
+
+    let () = con.set("bin", &[0xff_u8, 0xfe][..]).unwrap();
+    assert_error(&mut con, redis::cmd("STR.LEN").arg("bin"));
+    assert_error(&mut con, redis::cmd("STR.SUBSTR").arg("a").arg(1));
+}
+
+#[test]
+fn test_key_commands() {
+    let mut con = connect();
+    let () = con.set("a", "1").unwrap();
+    let () = con.set("b", "2").unwrap();
+    let _: i64 = con.sadd("s", "member").unwrap();
+
+    let ret: usize = con.exists(&["a", "s", "missing"]).unwrap();
+    assert_eq!(ret, 2);
+    let ret: usize = redis::cmd("TOUCH")
+        .arg("a")
+        .arg("missing")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 1);
+    assert_error(&mut con, &mut redis::cmd("EXISTS"));
+    assert_error(&mut con, &mut redis::cmd("TOUCH"));
+
+    let mut keys: Vec<String> = con.keys("*").unwrap();
+    keys.sort();
+    assert_eq!(keys, ["a", "b", "s"]);
+    assert_error(&mut con, &mut redis::cmd("KEYS"));
+
+    let mut keys: Vec<String> = con
+        .scan_match("[ab]")
+        .unwrap()
+        .collect::<RedisResult<_>>()
+        .unwrap();
+    keys.sort();
+    assert_eq!(keys, ["a", "b"]);
+    let (cursor, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+        .arg(0)
+        .arg("TYPE")
+        .arg("set")
+        .arg("COUNT")
+        .arg(1000)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!((cursor, keys), (0, vec!["s".to_string()]));
+    assert_error(&mut con, redis::cmd("SCAN").arg("x"));
+
+    let ret: String = redis::cmd("RANDOMKEY").query(&mut con).unwrap();
+    assert!(["a", "b", "s"].contains(&ret.as_str()));
+    assert_error(&mut con, redis::cmd("RANDOMKEY").arg("a"));
+
+    let ret: String = redis::cmd("OBJECT")
+        .arg("ENCODING")
+        .arg("a")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, "int");
+    let ret: Option<String> = redis::cmd("OBJECT")
+        .arg("ENCODING")
+        .arg("missing")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, None);
+    assert_error(&mut con, redis::cmd("OBJECT").arg("ENCODING"));
+    let ret: i64 = redis::cmd("OBJECT")
+        .arg("REFCOUNT")
+        .arg("a")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 1);
+    let ret: i64 = redis::cmd("OBJECT")
+        .arg("IDLETIME")
+        .arg("a")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 0);
+    let ret: i64 = redis::cmd("OBJECT")
+        .arg("FREQ")
+        .arg("a")
+        .query(&mut con)
+        .unwrap();
+    assert!(ret >= 5);
+    assert_error(&mut con, redis::cmd("OBJECT").arg("FREQ"));
+    assert_error(&mut con, redis::cmd("OBJECT").arg("NOSUCH").arg("a"));
+
+    let () = con.rename("a", "c").unwrap();
+    let ret: bool = con.rename_nx("c", "b").unwrap();
+    assert!(!ret);
+    assert_error(&mut con, redis::cmd("RENAME").arg("missing").arg("x"));
+    assert_error(&mut con, redis::cmd("RENAMENX").arg("c"));
+
+    let ret: usize = con.del(&["b", "c", "missing"]).unwrap();
+    assert_eq!(ret, 2);
+    let ret: usize = con.unlink("s").unwrap();
+    assert_eq!(ret, 1);
+    assert_error(&mut con, &mut redis::cmd("DEL"));
+    assert_error(&mut con, &mut redis::cmd("UNLINK"));
+}
+
+#[test]
+fn test_expire_commands() {
+    let mut con = connect();
+    let () = con.set("a", "1").unwrap();
+    let _: i64 = con.hset("h", "f", "v").unwrap();
+
+    let ret: bool = con.expire("a", 100).unwrap();
+    assert!(ret);
+    let ret: bool = con.expire("missing", 100).unwrap();
+    assert!(!ret);
+    let ret: bool = con.pexpire("h", 100_000).unwrap();
+    assert!(ret);
+    let ret: i64 = con.ttl("a").unwrap();
+    assert_eq!(ret, 100);
+    let ret: i64 = con.pttl("h").unwrap();
+    assert!(ret > 99_000 && ret <= 100_000);
+
+    let at = 4_000_000_000_i64;
+    let ret: bool = con.expire_at("a", at).unwrap();
+    assert!(ret);
+    let ret: bool = con.pexpire_at("h", at * 1000).unwrap();
+    assert!(ret);
+    let ret: i64 = redis::cmd("EXPIRETIME").arg("a").query(&mut con).unwrap();
+    assert_eq!(ret, at);
+    let ret: i64 = redis::cmd("PEXPIRETIME").arg("h").query(&mut con).unwrap();
+    assert_eq!(ret, at * 1000);
+
+    let ret: bool = con.persist("a").unwrap();
+    assert!(ret);
+    let ret: i64 = con.ttl("a").unwrap();
+    assert_eq!(ret, -1);
+    let ret: i64 = con.ttl("missing").unwrap();
+    assert_eq!(ret, -2);
+
+    let ret: bool = redis::cmd("EXPIRE")
+        .arg("a")
+        .arg(100)
+        .arg("XX")
+        .query(&mut con)
+        .unwrap();
+    assert!(!ret);
+    let ret: bool = redis::cmd("EXPIRE")
+        .arg("a")
+        .arg(100)
+        .arg("NX")
+        .query(&mut con)
+        .unwrap();
+    assert!(ret);
+    let ret: bool = redis::cmd("EXPIRE")
+        .arg("a")
+        .arg(200)
+        .arg("LT")
+        .query(&mut con)
+        .unwrap();
+    assert!(!ret);
+
+    // A time in the past deletes the key.
+    let ret: bool = con.pexpire_at("h", 1).unwrap();
+    assert!(ret);
+    let ret: Option<String> = con.hget("h", "f").unwrap();
+    assert_eq!(ret, None);
+
+    assert_error(&mut con, redis::cmd("EXPIRE").arg("a").arg("x"));
+    assert_error(&mut con, redis::cmd("PEXPIRE").arg("a"));
+    assert_error(
+        &mut con,
+        redis::cmd("EXPIRE").arg("a").arg(1).arg("NX").arg("GT"),
+    );
+    assert_error(&mut con, redis::cmd("EXPIREAT").arg("a").arg(i64::MAX));
+    assert_error(&mut con, redis::cmd("PEXPIREAT").arg("a").arg("x"));
+    assert_error(&mut con, &mut redis::cmd("PERSIST"));
+    assert_error(&mut con, redis::cmd("TTL").arg("a").arg("b"));
+    assert_error(&mut con, &mut redis::cmd("PTTL"));
+    assert_error(&mut con, &mut redis::cmd("EXPIRETIME"));
+    assert_error(&mut con, &mut redis::cmd("PEXPIRETIME"));
+}
+
+#[test]
+fn test_debug_ttl_commands() {
+    let mut con = connect();
+    let _: () = con.set("legacy:1", "v").unwrap();
+    let _: () = con.set("other", "v").unwrap();
+
+    // The keys are updated in the background, after the reply.
+    let ret: String = redis::cmd("DEBUG")
+        .arg(&["SET-TTL", "legacy:*", "100"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, "OK");
+    let mut ttl = -1;
+    for _ in 0..100 {
+        ttl = con.ttl("legacy:1").unwrap();
+        if ttl >= 0 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(ttl > 90);
+    let ret: i64 = con.ttl("other").unwrap();
+    assert_eq!(ret, -1);
+
+    let ret: String = redis::cmd("DEBUG")
+        .arg(&["EXPIRE-SCAN", "legacy:*"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, "OK");
+
+    assert_error(
+        &mut con,
+        redis::cmd("DEBUG").arg(&["SET-TTL", "legacy:*", "0"]),
+    );
+    assert_error(&mut con, redis::cmd("DEBUG").arg(&["SET-TTL", "legacy:*"]));
+    assert_error(&mut con, redis::cmd("DEBUG").arg("EXPIRE-SCAN"));
+}
+
+#[test]
+fn test_dump_restore_commands() {
+    let mut con = connect();
+    let _: i64 = con.hset("h", "f", "v").unwrap();
+
+    // The payload is binary, and round-trips through the client unchanged.
+    let payload: Vec<u8> = redis::cmd("DUMP").arg("h").query(&mut con).unwrap();
+    let ret: Option<Vec<u8>> = redis::cmd("DUMP").arg("missing").query(&mut con).unwrap();
+    assert_eq!(ret, None);
+
+    let () = redis::cmd("RESTORE")
+        .arg("copy")
+        .arg(100_000)
+        .arg(&payload)
+        .query(&mut con)
+        .unwrap();
+    let ret: String = con.hget("copy", "f").unwrap();
+    assert_eq!(ret, "v");
+    let ret: i64 = con.ttl("copy").unwrap();
+    assert_eq!(ret, 100);
+
+    let ret: RedisResult<()> = redis::cmd("RESTORE")
+        .arg("copy")
+        .arg(0)
+        .arg(&payload)
+        .query(&mut con);
+    assert_eq!(ret.unwrap_err().code(), Some("BUSYKEY"));
+    let () = redis::cmd("RESTORE")
+        .arg("copy")
+        .arg(0)
+        .arg(&payload)
+        .arg("REPLACE")
+        .query(&mut con)
+        .unwrap();
+    let ret: i64 = con.ttl("copy").unwrap();
+    assert_eq!(ret, -1);
+
+    assert_error(
+        &mut con,
+        redis::cmd("RESTORE").arg("other").arg(0).arg("garbage"),
+    );
+    assert_error(
+        &mut con,
+        redis::cmd("RESTORE").arg("other").arg(-1).arg(&payload),
+    );
+    assert_error(&mut con, redis::cmd("DUMP").arg("a").arg("b"));
+}
+
+#[test]
+fn test_server_commands() {
+    let mut con = connect();
+    let () = con.set("a", "1").unwrap();
+    let _: i64 = con.sadd("s", "member").unwrap();
+
+    let ret: usize = redis::cmd("DBSIZE").query(&mut con).unwrap();
+    assert_eq!(ret, 2);
+    let () = redis::cmd("FLUSHDB").arg("SYNC").query(&mut con).unwrap();
+    let ret: usize = redis::cmd("DBSIZE").query(&mut con).unwrap();
+    assert_eq!(ret, 0);
+
+    let () = con.set("a", "1").unwrap();
+    let () = redis::cmd("FLUSHALL").arg("ASYNC").query(&mut con).unwrap();
+    let ret: Option<String> = con.get("a").unwrap();
+    assert_eq!(ret, None);
+
+    assert_error(&mut con, redis::cmd("DBSIZE").arg("a"));
+    assert_error(&mut con, redis::cmd("FLUSHDB").arg("LATER"));
+    assert_error(&mut con, redis::cmd("FLUSHALL").arg("SYNC").arg("ASYNC"));
+}
+
+#[test]
+fn test_hash_commands() {
+    let mut con = connect();
+    let ret: i64 = con.hset("h", "a", "1").unwrap();
+    assert_eq!(ret, 1);
+    let _: i64 = con.hset("h", "b", "2").unwrap();
+    let ret: i64 = con.hset("h", "b", "2").unwrap();
+    assert_eq!(ret, 0);
+
+    let ret: Option<String> = con.hget("h", "a").unwrap();
+    assert_eq!(ret.as_deref(), Some("1"));
+    let ret: Option<String> = con.hget("h", "missing").unwrap();
+    assert_eq!(ret, None);
+    let ret: Vec<Option<String>> = redis::cmd("HMGET")
+        .arg("h")
+        .arg("a")
+        .arg("missing")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, [Some("1".to_string()), None]);
+    let mut ret: Vec<(String, String)> = con.hgetall("h").unwrap();
+    ret.sort();
+    assert_eq!(
+        ret,
+        [
+            ("a".to_string(), "1".to_string()),
+            ("b".to_string(), "2".to_string())
+        ]
+    );
+
+    let ret: i64 = redis::cmd("HSET")
+        .arg(&["h2", "a", "1", "b", "2", "a", "3"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 2);
+    let _: () = con.hset_multiple("h2", &[("b", "4"), ("c", "5")]).unwrap();
+    let ret: Vec<String> = redis::cmd("HMGET")
+        .arg(&["h2", "a", "b", "c"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, ["3", "4", "5"]);
+    let _: () = con.del("h2").unwrap();
+
+    assert_error(&mut con, redis::cmd("HSET").arg("h").arg("a"));
+    assert_error(&mut con, redis::cmd("HSET").arg(&["h", "a", "1", "b"]));
+    assert_error(&mut con, redis::cmd("HMSET").arg("h"));
+    assert_error(&mut con, redis::cmd("HGET").arg("h"));
+    assert_error(&mut con, &mut redis::cmd("HGETALL"));
+    assert_error(&mut con, redis::cmd("HMGET").arg("h"));
+
+    let ret: bool = con.hexists("h", "a").unwrap();
+    assert!(ret);
+    let ret: bool = con.hexists("h", "missing").unwrap();
+    assert!(!ret);
+    let ret: i64 = con.hlen("h").unwrap();
+    assert_eq!(ret, 2);
+    let ret: i64 = redis::cmd("HSTRLEN")
+        .arg(&["h", "a"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 1);
+    let ret: i64 = redis::cmd("HSTRLEN")
+        .arg(&["h", "missing"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 0);
+    assert_error(&mut con, redis::cmd("HEXISTS").arg("h"));
+    assert_error(&mut con, redis::cmd("HSTRLEN").arg("h"));
+    assert_error(&mut con, &mut redis::cmd("HLEN"));
+
+    let mut ret: Vec<String> = con.hkeys("h").unwrap();
+    ret.sort();
+    assert_eq!(ret, ["a", "b"]);
+    let mut ret: Vec<String> = con.hvals("h").unwrap();
+    ret.sort();
+    assert_eq!(ret, ["1", "2"]);
+    let ret: Vec<String> = con.hkeys("missing").unwrap();
+    assert!(ret.is_empty());
+    assert_error(&mut con, redis::cmd("HKEYS").arg("h").arg("a"));
+    assert_error(&mut con, &mut redis::cmd("HVALS"));
+
+    let (cursor, ret): (u64, Vec<String>) = redis::cmd("HSCAN")
+        .arg(&["h", "0", "MATCH", "a", "COUNT", "1000"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!((cursor, ret), (0, vec!["a".to_string(), "1".to_string()]));
+    let (_, ret): (u64, Vec<String>) = redis::cmd("HSCAN")
+        .arg(&["h", "0", "COUNT", "1000", "NOVALUES"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret.len(), 2);
+    assert_error(&mut con, redis::cmd("HSCAN").arg(&["h", "0", "COUNT"]));
+
+    let ret: String = redis::cmd("HRANDFIELD").arg("h").query(&mut con).unwrap();
+    assert!(ret == "a" || ret == "b");
+    let ret: Vec<String> = redis::cmd("HRANDFIELD")
+        .arg(&["h", "-3", "WITHVALUES"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret.len(), 6);
+    let ret: Vec<String> = redis::cmd("HRANDFIELD")
+        .arg(&["h", "5"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret.len(), 2);
+    assert_error(&mut con, redis::cmd("HRANDFIELD").arg(&["h", "WITHVALUES"]));
+
+    let ret: i64 = con.hincr("h", "a", 3).unwrap();
+    assert_eq!(ret, 4);
+    let ret: String = con.hincr("h", "a", 0.5).unwrap();
+    assert_eq!(ret, "4.5");
+    assert_error(&mut con, redis::cmd("HINCRBY").arg("h").arg("a").arg(1));
+    assert_error(&mut con, redis::cmd("HINCRBY").arg("h").arg("b").arg("x"));
+    let ret: String = redis::cmd("HINCRBYFLOAT")
+        .arg(&["h", "sum", "0.1"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, "0.1");
+    let ret: String = redis::cmd("HINCRBYFLOAT")
+        .arg(&["h", "sum", "0.2"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, "0.3");
+    let ret: String = con.hget("h", "sum").unwrap();
+    assert_eq!(ret, "0.3");
+    assert_error(&mut con, redis::cmd("HINCRBYFLOAT").arg(&["h", "sum", "x"]));
+    assert_error(&mut con, redis::cmd("HINCRBYFLOAT").arg(&["h", "sum"]));
+
+    let ret: Vec<i64> = redis::cmd("HEXPIRE")
+        .arg(&["h", "100", "FIELDS", "2", "a", "missing"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, [1, -2]);
+    let ret: Vec<i64> = redis::cmd("HTTL")
+        .arg(&["h", "FIELDS", "2", "a", "b"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, [100, -1]);
+    let ret: Vec<i64> = redis::cmd("HPEXPIRE")
+        .arg(&["h", "50000", "GT", "FIELDS", "1", "a"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, [0]);
+    let ret: Vec<i64> = redis::cmd("HPERSIST")
+        .arg(&["h", "FIELDS", "2", "a", "b"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, [1, -1]);
+    assert_error(
+        &mut con,
+        redis::cmd("HEXPIRE").arg(&["h", "100", "FIELDS", "2", "a"]),
+    );
+    assert_error(&mut con, redis::cmd("HTTL").arg(&["h", "a"]));
+
+    let ret: i64 = con.hdel("h", &["a", "sum", "missing"]).unwrap();
+    assert_eq!(ret, 2);
+    let ret: i64 = con.hdel("h", "b").unwrap();
+    assert_eq!(ret, 1);
+    let ret: bool = con.exists("h").unwrap();
+    assert!(!ret);
+    assert_error(&mut con, redis::cmd("HDEL").arg("h"));
+}
+
+#[test]
+fn test_set_commands() {
+    let mut con = connect();
+    let ret: i64 = con.sadd("s", &["a", "b", "a"]).unwrap();
+    assert_eq!(ret, 2);
+    let ret: bool = con.sismember("s", "a").unwrap();
+    assert!(ret);
+    let ret: bool = con.sismember("s", "c").unwrap();
+    assert!(!ret);
+    let mut ret: Vec<String> = con.smembers("s").unwrap();
+    ret.sort();
+    assert_eq!(ret, ["a", "b"]);
+
+    assert_error(&mut con, redis::cmd("SADD").arg("s"));
+    assert_error(&mut con, redis::cmd("SISMEMBER").arg("s"));
+    assert_error(&mut con, &mut redis::cmd("SMEMBERS"));
+
+    let ret: String = con.srandmember("s").unwrap();
+    assert!(ret == "a" || ret == "b");
+    let ret: Vec<String> = con.srandmember_multiple("s", 5).unwrap();
+    assert_eq!(ret.len(), 2);
+    let ret: Vec<String> = redis::cmd("SRANDMEMBER")
+        .arg(&["s", "-5"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret.len(), 5);
+    assert_error(&mut con, redis::cmd("SRANDMEMBER").arg(&["s", "x"]));
+
+    let _: i64 = con.sadd("s2", &["b", "c"]).unwrap();
+    let ret: Vec<String> = con.sinter(&["s", "s2"]).unwrap();
+    assert_eq!(ret, ["b"]);
+    let mut ret: Vec<String> = con.sunion(&["s", "s2"]).unwrap();
+    ret.sort();
+    assert_eq!(ret, ["a", "b", "c"]);
+    let ret: Vec<String> = con.sdiff(&["s", "s2"]).unwrap();
+    assert_eq!(ret, ["a"]);
+    assert_error(&mut con, &mut redis::cmd("SINTER"));
+
+    let ret: usize = con.sunionstore("s3", &["s", "s2"]).unwrap();
+    assert_eq!(ret, 3);
+    let ret: usize = con.sinterstore("s3", &["s", "s2"]).unwrap();
+    assert_eq!(ret, 1);
+    let ret: Vec<String> = con.smembers("s3").unwrap();
+    assert_eq!(ret, ["b"]);
+    let ret: usize = con.sdiffstore("s3", &["s2", "s2"]).unwrap();
+    assert_eq!(ret, 0);
+    let ret: bool = con.exists("s3").unwrap();
+    assert!(!ret);
+    assert_error(&mut con, redis::cmd("SDIFFSTORE").arg("s3"));
+
+    let ret: bool = con.smove("s2", "s4", "c").unwrap();
+    assert!(ret);
+    let ret: bool = con.smove("s2", "s4", "c").unwrap();
+    assert!(!ret);
+    let ret: Vec<String> = con.smembers("s4").unwrap();
+    assert_eq!(ret, ["c"]);
+    assert_error(&mut con, redis::cmd("SMOVE").arg(&["s2", "s4"]));
+
+    let ret: i64 = con.srem("s", &["a", "missing"]).unwrap();
+    assert_eq!(ret, 1);
+    let ret: i64 = con.srem("s", "b").unwrap();
+    assert_eq!(ret, 1);
+    let ret: bool = con.exists("s").unwrap();
+    assert!(!ret);
+    assert_error(&mut con, redis::cmd("SREM").arg("s"));
+}
+
+#[test]
+fn test_list_commands() {
+    let mut con = connect();
+    let ret: usize = con.lpush("l", &["b", "a"]).unwrap();
+    assert_eq!(ret, 2);
+    let ret: usize = con.rpush("l", &["c", "d"]).unwrap();
+    assert_eq!(ret, 4);
+    let ret: usize = con.llen("l").unwrap();
+    assert_eq!(ret, 4);
+    let ret: Option<String> = con.lpop("l", None).unwrap();
+    assert_eq!(ret.as_deref(), Some("a"));
+    let ret: Option<String> = con.rpop("l", None).unwrap();
+    assert_eq!(ret.as_deref(), Some("d"));
+    let ret: Option<String> = con.rpop("missing", None).unwrap();
+    assert_eq!(ret, None);
+    let (_, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+        .arg(0)
+        .arg("TYPE")
+        .arg("list")
+        .arg("COUNT")
+        .arg(1000)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(keys, ["l"]);
+    assert_error(&mut con, redis::cmd("LPUSH").arg("l"));
+    assert_error(&mut con, redis::cmd("LPOP").arg(&["l", "x"]));
+    assert_error(&mut con, &mut redis::cmd("LLEN"));
+
+    let ret: Vec<String> = con.lrange("l", 0, -1).unwrap();
+    assert_eq!(ret, ["b", "c"]);
+    let ret: Vec<String> = con.lrange("l", -1, 10).unwrap();
+    assert_eq!(ret, ["c"]);
+    assert_error(&mut con, redis::cmd("LRANGE").arg(&["l", "0", "x"]));
+
+    let () = con.lset("l", -1, "z").unwrap();
+    let ret: Option<String> = con.lindex("l", 1).unwrap();
+    assert_eq!(ret.as_deref(), Some("z"));
+    let ret: Option<String> = con.lindex("l", 5).unwrap();
+    assert_eq!(ret, None);
+    assert_error(&mut con, redis::cmd("LINDEX").arg("l"));
+    assert_error(&mut con, redis::cmd("LSET").arg(&["l", "5", "v"]));
+    assert_error(&mut con, redis::cmd("LSET").arg(&["missing", "0", "v"]));
+
+    let ret: i64 = con.linsert_before("l", "b", "a").unwrap();
+    assert_eq!(ret, 3);
+    let ret: i64 = con.linsert_after("l", "missing", "a").unwrap();
+    assert_eq!(ret, -1);
+    let ret: usize = con.lrem("l", 0, "a").unwrap();
+    assert_eq!(ret, 1);
+    let () = con.ltrim("l", 1, -1).unwrap();
+    let ret: Vec<String> = con.lrange("l", 0, -1).unwrap();
+    assert_eq!(ret, ["z"]);
+    assert_error(
+        &mut con,
+        redis::cmd("LINSERT").arg(&["l", "UNDER", "z", "a"]),
+    );
+    assert_error(&mut con, redis::cmd("LREM").arg(&["l", "x", "a"]));
+    assert_error(&mut con, redis::cmd("LTRIM").arg(&["l", "0"]));
+
+    let _: usize = con.rpush("l", &["a", "b"]).unwrap();
+    let ret: String = con
+        .lmove("l", "l2", redis::Direction::Left, redis::Direction::Right)
+        .unwrap();
+    assert_eq!(ret, "z");
+    let ret: String = con.rpoplpush("l", "l2").unwrap();
+    assert_eq!(ret, "b");
+    let ret: Vec<String> = con.lrange("l2", 0, -1).unwrap();
+    assert_eq!(ret, ["b", "z"]);
+    let ret: Option<String> = con.rpoplpush("missing", "l2").unwrap();
+    assert_eq!(ret, None);
+    assert_error(
+        &mut con,
+        redis::cmd("LMOVE").arg(&["l", "l2", "LEFT", "UP"]),
+    );
+
+    let _: usize = con.rpush("l3", &["a", "b", "a"]).unwrap();
+    let ret: Option<usize> = con.lpos("l3", "a", redis::LposOptions::default()).unwrap();
+    assert_eq!(ret, Some(0));
+    let ret: Vec<usize> = con
+        .lpos("l3", "a", redis::LposOptions::default().rank(-1).count(0))
+        .unwrap();
+    assert_eq!(ret, [2, 0]);
+    assert_error(&mut con, redis::cmd("LPOS").arg(&["l3", "a", "RANK", "0"]));
+
+    let ret: Option<[String; 2]> = con.blpop(&["missing", "l3"], 0.1).unwrap();
+    assert_eq!(ret, Some(["l3".to_string(), "a".to_string()]));
+    let ret: Option<[String; 2]> = con.brpop("l3", 0.1).unwrap();
+    assert_eq!(ret, Some(["l3".to_string(), "a".to_string()]));
+    let ret: Option<[String; 2]> = con.blpop("missing", 0.1).unwrap();
+    assert_eq!(ret, None);
+    assert_error(&mut con, redis::cmd("BLPOP").arg(&["l3", "-1"]));
+
+    let _: usize = con.rpush("l4", &["a", "b", "c"]).unwrap();
+    let ret: Option<(String, Vec<String>)> = con
+        .lmpop(2, &["missing", "l4"], redis::Direction::Right, 2)
+        .unwrap();
+    assert_eq!(
+        ret,
+        Some(("l4".to_string(), vec!["c".to_string(), "b".to_string()]))
+    );
+    let ret: Option<(String, Vec<String>)> =
+        con.blmpop(0.1, 1, "l4", redis::Direction::Left, 2).unwrap();
+    assert_eq!(ret, Some(("l4".to_string(), vec!["a".to_string()])));
+    let ret: Option<(String, Vec<String>)> =
+        con.blmpop(0.1, 1, "l4", redis::Direction::Left, 1).unwrap();
+    assert_eq!(ret, None);
+    assert_error(&mut con, redis::cmd("LMPOP").arg(&["2", "l4", "LEFT"]));
+
+    let _: usize = con.rpush("l5", &["a", "b", "c"]).unwrap();
+    let ret: Vec<String> = con.lpop("l5", NonZeroUsize::new(2)).unwrap();
+    assert_eq!(ret, ["a", "b"]);
+    let ret: Vec<String> = con.rpop("l5", NonZeroUsize::new(2)).unwrap();
+    assert_eq!(ret, ["c"]);
+    let ret: Option<Vec<String>> = con.rpop("l5", NonZeroUsize::new(2)).unwrap();
+    assert_eq!(ret, None);
+}
+
+#[test]
+fn test_delay_push_command() {
+    let mut con = connect();
+    let ret: usize = redis::cmd("DELAY.PUSH")
+        .arg(&["jobs", "a", "200"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 1);
+    let ret: usize = redis::cmd("DELAY.PUSH")
+        .arg(&["jobs", "b", "100"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 2);
+
+    // The jobs are handed to a blocked consumer in the order they are due.
+    let ret: usize = con.llen("jobs").unwrap();
+    assert_eq!(ret, 0);
+    let ret: (String, String) = con.blpop("jobs", 2.0).unwrap();
+    assert_eq!(ret, ("jobs".to_string(), "b".to_string()));
+    let ret: (String, String) = con.blpop("jobs", 2.0).unwrap();
+    assert_eq!(ret, ("jobs".to_string(), "a".to_string()));
+
+    assert_error(&mut con, redis::cmd("DELAY.PUSH").arg(&["jobs", "c", "-1"]));
+    assert_error(&mut con, redis::cmd("DELAY.PUSH").arg(&["jobs", "c"]));
+}
+
+#[test]
+fn test_zset_commands() {
+    let mut con = connect();
+    let ret: usize = con.zadd_multiple("z", &[(1.5, "a"), (2.0, "b")]).unwrap();
+    assert_eq!(ret, 2);
+    let ret: usize = con.zadd("z", "a", 3).unwrap();
+    assert_eq!(ret, 0);
+    let ret: usize = con.zcard("z").unwrap();
+    assert_eq!(ret, 2);
+    let ret: Option<f64> = con.zscore("z", "a").unwrap();
+    assert_eq!(ret, Some(3.0));
+    let ret: Option<f64> = con.zscore("z", "missing").unwrap();
+    assert_eq!(ret, None);
+    let (_, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+        .arg(0)
+        .arg("TYPE")
+        .arg("zset")
+        .arg("COUNT")
+        .arg(1000)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(keys, ["z"]);
+    assert_error(&mut con, redis::cmd("ZADD").arg(&["z", "nan", "a"]));
+    assert_error(&mut con, redis::cmd("ZADD").arg(&["z", "1"]));
+    assert_error(&mut con, &mut redis::cmd("ZCARD"));
+
+    let _: usize = con.zadd("z", "c", 0.5).unwrap();
+    let ret: Vec<String> = con.zrange("z", 0, -1).unwrap();
+    assert_eq!(ret, ["c", "b", "a"]);
+    let ret: Vec<(String, f64)> = con.zrange_withscores("z", 1, 1).unwrap();
+    assert_eq!(ret, [("b".to_string(), 2.0)]);
+    let ret: Vec<String> = con.zrevrange("z", 0, 1).unwrap();
+    assert_eq!(ret, ["a", "b"]);
+    let ret: Vec<String> = con.zrevrange_withscores("z", -1, -1).unwrap();
+    assert_eq!(ret, ["c", "0.5"]);
+    let ret: Vec<String> = redis::cmd("ZRANGE")
+        .arg(&["z", "0", "0", "REV"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, ["a"]);
+    assert_error(&mut con, redis::cmd("ZRANGE").arg(&["z", "0", "x"]));
+
+    let ret: f64 = con.zincr("z", "c", 2).unwrap();
+    assert_eq!(ret, 2.5);
+    let ret: f64 = con.zincr("z", "d", -1.5).unwrap();
+    assert_eq!(ret, -1.5);
+    let _: f64 = con.zincr("z", "d", "inf").unwrap();
+    assert_error(&mut con, redis::cmd("ZINCRBY").arg(&["z", "-inf", "d"]));
+
+    let ret: Option<usize> = con.zrank("z", "c").unwrap();
+    assert_eq!(ret, Some(1));
+    let ret: Option<usize> = con.zrevrank("z", "c").unwrap();
+    assert_eq!(ret, Some(2));
+    let ret: Option<usize> = con.zrank("z", "missing").unwrap();
+    assert_eq!(ret, None);
+    let ret: (usize, f64) = redis::cmd("ZRANK")
+        .arg(&["z", "b", "WITHSCORE"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, (0, 2.0));
+
+    let ret: usize = con.zrem("z", &["a", "missing"]).unwrap();
+    assert_eq!(ret, 1);
+    let ret: usize = con.zrembyscore("z", "(2", 2.5).unwrap();
+    assert_eq!(ret, 1);
+    let ret: usize = con.zremrangebyrank("z", -1, -1).unwrap();
+    assert_eq!(ret, 1);
+    let ret: Vec<String> = con.zrange("z", 0, -1).unwrap();
+    assert_eq!(ret, vec!["b"]);
+    let ret: redis::RedisResult<usize> = con.zrembyscore("z", "(x", 1);
+    assert!(ret.is_err());
+
+    let _: usize = con
+        .zadd_multiple("zlex", &[(0, "a"), (0, "b"), (0, "c")])
+        .unwrap();
+    let ret: usize = con.zrembylex("zlex", "(a", "[b").unwrap();
+    assert_eq!(ret, 1);
+    let ret: usize = con.zrembylex("zlex", "-", "+").unwrap();
+    assert_eq!(ret, 2);
+    let ret: bool = con.exists("zlex").unwrap();
+    assert!(!ret);
+
+    let _: usize = con
+        .zadd_multiple("zpop", &[(1, "a"), (2, "b"), (3, "c"), (4, "d")])
+        .unwrap();
+    let ret: Vec<String> = con.zpopmin("zpop", 1).unwrap();
+    assert_eq!(ret, ["a", "1"]);
+    let ret: Vec<String> = con.zpopmax("zpop", 2).unwrap();
+    assert_eq!(ret, ["d", "4", "c", "3"]);
+    let ret: Option<(String, String, f64)> = con.bzpopmax(&["missing", "zpop"], 0.1).unwrap();
+    assert_eq!(ret, Some(("zpop".to_string(), "b".to_string(), 2.0)));
+    let ret: Option<(String, String, f64)> = con.bzpopmin("zpop", 0.1).unwrap();
+    assert_eq!(ret, None);
+    let ret: Vec<String> = con.zpopmin("zpop", 1).unwrap();
+    assert!(ret.is_empty());
+    assert_error(&mut con, redis::cmd("ZPOPMIN").arg(&["zpop", "-1"]));
+
+    let _: usize = con
+        .zadd_multiple("z1", &[(1, "a"), (2, "b"), (3, "c")])
+        .unwrap();
+    let _: usize = con.sadd("s1", &["b", "d"]).unwrap();
+    let ret: usize = con
+        .zunionstore_weights("zu", &[("z1", 2), ("s1", 1)])
+        .unwrap();
+    assert_eq!(ret, 4);
+    let ret: Vec<(String, f64)> = con.zrange_withscores("zu", 0, -1).unwrap();
+    assert_eq!(
+        ret,
+        [
+            ("d".to_string(), 1.0),
+            ("a".to_string(), 2.0),
+            ("b".to_string(), 5.0),
+            ("c".to_string(), 6.0)
+        ]
+    );
+    let ret: usize = con.zinterstore_max("zi", &["z1", "s1"]).unwrap();
+    assert_eq!(ret, 1);
+    let ret: Option<f64> = con.zscore("zi", "b").unwrap();
+    assert_eq!(ret, Some(2.0));
+    let ret: Vec<String> = redis::cmd("ZDIFF")
+        .arg(&["2", "z1", "s1", "WITHSCORES"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, ["a", "1", "c", "3"]);
+    assert_error(&mut con, redis::cmd("ZUNIONSTORE").arg(&["zu", "0", "z1"]));
+
+    let (cursor, ret): (u64, Vec<String>) = redis::cmd("ZSCAN")
+        .arg(&["zu", "0", "MATCH", "[ab]", "COUNT", "100"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(cursor, 0);
+    let mut pairs: Vec<_> = ret.chunks(2).map(|pair| pair.to_vec()).collect();
+    pairs.sort();
+    assert_eq!(pairs, [["a", "2"], ["b", "5"]]);
+    assert_error(
+        &mut con,
+        redis::cmd("ZSCAN").arg(&["zu", "0", "COUNT", "0"]),
+    );
+
+    let ret: usize = redis::cmd("ZADD")
+        .arg(&["zopt", "XX", "1", "a"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 0);
+    let ret: usize = redis::cmd("ZADD")
+        .arg(&["zopt", "NX", "1", "a", "2", "b"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 2);
+    let ret: usize = redis::cmd("ZADD")
+        .arg(&["zopt", "GT", "CH", "0", "a", "3", "b", "1", "c"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 2);
+    let ret: Option<f64> = redis::cmd("ZADD")
+        .arg(&["zopt", "INCR", "1.5", "a"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, Some(2.5));
+    let ret: Option<f64> = redis::cmd("ZADD")
+        .arg(&["zopt", "LT", "INCR", "1", "a"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, None);
+    assert_error(
+        &mut con,
+        redis::cmd("ZADD").arg(&["zopt", "NX", "XX", "1", "a"]),
+    );
+
+    let ret: usize = con.zcount("zu", "(1", "+inf").unwrap();
+    assert_eq!(ret, 3);
+    let ret: usize = con.zlexcount("zlex2", "-", "+").unwrap();
+    assert_eq!(ret, 0);
+    let _: usize = con
+        .zadd_multiple("zlex2", &[(0, "a"), (0, "b"), (0, "c")])
+        .unwrap();
+    let ret: usize = con.zlexcount("zlex2", "(a", "[c").unwrap();
+    assert_eq!(ret, 2);
+    assert_error(&mut con, redis::cmd("ZCOUNT").arg(&["zu", "x", "1"]));
+
+    let ret: Vec<Option<f64>> = redis::cmd("ZMSCORE")
+        .arg(&["zu", "a", "missing", "c"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, [Some(2.0), None, Some(6.0)]);
+    assert_error(&mut con, redis::cmd("ZMSCORE").arg("zu"));
+
+    let ret: usize = redis::cmd("ZRANGESTORE")
+        .arg(&["zr", "zu", "(1", "+inf", "BYSCORE", "LIMIT", "0", "2"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 2);
+    let ret: Vec<String> = con.zrange("zr", 0, -1).unwrap();
+    assert_eq!(ret, ["a", "b"]);
+    let ret: usize = redis::cmd("ZRANGESTORE")
+        .arg(&["zr", "zu", "0", "0", "REV"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 1);
+    let ret: Vec<String> = con.zrange("zr", 0, -1).unwrap();
+    assert_eq!(ret, ["c"]);
+    assert_error(
+        &mut con,
+        redis::cmd("ZRANGESTORE").arg(&["zr", "zu", "0", "1", "LIMIT", "0", "1"]),
+    );
+}
+
+#[test]
+fn test_stream_commands() {
+    let mut con = connect();
+    let ret: String = redis::cmd("XADD")
+        .arg(&["x", "1-1", "a", "1", "b", "2"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, "1-1");
+    let ret: String = redis::cmd("XADD")
+        .arg(&["x", "1-*", "c", "3"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, "1-2");
+    let ret: String = redis::cmd("XADD")
+        .arg(&["x", "*", "d", "4"])
+        .query(&mut con)
+        .unwrap();
+    assert_ne!(ret, "1-3");
+    assert_error(&mut con, redis::cmd("XADD").arg(&["x", "1-2", "e", "5"]));
+    assert_error(&mut con, redis::cmd("XADD").arg(&["x2", "0-0", "e", "5"]));
+    let ret: Option<String> = redis::cmd("XADD")
+        .arg(&["x2", "NOMKSTREAM", "*", "e", "5"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, None);
+    let ret: usize = redis::cmd("XLEN").arg("x").query(&mut con).unwrap();
+    assert_eq!(ret, 3);
+    let ret: usize = redis::cmd("XLEN").arg("x2").query(&mut con).unwrap();
+    assert_eq!(ret, 0);
+
+    let ret: Vec<(String, Vec<String>)> = redis::cmd("XRANGE")
+        .arg(&["x", "-", "1"])
+        .query(&mut con)
+        .unwrap();
+    let expected = [
+        ("1-1".to_string(), vec!["a", "1", "b", "2"]),
+        ("1-2".to_string(), vec!["c", "3"]),
+    ];
+    assert_eq!(ret.len(), 2);
+    for ((id, fields), (expected_id, expected_fields)) in ret.iter().zip(&expected) {
+        assert_eq!(id, expected_id);
+        assert_eq!(fields, expected_fields);
+    }
+    let ret: Vec<(String, Vec<String>)> = redis::cmd("XRANGE")
+        .arg(&["x", "(1-1", "+", "COUNT", "1"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret.len(), 1);
+    assert_eq!(ret[0].0, "1-2");
+    let ret: Vec<(String, Vec<String>)> = redis::cmd("XREVRANGE")
+        .arg(&["x", "+", "-", "COUNT", "1"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret[0].1, ["d", "4"]);
+    assert_error(&mut con, redis::cmd("XRANGE").arg(&["x", "a", "+"]));
+
+    let (_, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+        .arg(0)
+        .arg("TYPE")
+        .arg("stream")
+        .arg("COUNT")
+        .arg(1000)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(keys, ["x"]);
+
+    // Each stream with its entries.
+    type Streams = Vec<(String, Vec<(String, Vec<String>)>)>;
+    let ret: Streams = redis::cmd("XREAD")
+        .arg(&["COUNT", "1", "STREAMS", "x", "missing", "1-1", "0"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret.len(), 1);
+    assert_eq!(ret[0].0, "x");
+    assert_eq!(ret[0].1[0].0, "1-2");
+    let ret: Value = redis::cmd("XREAD")
+        .arg(&["BLOCK", "10", "STREAMS", "x", "$"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, Value::Nil);
+    assert_error(
+        &mut con,
+        redis::cmd("XREAD").arg(&["STREAMS", "x", "y", "$"]),
+    );
+
+    let () = redis::cmd("XGROUP")
+        .arg(&["CREATE", "x", "g", "0"])
+        .query(&mut con)
+        .unwrap();
+    assert_error(
+        &mut con,
+        redis::cmd("XGROUP").arg(&["CREATE", "x", "g", "$"]),
+    );
+    assert_error(
+        &mut con,
+        redis::cmd("XGROUP").arg(&["CREATE", "y", "g", "$"]),
+    );
+    let ret: Streams = redis::cmd("XREADGROUP")
+        .arg(&["GROUP", "g", "alice", "COUNT", "2", "STREAMS", "x", ">"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret[0].1.len(), 2);
+    assert_eq!(ret[0].1[0].0, "1-1");
+    let ret: usize = redis::cmd("XACK")
+        .arg(&["x", "g", "1-1", "1-1"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 1);
+    let ret: Streams = redis::cmd("XREADGROUP")
+        .arg(&["GROUP", "g", "alice", "STREAMS", "x", "0"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret[0].1.len(), 1);
+    assert_eq!(ret[0].1[0].0, "1-2");
+    let ret: usize = redis::cmd("XGROUP")
+        .arg(&["DELCONSUMER", "x", "g", "alice"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 1);
+    assert_error(
+        &mut con,
+        redis::cmd("XREADGROUP").arg(&["GROUP", "h", "alice", "STREAMS", "x", ">"]),
+    );
+
+    let () = redis::cmd("XGROUP")
+        .arg(&["SETID", "x", "g", "0"])
+        .query(&mut con)
+        .unwrap();
+    let _: Value = redis::cmd("XREADGROUP")
+        .arg(&["GROUP", "g", "bob", "STREAMS", "x", ">"])
+        .query(&mut con)
+        .unwrap();
+    let (count, first, last, consumers): (usize, String, String, Vec<(String, String)>) =
+        redis::cmd("XPENDING")
+            .arg(&["x", "g"])
+            .query(&mut con)
+            .unwrap();
+    assert_eq!((count, first.as_str()), (3, "1-1"));
+    assert_ne!(last, "1-1");
+    assert_eq!(consumers, [("bob".to_string(), "3".to_string())]);
+    let ret: Vec<(String, String, u64, u64)> = redis::cmd("XPENDING")
+        .arg(&["x", "g", "-", "+", "1", "bob"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret.len(), 1);
+    assert_eq!(
+        (ret[0].0.as_str(), ret[0].1.as_str(), ret[0].3),
+        ("1-1", "bob", 1)
+    );
+    let ret: Vec<String> = redis::cmd("XCLAIM")
+        .arg(&["x", "g", "carol", "0", "1-1", "1-2", "JUSTID"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, ["1-1", "1-2"]);
+    let (next, claimed, deleted): (String, Vec<String>, Vec<String>) = redis::cmd("XAUTOCLAIM")
+        .arg(&["x", "g", "dave", "0", "0", "COUNT", "1", "JUSTID"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(
+        (next.as_str(), claimed, deleted),
+        ("1-2", vec!["1-1".to_string()], vec![])
+    );
+    assert_error(&mut con, redis::cmd("XPENDING").arg(&["x", "h"]));
+    assert_error(
+        &mut con,
+        redis::cmd("XCLAIM").arg(&["x", "g", "carol", "0"]),
+    );
+
+    let ret: String = redis::cmd("XADD")
+        .arg(&["x", "MAXLEN", "=", "3", "*", "e", "5"])
+        .query(&mut con)
+        .unwrap();
+    assert!(!ret.is_empty());
+    let ret: usize = redis::cmd("XLEN").arg("x").query(&mut con).unwrap();
+    assert_eq!(ret, 3);
+    let ret: usize = redis::cmd("XDEL")
+        .arg(&["x", "1-1", "1-2", "9-9"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 1);
+    let ret: usize = redis::cmd("XTRIM")
+        .arg(&["x", "MAXLEN", "~", "1"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 0);
+    let ret: usize = redis::cmd("XTRIM")
+        .arg(&["x", "MINID", "0"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 0);
+    let ret: usize = redis::cmd("XTRIM")
+        .arg(&["x", "MAXLEN", "1"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 1);
+    assert_error(
+        &mut con,
+        redis::cmd("XTRIM").arg(&["x", "MAXLEN", "1", "LIMIT", "10"]),
+    );
+
+    let ret: HashMap<String, Value> = redis::cmd("XINFO")
+        .arg(&["STREAM", "x"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret["length"], Value::Int(1));
+    assert_eq!(ret["entries-added"], Value::Int(4));
+    assert_eq!(
+        ret["max-deleted-entry-id"],
+        Value::BulkString(b"1-2".to_vec())
+    );
+    let ret: Vec<HashMap<String, Value>> = redis::cmd("XINFO")
+        .arg(&["GROUPS", "x"])
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret.len(), 1);
+    assert_eq!(ret[0]["name"], Value::BulkString(b"g".to_vec()));
+    assert_eq!(ret[0]["lag"], Value::Int(1));
+    let ret: Vec<HashMap<String, Value>> = redis::cmd("XINFO")
+        .arg(&["CONSUMERS", "x", "g"])
+        .query(&mut con)
+        .unwrap();
+    let names: Vec<&Value> = ret.iter().map(|consumer| &consumer["name"]).collect();
+    assert_eq!(names.len(), 3);
+    assert_error(&mut con, redis::cmd("XINFO").arg(&["STREAM", "missing"]));
+}
+
+#[test]
+fn test_blocking_xread_readers() {
+    let addr = start_server();
+    let open = || {
+        redis::Client::open(format!("redis://{}/", addr))
+            .and_then(|client| client.get_connection())
+            .expect("failed to connect")
+    };
+    let read = |timeout: &'static str, id: &'static str| {
+        let mut con = open();
+        std::thread::spawn(move || {
+            redis::cmd("XREAD")
+                .arg(&["BLOCK", timeout, "STREAMS", "s", id])
+                .query::<Value>(&mut con)
+                .unwrap()
+        })
+    };
+
+    // The first reader waits for entries after the one added, the second one is served it
+    // before timing out.
+    let first = read("0", "9999999999999-0");
+    std::thread::sleep(Duration::from_millis(100));
+    let second = read("1000", "$");
+    std::thread::sleep(Duration::from_millis(100));
+    let mut con = open();
+    let _: String = con.xadd("s", "1-1", &[("f", "v")]).unwrap();
+    assert_ne!(second.join().unwrap(), Value::Nil);
+    let _: String = con.xadd("s", "9999999999999-1", &[("f", "v")]).unwrap();
+    assert_ne!(first.join().unwrap(), Value::Nil);
+}
+
+#[test]
+fn test_sort_command() {
+    let mut con = connect();
+    let _: i64 = con.sadd("s", &["3", "1", "2"]).unwrap();
+    let () = con.set("w_1", "30").unwrap();
+    let () = con.set("w_2", "20").unwrap();
+    let () = con.set("w_3", "10").unwrap();
+
+    let ret: Vec<String> = redis::cmd("SORT").arg("s").query(&mut con).unwrap();
+    assert_eq!(ret, vec!["1", "2", "3"]);
+    let ret: Vec<String> = redis::cmd("SORT")
+        .arg("s")
+        .arg("BY")
+        .arg("w_*")
+        .arg("LIMIT")
+        .arg(0)
+        .arg(2)
+        .arg("GET")
+        .arg("#")
+        .arg("GET")
+        .arg("w_*")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, vec!["3", "10", "2", "20"]);
+    let ret: Vec<String> = redis::cmd("SORT")
+        .arg("s")
+        .arg("DESC")
+        .arg("ALPHA")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, vec!["3", "2", "1"]);
+
+    let _: usize = con.rpush("l", &["b", "c", "a"]).unwrap();
+    let () = con.set("d", "old").unwrap();
+    let ret: usize = redis::cmd("SORT")
+        .arg("l")
+        .arg("ALPHA")
+        .arg("STORE")
+        .arg("d")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 3);
+    let ret: Vec<String> = con.lrange("d", 0, -1).unwrap();
+    assert_eq!(ret, vec!["a", "b", "c"]);
+
+    assert_error(&mut con, redis::cmd("SORT").arg("s").arg("LIMIT").arg(0));
+    assert_error(&mut con, redis::cmd("SORT").arg("s").arg("STORE"));
+}
+
+#[test]
+fn test_bloom_commands() {
+    let mut con = connect();
+    let () = redis::cmd("BF.RESERVE")
+        .arg("bf")
+        .arg(0.01)
+        .arg(100)
+        .query(&mut con)
+        .unwrap();
+    let ret: bool = redis::cmd("BF.ADD")
+        .arg("bf")
+        .arg("a")
+        .query(&mut con)
+        .unwrap();
+    assert!(ret);
+    let ret: Vec<bool> = redis::cmd("BF.MADD")
+        .arg("bf")
+        .arg("a")
+        .arg("b")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, [false, true]);
+    let ret: bool = redis::cmd("BF.EXISTS")
+        .arg("bf")
+        .arg("b")
+        .query(&mut con)
+        .unwrap();
+    assert!(ret);
+    let ret: Vec<bool> = redis::cmd("BF.MEXISTS")
+        .arg("bf")
+        .arg("a")
+        .arg("c")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, [true, false]);
+
+    assert_error(
+        &mut con,
+        redis::cmd("BF.RESERVE").arg("bf").arg(0.01).arg(100),
+    );
+    assert_error(&mut con, redis::cmd("BF.RESERVE").arg("x").arg(2).arg(100));
+    assert_error(&mut con, redis::cmd("BF.ADD").arg("bf"));
+    assert_error(&mut con, redis::cmd("BF.MADD").arg("bf"));
+    assert_error(&mut con, redis::cmd("BF.EXISTS").arg("bf"));
+    assert_error(&mut con, redis::cmd("BF.MEXISTS").arg("bf"));
+}
+
+#[test]
+fn test_ratelimit_commands() {
+    let mut con = connect();
+    let ret: (bool, u64, i64) = redis::cmd("RATELIMIT.CHECK")
+        .arg("user")
+        .arg(1)
+        .arg(0.001)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, (true, 0, -1));
+    let (allowed, remaining, retry_after): (bool, u64, i64) = redis::cmd("RATELIMIT.CHECK")
+        .arg("user")
+        .arg(1)
+        .arg(0.001)
+        .query(&mut con)
+        .unwrap();
+    assert!(!allowed);
+    assert_eq!(remaining, 0);
+    assert!(retry_after > 0);
+
+    assert_error(&mut con, redis::cmd("RATELIMIT.CHECK").arg("user").arg(1));
+    assert_error(
+        &mut con,
+        redis::cmd("RATELIMIT.CHECK").arg("user").arg(0).arg(1),
+    );
+}
+
+#[test]
+fn test_counter_commands() {
+    let mut con = connect();
+    let () = redis::cmd("COUNTER.INCR")
+        .arg("hits")
+        .query(&mut con)
+        .unwrap();
+    let () = redis::cmd("COUNTER.INCR")
+        .arg("hits")
+        .arg(41)
+        .query(&mut con)
+        .unwrap();
+    let ret: i64 = redis::cmd("COUNTER.GET")
+        .arg("hits")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, 42);
+    let ret: String = redis::cmd("OBJECT")
+        .arg("ENCODING")
+        .arg("hits")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(ret, "striped");
+
+    assert_error(&mut con, redis::cmd("COUNTER.INCR").arg("hits").arg("x"));
+    assert_error(&mut con, redis::cmd("COUNTER.GET").arg("hits").arg("x"));
+}
+
+#[test]
+fn test_pipeline() {
+    let mut con = connect();
+    let (a, n): (String, i64) = redis::pipe()
+        .set("a", "1")
+        .ignore()
+        .get("a")
+        .incr("n", 2)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!((a.as_str(), n), ("1", 2));
+}